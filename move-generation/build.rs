@@ -1,4 +1,17 @@
+#[path = "src/magic_search.rs"]
+#[allow(dead_code)]
+mod magic_search;
+
+use magic_search::{find_magics, to_rust_source};
+
 fn main() {
     // Required for PyO3 to build a native extension module
     pyo3_build_config::add_extension_module_link_args();
+
+    // Search the slider magics once, at build time, so the library only
+    // has to fill its attack table
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src/magic_search.rs");
+    let path = std::path::Path::new(&std::env::var("OUT_DIR").unwrap()).join("magics.rs");
+    std::fs::write(path, to_rust_source(&find_magics(0x9e37_79b9_7f4a_7c15))).unwrap();
 }
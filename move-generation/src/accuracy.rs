@@ -0,0 +1,193 @@
+//! Accuracy and centipawn-loss metrics for analysed games
+
+use crate::score::Score;
+use crate::types::Color;
+
+/// Evaluations beyond this are treated as decided and clamped
+pub(crate) const MAX_CP: i32 = 1000;
+
+/// Per-player summary of an analysed game
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PlayerAccuracy {
+    /// Number of moves the player made
+    pub moves: u32,
+    /// Average centipawn loss per move
+    pub acpl: f64,
+    /// Lichess-style accuracy percentage (0-100)
+    pub accuracy: f64,
+    pub inaccuracies: u32,
+    pub mistakes: u32,
+    pub blunders: u32,
+}
+
+/// Accuracy summary of both players
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GameAccuracy {
+    pub white: PlayerAccuracy,
+    pub black: PlayerAccuracy,
+}
+
+impl GameAccuracy {
+    /// Summary as PGN tag pairs, e.g. ("WhiteACPL", "23")
+    pub fn to_pgn_tags(&self) -> Vec<(String, String)> {
+        let mut tags = Vec::new();
+        for (name, player) in [("White", &self.white), ("Black", &self.black)] {
+            tags.push((format!("{}ACPL", name), format!("{:.0}", player.acpl)));
+            tags.push((format!("{}Accuracy", name), format!("{:.1}", player.accuracy)));
+            tags.push((format!("{}Inaccuracies", name), player.inaccuracies.to_string()));
+            tags.push((format!("{}Mistakes", name), player.mistakes.to_string()));
+            tags.push((format!("{}Blunders", name), player.blunders.to_string()));
+        }
+        tags
+    }
+}
+
+/// How bad a move is, by the drop in the mover's winning chances
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Judgement {
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+impl Judgement {
+    /// Judgement of a move dropping the winning chances by more than 0.1,
+    /// 0.2 or 0.3, None for a smaller drop
+    pub fn from_drop(drop: f64) -> Option<Judgement> {
+        if drop > 0.3 {
+            Some(Judgement::Blunder)
+        } else if drop > 0.2 {
+            Some(Judgement::Mistake)
+        } else if drop > 0.1 {
+            Some(Judgement::Inaccuracy)
+        } else {
+            None
+        }
+    }
+
+    /// Numeric Annotation Glyph: 6 (?!), 2 (?) or 4 (??)
+    pub fn nag(self) -> u8 {
+        match self {
+            Judgement::Inaccuracy => 6,
+            Judgement::Mistake => 2,
+            Judgement::Blunder => 4,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Judgement::Inaccuracy => "Inaccuracy",
+            Judgement::Mistake => "Mistake",
+            Judgement::Blunder => "Blunder",
+        }
+    }
+}
+
+/// Winning chances in [-1, 1] for a centipawn evaluation (Lichess model)
+pub fn winning_chances(cp: i32) -> f64 {
+    let cp = cp.clamp(-MAX_CP, MAX_CP) as f64;
+    2.0 / (1.0 + (-0.00368208 * cp).exp()) - 1.0
+}
+
+/// Win percentage in [0, 100] for a centipawn evaluation
+pub fn win_percent(cp: i32) -> f64 {
+    50.0 + 50.0 * winning_chances(cp)
+}
+
+/// Accuracy of a single move given the mover's win percentage before and after it
+pub fn move_accuracy(win_before: f64, win_after: f64) -> f64 {
+    if win_after >= win_before {
+        return 100.0;
+    }
+    let raw = 103.1668100711649 * (-0.04354415386753951 * (win_before - win_after)).exp()
+        - 3.166924740191411;
+    raw.clamp(0.0, 100.0)
+}
+
+/// Compute per-player metrics from the evaluations of a game.
+///
+/// `evals` holds scores from White's point of view, as `Score`s or plain
+/// centipawns: the start position followed by the position after every ply,
+/// so a game of `n` plies has `n + 1` entries. `first_mover` is the side that
+/// played the first ply. Mates count as decided evaluations.
+///
+/// Moves are classified by the drop in winning chances, see
+/// `Judgement::from_drop`. Accuracy is the mean of the volatility-
+/// weighted and harmonic means of the per-move accuracies, as on Lichess.
+pub fn game_accuracy<S: Copy + Into<Score>>(evals: &[S], first_mover: Color) -> GameAccuracy {
+    let mut result = GameAccuracy::default();
+    if evals.len() < 2 {
+        return result;
+    }
+    let evals: Vec<i32> = evals.iter().map(|&eval| eval.into().to_cp()).collect();
+
+    let win_percents: Vec<f64> = evals.iter().map(|&cp| win_percent(cp)).collect();
+    let weights = volatility_weights(&win_percents);
+
+    // (accuracy, weight) per move and per player
+    let mut per_move: [Vec<(f64, f64)>; 2] = [Vec::new(), Vec::new()];
+    let mut cp_loss = [0i64; 2];
+
+    for (ply, pair) in evals.windows(2).enumerate() {
+        let white_moved = (ply % 2 == 0) == (first_mover == Color::White);
+        let idx = if white_moved { 0 } else { 1 };
+        let sign = if white_moved { 1 } else { -1 };
+
+        let before = sign * pair[0].clamp(-MAX_CP, MAX_CP);
+        let after = sign * pair[1].clamp(-MAX_CP, MAX_CP);
+        cp_loss[idx] += (before - after).max(0) as i64;
+
+        let drop = winning_chances(before) - winning_chances(after);
+        let player = if white_moved { &mut result.white } else { &mut result.black };
+        player.moves += 1;
+        match Judgement::from_drop(drop) {
+            Some(Judgement::Blunder) => player.blunders += 1,
+            Some(Judgement::Mistake) => player.mistakes += 1,
+            Some(Judgement::Inaccuracy) => player.inaccuracies += 1,
+            None => {}
+        }
+
+        let accuracy = move_accuracy(win_percent(before), win_percent(after));
+        per_move[idx].push((accuracy, weights[ply]));
+    }
+
+    for (idx, player) in [&mut result.white, &mut result.black].into_iter().enumerate() {
+        if player.moves == 0 {
+            continue;
+        }
+        player.acpl = cp_loss[idx] as f64 / player.moves as f64;
+        player.accuracy = (weighted_mean(&per_move[idx]) + harmonic_mean(&per_move[idx])) / 2.0;
+    }
+
+    result
+}
+
+/// One weight per move: the standard deviation of win percentages in a
+/// window around it, so moves in sharp positions count more
+fn volatility_weights(win_percents: &[f64]) -> Vec<f64> {
+    let window = (win_percents.len() / 10).clamp(2, 8).min(win_percents.len());
+    let mut windows: Vec<&[f64]> = vec![&win_percents[..window]; window.saturating_sub(2)];
+    windows.extend(win_percents.windows(window));
+
+    windows.iter()
+        .take(win_percents.len() - 1)
+        .map(|xs| std_dev(xs).clamp(0.5, 12.0))
+        .collect()
+}
+
+fn std_dev(xs: &[f64]) -> f64 {
+    let mean = xs.iter().sum::<f64>() / xs.len() as f64;
+    (xs.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / xs.len() as f64).sqrt()
+}
+
+fn weighted_mean(values: &[(f64, f64)]) -> f64 {
+    let total_weight: f64 = values.iter().map(|(_, w)| w).sum();
+    values.iter().map(|(v, w)| v * w).sum::<f64>() / total_weight
+}
+
+fn harmonic_mean(values: &[(f64, f64)]) -> f64 {
+    if values.iter().any(|&(v, _)| v <= 0.0) {
+        return 0.0;
+    }
+    values.len() as f64 / values.iter().map(|(v, _)| 1.0 / v).sum::<f64>()
+}
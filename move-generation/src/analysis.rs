@@ -0,0 +1,84 @@
+//! Game analysis: the engine's evaluation after every ply of a game, for
+//! eval bars and graphs or as `[%eval]` annotations
+
+use crate::accuracy::win_percent;
+use crate::eval::Evaluator;
+use crate::game::board_outcome;
+use crate::pgn::{ChessError, Eval, PgnGame};
+use crate::score::Score;
+use crate::search::{Engine, SearchHandle, SearchLimits, SearchOptions};
+use crate::types::{Board, Color};
+use std::collections::HashMap;
+
+/// Evaluation of the position after one move of a game
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlyEval {
+    /// 1 for the position after the first move
+    pub ply: usize,
+    pub san: String,
+    /// From White's point of view; None once the move ends the game
+    pub score: Option<Score>,
+    /// White's chances in [0, 100], the usual y axis of an eval graph: the
+    /// score as a win percentage, or 100, 50 or 0 once the game is over
+    pub white_win_percent: f64,
+}
+
+/// Searches the positions of games, remembering every score by Zobrist hash
+/// so transpositions and games sharing an opening are searched once
+pub struct GameAnalyzer<E> {
+    evaluator: E,
+    engine: Engine,
+    limits: SearchLimits,
+    cache: HashMap<u64, Score>,
+}
+
+impl<E: Evaluator> GameAnalyzer<E> {
+    pub fn new(evaluator: E, limits: SearchLimits, options: SearchOptions) -> Self {
+        GameAnalyzer { evaluator, engine: Engine::new(options), limits, cache: HashMap::new() }
+    }
+
+    /// Score of `board` for the side to move
+    pub fn evaluate(&mut self, board: &Board) -> Result<Score, ChessError> {
+        if let Some(&score) = self.cache.get(&board.hash) {
+            return Ok(score);
+        }
+        let result = self.engine.search(board, &self.limits, &self.evaluator, &SearchHandle::new(), |_| {})?;
+        self.cache.insert(board.hash, result.score);
+        Ok(result.score)
+    }
+
+    /// Evaluation after every move of the main line
+    pub fn eval_graph(&mut self, game: &PgnGame) -> Result<Vec<PlyEval>, ChessError> {
+        let mut evals = Vec::with_capacity(game.moves.len());
+        for (ply, (mut board, mv)) in game.replay()?.into_iter().enumerate() {
+            board.make_move(&mv);
+            let (score, white_win_percent) = match board_outcome(&board) {
+                Some(outcome) => (None, match outcome.winner {
+                    Some(Color::White) => 100.0,
+                    Some(Color::Black) => 0.0,
+                    None => 50.0,
+                }),
+                None => {
+                    let score = self.evaluate(&board)?;
+                    let score = if board.side_to_move == Color::White { score } else { -score };
+                    (Some(score), win_percent(score.to_cp()))
+                }
+            };
+            evals.push(PlyEval { ply: ply + 1, san: game.moves[ply].clone(), score, white_win_percent });
+        }
+        Ok(evals)
+    }
+
+    /// `eval_graph`, also stored in `game.evals` so that `write_pgn` adds
+    /// them as `[%eval]` comments
+    pub fn annotate(&mut self, game: &mut PgnGame) -> Result<Vec<PlyEval>, ChessError> {
+        let evals = self.eval_graph(game)?;
+        game.evals = evals.iter().map(|eval| eval.score.map(Eval::from)).collect();
+        Ok(evals)
+    }
+
+    /// Number of positions searched so far
+    pub fn cache_len(&self) -> usize {
+        self.cache.len()
+    }
+}
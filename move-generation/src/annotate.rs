@@ -0,0 +1,112 @@
+//! Engine-annotated PGN for post-game review: every position of a game is
+//! searched by a match `Player`, the built-in search or a UCI engine, and
+//! the game is written back with `[%eval]` comments, `?!`/`?`/`??` NAGs and
+//! the engine's line where a move lost ground
+
+use crate::accuracy::{winning_chances, Judgement, MAX_CP};
+use crate::game::board_outcome;
+use crate::match_runner::{MoveRequest, Player};
+use crate::notation::to_san;
+use crate::pgn::{ChessError, Eval, MoveAnnotation, PgnGame};
+use crate::score::Score;
+use crate::search::SearchLimits;
+use crate::types::{Board, Color, Move};
+use std::io;
+
+/// A game with its analysis
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnnotatedGame {
+    /// The game, with `evals` filled in from White's point of view
+    pub game: PgnGame,
+    /// Judgement of each move, None for a good one
+    pub judgements: Vec<Option<Judgement>>,
+    /// NAG, comment and better line of each move
+    pub annotations: Vec<MoveAnnotation>,
+}
+
+impl AnnotatedGame {
+    pub fn write_pgn<W: io::Write>(&self, out: W) -> io::Result<()> {
+        self.game.write_annotated(out, &self.annotations)
+    }
+
+    pub fn to_pgn(&self) -> String {
+        let mut out = Vec::new();
+        self.write_pgn(&mut out).expect("writing to a Vec cannot fail");
+        String::from_utf8(out).expect("PGN output is UTF-8")
+    }
+}
+
+/// Search every position of the main line of `game` within `limits` and
+/// judge each move by the drop in the mover's winning chances. Inaccuracies,
+/// mistakes and blunders get their NAG, a comment such as "Blunder. Nc3 was
+/// best." and the engine's line as a variation. Existing evals are replaced.
+pub fn annotate_game(game: &PgnGame, player: &mut dyn Player, limits: &SearchLimits) -> Result<AnnotatedGame, ChessError> {
+    let replay = game.replay()?;
+    let start = game.starting_board()?;
+    let mut boards: Vec<Board> = replay.iter().map(|(board, _)| board.clone()).collect();
+    let mut last = replay.last().map_or_else(|| start.clone(), |(board, _)| board.clone());
+    if let Some((_, mv)) = replay.last() {
+        last.make_move(mv);
+    }
+    boards.push(last);
+    let moves: Vec<Move> = replay.into_iter().map(|(_, mv)| mv).collect();
+
+    // Best move, line and score for the side to move of every position
+    // that is not over
+    player.new_game()?;
+    let mut searches = Vec::with_capacity(boards.len());
+    for (ply, board) in boards.iter().enumerate() {
+        if board_outcome(board).is_some() {
+            searches.push(None);
+            continue;
+        }
+        let request = MoveRequest { start: &start, moves: &moves[..ply], board, limits, clock: None };
+        let choice = player.choose_move(&request)?;
+        let score = choice.score.ok_or_else(|| ChessError::ParseError("the engine reports no score".to_string()))?;
+        searches.push(Some((choice, score)));
+    }
+
+    let mut annotated = AnnotatedGame { game: game.clone(), judgements: Vec::new(), annotations: Vec::new() };
+    annotated.game.evals.clear();
+    for (ply, mv) in moves.iter().enumerate() {
+        let (before, after) = (&boards[ply], &boards[ply + 1]);
+        let mover = before.side_to_move;
+        // Winning chances of the mover after the move
+        let chances = match (&searches[ply + 1], board_outcome(after)) {
+            (Some((_, score)), _) => -chances_of(*score),
+            (None, Some(outcome)) if outcome.winner == Some(mover) => 1.0,
+            (None, Some(outcome)) if outcome.winner.is_some() => -1.0,
+            _ => 0.0,
+        };
+        let eval = searches[ply + 1].as_ref().map(|(_, score)| {
+            Eval::from(if after.side_to_move == Color::White { *score } else { -*score })
+        });
+        annotated.game.evals.push(eval);
+
+        let Some((best, score)) = &searches[ply] else {
+            annotated.judgements.push(None);
+            annotated.annotations.push(MoveAnnotation::default());
+            continue;
+        };
+        let judgement = Judgement::from_drop(chances_of(*score) - chances).filter(|_| best.mv != *mv);
+        let mut annotation = MoveAnnotation::default();
+        if let Some(judgement) = judgement {
+            annotation.nag = Some(judgement.nag());
+            annotation.comment = Some(format!("{}. {} was best.", judgement.name(), to_san(before, &best.mv)));
+            let line = if best.pv.first() == Some(&best.mv) { &best.pv[..] } else { std::slice::from_ref(&best.mv) };
+            let mut board = before.clone();
+            for mv in line {
+                annotation.variation.push(to_san(&board, mv));
+                board.make_move(mv);
+            }
+        }
+        annotated.judgements.push(judgement);
+        annotated.annotations.push(annotation);
+    }
+    Ok(annotated)
+}
+
+/// Winning chances in [-1, 1] for the side a score belongs to
+fn chances_of(score: Score) -> f64 {
+    winning_chances(score.to_cp().clamp(-MAX_CP, MAX_CP))
+}
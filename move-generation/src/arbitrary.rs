@@ -0,0 +1,74 @@
+//! proptest `Arbitrary` implementations, enabled with the `proptest` feature
+
+use crate::pgn::Position;
+use crate::random::{generate_random_position, PositionConstraints};
+use crate::types::{Board, Color, Move, Piece};
+use proptest::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+impl Arbitrary for Color {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Color>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        prop_oneof![Just(Color::White), Just(Color::Black)].boxed()
+    }
+}
+
+impl Arbitrary for Piece {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Piece>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        prop_oneof![
+            Just(Piece::Pawn),
+            Just(Piece::Knight),
+            Just(Piece::Bishop),
+            Just(Piece::Rook),
+            Just(Piece::Queen),
+            Just(Piece::King),
+        ]
+        .boxed()
+    }
+}
+
+/// Any from/to pair, optionally promoting to a knight, bishop, rook or queen.
+/// The move need not be legal in any particular position.
+impl Arbitrary for Move {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Move>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        let promotion = prop::option::of(prop_oneof![
+            Just(Piece::Knight),
+            Just(Piece::Bishop),
+            Just(Piece::Rook),
+            Just(Piece::Queen),
+        ]);
+        (0u8..64, 0u8..64, promotion)
+            .prop_map(|(from, to, promotion)| Move { from, to, promotion, drop: None })
+            .boxed()
+    }
+}
+
+/// Legal positions from `generate_random_position`, seeded by the strategy
+impl Arbitrary for Board {
+    type Parameters = PositionConstraints;
+    type Strategy = BoxedStrategy<Board>;
+
+    fn arbitrary_with(constraints: PositionConstraints) -> Self::Strategy {
+        any::<u64>()
+            .prop_map(move |seed| generate_random_position(&mut StdRng::seed_from_u64(seed), &constraints))
+            .boxed()
+    }
+}
+
+impl Arbitrary for Position {
+    type Parameters = PositionConstraints;
+    type Strategy = BoxedStrategy<Position>;
+
+    fn arbitrary_with(constraints: PositionConstraints) -> Self::Strategy {
+        any_with::<Board>(constraints).prop_map(|board| Position::from(&board)).boxed()
+    }
+}
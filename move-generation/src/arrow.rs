@@ -0,0 +1,137 @@
+use crate::encoding::encode_move;
+use crate::pgn::{ChessError, Eval, PgnGame, PgnGameReader, Position};
+use crate::zobrist;
+use arrow_array::builder::{Float32Builder, Int32Builder, Int8Builder, StringBuilder, UInt16Builder, UInt64Builder};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use std::io;
+use std::sync::Arc;
+
+/// Schema of the position batches: one row per move played
+pub fn position_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("fen", DataType::Utf8, false),
+        Field::new("hash", DataType::UInt64, false),
+        Field::new("result", DataType::Int8, true),
+        Field::new("white_elo", DataType::UInt16, true),
+        Field::new("black_elo", DataType::UInt16, true),
+        Field::new("ply", DataType::UInt16, false),
+        Field::new("eval", DataType::Float32, true),
+        Field::new("mate", DataType::Int32, true),
+        Field::new("move", DataType::UInt16, false),
+    ]))
+}
+
+/// Accumulates the positions of replayed games into Arrow columns.
+///
+/// Each row is the position before a move: its FEN and Zobrist hash, the game
+/// result from White's point of view (1, 0, -1, null if unfinished), the
+/// players' ratings, the ply, the `[%eval]` annotation of the position (pawns
+/// or mate distance) and the move played as a from*64+to index.
+#[derive(Default)]
+pub struct PositionBatchBuilder {
+    fen: StringBuilder,
+    hash: UInt64Builder,
+    result: Int8Builder,
+    white_elo: UInt16Builder,
+    black_elo: UInt16Builder,
+    ply: UInt16Builder,
+    eval: Float32Builder,
+    mate: Int32Builder,
+    mv: UInt16Builder,
+    rows: usize,
+}
+
+impl PositionBatchBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of rows added since the last `finish`
+    pub fn len(&self) -> usize {
+        self.rows
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows == 0
+    }
+
+    /// Replay a game and append one row per move
+    pub fn add_game(&mut self, game: &PgnGame) -> Result<(), ChessError> {
+        let positions = game.replay()?;
+        let result = match game.result.as_deref() {
+            Some("1-0") => Some(1),
+            Some("0-1") => Some(-1),
+            Some("1/2-1/2") => Some(0),
+            _ => None,
+        };
+        let elo = |tag| game.header(tag).and_then(|value| value.parse::<u16>().ok());
+        let (white_elo, black_elo) = (elo("WhiteElo"), elo("BlackElo"));
+
+        for (ply, (board, mv)) in positions.iter().enumerate() {
+            // The annotation after the previous move evaluates this position
+            let eval = ply.checked_sub(1).and_then(|prev| game.evals.get(prev).copied().flatten());
+            self.fen.append_value(Position::from(board).to_fen());
+            self.hash.append_value(zobrist::hash(board));
+            self.result.append_option(result);
+            self.white_elo.append_option(white_elo);
+            self.black_elo.append_option(black_elo);
+            self.ply.append_value(ply as u16);
+            self.eval.append_option(match eval {
+                Some(Eval::Pawns(pawns)) => Some(pawns),
+                _ => None,
+            });
+            self.mate.append_option(match eval {
+                Some(Eval::Mate(moves)) => Some(moves),
+                _ => None,
+            });
+            self.mv.append_value(encode_move(mv));
+        }
+        self.rows += positions.len();
+        Ok(())
+    }
+
+    /// Build a RecordBatch from the rows added so far and reset the builder
+    pub fn finish(&mut self) -> RecordBatch {
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(self.fen.finish()),
+            Arc::new(self.hash.finish()),
+            Arc::new(self.result.finish()),
+            Arc::new(self.white_elo.finish()),
+            Arc::new(self.black_elo.finish()),
+            Arc::new(self.ply.finish()),
+            Arc::new(self.eval.finish()),
+            Arc::new(self.mate.finish()),
+            Arc::new(self.mv.finish()),
+        ];
+        self.rows = 0;
+        RecordBatch::try_new(position_schema(), columns).expect("columns match the schema")
+    }
+}
+
+/// Stream a PGN source as RecordBatches of about `batch_size` rows; a batch is
+/// cut at the first game boundary past that size
+pub fn record_batches<R: io::Read>(
+    games: PgnGameReader<R>,
+    batch_size: usize,
+) -> impl Iterator<Item = Result<RecordBatch, ChessError>> {
+    let mut games = games;
+    let mut builder = PositionBatchBuilder::new();
+    std::iter::from_fn(move || {
+        loop {
+            match games.next_game() {
+                Ok(Some(game)) => {
+                    if let Err(e) = builder.add_game(&game) {
+                        return Some(Err(e));
+                    }
+                    if builder.len() >= batch_size {
+                        return Some(Ok(builder.finish()));
+                    }
+                }
+                Ok(None) if builder.is_empty() => return None,
+                Ok(None) => return Some(Ok(builder.finish())),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    })
+}
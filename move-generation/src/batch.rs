@@ -0,0 +1,19 @@
+//! Running a computation over many positions on the rayon pool
+
+use crate::pgn::{ChessError, Position};
+use crate::types::Board;
+use rayon::prelude::*;
+
+/// Parse each FEN and apply `f` to its board, spreading the positions over
+/// the rayon workers. Results come back in input order, with an error for
+/// each FEN that fails to parse.
+pub fn map_fens<S, T, F>(fens: &[S], f: F) -> Vec<Result<T, ChessError>>
+where
+    S: AsRef<str> + Sync,
+    T: Send,
+    F: Fn(&Board) -> T + Sync,
+{
+    fens.par_iter()
+        .map(|fen| Ok(f(&Board::from(fen.as_ref().parse::<Position>()?))))
+        .collect()
+}
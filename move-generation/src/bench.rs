@@ -0,0 +1,177 @@
+//! Throughput benchmarks over a fixed set of positions, for comparing builds
+//! and libraries with reproducible numbers
+
+use crate::pgn::{ChessError, Position};
+use crate::perft::perft;
+use crate::rules::generate_legal_moves;
+use crate::types::Board;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt;
+use std::hint::black_box;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// The standard perft test positions: the start position, Kiwipete and
+/// positions 3 to 6 of the Chess Programming Wiki
+pub const BENCH_FENS: [&str; 6] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+];
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static COUNTING: AtomicBool = AtomicBool::new(false);
+
+/// Global allocator that counts allocations, so benchmarks can report them.
+/// Install it in a binary with
+/// `#[global_allocator] static ALLOC: CountingAllocator = CountingAllocator;`
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        COUNTING.store(true, Ordering::Relaxed);
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+/// Allocations made so far, or None when `CountingAllocator` is not the
+/// global allocator
+fn allocations() -> Option<u64> {
+    COUNTING.load(Ordering::Relaxed).then(|| ALLOCATIONS.load(Ordering::Relaxed))
+}
+
+/// How much work each benchmark does
+#[derive(Debug, Clone, Copy)]
+pub struct BenchConfig {
+    /// Passes over the position set for move generation, make/unmake and
+    /// FEN parsing
+    pub iterations: u32,
+    pub perft_depth: u32,
+}
+
+impl Default for BenchConfig {
+    fn default() -> Self {
+        BenchConfig { iterations: 10_000, perft_depth: 3 }
+    }
+}
+
+/// Measurement of one benchmark
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BenchResult {
+    /// Units of work done: positions, nodes or moves
+    pub count: u64,
+    pub elapsed: Duration,
+    /// Heap allocations, when counted
+    pub allocations: Option<u64>,
+}
+
+impl BenchResult {
+    pub fn per_second(&self) -> f64 {
+        self.count as f64 / self.elapsed.as_secs_f64().max(1e-9)
+    }
+
+    fn measure(work: impl FnOnce() -> u64) -> BenchResult {
+        let allocations_before = allocations();
+        let start = Instant::now();
+        let count = work();
+        let elapsed = start.elapsed();
+        let allocations = allocations().zip(allocations_before).map(|(after, before)| after - before);
+        BenchResult { count, elapsed, allocations }
+    }
+}
+
+/// Results of a benchmark run
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BenchStats {
+    /// Legal move generation, counted in positions
+    pub movegen: BenchResult,
+    /// Perft, counted in leaf nodes
+    pub perft: BenchResult,
+    /// Making and unmaking every legal move, counted in moves
+    pub make_unmake: BenchResult,
+    /// FEN parsing into a board, counted in positions
+    pub fen_parsing: BenchResult,
+}
+
+/// Run the benchmarks over `BENCH_FENS`
+pub fn run(config: &BenchConfig) -> Result<BenchStats, ChessError> {
+    let boards = BENCH_FENS.iter()
+        .map(|fen| Ok(Board::from(fen.parse::<Position>()?)))
+        .collect::<Result<Vec<Board>, ChessError>>()?;
+
+    let movegen = BenchResult::measure(|| {
+        let mut count = 0;
+        for _ in 0..config.iterations {
+            for board in &boards {
+                black_box(generate_legal_moves(black_box(board)));
+                count += 1;
+            }
+        }
+        count
+    });
+
+    let perft = BenchResult::measure(|| boards.iter().map(|board| perft(black_box(board), config.perft_depth)).sum());
+
+    let legal: Vec<_> = boards.iter().map(generate_legal_moves).collect();
+    let make_unmake = BenchResult::measure(|| {
+        let mut boards = boards.clone();
+        let mut count = 0;
+        for _ in 0..config.iterations {
+            for (board, moves) in boards.iter_mut().zip(&legal) {
+                for mv in moves {
+                    let undo = board.make_move(mv);
+                    board.unmake_move(black_box(mv), undo);
+                    count += 1;
+                }
+            }
+        }
+        count
+    });
+
+    let fen_parsing = BenchResult::measure(|| {
+        let mut count = 0;
+        for _ in 0..config.iterations {
+            for fen in BENCH_FENS {
+                if let Ok(position) = black_box(fen).parse::<Position>() {
+                    black_box(Board::from(position));
+                }
+                count += 1;
+            }
+        }
+        count
+    });
+
+    Ok(BenchStats { movegen, perft, make_unmake, fen_parsing })
+}
+
+impl fmt::Display for BenchStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, unit, result) in [
+            ("movegen", "positions", &self.movegen),
+            ("perft", "nodes", &self.perft),
+            ("make/unmake", "moves", &self.make_unmake),
+            ("fen parsing", "positions", &self.fen_parsing),
+        ] {
+            write!(f, "{:<12} {:>12} {:<9} {:>9.3}s {:>14.0} {}/s", name, result.count, unit,
+                result.elapsed.as_secs_f64(), result.per_second(), unit)?;
+            match result.allocations {
+                Some(allocations) => writeln!(f, " {:>10} allocs", allocations)?,
+                None => writeln!(f)?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,35 @@
+use move_generation::bench::{run, BenchConfig, CountingAllocator};
+use std::process::ExitCode;
+
+#[global_allocator]
+static ALLOC: CountingAllocator = CountingAllocator;
+
+const USAGE: &str = "usage: bench [--iterations N] [--depth D]";
+
+fn main() -> ExitCode {
+    let mut config = BenchConfig::default();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let value = iter.next().and_then(|n| n.parse().ok());
+        match (arg.as_str(), value) {
+            ("--iterations", Some(n)) => config.iterations = n,
+            ("--depth", Some(d)) => config.perft_depth = d,
+            _ => {
+                eprintln!("{}", USAGE);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    match run(&config) {
+        Ok(stats) => {
+            print!("{}", stats);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("bench: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! Match between two engines, each `builtin` or the path of a UCI engine
+
+use move_generation::match_runner::{openings_from_pgn, run_match, MatchConfig, Opening, Player, SearchPlayer, UciEngine};
+use move_generation::pgn::{ChessError, PgnGameReader};
+use move_generation::search::SearchOptions;
+use move_generation::stats::Sprt;
+use move_generation::tune::EvalParams;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::process::ExitCode;
+use std::str::FromStr;
+use std::time::Duration;
+
+const USAGE: &str = "usage: engine_match <engine1> <engine2> [--games N] [--tc 10+0.1] [--depth D] \
+    [--openings file.pgn|file.epd] [--plies N] [--sprt ELO0 ELO1] [--resign CP] [--draw CP] \
+    [--max-plies N] [--pgn output.pgn]";
+
+fn player(engine: &str) -> Result<Box<dyn Player>, ChessError> {
+    if engine == "builtin" {
+        return Ok(Box::new(SearchPlayer::new("builtin", EvalParams::default(), SearchOptions::default())));
+    }
+    Ok(Box::new(UciEngine::spawn(engine, &[])?))
+}
+
+fn openings(path: &str, plies: usize) -> Result<Vec<Opening>, ChessError> {
+    let file = File::open(path)?;
+    if path.ends_with(".pgn") {
+        return openings_from_pgn(PgnGameReader::new(file).lenient(), plies);
+    }
+    let mut openings = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            openings.push(Opening::from_epd(&line)?);
+        }
+    }
+    Ok(openings)
+}
+
+/// Parse an option's value, clearing `valid` when it is missing or malformed
+fn parse<T: FromStr>(value: Option<&String>, valid: &mut bool) -> Option<T> {
+    let value = value.and_then(|v| v.parse().ok());
+    *valid &= value.is_some();
+    value
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut config = MatchConfig { games: 2, event: "Engine match".to_string(), ..MatchConfig::default() };
+    let (mut opening_file, mut plies, mut output) = (None::<String>, Some(8), None::<String>);
+    let mut valid = true;
+    let mut positional = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--games" => config.games = parse(iter.next(), &mut valid).unwrap_or(0),
+            "--tc" => config.time_control = parse(iter.next(), &mut valid),
+            "--depth" => config.limits.depth = parse(iter.next(), &mut valid),
+            "--openings" => opening_file = parse(iter.next(), &mut valid),
+            "--plies" => plies = parse(iter.next(), &mut valid),
+            "--sprt" => {
+                let elo0 = parse(iter.next(), &mut valid);
+                let elo1 = parse(iter.next(), &mut valid);
+                config.sprt = elo0.zip(elo1).map(|(elo0, elo1)| Sprt::new(elo0, elo1, 0.05, 0.05));
+            }
+            "--resign" => config.adjudication.resign_cp = parse(iter.next(), &mut valid),
+            "--draw" => config.adjudication.draw_cp = parse(iter.next(), &mut valid),
+            "--max-plies" => config.adjudication.max_plies = parse(iter.next(), &mut valid),
+            "--pgn" => output = parse(iter.next(), &mut valid),
+            _ => positional.push(arg.as_str()),
+        }
+    }
+    let (true, Some(plies), [first, second]) = (valid, plies, positional.as_slice()) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::FAILURE;
+    };
+    if config.time_control.is_none() && config.limits.depth.is_none() {
+        config.limits.movetime = Some(Duration::from_millis(100));
+    }
+
+    let result = (|| -> Result<_, ChessError> {
+        if let Some(path) = &opening_file {
+            config.openings = openings(path, plies)?;
+        }
+        let mut pgn = match &output {
+            Some(path) => Some(BufWriter::new(File::create(path)?)),
+            None => None,
+        };
+        let (mut first, mut second) = (player(first)?, player(second)?);
+        let mut written = Ok(());
+        let progress = run_match(&config, first.as_mut(), second.as_mut(), |game, progress| {
+            let wdl = progress.wdl;
+            eprintln!(
+                "game {}: {} - {} {}  (+{} ={} -{})",
+                game.round,
+                game.game.header("White").unwrap_or("?"),
+                game.game.header("Black").unwrap_or("?"),
+                game.game.result.as_deref().unwrap_or("*"),
+                wdl.wins,
+                wdl.draws,
+                wdl.losses,
+            );
+            if let Some(report) = &progress.sprt {
+                eprintln!("{}", report);
+            }
+            if let (Some(out), Ok(())) = (pgn.as_mut(), &written) {
+                written = game.game.write_pgn(&mut *out).and_then(|_| writeln!(out));
+            }
+        })?;
+        written?;
+        if let Some(out) = pgn.as_mut() {
+            out.flush()?;
+        }
+        Ok(progress)
+    })();
+    match result {
+        Ok(progress) => {
+            let wdl = progress.wdl;
+            println!("Score of {} games: {} - {} - {}", wdl.games(), wdl.wins, wdl.losses, wdl.draws);
+            if let Some(elo) = wdl.elo() {
+                println!("{}", elo);
+            }
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("engine_match: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
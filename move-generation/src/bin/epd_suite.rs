@@ -0,0 +1,68 @@
+//! Run an EPD test suite, e.g. WAC, STS or Arasan, with the built-in search
+//! or a UCI engine and report the positions solved
+
+use move_generation::epd::{read_epd, run_suite};
+use move_generation::match_runner::{Player, SearchPlayer, UciEngine};
+use move_generation::pgn::ChessError;
+use move_generation::search::{SearchLimits, SearchOptions};
+use move_generation::tune::EvalParams;
+use std::fs::File;
+use std::io::BufReader;
+use std::process::ExitCode;
+use std::time::Duration;
+
+const USAGE: &str = "usage: epd_suite <suite.epd> [--engine builtin|path] [--depth D] [--movetime MS] [--threads N]";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (mut engine, mut threads) = (Some("builtin".to_string()), Some(1));
+    let mut limits = SearchLimits::default();
+    let mut valid = true;
+    let mut positional = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--engine" => engine = iter.next().cloned(),
+            "--threads" => threads = iter.next().and_then(|n| n.parse().ok()),
+            "--depth" => {
+                limits.depth = iter.next().and_then(|n| n.parse().ok());
+                valid &= limits.depth.is_some();
+            }
+            "--movetime" => {
+                limits.movetime = iter.next().and_then(|n| n.parse().ok()).map(Duration::from_millis);
+                valid &= limits.movetime.is_some();
+            }
+            _ => positional.push(arg.as_str()),
+        }
+    }
+    let (true, Some(engine), Some(threads), [suite]) = (valid, engine, threads, positional.as_slice()) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::FAILURE;
+    };
+    if limits.depth.is_none() && limits.movetime.is_none() {
+        limits.movetime = Some(Duration::from_secs(1));
+    }
+
+    let result = (|| -> Result<_, ChessError> {
+        let entries = read_epd(BufReader::new(File::open(suite)?))?;
+        let mut player: Box<dyn Player> = if engine == "builtin" {
+            let options = SearchOptions { threads, ..SearchOptions::default() };
+            Box::new(SearchPlayer::new("builtin", EvalParams::default(), options))
+        } else {
+            let mut uci = UciEngine::spawn(&engine, &[])?;
+            uci.set_option("Threads", &threads.to_string())?;
+            Box::new(uci)
+        };
+        run_suite(&entries, player.as_mut(), &limits, |result| println!("{}", result))
+    })();
+    match result {
+        Ok(report) => {
+            println!("{}", report);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("epd_suite: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use move_generation::magic_search::{find_magics, to_bytes, to_rust_source};
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: find_magics [--seed N] [--binary out.bin]";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (mut seed, mut binary) = (0x9e37_79b9_7f4a_7c15u64, None);
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match (arg.as_str(), iter.next()) {
+            ("--seed", Some(n)) if n.parse::<u64>().is_ok() => seed = n.parse().unwrap(),
+            ("--binary", Some(path)) => binary = Some(path),
+            _ => {
+                eprintln!("{}", USAGE);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let magics = find_magics(seed);
+    match binary {
+        Some(path) => {
+            if let Err(e) = std::fs::write(path, to_bytes(&magics)) {
+                eprintln!("find_magics: {}", e);
+                return ExitCode::FAILURE;
+            }
+            eprintln!("wrote {} magics to {}", magics.len(), path);
+        }
+        None => print!("{}", to_rust_source(&magics)),
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,27 @@
+use move_generation::jsonl::pgn_to_jsonl;
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() || args.len() > 2 {
+        eprintln!("usage: pgn2jsonl <input.pgn> [output.jsonl]");
+        return ExitCode::FAILURE;
+    }
+
+    let result = File::open(&args[0]).map_err(Into::into).and_then(|input| match args.get(1) {
+        Some(path) => pgn_to_jsonl(input, BufWriter::new(File::create(path)?)),
+        None => pgn_to_jsonl(input, BufWriter::new(io::stdout().lock())),
+    });
+    match result {
+        Ok(count) => {
+            eprintln!("{} games written", count);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("pgn2jsonl: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,70 @@
+//! Annotate the games of a PGN file with the built-in search or a UCI
+//! engine: evals, mistake and blunder NAGs, and better-move variations
+
+use move_generation::annotate::annotate_game;
+use move_generation::match_runner::{Player, SearchPlayer, UciEngine};
+use move_generation::pgn::{ChessError, PgnGameReader};
+use move_generation::search::{SearchLimits, SearchOptions};
+use move_generation::tune::EvalParams;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::process::ExitCode;
+use std::time::Duration;
+
+const USAGE: &str = "usage: pgn_annotate <input.pgn> <output.pgn> [--engine builtin|path] [--depth D] [--movetime MS]";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut engine = Some("builtin".to_string());
+    let mut limits = SearchLimits::default();
+    let mut valid = true;
+    let mut positional = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--engine" => engine = iter.next().cloned(),
+            "--depth" => {
+                limits.depth = iter.next().and_then(|n| n.parse().ok());
+                valid &= limits.depth.is_some();
+            }
+            "--movetime" => {
+                limits.movetime = iter.next().and_then(|n| n.parse().ok()).map(Duration::from_millis);
+                valid &= limits.movetime.is_some();
+            }
+            _ => positional.push(arg.as_str()),
+        }
+    }
+    let (true, Some(engine), [input, output]) = (valid, engine, positional.as_slice()) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::FAILURE;
+    };
+    if limits.depth.is_none() && limits.movetime.is_none() {
+        limits.depth = Some(12);
+    }
+
+    let result = (|| -> Result<usize, ChessError> {
+        let mut player: Box<dyn Player> = if engine == "builtin" {
+            Box::new(SearchPlayer::new("builtin", EvalParams::default(), SearchOptions::default()))
+        } else {
+            Box::new(UciEngine::spawn(&engine, &[])?)
+        };
+        let mut out = BufWriter::new(File::create(output)?);
+        let mut annotated = 0;
+        for game in PgnGameReader::new(File::open(input)?).lenient() {
+            annotate_game(&game?, player.as_mut(), &limits)?.write_pgn(&mut out)?;
+            annotated += 1;
+        }
+        out.flush()?;
+        Ok(annotated)
+    })();
+    match result {
+        Ok(games) => {
+            eprintln!("annotated {} games", games);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("pgn_annotate: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
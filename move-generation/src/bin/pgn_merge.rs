@@ -0,0 +1,38 @@
+use move_generation::pgn_tools::{merge_pgn, DedupKey};
+use std::fs::File;
+use std::io::BufWriter;
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: pgn_merge [--by-headers] -o <output.pgn> <input.pgn>...";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (mut key, mut output, mut inputs) = (DedupKey::Movetext, None, Vec::new());
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--by-headers" => key = DedupKey::Headers,
+            "-o" => output = iter.next(),
+            _ => inputs.push(arg),
+        }
+    }
+    let Some(output) = output.filter(|_| !inputs.is_empty()) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::FAILURE;
+    };
+
+    let result = inputs.iter().map(File::open).collect::<Result<Vec<_>, _>>()
+        .and_then(|sources| Ok((sources, BufWriter::new(File::create(output)?))))
+        .map_err(Into::into)
+        .and_then(|(sources, writer)| merge_pgn(sources, writer, key));
+    match result {
+        Ok(stats) => {
+            eprintln!("wrote {} games, skipped {} duplicates", stats.games, stats.duplicates);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("pgn_merge: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
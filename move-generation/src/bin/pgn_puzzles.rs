@@ -0,0 +1,43 @@
+use move_generation::pgn::PgnGameReader;
+use move_generation::puzzle::{mine_pgn, PuzzleMiner, PuzzleOptions};
+use move_generation::search::{SearchLimits, SearchOptions};
+use move_generation::tune::EvalParams;
+use std::fs::File;
+use std::io::BufWriter;
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: pgn_puzzles <input.pgn> <output.jsonl> [--depth N] [--plies N] [--threads N]";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (mut depth, mut plies, mut threads) = (Some(8), Some(7), Some(1));
+    let mut positional = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--depth" => depth = iter.next().and_then(|n| n.parse().ok()),
+            "--plies" => plies = iter.next().and_then(|n| n.parse().ok()),
+            "--threads" => threads = iter.next().and_then(|n| n.parse().ok()),
+            _ => positional.push(arg.as_str()),
+        }
+    }
+    let (Some(depth), Some(max_plies), Some(threads), [input, output]) = (depth, plies, threads, positional.as_slice()) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::FAILURE;
+    };
+
+    let options = PuzzleOptions { limits: SearchLimits { depth: Some(depth), ..Default::default() }, max_plies, ..Default::default() };
+    let mut miner = PuzzleMiner::new(EvalParams::default(), options, SearchOptions { threads, ..Default::default() });
+    let result = File::open(input).map_err(Into::into)
+        .and_then(|file| mine_pgn(&mut miner, PgnGameReader::new(file).lenient(), BufWriter::new(File::create(output)?)));
+    match result {
+        Ok(written) => {
+            eprintln!("wrote {} puzzles", written);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("pgn_puzzles: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use move_generation::pgn::PgnGameReader;
+use move_generation::repertoire::build_tree;
+use std::fs::File;
+use std::io::BufWriter;
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: pgn_repertoire <input.pgn> <output.pgn> [--plies N] [--min-games M]";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (mut plies, mut min_games) = (Some(16), Some(10));
+    let mut positional = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--plies" => plies = iter.next().and_then(|n| n.parse().ok()),
+            "--min-games" => min_games = iter.next().and_then(|n| n.parse().ok()),
+            _ => positional.push(arg.as_str()),
+        }
+    }
+    let (Some(plies), Some(min_games), [input, output]) = (plies, min_games, positional.as_slice()) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::FAILURE;
+    };
+
+    let result = File::open(input).map_err(Into::into)
+        .and_then(|file| build_tree(PgnGameReader::new(file).lenient(), plies))
+        .and_then(|tree| Ok(tree.write_repertoire(BufWriter::new(File::create(output)?), min_games)?));
+    match result {
+        Ok(chapters) => {
+            eprintln!("wrote {} chapters", chapters);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("pgn_repertoire: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use move_generation::pgn_tools::{split_pgn, ShardMode};
+use std::fs::File;
+use std::path::Path;
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: pgn_split <input.pgn> <output-dir> (--shards N | --games M) [--gzip]";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (mut mode, mut compress) = (None, false);
+    let mut positional = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--shards" => mode = iter.next().and_then(|n| n.parse().ok()).map(ShardMode::Count),
+            "--games" => mode = iter.next().and_then(|n| n.parse().ok()).map(ShardMode::GamesPerShard),
+            "--gzip" => compress = true,
+            _ => positional.push(arg.as_str()),
+        }
+    }
+    let (Some(mode), [input, dir]) = (mode, positional.as_slice()) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::FAILURE;
+    };
+
+    let stem = Path::new(input).file_stem().and_then(|s| s.to_str()).unwrap_or("games");
+    let result = File::open(input).map_err(Into::into)
+        .and_then(|file| split_pgn(file, Path::new(dir), stem, mode, compress));
+    match result {
+        Ok(paths) => {
+            eprintln!("wrote {} shards", paths.len());
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("pgn_split: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,52 @@
+//! HTTP front end for the JSON commands in `move_generation::service`.
+//!
+//! Each command is a POST route taking a JSON body, e.g.
+//! `curl -d '{"fen": "..."}' localhost:8080/legal_moves`.
+
+use move_generation::service::handle_request;
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+fn respond(mut request: Request) -> std::io::Result<()> {
+    let cmd = request.url().trim_start_matches('/').split('?').next().unwrap_or("").to_string();
+    let (status, body) = if *request.method() != Method::Post {
+        (405, json!({ "error": "use POST with a JSON body" }))
+    } else {
+        let mut text = String::new();
+        request.as_reader().read_to_string(&mut text)?;
+        match serde_json::from_str::<Value>(&text) {
+            Ok(Value::Object(mut params)) => {
+                params.insert("cmd".to_string(), Value::String(cmd));
+                match handle_request(&Value::Object(params)) {
+                    Ok(result) => (200, result),
+                    Err(e) => (400, json!({ "error": e.to_string() })),
+                }
+            }
+            _ => (400, json!({ "error": "body must be a JSON object" })),
+        }
+    };
+
+    let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+    request.respond(Response::from_string(body.to_string()).with_status_code(status).with_header(content_type))
+}
+
+fn main() {
+    let address = std::env::args().nth(1).unwrap_or_else(|| "127.0.0.1:8080".to_string());
+    let server = match Server::http(&address) {
+        Ok(server) => server,
+        Err(e) => {
+            eprintln!("serve: cannot listen on {}: {}", address, e);
+            std::process::exit(1);
+        }
+    };
+    eprintln!("listening on http://{}", address);
+
+    // Requests are handled on the rayon pool so a deep perft does not block others
+    for request in server.incoming_requests() {
+        rayon::spawn(move || {
+            if let Err(e) = respond(request) {
+                eprintln!("serve: {}", e);
+            }
+        });
+    }
+}
@@ -0,0 +1,10 @@
+//! Line-delimited JSON over stdin/stdout, for tools that cannot load the
+//! Python extension: `{"cmd": "legal_moves", "fen": "..."}` in, one JSON
+//! reply per line out.
+
+use move_generation::service::serve_lines;
+use std::io;
+
+fn main() -> io::Result<()> {
+    serve_lines(io::stdin().lock(), io::stdout().lock())
+}
@@ -0,0 +1,54 @@
+use move_generation::pgn::PgnGameReader;
+use move_generation::tune::{samples_from_games, EvalParams, TuneConfig, Tuner};
+use std::fs::File;
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: texel_tune <games.pgn> [--passes N] [--step S] [--fix-material] [--output params.rs]";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut config = TuneConfig::default();
+    let (mut passes, mut step, mut output) = (Some(config.max_passes), Some(config.step), None);
+    let mut positional = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--passes" => passes = iter.next().and_then(|n| n.parse().ok()),
+            "--step" => step = iter.next().and_then(|n| n.parse().ok()),
+            "--fix-material" => config.fix_material = true,
+            "--output" => output = iter.next(),
+            _ => positional.push(arg.as_str()),
+        }
+    }
+    let (Some(passes), Some(step), [input]) = (passes, step, positional.as_slice()) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::FAILURE;
+    };
+    config.max_passes = passes;
+    config.step = step;
+
+    let samples = match File::open(input).map_err(Into::into).and_then(|file| samples_from_games(PgnGameReader::new(file).lenient())) {
+        Ok(samples) => samples,
+        Err(e) => {
+            eprintln!("texel_tune: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut tuner = Tuner::new(samples);
+    let params = EvalParams::default();
+    let k = tuner.fit_k(&params);
+    eprintln!("{} positions, k = {:.4}, error {:.6}", tuner.samples().len(), k, tuner.error(&params));
+
+    let tuned = tuner.tune(&params, &config, |pass, error| eprintln!("pass {}: error {:.6}", pass, error));
+    let source = tuned.to_rust_source();
+    match output {
+        Some(path) => {
+            if let Err(e) = std::fs::write(path, source) {
+                eprintln!("texel_tune: {}", e);
+                return ExitCode::FAILURE;
+            }
+        }
+        None => print!("{}", source),
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,9 @@
+//! UCI chess engine on stdin/stdout, for GUIs such as Arena, Cute Chess or
+//! the lichess-bot bridge
+
+use move_generation::uci;
+use std::io;
+
+fn main() -> io::Result<()> {
+    uci::run(io::stdin().lock(), io::stdout())
+}
@@ -0,0 +1,65 @@
+//! Persistent position cache keyed by Zobrist hash (`cache` feature)
+
+use crate::pgn::ChessError;
+use crate::types::Board;
+use crate::zobrist;
+use std::io;
+use std::path::Path;
+
+/// On-disk key-value store mapping position hashes to arbitrary bytes, such
+/// as evaluations or annotations, that survives between runs.
+///
+/// Handles are cheap to clone and may be shared between threads; a database
+/// directory can only be opened by one process at a time.
+#[derive(Clone)]
+pub struct PositionCache {
+    db: sled::Db,
+}
+
+fn io_error(err: sled::Error) -> ChessError {
+    ChessError::IoError(io::Error::from(err))
+}
+
+impl PositionCache {
+    /// Open the cache in directory `path`, creating it if needed
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, ChessError> {
+        Ok(PositionCache { db: sled::open(path).map_err(io_error)? })
+    }
+
+    pub fn get(&self, key: u64) -> Result<Option<Vec<u8>>, ChessError> {
+        Ok(self.db.get(key.to_be_bytes()).map_err(io_error)?.map(|value| value.to_vec()))
+    }
+
+    pub fn put(&self, key: u64, value: &[u8]) -> Result<(), ChessError> {
+        self.db.insert(key.to_be_bytes(), value).map_err(io_error)?;
+        Ok(())
+    }
+
+    pub fn remove(&self, key: u64) -> Result<(), ChessError> {
+        self.db.remove(key.to_be_bytes()).map_err(io_error)?;
+        Ok(())
+    }
+
+    /// Value stored for a board under its Zobrist hash
+    pub fn get_board(&self, board: &Board) -> Result<Option<Vec<u8>>, ChessError> {
+        self.get(zobrist::hash(board))
+    }
+
+    pub fn put_board(&self, board: &Board, value: &[u8]) -> Result<(), ChessError> {
+        self.put(zobrist::hash(board), value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.db.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.db.is_empty()
+    }
+
+    /// Write pending changes to disk; also happens periodically and on drop
+    pub fn flush(&self) -> Result<(), ChessError> {
+        self.db.flush().map_err(io_error)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,76 @@
+//! Chess960 (Fischer Random) starting positions
+
+use crate::types::{Board, CastlingRights, Color, Piece};
+use rand::Rng;
+
+/// Number of Chess960 starting positions
+pub const CHESS960_POSITIONS: u16 = 960;
+
+/// Squares (among the five left after bishops and queen) taken by the
+/// knights, indexed by the knight digit of the position number
+const KNIGHT_PLACEMENTS: [(usize, usize); 10] = [
+    (0, 1), (0, 2), (0, 3), (0, 4), (1, 2), (1, 3), (1, 4), (2, 3), (2, 4), (3, 4),
+];
+
+/// Back rank of Chess960 position `n` using the standard (Scharnagl) numbering
+fn back_rank(n: u16) -> [Piece; 8] {
+    let mut rank: [Option<Piece>; 8] = [None; 8];
+    let mut n = n as usize;
+
+    // Light-squared bishop on b, d, f or h; dark-squared one on a, c, e or g
+    rank[2 * (n % 4) + 1] = Some(Piece::Bishop);
+    n /= 4;
+    rank[2 * (n % 4)] = Some(Piece::Bishop);
+    n /= 4;
+
+    let empty = |rank: &[Option<Piece>; 8]| -> Vec<usize> {
+        (0..8).filter(|&file| rank[file].is_none()).collect()
+    };
+
+    let queen_file = empty(&rank)[n % 6];
+    rank[queen_file] = Some(Piece::Queen);
+    n /= 6;
+
+    let free = empty(&rank);
+    let (first, second) = KNIGHT_PLACEMENTS[n];
+    rank[free[first]] = Some(Piece::Knight);
+    rank[free[second]] = Some(Piece::Knight);
+
+    // Rook, king, rook on the remaining three squares
+    for (file, piece) in empty(&rank).into_iter().zip([Piece::Rook, Piece::King, Piece::Rook]) {
+        rank[file] = Some(piece);
+    }
+
+    rank.map(|piece| piece.expect("every file is filled"))
+}
+
+impl Board {
+    /// Chess960 starting position number `n` (0..959), with 518 being the
+    /// standard arrangement.
+    ///
+    /// Castling rights are only set for position 518 until Chess960
+    /// castling is supported.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is 960 or more.
+    pub fn chess960_start(n: u16) -> Board {
+        assert!(n < CHESS960_POSITIONS, "Chess960 position {} out of range", n);
+        let mut squares = [None; 64];
+        for (file, piece) in back_rank(n).into_iter().enumerate() {
+            squares[file] = Some((piece, Color::White));
+            squares[8 + file] = Some((Piece::Pawn, Color::White));
+            squares[48 + file] = Some((Piece::Pawn, Color::Black));
+            squares[56 + file] = Some((piece, Color::Black));
+        }
+        let mut board = Board::from_squares(squares);
+        board.castling_rights = if n == 518 { CastlingRights::standard() } else { CastlingRights::NONE };
+        board.refresh();
+        board
+    }
+
+    /// A uniformly random Chess960 starting position
+    pub fn chess960_random<R: Rng + ?Sized>(rng: &mut R) -> Board {
+        Board::chess960_start(rng.gen_range(0..CHESS960_POSITIONS))
+    }
+}
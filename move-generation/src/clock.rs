@@ -0,0 +1,246 @@
+//! Time controls and clock simulation
+
+use crate::pgn::ChessError;
+use crate::types::Color;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Time control of a game, as found in the PGN `TimeControl` tag
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TimeControl {
+    /// A single period for the whole game, e.g. "300"
+    SuddenDeath { base: Duration },
+    /// A base time plus an increment added after every move, e.g. "180+2"
+    Increment { base: Duration, increment: Duration },
+    /// `base` for every `moves` moves, topped up when the period is completed, e.g. "40/7200"
+    MovesIn { moves: u32, base: Duration, increment: Duration },
+}
+
+impl TimeControl {
+    /// Time on each clock at the start of the game
+    pub fn initial_time(&self) -> Duration {
+        match *self {
+            TimeControl::SuddenDeath { base } => base,
+            TimeControl::Increment { base, .. } => base,
+            TimeControl::MovesIn { base, .. } => base,
+        }
+    }
+
+    /// Speed class by the estimated game duration, the initial time plus
+    /// 40 increments: under 30 s ultrabullet, 3 min bullet, 8 min blitz and
+    /// 25 min rapid, classical beyond
+    pub fn speed(&self) -> Speed {
+        let increment = match *self {
+            TimeControl::SuddenDeath { .. } => Duration::ZERO,
+            TimeControl::Increment { increment, .. } | TimeControl::MovesIn { increment, .. } => increment,
+        };
+        let estimate = increment.checked_mul(40)
+            .and_then(|increments| self.initial_time().checked_add(increments))
+            .unwrap_or(Duration::MAX);
+        match estimate.as_secs() {
+            0..=29 => Speed::UltraBullet,
+            30..=179 => Speed::Bullet,
+            180..=479 => Speed::Blitz,
+            480..=1499 => Speed::Rapid,
+            _ => Speed::Classical,
+        }
+    }
+
+    /// Time credited to a player after completing their `move_number`-th move (1-based)
+    pub fn bonus_after_move(&self, move_number: u32) -> Duration {
+        match *self {
+            TimeControl::SuddenDeath { .. } => Duration::ZERO,
+            TimeControl::Increment { increment, .. } => increment,
+            TimeControl::MovesIn { moves, base, increment } => {
+                if move_number.is_multiple_of(moves) {
+                    base.saturating_add(increment)
+                } else {
+                    increment
+                }
+            }
+        }
+    }
+}
+
+/// Speed class of a game, as Lichess groups time controls
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Speed {
+    UltraBullet,
+    Bullet,
+    Blitz,
+    Rapid,
+    Classical,
+    /// Days per move, written "-" in the `TimeControl` tag
+    Correspondence,
+}
+
+impl Speed {
+    /// Speed of a `TimeControl` tag value, None when it does not parse
+    pub fn from_tag(tag: &str) -> Option<Speed> {
+        if tag.trim() == "-" {
+            return Some(Speed::Correspondence);
+        }
+        tag.parse::<TimeControl>().ok().map(|tc| tc.speed())
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Speed::UltraBullet => "ultrabullet",
+            Speed::Bullet => "bullet",
+            Speed::Blitz => "blitz",
+            Speed::Rapid => "rapid",
+            Speed::Classical => "classical",
+            Speed::Correspondence => "correspondence",
+        }
+    }
+}
+
+impl FromStr for Speed {
+    type Err = ChessError;
+
+    /// Parse a name as written by `as_str`, in any case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [Speed::UltraBullet, Speed::Bullet, Speed::Blitz, Speed::Rapid, Speed::Classical, Speed::Correspondence]
+            .into_iter()
+            .find(|speed| speed.as_str().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| ChessError::ParseError(format!("Invalid speed: {}", s)))
+    }
+}
+
+impl FromStr for TimeControl {
+    type Err = ChessError;
+
+    /// Parse the PGN `TimeControl` tag forms "S", "S+I", "M/S" and "M/S+I" (seconds)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ChessError::ParseError(format!("Invalid time control: {}", s));
+        let seconds = |v: &str| -> Result<Duration, ChessError> {
+            let secs: f64 = v.trim().parse().map_err(|_| invalid())?;
+            Duration::try_from_secs_f64(secs).map_err(|_| invalid())
+        };
+
+        let (moves, rest) = match s.trim().split_once('/') {
+            Some((m, rest)) => (Some(m.trim().parse::<u32>().map_err(|_| invalid())?), rest),
+            None => (None, s.trim()),
+        };
+        let (base, increment) = match rest.split_once('+') {
+            Some((b, i)) => (seconds(b)?, Some(seconds(i)?)),
+            None => (seconds(rest)?, None),
+        };
+
+        Ok(match (moves, increment) {
+            (Some(0), _) => return Err(invalid()),
+            (Some(moves), inc) => TimeControl::MovesIn {
+                moves,
+                base,
+                increment: inc.unwrap_or(Duration::ZERO),
+            },
+            (None, Some(increment)) => TimeControl::Increment { base, increment },
+            (None, None) => TimeControl::SuddenDeath { base },
+        })
+    }
+}
+
+/// Parse the time from a PGN `[%clk h:mm:ss]` comment command, if present
+pub fn parse_clk(comment: &str) -> Option<Duration> {
+    let start = comment.find("[%clk")? + "[%clk".len();
+    let end = start + comment[start..].find(']')?;
+    let value = comment[start..end].trim();
+
+    let mut secs = 0.0;
+    for part in value.split(':') {
+        let v: f64 = part.parse().ok()?;
+        if v < 0.0 {
+            return None;
+        }
+        secs = secs * 60.0 + v;
+    }
+    Duration::try_from_secs_f64(secs).ok()
+}
+
+/// Simulated pair of chess clocks following a time control
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Clock {
+    time_control: TimeControl,
+    remaining: [Duration; 2],
+    moves_made: [u32; 2],
+    side_to_move: Color,
+    flagged: Option<Color>,
+}
+
+impl Clock {
+    /// Create a clock with both sides at the initial time, White to move
+    pub fn new(time_control: TimeControl) -> Self {
+        let initial = time_control.initial_time();
+        Clock {
+            time_control,
+            remaining: [initial, initial],
+            moves_made: [0, 0],
+            side_to_move: Color::White,
+            flagged: None,
+        }
+    }
+
+    /// Create a clock for a game that starts with `color` to move
+    pub fn starting_with(time_control: TimeControl, color: Color) -> Self {
+        Clock { side_to_move: color, ..Clock::new(time_control) }
+    }
+
+    /// Time control the clock was created with
+    pub fn time_control(&self) -> TimeControl {
+        self.time_control
+    }
+
+    /// Time left on the clock of the given side
+    pub fn remaining(&self, color: Color) -> Duration {
+        self.remaining[color.index()]
+    }
+
+    /// Number of moves completed by the given side
+    pub fn moves_made(&self, color: Color) -> u32 {
+        self.moves_made[color.index()]
+    }
+
+    /// Side whose clock is running
+    pub fn side_to_move(&self) -> Color {
+        self.side_to_move
+    }
+
+    /// Side that ran out of time, if any
+    pub fn flagged(&self) -> Option<Color> {
+        self.flagged
+    }
+
+    /// Charge `elapsed` to the side to move, credit the increment and pass the move.
+    /// Returns the mover's remaining time; a side that overstepped is flagged at zero.
+    pub fn advance(&mut self, elapsed: Duration) -> Duration {
+        let idx = self.side_to_move.index();
+
+        if elapsed > self.remaining[idx] {
+            self.remaining[idx] = Duration::ZERO;
+            if self.flagged.is_none() {
+                self.flagged = Some(self.side_to_move);
+            }
+        } else {
+            self.moves_made[idx] += 1;
+            self.remaining[idx] = (self.remaining[idx] - elapsed)
+                .saturating_add(self.time_control.bonus_after_move(self.moves_made[idx]));
+        }
+
+        let remaining = self.remaining[idx];
+        self.side_to_move = self.side_to_move.opposite();
+        remaining
+    }
+
+    /// Pass the move given the mover's clock reading after it (e.g. from a `%clk` comment).
+    /// Returns the time spent on the move, which may be zero for inconsistent readings.
+    pub fn advance_to(&mut self, remaining: Duration) -> Duration {
+        let idx = self.side_to_move.index();
+        let bonus = self.time_control.bonus_after_move(self.moves_made[idx] + 1);
+        let elapsed = self.remaining[idx].saturating_add(bonus).saturating_sub(remaining);
+
+        self.moves_made[idx] += 1;
+        self.remaining[idx] = remaining;
+        self.side_to_move = self.side_to_move.opposite();
+        elapsed
+    }
+}
@@ -0,0 +1,81 @@
+//! Counters of generator and perft work, for spotting performance
+//! regressions before they show up in wall-clock time. They are only
+//! compiled in with the `counters` feature; without it every count is a
+//! no-op and snapshots read zero.
+
+#[cfg(feature = "counters")]
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Counter {
+    MovesGenerated,
+    AttackQueries,
+    TtProbes,
+    TtHits,
+    Nodes,
+}
+
+#[cfg(feature = "counters")]
+static COUNTERS: [AtomicU64; 5] = [const { AtomicU64::new(0) }; 5];
+
+/// Add `n` to a counter
+#[inline(always)]
+pub(crate) fn count(counter: Counter, n: u64) {
+    #[cfg(feature = "counters")]
+    COUNTERS[counter as usize].fetch_add(n, Ordering::Relaxed);
+    #[cfg(not(feature = "counters"))]
+    let _ = (counter, n);
+}
+
+/// Totals since start-up or the last `reset`, over all threads
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Pseudo-legal moves produced by the generators, drops included
+    pub moves_generated: u64,
+    /// Calls to `is_square_attacked`
+    pub attack_queries: u64,
+    /// Perft transposition table lookups, and those that found an entry
+    pub tt_probes: u64,
+    pub tt_hits: u64,
+    /// Positions perft made moves into; leaves counted in bulk at the last
+    /// ply are not visited
+    pub nodes: u64,
+}
+
+impl Stats {
+    /// Current totals
+    pub fn snapshot() -> Stats {
+        #[cfg(feature = "counters")]
+        {
+            let read = |counter: Counter| COUNTERS[counter as usize].load(Ordering::Relaxed);
+            Stats {
+                moves_generated: read(Counter::MovesGenerated),
+                attack_queries: read(Counter::AttackQueries),
+                tt_probes: read(Counter::TtProbes),
+                tt_hits: read(Counter::TtHits),
+                nodes: read(Counter::Nodes),
+            }
+        }
+        #[cfg(not(feature = "counters"))]
+        Stats::default()
+    }
+
+    /// Set every counter back to zero
+    pub fn reset() {
+        #[cfg(feature = "counters")]
+        for counter in &COUNTERS {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// Work done between two snapshots
+    pub fn since(&self, earlier: &Stats) -> Stats {
+        Stats {
+            moves_generated: self.moves_generated - earlier.moves_generated,
+            attack_queries: self.attack_queries - earlier.attack_queries,
+            tt_probes: self.tt_probes - earlier.tt_probes,
+            tt_hits: self.tt_hits - earlier.tt_hits,
+            nodes: self.nodes - earlier.nodes,
+        }
+    }
+}
@@ -0,0 +1,86 @@
+//! Cuckoo tables for upcoming-repetition detection.
+//!
+//! Every reversible move of a non-pawn piece between two squares has a key:
+//! the XOR of the piece's Zobrist keys on both squares and the side key. When
+//! the XOR of the current position's hash with an earlier one equals such a
+//! key, a single move may return to the earlier position.
+
+use crate::types::{Color, Piece};
+use crate::zobrist::{piece_key, side_key};
+use std::sync::OnceLock;
+
+const TABLE_SIZE: usize = 8192;
+
+struct CuckooTable {
+    keys: [u64; TABLE_SIZE],
+    moves: [(u8, u8); TABLE_SIZE],
+}
+
+fn h1(key: u64) -> usize {
+    (key & 0x1FFF) as usize
+}
+
+fn h2(key: u64) -> usize {
+    ((key >> 16) & 0x1FFF) as usize
+}
+
+/// Whether `piece` standing on `from` attacks `to` on an empty board
+fn attacks_empty(piece: Piece, from: u8, to: u8) -> bool {
+    let (dr, df) = ((to / 8) as i8 - (from / 8) as i8, (to % 8) as i8 - (from % 8) as i8);
+    match piece {
+        Piece::Knight => (dr.abs(), df.abs()) == (1, 2) || (dr.abs(), df.abs()) == (2, 1),
+        Piece::Bishop => dr.abs() == df.abs() && dr != 0,
+        Piece::Rook => (dr == 0) != (df == 0),
+        Piece::Queen => attacks_empty(Piece::Bishop, from, to) || attacks_empty(Piece::Rook, from, to),
+        Piece::King => dr.abs() <= 1 && df.abs() <= 1 && (dr, df) != (0, 0),
+        Piece::Pawn => false,
+    }
+}
+
+fn table() -> &'static CuckooTable {
+    static TABLE: OnceLock<CuckooTable> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut table = CuckooTable { keys: [0; TABLE_SIZE], moves: [(0, 0); TABLE_SIZE] };
+        for color in [Color::White, Color::Black] {
+            for piece in [Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen, Piece::King] {
+                for s1 in 0..64u8 {
+                    for s2 in s1 + 1..64 {
+                        if !attacks_empty(piece, s1, s2) {
+                            continue;
+                        }
+                        let mut key = piece_key(piece, color, s1) ^ piece_key(piece, color, s2) ^ side_key();
+                        let mut mv = (s1, s2);
+                        // Insert, kicking out residents to their other slot until one lands empty
+                        let mut slot = h1(key);
+                        loop {
+                            std::mem::swap(&mut table.keys[slot], &mut key);
+                            std::mem::swap(&mut table.moves[slot], &mut mv);
+                            if key == 0 {
+                                break;
+                            }
+                            slot = if slot == h1(key) { h2(key) } else { h1(key) };
+                        }
+                    }
+                }
+            }
+        }
+        table
+    })
+}
+
+/// The squares of the reversible move whose key is `move_key`, lower square first
+pub fn lookup(move_key: u64) -> Option<(u8, u8)> {
+    let table = table();
+    [h1(move_key), h2(move_key)].into_iter()
+        .find(|&slot| table.keys[slot] == move_key)
+        .map(|slot| table.moves[slot])
+}
+
+/// Squares strictly between two squares on a line, empty when not aligned
+pub fn between(s1: u8, s2: u8) -> impl Iterator<Item = u8> {
+    let (dr, df) = ((s2 / 8) as i8 - (s1 / 8) as i8, (s2 % 8) as i8 - (s1 % 8) as i8);
+    let aligned = dr == 0 || df == 0 || dr.abs() == df.abs();
+    let steps = if aligned { dr.abs().max(df.abs()) } else { 0 };
+    let step = dr.signum() * 8 + df.signum();
+    (1..steps).map(move |i| (s1 as i8 + step * i) as u8)
+}
@@ -0,0 +1,36 @@
+use crate::types::{Board, Color, Move};
+
+/// Number of 8x8 planes produced by `encode_board`
+pub const PLANES: usize = 18;
+
+/// Encode a board as `PLANES` 8x8 planes of 0.0/1.0, square a1 first.
+///
+/// Planes 0-5 hold the White pawns to kings, 6-11 the Black ones, 12 is all
+/// ones when White is to move, 13-16 are the K, Q, k, q castling rights and
+/// 17 marks the en passant square.
+pub fn encode_board(board: &Board) -> Vec<f32> {
+    let mut planes = vec![0.0; PLANES * 64];
+    for (sq, square) in board.squares().iter().enumerate() {
+        if let Some((piece, color)) = square {
+            planes[(color.index() * 6 + piece.index()) * 64 + sq] = 1.0;
+        }
+    }
+    if board.side_to_move == Color::White {
+        planes[12 * 64..13 * 64].fill(1.0);
+    }
+    for (i, (color, kingside)) in [(Color::White, true), (Color::White, false), (Color::Black, true), (Color::Black, false)].into_iter().enumerate() {
+        if board.castling_rights.has(color, kingside) {
+            planes[(13 + i) * 64..(14 + i) * 64].fill(1.0);
+        }
+    }
+    if let Some(ep) = board.en_passant {
+        planes[17 * 64 + ep as usize] = 1.0;
+    }
+    planes
+}
+
+/// Index of a move in a 64x64 from-to policy; promotions share the index of
+/// the underlying pawn move
+pub fn encode_move(mv: &Move) -> u16 {
+    mv.from as u16 * 64 + mv.to as u16
+}
@@ -0,0 +1,201 @@
+//! EPD test suites such as WAC, STS or Arasan: reading the positions with
+//! their `bm` (best move) and `am` (avoid move) operations, and running an
+//! engine over them
+
+use crate::match_runner::{MoveRequest, Player};
+use crate::notation::{parse_san, parse_uci, to_san};
+use crate::pgn::{ChessError, Position};
+use crate::score::Score;
+use crate::search::SearchLimits;
+use crate::types::{Board, Move};
+use std::fmt;
+use std::io::BufRead;
+use std::time::{Duration, Instant};
+
+/// A position of a suite with its operations
+#[derive(Clone, Debug, PartialEq)]
+pub struct EpdEntry {
+    pub board: Board,
+    /// Operations in order, as opcode and operand with quotes removed
+    pub operations: Vec<(String, String)>,
+    /// Moves of `bm`, any of which solves the position
+    pub best_moves: Vec<Move>,
+    /// Moves of `am`, none of which may be played
+    pub avoid_moves: Vec<Move>,
+}
+
+impl EpdEntry {
+    /// Operand of the first operation `opcode`
+    pub fn operation(&self, opcode: &str) -> Option<&str> {
+        self.operations.iter().find(|(op, _)| op == opcode).map(|(_, operand)| operand.as_str())
+    }
+
+    /// The `id` operation, e.g. "WAC.001"
+    pub fn id(&self) -> Option<&str> {
+        self.operation("id")
+    }
+
+    /// Whether `mv` is one of the best moves, if any, and none of the moves
+    /// to avoid
+    pub fn accepts(&self, mv: &Move) -> bool {
+        (self.best_moves.is_empty() || self.best_moves.contains(mv)) && !self.avoid_moves.contains(mv)
+    }
+}
+
+/// Parse an EPD line: four FEN fields followed by `opcode operand;`
+/// operations. `bm` and `am` moves are read as SAN, or as UCI when they are
+/// not SAN.
+pub fn parse_epd(line: &str) -> Result<EpdEntry, ChessError> {
+    let invalid = || ChessError::ParseError(format!("Invalid EPD: {}", line));
+    let mut fields = line.trim().splitn(5, char::is_whitespace);
+    let fen: Vec<&str> = fields.by_ref().take(4).collect();
+    if fen.len() < 4 {
+        return Err(invalid());
+    }
+    let board = Board::from(fen.join(" ").parse::<Position>()?);
+
+    let mut operations = Vec::new();
+    let mut rest = fields.next().unwrap_or("").trim_start();
+    while !rest.is_empty() {
+        // Semicolons inside quoted operands do not end the operation
+        let mut quoted = false;
+        let end = rest.char_indices()
+            .find(|&(_, c)| {
+                quoted ^= c == '"';
+                c == ';' && !quoted
+            })
+            .map_or(rest.len(), |(i, _)| i);
+        let operation = rest[..end].trim();
+        rest = rest.get(end + 1..).unwrap_or("").trim_start();
+        if operation.is_empty() {
+            continue;
+        }
+        let (opcode, operand) = operation.split_once(char::is_whitespace).unwrap_or((operation, ""));
+        operations.push((opcode.to_string(), operand.trim().trim_matches('"').to_string()));
+    }
+
+    let moves = |opcode: &str| -> Result<Vec<Move>, ChessError> {
+        operations.iter()
+            .filter(|(op, _)| op == opcode)
+            .flat_map(|(_, operand)| operand.split_whitespace())
+            .map(|text| parse_san(&board, text).or_else(|_| parse_uci(&board, text)))
+            .collect()
+    };
+    let best_moves = moves("bm")?;
+    let avoid_moves = moves("am")?;
+    Ok(EpdEntry { board, operations, best_moves, avoid_moves })
+}
+
+/// Read a suite, one position per line; blank lines and lines starting with
+/// `#` are skipped
+pub fn read_epd<R: BufRead>(reader: R) -> Result<Vec<EpdEntry>, ChessError> {
+    let mut entries = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if !line.trim().is_empty() && !line.trim_start().starts_with('#') {
+            entries.push(parse_epd(&line)?);
+        }
+    }
+    Ok(entries)
+}
+
+/// An engine's answer to one position
+#[derive(Clone, Debug, PartialEq)]
+pub struct EpdResult {
+    /// The `id` of the position, or its 1-based number in the suite
+    pub id: String,
+    /// Move played and the expected ones, in SAN
+    pub played: String,
+    pub best_moves: Vec<String>,
+    pub avoid_moves: Vec<String>,
+    pub solved: bool,
+    /// Score for the side to move, when the engine reports one
+    pub score: Option<Score>,
+    pub time: Duration,
+}
+
+impl fmt::Display for EpdResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.id, self.played)?;
+        if !self.best_moves.is_empty() {
+            write!(f, " bm {}", self.best_moves.join(" "))?;
+        }
+        if !self.avoid_moves.is_empty() {
+            write!(f, " am {}", self.avoid_moves.join(" "))?;
+        }
+        if let Some(score) = self.score {
+            write!(f, " ({})", score)?;
+        }
+        write!(f, " {}", if self.solved { "ok" } else { "FAILED" })
+    }
+}
+
+/// Results of a suite run
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SuiteReport {
+    pub results: Vec<EpdResult>,
+}
+
+impl SuiteReport {
+    pub fn solved(&self) -> usize {
+        self.results.iter().filter(|result| result.solved).count()
+    }
+
+    pub fn total(&self) -> usize {
+        self.results.len()
+    }
+
+    /// Positions not solved
+    pub fn failed(&self) -> impl Iterator<Item = &EpdResult> {
+        self.results.iter().filter(|result| !result.solved)
+    }
+}
+
+impl fmt::Display for SuiteReport {
+    /// "Solved 287/300 (95.7%) in 301.2 s", then the ids of the failures
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let time: Duration = self.results.iter().map(|result| result.time).sum();
+        let percent = if self.total() == 0 { 0.0 } else { 100.0 * self.solved() as f64 / self.total() as f64 };
+        write!(f, "Solved {}/{} ({:.1}%) in {:.1} s", self.solved(), self.total(), percent, time.as_secs_f64())?;
+        let failed: Vec<&str> = self.failed().map(|result| result.id.as_str()).collect();
+        if !failed.is_empty() {
+            write!(f, "\nFailed: {}", failed.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Search every position with a `bm` or `am` operation within `limits` and
+/// score the moves, calling `on_result` after each position. The player's
+/// `new_game` is called before every position, so no search state carries
+/// over.
+pub fn run_suite(
+    entries: &[EpdEntry],
+    player: &mut dyn Player,
+    limits: &SearchLimits,
+    mut on_result: impl FnMut(&EpdResult),
+) -> Result<SuiteReport, ChessError> {
+    let mut report = SuiteReport::default();
+    for (index, entry) in entries.iter().enumerate() {
+        if entry.best_moves.is_empty() && entry.avoid_moves.is_empty() {
+            continue;
+        }
+        player.new_game()?;
+        let request = MoveRequest { start: &entry.board, moves: &[], board: &entry.board, limits, clock: None };
+        let started = Instant::now();
+        let choice = player.choose_move(&request)?;
+        let san = |moves: &[Move]| moves.iter().map(|mv| to_san(&entry.board, mv)).collect();
+        let result = EpdResult {
+            id: entry.id().map_or_else(|| (index + 1).to_string(), str::to_string),
+            played: to_san(&entry.board, &choice.mv),
+            best_moves: san(&entry.best_moves),
+            avoid_moves: san(&entry.avoid_moves),
+            solved: entry.accepts(&choice.mv),
+            score: choice.score,
+            time: started.elapsed(),
+        };
+        on_result(&result);
+        report.results.push(result);
+    }
+    Ok(report)
+}
@@ -0,0 +1,129 @@
+//! Pluggable position evaluation, for searches and batch scoring
+
+use crate::move_gen::{attacked_mask, attackers, king_attacks};
+use crate::pgn::{ChessError, Position};
+use crate::score::Score;
+use crate::tune::EvalParams;
+use crate::types::{Board, Color, Piece};
+use rayon::prelude::*;
+
+/// Something that scores positions, such as hand-written tables or a
+/// trained value network
+pub trait Evaluator: Sync {
+    /// Score of `board` from the side to move's point of view
+    fn evaluate(&self, board: &Board) -> Result<Score, ChessError>;
+
+    /// Scores of many positions in order. The default spreads single
+    /// evaluations over the rayon pool; network evaluators override it to
+    /// run the whole batch at once.
+    fn evaluate_batch(&self, boards: &[Board]) -> Result<Vec<Score>, ChessError> {
+        boards.par_iter().map(|board| self.evaluate(board)).collect()
+    }
+}
+
+/// Tapered material and piece-square evaluation, as fitted by `tune::Tuner`
+impl Evaluator for EvalParams {
+    fn evaluate(&self, board: &Board) -> Result<Score, ChessError> {
+        let white = EvalParams::evaluate(self, board);
+        Ok(Score::Cp(if board.side_to_move == Color::White { white } else { -white }))
+    }
+}
+
+/// Parse the FENs and score them with one batch call, failing on the first
+/// FEN that does not parse
+pub fn evaluate_fens<S: AsRef<str>, E: Evaluator + ?Sized>(fens: &[S], evaluator: &E) -> Result<Vec<Score>, ChessError> {
+    let boards = fens.iter()
+        .map(|fen| Ok(Board::from(fen.as_ref().parse::<Position>()?)))
+        .collect::<Result<Vec<Board>, ChessError>>()?;
+    evaluator.evaluate_batch(&boards)
+}
+
+/// Number of values in `KingSafety::features`
+pub const KING_SAFETY_FEATURES: usize = 7;
+
+/// How well one side's king is sheltered and how hard it is attacked; all
+/// zero for a side without a king
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct KingSafety {
+    /// Own pawns on the king's file and the files beside it, one or two
+    /// ranks in front of the king
+    pub shield_pawns: u32,
+    /// Of those files, the ones without such a pawn
+    pub shield_gaps: u32,
+    /// Of those files, the ones without pawns of either color
+    pub open_files: u32,
+    /// Of those files, the ones with enemy pawns but no own pawns
+    pub half_open_files: u32,
+    /// Enemy knights, bishops, rooks and queens attacking the king zone, the
+    /// king's square and the squares around it
+    pub zone_attackers: u32,
+    /// `zone_attackers` weighted 2 for a knight or bishop, 3 for a rook and
+    /// 5 for a queen
+    pub zone_attack_weight: u32,
+    /// Squares of the king zone the enemy attacks with any piece
+    pub attacked_zone_squares: u32,
+}
+
+impl KingSafety {
+    /// The features in field order, for a network or a linear evaluation
+    pub fn features(&self) -> [f32; KING_SAFETY_FEATURES] {
+        [
+            self.shield_pawns, self.shield_gaps, self.open_files, self.half_open_files,
+            self.zone_attackers, self.zone_attack_weight, self.attacked_zone_squares,
+        ].map(|value| value as f32)
+    }
+}
+
+/// King-safety features of the king of `color`
+pub fn king_safety(board: &Board, color: Color) -> KingSafety {
+    let Some(king) = board.king_square(color) else { return KingSafety::default() };
+    let enemy = color.opposite();
+    let (king_file, king_rank) = ((king % 8) as i8, (king / 8) as i8);
+    let forward = if color == Color::White { 1 } else { -1 };
+    let own_pawns = u64::from(board.pieces(Piece::Pawn, color));
+    let enemy_pawns = u64::from(board.pieces(Piece::Pawn, enemy));
+
+    let mut safety = KingSafety::default();
+    for file in (king_file - 1..=king_file + 1).filter(|file| (0..8).contains(file)) {
+        let file_mask = 0x0101_0101_0101_0101u64 << file;
+        let shield = [1, 2].iter()
+            .map(|&step| king_rank + step * forward)
+            .filter(|rank| (0..8).contains(rank))
+            .filter(|&rank| own_pawns & (1u64 << (rank * 8 + file)) != 0)
+            .count() as u32;
+        safety.shield_pawns += shield;
+        safety.shield_gaps += (shield == 0) as u32;
+        match (own_pawns & file_mask != 0, enemy_pawns & file_mask != 0) {
+            (false, false) => safety.open_files += 1,
+            (false, true) => safety.half_open_files += 1,
+            _ => {}
+        }
+    }
+
+    let zone = king_attacks(1u64 << king) | (1u64 << king);
+    let mut zone_attackers = 0u64;
+    let mut squares = zone;
+    while squares != 0 {
+        let sq = squares.trailing_zeros() as u8;
+        squares &= squares - 1;
+        zone_attackers |= u64::from(attackers(board, sq, enemy));
+    }
+    for (piece, weight) in [(Piece::Knight, 2), (Piece::Bishop, 2), (Piece::Rook, 3), (Piece::Queen, 5)] {
+        let count = (zone_attackers & u64::from(board.pieces(piece, enemy))).count_ones();
+        safety.zone_attackers += count;
+        safety.zone_attack_weight += count * weight;
+    }
+    safety.attacked_zone_squares = (zone & u64::from(attacked_mask(board, enemy))).count_ones();
+    safety
+}
+
+/// King-safety features of both sides, White's then Black's, as network
+/// input next to `encoding::encode_board`
+pub fn king_safety_features(board: &Board) -> [f32; 2 * KING_SAFETY_FEATURES] {
+    let mut features = [0.0; 2 * KING_SAFETY_FEATURES];
+    for color in [Color::White, Color::Black] {
+        let start = color.index() * KING_SAFETY_FEATURES;
+        features[start..start + KING_SAFETY_FEATURES].copy_from_slice(&king_safety(board, color).features());
+    }
+    features
+}
@@ -0,0 +1,152 @@
+//! Opening explorer: the moves played from any position in a collection of
+//! games, with their results, filtered by rating and speed at query time
+
+use crate::clock::Speed;
+use crate::game::repetition_key;
+use crate::pgn::{ChessError, PgnGame, PgnGameReader, Position};
+use crate::repertoire::TreeStats;
+use crate::types::{Board, Move};
+use std::collections::HashMap;
+use std::io::Read;
+
+/// Which games an explorer query counts; the default counts all of them
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExplorerFilter {
+    /// Least average rating of the two players; games without ratings are
+    /// left out once a band is set
+    pub min_elo: Option<u32>,
+    pub max_elo: Option<u32>,
+    /// Speeds to count, all when empty; games without a known speed are
+    /// left out otherwise
+    pub speeds: Vec<Speed>,
+}
+
+/// A move played from the queried position
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExplorerMove {
+    pub mv: Move,
+    /// SAN as first seen in the games
+    pub san: String,
+    pub stats: TreeStats,
+    /// Average of the players' mean rating over the counted games with
+    /// ratings
+    pub avg_elo: Option<u32>,
+}
+
+struct GameRecord {
+    result: Option<String>,
+    /// Mean rating of the two players, or the one rating known
+    elo: Option<u32>,
+    speed: Option<Speed>,
+}
+
+impl GameRecord {
+    fn passes(&self, filter: &ExplorerFilter) -> bool {
+        let in_band = match (filter.min_elo, filter.max_elo, self.elo) {
+            (None, None, _) => true,
+            (min, max, Some(elo)) => min.is_none_or(|min| elo >= min) && max.is_none_or(|max| elo <= max),
+            (_, _, None) => false,
+        };
+        in_band && (filter.speeds.is_empty() || self.speed.is_some_and(|speed| filter.speeds.contains(&speed)))
+    }
+}
+
+struct MoveGames {
+    mv: Move,
+    san: String,
+    /// Indexes into `OpeningExplorer::games`, ascending
+    games: Vec<u32>,
+}
+
+/// Index of the opening moves of games by position, so that transpositions
+/// meet and games set up from a FEN count too
+#[derive(Default)]
+pub struct OpeningExplorer {
+    max_ply: usize,
+    games: Vec<GameRecord>,
+    /// Position key, as for repetitions, to the moves played from it
+    positions: HashMap<u64, Vec<MoveGames>>,
+}
+
+impl OpeningExplorer {
+    /// An empty explorer indexing the first `max_ply` moves of each game
+    pub fn new(max_ply: usize) -> Self {
+        OpeningExplorer { max_ply, ..Default::default() }
+    }
+
+    /// Games entered so far
+    pub fn len(&self) -> usize {
+        self.games.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.games.is_empty()
+    }
+
+    /// Enter a game with its result, `WhiteElo`/`BlackElo` and `TimeControl`
+    /// tags
+    pub fn add_game(&mut self, game: &PgnGame) -> Result<(), ChessError> {
+        let positions = game.replay()?;
+        let index = self.games.len() as u32;
+        let elos: Vec<u32> = ["WhiteElo", "BlackElo"].iter().filter_map(|tag| game.header(tag)?.trim().parse().ok()).collect();
+        self.games.push(GameRecord {
+            result: game.result.clone(),
+            elo: (!elos.is_empty()).then(|| (elos.iter().map(|&elo| elo as u64).sum::<u64>() / elos.len() as u64) as u32),
+            speed: game.header("TimeControl").and_then(Speed::from_tag),
+        });
+
+        for ((board, mv), san) in positions.into_iter().zip(&game.moves).take(self.max_ply) {
+            let moves = self.positions.entry(repetition_key(&board)).or_default();
+            let entry = match moves.iter().position(|entry| entry.mv == mv) {
+                Some(i) => &mut moves[i],
+                None => {
+                    let san = san.trim_end_matches(['!', '?']).to_string();
+                    moves.push(MoveGames { mv, san, games: Vec::new() });
+                    moves.last_mut().expect("just pushed")
+                }
+            };
+            // A game repeating the position counts once
+            if entry.games.last() != Some(&index) {
+                entry.games.push(index);
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves played from `board` in the games `filter` lets through, most
+    /// played first
+    pub fn explore(&self, board: &Board, filter: &ExplorerFilter) -> Vec<ExplorerMove> {
+        let Some(moves) = self.positions.get(&repetition_key(board)) else { return Vec::new() };
+        let mut explored: Vec<ExplorerMove> = moves.iter()
+            .filter_map(|entry| {
+                let mut stats = TreeStats::default();
+                let (mut elo_sum, mut rated) = (0u64, 0u64);
+                for game in entry.games.iter().map(|&i| &self.games[i as usize]).filter(|game| game.passes(filter)) {
+                    stats.add(game.result.as_deref());
+                    if let Some(elo) = game.elo {
+                        elo_sum += elo as u64;
+                        rated += 1;
+                    }
+                }
+                let avg_elo = (rated > 0).then(|| (elo_sum / rated) as u32);
+                (stats.games > 0).then(|| ExplorerMove { mv: entry.mv.clone(), san: entry.san.clone(), stats, avg_elo })
+            })
+            .collect();
+        explored.sort_by_key(|mv| std::cmp::Reverse(mv.stats.games));
+        explored
+    }
+
+    /// `explore` for a position given as FEN
+    pub fn explore_fen(&self, fen: &str, filter: &ExplorerFilter) -> Result<Vec<ExplorerMove>, ChessError> {
+        Ok(self.explore(&Board::from(fen.parse::<Position>()?), filter))
+    }
+}
+
+/// Index every game of a PGN source
+pub fn build_explorer<R: Read>(games: PgnGameReader<R>, max_ply: usize) -> Result<OpeningExplorer, ChessError> {
+    let mut explorer = OpeningExplorer::new(max_ply);
+    for game in games {
+        explorer.add_game(&game?)?;
+    }
+    Ok(explorer)
+}
@@ -0,0 +1,221 @@
+//! Game state with move history and draw rules
+
+use crate::notation::to_uci;
+use crate::pgn::ChessError;
+use crate::rules::{generate_legal_moves, has_legal_en_passant, is_checkmate, is_insufficient_material, is_stalemate, is_variant_draw, variant_winner};
+use crate::types::{Board, Color, Move, Undo};
+use crate::cuckoo;
+use crate::zobrist;
+
+/// Why a game is (or may be declared) drawn
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DrawReason {
+    Stalemate,
+    InsufficientMaterial,
+    ThreefoldRepetition,
+    FivefoldRepetition,
+    FiftyMoveRule,
+    SeventyFiveMoveRule,
+    /// Drawn by a variant rule, e.g. both kings reaching the eighth rank in Racing Kings
+    VariantRule,
+}
+
+/// Draw status under the FIDE Laws
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DrawClaim {
+    /// The game is drawn without any claim (FIDE 5.2, 9.6)
+    Automatic(DrawReason),
+    /// The player to move may claim a draw (FIDE 9.2, 9.3)
+    Claimable(DrawReason),
+}
+
+/// How a finished game ended
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Termination {
+    Checkmate,
+    /// Won by a variant rule, e.g. a king reaching the hill
+    VariantWin,
+    Draw(DrawReason),
+}
+
+/// Termination of a finished game and its winner, None for a draw
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Outcome {
+    pub termination: Termination,
+    pub winner: Option<Color>,
+}
+
+impl Outcome {
+    /// PGN result: "1-0", "0-1" or "1/2-1/2"
+    pub fn result(&self) -> &'static str {
+        match self.winner {
+            Some(Color::White) => "1-0",
+            Some(Color::Black) => "0-1",
+            None => "1/2-1/2",
+        }
+    }
+}
+
+/// Outcome decided by the position alone, without a claim: checkmate, a
+/// variant win or draw, stalemate, insufficient material or the 75-move rule
+pub fn board_outcome(board: &Board) -> Option<Outcome> {
+    let draw = |reason| Some(Outcome { termination: Termination::Draw(reason), winner: None });
+    if let Some(winner) = variant_winner(board) {
+        return Some(Outcome { termination: Termination::VariantWin, winner: Some(winner) });
+    }
+    if is_checkmate(board) {
+        return Some(Outcome { termination: Termination::Checkmate, winner: Some(board.side_to_move.opposite()) });
+    }
+    if is_stalemate(board) {
+        return draw(DrawReason::Stalemate);
+    }
+    if is_variant_draw(board) {
+        return draw(DrawReason::VariantRule);
+    }
+    if is_insufficient_material(board) {
+        return draw(DrawReason::InsufficientMaterial);
+    }
+    if board.halfmove_clock >= 150 {
+        return draw(DrawReason::SeventyFiveMoveRule);
+    }
+    None
+}
+
+/// A game: the current board plus the moves and positions that led to it
+#[derive(Clone, Debug)]
+pub struct Game {
+    board: Board,
+    moves: Vec<Move>,
+    undos: Vec<Undo>,
+    /// Repetition key of every position so far, including the current one
+    keys: Vec<u64>,
+}
+
+impl Game {
+    /// Start a game from the given position
+    pub fn new(mut board: Board) -> Self {
+        board.refresh();
+        let key = repetition_key(&board);
+        Game {
+            board,
+            moves: Vec::new(),
+            undos: Vec::new(),
+            keys: vec![key],
+        }
+    }
+
+    /// Current position
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Moves played so far
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    /// Play a legal move
+    pub fn push(&mut self, mv: Move) -> Result<(), ChessError> {
+        let legal = generate_legal_moves(&self.board);
+        if !legal.contains(&mv) {
+            return Err(ChessError::IllegalMove(to_uci(&mv)));
+        }
+        let undo = self.board.make_move(&mv);
+        self.keys.push(repetition_key(&self.board));
+        self.undos.push(undo);
+        self.moves.push(mv);
+        Ok(())
+    }
+
+    /// Take back the last move, returning it
+    pub fn pop(&mut self) -> Option<Move> {
+        let mv = self.moves.pop()?;
+        let undo = self.undos.pop()?;
+        self.board.unmake_move(&mv, undo);
+        self.keys.pop();
+        Some(mv)
+    }
+
+    /// How many times the current position has occurred, including now
+    pub fn repetition_count(&self) -> usize {
+        let current = self.keys[self.keys.len() - 1];
+        self.keys.iter().filter(|&&key| key == current).count()
+    }
+
+    /// Outcome of the game if it is over without a claim; adds fivefold
+    /// repetition to `board_outcome`
+    pub fn outcome(&self) -> Option<Outcome> {
+        board_outcome(&self.board).or_else(|| (self.repetition_count() >= 5).then_some(Outcome {
+            termination: Termination::Draw(DrawReason::FivefoldRepetition),
+            winner: None,
+        }))
+    }
+
+    /// Whether the player to move has a reversible move back to a position
+    /// that already occurred since the last capture or pawn move. Uses the
+    /// cuckoo tables, so no moves are generated.
+    pub fn has_upcoming_repetition(&self) -> bool {
+        let end = (self.board.halfmove_clock as usize).min(self.keys.len() - 1);
+        let current = self.keys[self.keys.len() - 1];
+        (3..=end).step_by(2).any(|plies_back| {
+            let earlier = self.keys[self.keys.len() - 1 - plies_back];
+            let Some((s1, s2)) = cuckoo::lookup(current ^ earlier) else { return false };
+            let squares = self.board.squares();
+            if cuckoo::between(s1, s2).any(|sq| squares[sq as usize].is_some()) {
+                return false;
+            }
+            // The piece making the move must belong to the player to move
+            let piece = squares[s1 as usize].or(squares[s2 as usize]);
+            matches!(piece, Some((_, color)) if color == self.board.side_to_move)
+        })
+    }
+
+    /// Whether the player to move could claim a draw by making `mv`, i.e. the
+    /// move would produce a third repetition or complete fifty moves
+    fn move_enables_claim(&self, mv: &Move) -> Option<DrawReason> {
+        let mut board = self.board.clone();
+        board.make_move(mv);
+        let key = repetition_key(&board);
+        if self.keys.iter().filter(|&&k| k == key).count() >= 2 {
+            Some(DrawReason::ThreefoldRepetition)
+        } else if board.halfmove_clock >= 100 {
+            Some(DrawReason::FiftyMoveRule)
+        } else {
+            None
+        }
+    }
+}
+
+/// Key identifying a position for repetition purposes: the en passant square
+/// only counts when an en passant capture is actually possible
+pub(crate) fn repetition_key(board: &Board) -> u64 {
+    let key = board.hash;
+    match board.en_passant {
+        Some(ep) if !has_legal_en_passant(board) => key ^ zobrist::en_passant_key(ep),
+        _ => key,
+    }
+}
+
+/// Report whether the game is drawn automatically, or can be claimed drawn by
+/// the player to move. Checkmate and variant wins take precedence over every
+/// draw rule.
+pub fn can_claim_draw(game: &Game) -> Option<DrawClaim> {
+    if let Some(outcome) = game.outcome() {
+        return match outcome.termination {
+            Termination::Draw(reason) => Some(DrawClaim::Automatic(reason)),
+            _ => None,
+        };
+    }
+
+    let board = game.board();
+    if game.repetition_count() >= 3 {
+        return Some(DrawClaim::Claimable(DrawReason::ThreefoldRepetition));
+    }
+    if board.halfmove_clock >= 100 {
+        return Some(DrawClaim::Claimable(DrawReason::FiftyMoveRule));
+    }
+    generate_legal_moves(board)
+        .iter()
+        .find_map(|mv| game.move_enables_claim(mv))
+        .map(DrawClaim::Claimable)
+}
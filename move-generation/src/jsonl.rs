@@ -0,0 +1,55 @@
+use crate::notation::to_standard_uci;
+use crate::pgn::{ChessError, PgnGame, PgnGameReader, Position};
+use rayon::prelude::*;
+use serde_json::{json, Map, Value};
+use std::io::{self, Write};
+
+/// Number of games converted in parallel before they are written out
+const CHUNK_SIZE: usize = 256;
+
+/// JSON object for a game: its headers, UCI moves, result and final FEN, plus
+/// per-move evaluations when the movetext has any
+pub fn game_to_json(game: &PgnGame) -> Result<Value, ChessError> {
+    let mut board = game.starting_board()?;
+    let mut moves = Vec::with_capacity(game.moves.len());
+    for (before, mv) in game.replay()? {
+        moves.push(to_standard_uci(&before, &mv));
+        board.make_move(&mv);
+    }
+
+    let headers: Map<String, Value> = game.headers.iter()
+        .map(|(name, value)| (name.clone(), Value::String(value.clone())))
+        .collect();
+    let mut object = json!({
+        "headers": headers,
+        "moves": moves,
+        "result": game.result,
+        "final_fen": Position::from(&board).to_fen(),
+    });
+    if game.evals.iter().any(Option::is_some) {
+        object["evals"] = json!(game.evals);
+    }
+    Ok(object)
+}
+
+/// Convert a PGN stream to JSON Lines, one game per line in input order.
+/// Games are converted in parallel chunks; returns the number written.
+pub fn pgn_to_jsonl<R: io::Read, W: Write>(reader: R, mut writer: W) -> Result<usize, ChessError> {
+    let mut games = PgnGameReader::new(reader);
+    let mut written = 0;
+    loop {
+        let chunk = games.by_ref().take(CHUNK_SIZE).collect::<Result<Vec<_>, _>>()?;
+        if chunk.is_empty() {
+            break;
+        }
+        let lines = chunk.par_iter()
+            .map(|game| game_to_json(game).map(|object| object.to_string()))
+            .collect::<Result<Vec<_>, _>>()?;
+        for line in &lines {
+            writeln!(writer, "{}", line)?;
+        }
+        written += lines.len();
+    }
+    writer.flush()?;
+    Ok(written)
+}
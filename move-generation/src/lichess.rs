@@ -0,0 +1,138 @@
+//! Streaming a player's games from the Lichess export API
+
+use crate::pgn::{ChessError, PgnGameReader};
+use serde_json::Value;
+use std::io::{self, BufRead, BufReader, Read};
+use std::thread;
+use std::time::Duration;
+
+pub const LICHESS_URL: &str = "https://lichess.org";
+
+/// Filters and extras of a game export; all optional
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportOptions {
+    /// Only games played from this time on, in milliseconds since the epoch;
+    /// the way to fetch just what is new since the last run
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+    pub max: Option<u32>,
+    pub rated: Option<bool>,
+    /// Comma-separated speeds or variants, e.g. "blitz,rapid"
+    pub perf_type: Option<String>,
+    /// Include `[%eval]` comments of analysed games
+    pub evals: bool,
+    /// Include `[%clk]` comments
+    pub clocks: bool,
+    /// Personal API token; authenticated requests get a higher rate limit
+    pub token: Option<String>,
+}
+
+/// Client for the game export endpoints.
+///
+/// When Lichess answers 429 Too Many Requests the request is retried after
+/// the Retry-After delay, or a full minute as the API documentation asks.
+pub struct LichessClient {
+    agent: ureq::Agent,
+    base_url: String,
+    max_retries: u32,
+    retry_delay: Duration,
+}
+
+impl Default for LichessClient {
+    fn default() -> Self {
+        LichessClient::new()
+    }
+}
+
+impl LichessClient {
+    pub fn new() -> Self {
+        LichessClient {
+            agent: ureq::AgentBuilder::new().user_agent("move_generation").build(),
+            base_url: LICHESS_URL.to_string(),
+            max_retries: 3,
+            retry_delay: Duration::from_secs(60),
+        }
+    }
+
+    /// Talk to another server, e.g. a mirror or a local test server
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// How often and after how long a rate-limited request is retried when
+    /// the response gives no Retry-After
+    pub fn with_retries(mut self, max_retries: u32, retry_delay: Duration) -> Self {
+        self.max_retries = max_retries;
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    /// Export URL of a user's games
+    pub fn export_url(&self, user: &str, options: &ExportOptions) -> Result<String, ChessError> {
+        if user.is_empty() || !user.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+            return Err(ChessError::ParseError(format!("Invalid Lichess username: {}", user)));
+        }
+        let mut params = Vec::new();
+        if let Some(since) = options.since {
+            params.push(format!("since={}", since));
+        }
+        if let Some(until) = options.until {
+            params.push(format!("until={}", until));
+        }
+        if let Some(max) = options.max {
+            params.push(format!("max={}", max));
+        }
+        if let Some(rated) = options.rated {
+            params.push(format!("rated={}", rated));
+        }
+        if let Some(perf_type) = &options.perf_type {
+            params.push(format!("perfType={}", perf_type));
+        }
+        params.push(format!("evals={}", options.evals));
+        params.push(format!("clocks={}", options.clocks));
+        Ok(format!("{}/api/games/user/{}?{}", self.base_url, user, params.join("&")))
+    }
+
+    /// Stream a user's games as PGN, ready for the rest of the PGN tooling
+    pub fn user_games(&self, user: &str, options: &ExportOptions) -> Result<PgnGameReader<impl Read>, ChessError> {
+        let body = self.get(&self.export_url(user, options)?, "application/x-chess-pgn", options)?;
+        Ok(PgnGameReader::new(body))
+    }
+
+    /// Stream a user's games as the JSON objects of the NDJSON export
+    pub fn user_games_json(
+        &self,
+        user: &str,
+        options: &ExportOptions,
+    ) -> Result<impl Iterator<Item = Result<Value, ChessError>>, ChessError> {
+        let body = self.get(&self.export_url(user, options)?, "application/x-ndjson", options)?;
+        Ok(BufReader::new(body).lines()
+            .filter(|line| !matches!(line, Ok(line) if line.trim().is_empty()))
+            .map(|line| {
+                let line = line?;
+                serde_json::from_str(&line).map_err(|e| ChessError::ParseError(e.to_string()))
+            }))
+    }
+
+    fn get(&self, url: &str, accept: &str, options: &ExportOptions) -> Result<impl Read, ChessError> {
+        let mut attempt = 0;
+        loop {
+            let mut request = self.agent.get(url).set("Accept", accept);
+            if let Some(token) = &options.token {
+                request = request.set("Authorization", &format!("Bearer {}", token));
+            }
+            match request.call() {
+                Ok(response) => return Ok(response.into_reader()),
+                Err(ureq::Error::Status(429, response)) if attempt < self.max_retries => {
+                    let delay = response.header("Retry-After")
+                        .and_then(|seconds| seconds.trim().parse().ok())
+                        .map_or(self.retry_delay, Duration::from_secs);
+                    thread::sleep(delay);
+                    attempt += 1;
+                }
+                Err(e) => return Err(ChessError::IoError(io::Error::other(e.to_string()))),
+            }
+        }
+    }
+}
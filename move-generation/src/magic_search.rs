@@ -0,0 +1,184 @@
+//! Magic bitboard search. The build script includes this file to generate
+//! the magic numbers the library uses, so it must not use the rest of the
+//! crate. With the `magic_finder` feature it is public, for regenerating
+//! tables or finding magics for other pieces.
+
+/// Squares whose occupancy decides a slider's attacks from `sq`: its rays,
+/// without the board edge each ray ends on
+pub fn relevant_mask(sq: u8, bishop: bool) -> u64 {
+    let (rank, file) = ((sq / 8) as i8, (sq % 8) as i8);
+    let mut mask = 0;
+    for (dr, df) in directions(bishop) {
+        let (mut r, mut f) = (rank + dr, file + df);
+        while (0..8).contains(&(r + dr)) && (0..8).contains(&(f + df)) {
+            mask |= 1 << (r * 8 + f);
+            r += dr;
+            f += df;
+        }
+    }
+    mask
+}
+
+/// Squares a rook (or bishop) on `sq` attacks given the occupied squares,
+/// by walking each ray up to and including the first blocker
+pub fn sliding_attacks(sq: u8, occupied: u64, bishop: bool) -> u64 {
+    let (rank, file) = ((sq / 8) as i8, (sq % 8) as i8);
+    let mut attacks = 0;
+    for (dr, df) in directions(bishop) {
+        let (mut r, mut f) = (rank + dr, file + df);
+        while (0..8).contains(&r) && (0..8).contains(&f) {
+            let bit = 1 << (r * 8 + f);
+            attacks |= bit;
+            if occupied & bit != 0 {
+                break;
+            }
+            r += dr;
+            f += df;
+        }
+    }
+    attacks
+}
+
+fn directions(bishop: bool) -> [(i8, i8); 4] {
+    if bishop {
+        [(1, 1), (1, -1), (-1, 1), (-1, -1)]
+    } else {
+        [(1, 0), (-1, 0), (0, 1), (0, -1)]
+    }
+}
+
+/// Every subset of `mask`, starting with the empty set
+pub fn subsets(mask: u64) -> impl Iterator<Item = u64> {
+    let mut subset = 0u64;
+    let mut done = false;
+    std::iter::from_fn(move || {
+        if done {
+            return None;
+        }
+        let current = subset;
+        // Carry-rippler: step to the next subset, wrapping to 0 at the end
+        subset = subset.wrapping_sub(mask) & mask;
+        done = subset == 0;
+        Some(current)
+    })
+}
+
+/// xorshift64* generator, seeded so the search is reproducible
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed.max(1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Random number with few bits set, which makes good magic candidates
+    fn sparse_u64(&mut self) -> u64 {
+        self.next_u64() & self.next_u64() & self.next_u64()
+    }
+}
+
+/// Find a magic multiplier for a rook or bishop on `sq` whose index,
+/// `(occupied & mask) * magic >> (64 - bits)` with `bits` the size of the
+/// relevant mask, never maps two occupancies with different attacks to the
+/// same slot
+pub fn find_magic(sq: u8, bishop: bool, rng: &mut Rng) -> u64 {
+    find_magic_for(relevant_mask(sq, bishop), |occupied| sliding_attacks(sq, occupied, bishop), rng)
+}
+
+/// Find a magic for any attack function of the occupancy of `mask`, such
+/// as the pieces of a variant
+pub fn find_magic_for(mask: u64, attacks_of: impl Fn(u64) -> u64, rng: &mut Rng) -> u64 {
+    let bits = mask.count_ones();
+    let occupancies: Vec<u64> = subsets(mask).collect();
+    let attacks: Vec<u64> = occupancies.iter().map(|&occupied| attacks_of(occupied)).collect();
+    let mut table = vec![0u64; 1 << bits];
+    let mut used = vec![0u32; 1 << bits];
+
+    for attempt in 1u32.. {
+        let magic = rng.sparse_u64();
+        // Cheap filter: the top byte of mask * magic needs enough bits set
+        if (mask.wrapping_mul(magic) & 0xff00_0000_0000_0000).count_ones() < 6 {
+            continue;
+        }
+        let fits = occupancies.iter().zip(&attacks).all(|(&occupied, &attack)| {
+            let index = (occupied.wrapping_mul(magic) >> (64 - bits)) as usize;
+            if used[index] != attempt {
+                used[index] = attempt;
+                table[index] = attack;
+                true
+            } else {
+                table[index] == attack
+            }
+        });
+        if fits {
+            return magic;
+        }
+    }
+    unreachable!("the attempt counter does not run out before a magic is found")
+}
+
+/// Magic of one square, with where its attacks start in a table holding
+/// the rook and then the bishop attacks of every square
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FoundMagic {
+    pub square: u8,
+    pub bishop: bool,
+    pub mask: u64,
+    pub magic: u64,
+    pub shift: u32,
+    pub offset: usize,
+}
+
+/// Search the rook magics of all 64 squares, then the bishop magics, from
+/// a seed; the same seed always gives the same magics
+pub fn find_magics(seed: u64) -> Vec<FoundMagic> {
+    let mut rng = Rng::new(seed);
+    let mut magics = Vec::with_capacity(128);
+    let mut offset = 0;
+    for bishop in [false, true] {
+        for square in 0..64 {
+            let mask = relevant_mask(square, bishop);
+            let magic = find_magic(square, bishop, &mut rng);
+            magics.push(FoundMagic { square, bishop, mask, magic, shift: 64 - mask.count_ones(), offset });
+            offset += 1 << mask.count_ones();
+        }
+    }
+    magics
+}
+
+/// Rust source defining `ROOK_MAGICS`, `BISHOP_MAGICS` and `TABLE_SIZE` as
+/// the library uses them
+pub fn to_rust_source(magics: &[FoundMagic]) -> String {
+    let mut source = String::from("// Generated by build.rs; do not edit\n");
+    for (name, bishop) in [("ROOK_MAGICS", false), ("BISHOP_MAGICS", true)] {
+        source += &format!("pub(crate) const {}: [Magic; 64] = [\n", name);
+        for m in magics.iter().filter(|m| m.bishop == bishop) {
+            source += &format!("    Magic {{ mask: {:#018x}, magic: {:#018x}, shift: {}, offset: {} }},\n",
+                m.mask, m.magic, m.shift, m.offset);
+        }
+        source += "];\n";
+    }
+    let size: usize = magics.iter().map(|m| 1 << (64 - m.shift)).sum();
+    source + &format!("pub(crate) const TABLE_SIZE: usize = {};\n", size)
+}
+
+/// Binary form: for each magic in order, its mask and magic as
+/// little-endian u64, its shift as a byte and its offset as a
+/// little-endian u32
+pub fn to_bytes(magics: &[FoundMagic]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(magics.len() * 21);
+    for m in magics {
+        bytes.extend_from_slice(&m.mask.to_le_bytes());
+        bytes.extend_from_slice(&m.magic.to_le_bytes());
+        bytes.push(m.shift as u8);
+        bytes.extend_from_slice(&(m.offset as u32).to_le_bytes());
+    }
+    bytes
+}
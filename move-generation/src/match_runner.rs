@@ -0,0 +1,586 @@
+//! Engine matches: games between two players, each the built-in search or
+//! an external UCI engine, with a clock, opening positions, adjudication,
+//! an optional SPRT stopping rule and the games as PGN
+
+use crate::clock::{Clock, TimeControl};
+use crate::eval::Evaluator;
+use crate::game::{can_claim_draw, Game};
+use crate::notation::{parse_uci, to_standard_uci, to_uci};
+use crate::pgn::{starting_board, ChessError, Eval, PgnGame, PgnGameReader, Position};
+use crate::score::Score;
+use crate::search::{Engine, SearchHandle, SearchLimits, SearchOptions};
+use crate::stats::{Sprt, SprtReport, SprtStatus, Wdl};
+use crate::types::{Board, Color, Move, Variant};
+use crate::uci::move_time;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Clock readings sent with a move request
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClockState {
+    /// Time left for White and Black, by `Color::index`
+    pub remaining: [Duration; 2],
+    pub increment: Duration,
+    /// Moves until the next time control, for "moves in" controls
+    pub moves_to_go: Option<u32>,
+}
+
+/// What a player is asked to move in
+pub struct MoveRequest<'a> {
+    /// Position the game started from, and the moves played since
+    pub start: &'a Board,
+    pub moves: &'a [Move],
+    /// Position after `moves`
+    pub board: &'a Board,
+    /// Fixed limits from the match configuration
+    pub limits: &'a SearchLimits,
+    /// None in games without a clock
+    pub clock: Option<ClockState>,
+}
+
+/// A player's move, with its score for the player and the line it expects
+/// when it reports them
+#[derive(Clone, Debug, PartialEq)]
+pub struct MoveChoice {
+    pub mv: Move,
+    pub score: Option<Score>,
+    /// Principal variation starting with `mv`, or empty
+    pub pv: Vec<Move>,
+}
+
+/// One side of a match
+pub trait Player {
+    fn name(&self) -> String;
+
+    /// Called before each game
+    fn new_game(&mut self) -> Result<(), ChessError> {
+        Ok(())
+    }
+
+    /// The move to play; an `IllegalMove` error forfeits the game, any other
+    /// error ends the match
+    fn choose_move(&mut self, request: &MoveRequest) -> Result<MoveChoice, ChessError>;
+}
+
+/// The built-in search as a player
+pub struct SearchPlayer<E> {
+    name: String,
+    engine: Engine,
+    evaluator: E,
+    /// Time kept back from every move for the runner's own work
+    pub overhead: Duration,
+}
+
+impl<E: Evaluator> SearchPlayer<E> {
+    pub fn new(name: impl Into<String>, evaluator: E, options: SearchOptions) -> Self {
+        SearchPlayer { name: name.into(), engine: Engine::new(options), evaluator, overhead: Duration::from_millis(10) }
+    }
+}
+
+impl<E: Evaluator> Player for SearchPlayer<E> {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn new_game(&mut self) -> Result<(), ChessError> {
+        self.engine.clear();
+        Ok(())
+    }
+
+    fn choose_move(&mut self, request: &MoveRequest) -> Result<MoveChoice, ChessError> {
+        let mut limits = request.limits.clone();
+        if let Some(clock) = request.clock {
+            let remaining = clock.remaining[request.board.side_to_move.index()];
+            let budget = move_time(remaining, clock.increment, clock.moves_to_go, self.overhead);
+            limits.movetime = Some(limits.movetime.map_or(budget, |movetime| movetime.min(budget)));
+        }
+        let result = self.engine.search(request.board, &limits, &self.evaluator, &SearchHandle::new(), |_| {})?;
+        let mv = result.best_move.ok_or_else(|| ChessError::IllegalMove("no move found".to_string()))?;
+        Ok(MoveChoice { mv, score: Some(result.score), pv: result.pv })
+    }
+}
+
+/// Grace period past its clock before a silent engine is told to stop, and
+/// again before it is given up on
+const UCI_GRACE: Duration = Duration::from_secs(5);
+
+/// Longest wait for a reply that no clock or movetime bounds: the
+/// handshake, `isready`, and searches limited by depth or nodes only
+const UCI_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// An external engine speaking UCI on its standard input and output
+pub struct UciEngine {
+    name: String,
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+    timeout: Duration,
+}
+
+impl UciEngine {
+    /// Start `command` with `args` and complete the `uci` handshake; the
+    /// name is the one the engine announces
+    pub fn spawn(command: &str, args: &[&str]) -> Result<UciEngine, ChessError> {
+        let mut child = Command::new(command)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut engine = UciEngine { name: command.to_string(), child, stdin, lines, timeout: UCI_TIMEOUT };
+        engine.send("uci")?;
+        let deadline = Instant::now() + engine.timeout;
+        loop {
+            let line = engine.read_line(deadline)?.ok_or_else(no_answer)?;
+            if let Some(name) = line.strip_prefix("id name ") {
+                engine.name = name.trim().to_string();
+            } else if line.trim() == "uciok" {
+                break;
+            }
+        }
+        engine.ready()?;
+        Ok(engine)
+    }
+
+    /// Wait at most `timeout` for replies that neither a clock nor a
+    /// movetime bounds, such as `readyok` or the move of a depth-limited
+    /// search; one minute by default
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Send `setoption` and wait until the engine is ready again
+    pub fn set_option(&mut self, name: &str, value: &str) -> Result<(), ChessError> {
+        self.send(&format!("setoption name {} value {}", name, value))?;
+        self.ready()
+    }
+
+    fn send(&mut self, line: &str) -> io::Result<()> {
+        writeln!(self.stdin, "{}", line)?;
+        self.stdin.flush()
+    }
+
+    /// Next line from the engine, None once `deadline` has passed
+    fn read_line(&mut self, deadline: Instant) -> Result<Option<String>, ChessError> {
+        match self.lines.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(line) => Ok(Some(line)),
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => {
+                Err(ChessError::IoError(io::Error::new(io::ErrorKind::UnexpectedEof, "engine exited")))
+            }
+        }
+    }
+
+    fn ready(&mut self) -> Result<(), ChessError> {
+        self.send("isready")?;
+        let deadline = Instant::now() + self.timeout;
+        while self.read_line(deadline)?.ok_or_else(no_answer)?.trim() != "readyok" {}
+        Ok(())
+    }
+}
+
+impl Player for UciEngine {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn new_game(&mut self) -> Result<(), ChessError> {
+        self.send("ucinewgame")?;
+        self.ready()
+    }
+
+    fn choose_move(&mut self, request: &MoveRequest) -> Result<MoveChoice, ChessError> {
+        let mut position = format!("position fen {}", Position::from(request.start).to_fen());
+        if !request.moves.is_empty() {
+            position.push_str(" moves");
+            let mut board = request.start.clone();
+            for mv in request.moves {
+                position.push(' ');
+                position.push_str(&to_standard_uci(&board, mv));
+                board.make_move(mv);
+            }
+        }
+        self.send(&position)?;
+
+        let mut go = String::from("go");
+        let limits = request.limits;
+        if let Some(depth) = limits.depth {
+            go.push_str(&format!(" depth {}", depth));
+        }
+        if let Some(nodes) = limits.nodes {
+            go.push_str(&format!(" nodes {}", nodes));
+        }
+        if let Some(movetime) = limits.movetime {
+            go.push_str(&format!(" movetime {}", movetime.as_millis()));
+        }
+        // One deadline for the whole search, however many info lines come
+        let mut budget = limits.movetime.unwrap_or(self.timeout);
+        if let Some(clock) = request.clock {
+            let [white, black] = clock.remaining.map(|time| time.as_millis());
+            let increment = clock.increment.as_millis();
+            go.push_str(&format!(" wtime {} btime {} winc {} binc {}", white, black, increment, increment));
+            if let Some(moves) = clock.moves_to_go {
+                go.push_str(&format!(" movestogo {}", moves));
+            }
+            budget = clock.remaining[request.board.side_to_move.index()];
+        }
+        self.send(&go)?;
+        let mut deadline = Instant::now() + budget + UCI_GRACE;
+
+        let mut score = None;
+        let mut pv = Vec::new();
+        let mut stopped = false;
+        loop {
+            let Some(line) = self.read_line(deadline)? else {
+                if stopped {
+                    return Err(no_answer());
+                }
+                self.send("stop")?;
+                stopped = true;
+                deadline = Instant::now() + UCI_GRACE;
+                continue;
+            };
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some("info") => {
+                    let words: Vec<&str> = words.collect();
+                    if let Some(i) = words.iter().position(|&word| word == "score") {
+                        if let (Some(kind), Some(value)) = (words.get(i + 1), words.get(i + 2)) {
+                            score = format!("{} {}", kind, value).parse().ok().or(score);
+                        }
+                    }
+                    if let Some(i) = words.iter().position(|&word| word == "pv") {
+                        pv = parse_pv(request.board, &words[i + 1..]);
+                    }
+                }
+                Some("bestmove") => {
+                    let uci = words.next().unwrap_or("(none)");
+                    let mv = parse_uci(request.board, uci)?;
+                    if pv.first() != Some(&mv) {
+                        pv = vec![mv.clone()];
+                    }
+                    return Ok(MoveChoice { mv, score, pv });
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn no_answer() -> ChessError {
+    ChessError::IoError(io::Error::new(io::ErrorKind::TimedOut, "engine does not answer"))
+}
+
+/// Moves of a `pv` from `board`, up to the first that does not parse
+fn parse_pv(board: &Board, moves: &[&str]) -> Vec<Move> {
+    let mut board = board.clone();
+    let mut pv = Vec::new();
+    for uci in moves {
+        let Ok(mv) = parse_uci(&board, uci) else { break };
+        board.make_move(&mv);
+        pv.push(mv);
+    }
+    pv
+}
+
+impl Drop for UciEngine {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        let started = Instant::now();
+        while started.elapsed() < Duration::from_secs(1) {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Where a game of the match starts: a position, the standard start when
+/// None, and UCI moves played from it before the players take over
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Opening {
+    pub fen: Option<String>,
+    pub moves: Vec<String>,
+}
+
+impl Opening {
+    /// An opening from the first four fields of an EPD line (placement,
+    /// side to move, castling and en passant), operations ignored
+    pub fn from_epd(line: &str) -> Result<Opening, ChessError> {
+        let fields: Vec<&str> = line.split_whitespace().take(4).collect();
+        if fields.len() < 4 {
+            return Err(ChessError::ParseError(format!("Invalid EPD: {}", line)));
+        }
+        let fen = format!("{} 0 1", fields.join(" "));
+        fen.parse::<Position>()?;
+        Ok(Opening { fen: Some(fen), moves: Vec::new() })
+    }
+
+    fn board(&self) -> Result<Board, ChessError> {
+        Ok(match &self.fen {
+            Some(fen) => Board::from(fen.parse::<Position>()?),
+            None => starting_board(Variant::Standard),
+        })
+    }
+}
+
+/// Openings from the first `plies` moves of each game of a PGN source
+pub fn openings_from_pgn<R: Read>(games: PgnGameReader<R>, plies: usize) -> Result<Vec<Opening>, ChessError> {
+    let mut openings = Vec::new();
+    for game in games {
+        let game = game?;
+        let moves = game.replay()?.iter().take(plies).map(|(_, mv)| to_uci(mv)).collect();
+        openings.push(Opening { fen: game.header("FEN").map(str::to_string), moves });
+    }
+    Ok(openings)
+}
+
+/// When the runner ends a game early on the players' scores; scores are
+/// taken from both players' reports, from White's point of view
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Adjudication {
+    /// A side loses once every score for `resign_moves` moves of each side
+    /// is at least this far against it
+    pub resign_cp: Option<i32>,
+    pub resign_moves: usize,
+    /// The game is drawn once every score for `draw_moves` moves of each
+    /// side is within this of zero, from ply `draw_min_ply` on
+    pub draw_cp: Option<i32>,
+    pub draw_moves: usize,
+    pub draw_min_ply: usize,
+    /// Game length in plies, opening included, at which it is drawn
+    pub max_plies: Option<usize>,
+}
+
+impl Default for Adjudication {
+    fn default() -> Self {
+        Adjudication { resign_cp: None, resign_moves: 3, draw_cp: None, draw_moves: 8, draw_min_ply: 80, max_plies: None }
+    }
+}
+
+impl Adjudication {
+    /// Winner, or None for a draw, when the scores so far end the game
+    fn decide(&self, scores: &[Option<i32>], ply: usize) -> Option<Option<Color>> {
+        let last = |moves: usize| -> Option<Vec<i32>> {
+            let window = scores.get(scores.len().checked_sub(2 * moves)?..)?;
+            (moves > 0).then(|| window.iter().copied().collect::<Option<Vec<i32>>>())?
+        };
+        if let (Some(cp), Some(window)) = (self.resign_cp, last(self.resign_moves)) {
+            if window.iter().all(|&score| score >= cp) {
+                return Some(Some(Color::White));
+            }
+            if window.iter().all(|&score| score <= -cp) {
+                return Some(Some(Color::Black));
+            }
+        }
+        if let (Some(cp), Some(window)) = (self.draw_cp, last(self.draw_moves)) {
+            if ply >= self.draw_min_ply && window.iter().all(|score| score.abs() <= cp) {
+                return Some(None);
+            }
+        }
+        None
+    }
+}
+
+/// Settings of a match
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MatchConfig {
+    /// Number of games; each opening is played twice, with colors reversed
+    pub games: usize,
+    /// Clock of both players; None plays on `limits` alone
+    pub time_control: Option<TimeControl>,
+    /// Fixed limits for every move, e.g. a depth
+    pub limits: SearchLimits,
+    /// Openings, used in turn; the standard start when empty
+    pub openings: Vec<Opening>,
+    pub adjudication: Adjudication,
+    /// `Event` tag of the games
+    pub event: String,
+    /// Stop as soon as this test accepts a hypothesis
+    pub sprt: Option<Sprt>,
+}
+
+/// A finished game of a match
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatchGame {
+    /// 1 for the first game
+    pub round: usize,
+    /// Whether the first player had White
+    pub first_is_white: bool,
+    /// The game with its tags, the players' scores as `[%eval]` and a
+    /// `Termination` tag
+    pub game: PgnGame,
+}
+
+/// Results so far, for the first player
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MatchProgress {
+    pub wdl: Wdl,
+    /// State of the test, when the match has one
+    pub sprt: Option<SprtReport>,
+}
+
+/// Play `config.games` games between `first` and `second`, or fewer when
+/// the SPRT ends the match, calling `on_game` after each with the results
+/// so far. Returns the final results.
+pub fn run_match(
+    config: &MatchConfig,
+    first: &mut dyn Player,
+    second: &mut dyn Player,
+    mut on_game: impl FnMut(&MatchGame, &MatchProgress),
+) -> Result<MatchProgress, ChessError> {
+    let standard = [Opening::default()];
+    let openings = if config.openings.is_empty() { &standard[..] } else { &config.openings[..] };
+    let mut progress = MatchProgress { wdl: Wdl::default(), sprt: None };
+    for index in 0..config.games {
+        let first_is_white = index % 2 == 0;
+        let opening = &openings[index / 2 % openings.len()];
+        let game = if first_is_white {
+            play_game(config, first, second, opening, index + 1)?
+        } else {
+            play_game(config, second, first, opening, index + 1)?
+        };
+        match (game.result.as_deref(), first_is_white) {
+            (Some("1-0"), true) | (Some("0-1"), false) => progress.wdl.wins += 1,
+            (Some("0-1"), true) | (Some("1-0"), false) => progress.wdl.losses += 1,
+            _ => progress.wdl.draws += 1,
+        }
+        progress.sprt = config.sprt.map(|sprt| sprt.report(&progress.wdl));
+        on_game(&MatchGame { round: index + 1, first_is_white, game }, &progress);
+        if progress.sprt.is_some_and(|report| report.status != SprtStatus::Continue) {
+            break;
+        }
+    }
+    Ok(progress)
+}
+
+/// Play one game from `opening` and return it as PGN
+pub fn play_game(
+    config: &MatchConfig,
+    white: &mut dyn Player,
+    black: &mut dyn Player,
+    opening: &Opening,
+    round: usize,
+) -> Result<PgnGame, ChessError> {
+    white.new_game()?;
+    black.new_game()?;
+    let start = opening.board()?;
+    let mut game = Game::new(start.clone());
+    for uci in &opening.moves {
+        game.push(parse_uci(game.board(), uci)?)?;
+    }
+    let book_plies = game.moves().len();
+    let mut clock = config.time_control.map(|tc| Clock::starting_with(tc, game.board().side_to_move));
+    let mut evals: Vec<Option<Eval>> = vec![None; book_plies];
+    let mut scores: Vec<Option<i32>> = Vec::new();
+
+    let (result, termination) = loop {
+        if let Some(outcome) = game.outcome() {
+            break (outcome.result(), "normal");
+        }
+        if can_claim_draw(&game).is_some() {
+            break ("1/2-1/2", "normal");
+        }
+        if config.adjudication.max_plies.is_some_and(|max| game.moves().len() >= max) {
+            break ("1/2-1/2", "adjudication");
+        }
+        if let Some(winner) = config.adjudication.decide(&scores, game.moves().len()) {
+            break (result_for(winner), "adjudication");
+        }
+
+        let side = game.board().side_to_move;
+        let request = MoveRequest {
+            start: &start,
+            moves: game.moves(),
+            board: game.board(),
+            limits: &config.limits,
+            clock: clock.as_ref().map(clock_state),
+        };
+        let started = Instant::now();
+        let choice = if side == Color::White { white.choose_move(&request) } else { black.choose_move(&request) };
+        let choice = match choice {
+            Ok(choice) => choice,
+            Err(ChessError::IllegalMove(_)) => break (result_for(Some(side.opposite())), "rules infraction"),
+            Err(e) => return Err(e),
+        };
+        if let Some(clock) = clock.as_mut() {
+            clock.advance(started.elapsed());
+            if clock.flagged() == Some(side) {
+                break (result_for(Some(side.opposite())), "time forfeit");
+            }
+        }
+        if game.push(choice.mv).is_err() {
+            break (result_for(Some(side.opposite())), "rules infraction");
+        }
+        let white_score = choice.score.map(|score| if side == Color::White { score } else { -score });
+        evals.push(white_score.map(Eval::from));
+        scores.push(white_score.map(Score::to_cp));
+    };
+
+    let mut headers = vec![
+        ("Event".to_string(), config.event.clone()),
+        ("Round".to_string(), round.to_string()),
+        ("White".to_string(), white.name()),
+        ("Black".to_string(), black.name()),
+        ("Result".to_string(), result.to_string()),
+    ];
+    if let Some(tc) = &config.time_control {
+        headers.push(("TimeControl".to_string(), time_control_tag(tc)));
+    }
+    headers.push(("Termination".to_string(), termination.to_string()));
+    let ucis: Vec<String> = game.moves().iter().map(to_uci).collect();
+    let mut pgn = PgnGame::from_uci(opening.fen.as_deref(), &ucis, headers)?;
+    pgn.evals = evals;
+    Ok(pgn)
+}
+
+fn result_for(winner: Option<Color>) -> &'static str {
+    match winner {
+        Some(Color::White) => "1-0",
+        Some(Color::Black) => "0-1",
+        None => "1/2-1/2",
+    }
+}
+
+fn clock_state(clock: &Clock) -> ClockState {
+    let side = clock.side_to_move();
+    let (increment, moves_to_go) = match clock.time_control() {
+        TimeControl::SuddenDeath { .. } => (Duration::ZERO, None),
+        TimeControl::Increment { increment, .. } => (increment, None),
+        TimeControl::MovesIn { moves, increment, .. } => (increment, Some(moves - clock.moves_made(side) % moves)),
+    };
+    ClockState { remaining: [clock.remaining(Color::White), clock.remaining(Color::Black)], increment, moves_to_go }
+}
+
+/// The PGN `TimeControl` tag of a time control, in seconds
+fn time_control_tag(tc: &TimeControl) -> String {
+    let seconds = |time: Duration| {
+        let secs = time.as_secs_f64();
+        if secs.fract() == 0.0 { format!("{}", secs as u64) } else { format!("{}", secs) }
+    };
+    match *tc {
+        TimeControl::SuddenDeath { base } => seconds(base),
+        TimeControl::Increment { base, increment } => format!("{}+{}", seconds(base), seconds(increment)),
+        TimeControl::MovesIn { moves, base, increment } if increment.is_zero() => format!("{}/{}", moves, seconds(base)),
+        TimeControl::MovesIn { moves, base, increment } => format!("{}/{}+{}", moves, seconds(base), seconds(increment)),
+    }
+}
@@ -0,0 +1,408 @@
+//! Monte Carlo tree search with batched leaf evaluation, for engines driven
+//! by a neural network.
+//!
+//! Instead of evaluating one leaf at a time, `Mcts::collect` walks the tree
+//! up to `batch_size` times, steering later walks away from earlier ones with
+//! a virtual loss, and returns the leaf positions. The caller evaluates them
+//! together, typically in one GPU call, and hands the results to
+//! `Mcts::submit`, which expands the leaves and backs the values up.
+//! `Mcts::run` drives that loop with a closure.
+//!
+//! For self-play, Dirichlet noise can be mixed into the root priors and the
+//! move to play sampled from the visit counts with a temperature. Between
+//! moves of a game, `Mcts::advance` keeps the subtree of the move played.
+
+use crate::accuracy::winning_chances;
+use crate::encoding::encode_move;
+use crate::eval::Evaluator;
+use crate::game::board_outcome;
+use crate::notation::to_uci;
+use crate::pgn::ChessError;
+use crate::rules::generate_legal_moves;
+use crate::score::Score;
+use crate::types::{Board, Move};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Length of a policy: one entry per `encode_move` index
+pub const POLICY_SIZE: usize = 64 * 64;
+
+/// Network output for one leaf
+#[derive(Clone, Debug, PartialEq)]
+pub struct LeafEval {
+    /// Expected result for the side to move, from -1 (loss) to 1 (win)
+    pub value: f32,
+    /// Prior weights by `encode_move` index, `POLICY_SIZE` long and
+    /// normalised over the legal moves; uniform priors when None
+    pub policy: Option<Vec<f32>>,
+}
+
+impl LeafEval {
+    /// Value-only evaluation from a score, through the winning-chances curve
+    pub fn from_score(score: Score) -> LeafEval {
+        let value = match score {
+            Score::Cp(cp) => winning_chances(cp) as f32,
+            Score::Mate(n) if n > 0 => 1.0,
+            Score::Mate(_) => -1.0,
+        };
+        LeafEval { value, policy: None }
+    }
+}
+
+/// Search settings
+#[derive(Clone, Debug, PartialEq)]
+pub struct MctsConfig {
+    /// Most leaves handed out by one `collect`
+    pub batch_size: usize,
+    /// Weight of the prior-driven exploration term in PUCT selection
+    pub c_puct: f32,
+    /// Concentration of the Dirichlet noise added to the root priors
+    pub dirichlet_alpha: f32,
+    /// Share of noise in the root priors; 0 turns the noise off
+    pub dirichlet_epsilon: f32,
+    /// Temperature of `choose_move`: 0 plays the most visited move, 1 samples
+    /// in proportion to visits
+    pub temperature: f32,
+    /// Seed for the noise and move sampling, or None for a random one
+    pub seed: Option<u64>,
+}
+
+impl Default for MctsConfig {
+    /// AlphaZero's chess settings, with noise and sampling off
+    fn default() -> Self {
+        MctsConfig { batch_size: 16, c_puct: 1.5, dirichlet_alpha: 0.3, dirichlet_epsilon: 0.0, temperature: 0.0, seed: None }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Node {
+    mv: Option<Move>,
+    children: Vec<usize>,
+    expanded: bool,
+    prior: f32,
+    visits: u32,
+    /// Sum of backed-up values for the side that played `mv`
+    value_sum: f32,
+    /// Walks in the current batch passing through this node
+    virtual_loss: u32,
+}
+
+impl Node {
+    fn new(mv: Option<Move>, prior: f32) -> Node {
+        Node { mv, children: Vec::new(), expanded: false, prior, visits: 0, value_sum: 0.0, virtual_loss: 0 }
+    }
+}
+
+/// Search tree rooted at a position
+#[derive(Clone, Debug)]
+pub struct Mcts {
+    root: Board,
+    nodes: Vec<Node>,
+    config: MctsConfig,
+    /// Paths (node indices from the root) to the leaves awaiting `submit`
+    pending: Vec<Vec<usize>>,
+    rng: StdRng,
+}
+
+impl Mcts {
+    pub fn new(root: Board, config: MctsConfig) -> Mcts {
+        let rng = match config.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        Mcts { root, nodes: vec![Node::new(None, 1.0)], config, pending: Vec::new(), rng }
+    }
+
+    pub fn root(&self) -> &Board {
+        &self.root
+    }
+
+    /// Completed playouts, i.e. visits of the root
+    pub fn playouts(&self) -> u32 {
+        self.nodes[0].visits
+    }
+
+    /// Select up to `batch_size` leaves and return their positions for
+    /// evaluation. Game-ending leaves are scored on the spot and not
+    /// returned, so the batch may come back short or empty. The results must
+    /// be passed to `submit` before the next call.
+    pub fn collect(&mut self) -> Result<Vec<Board>, ChessError> {
+        if !self.pending.is_empty() {
+            return Err(ChessError::ParseError("the previous batch has not been submitted".to_string()));
+        }
+        let mut boards = Vec::new();
+        for _ in 0..self.config.batch_size.max(1) {
+            let (path, board) = self.select();
+            let leaf = *path.last().unwrap();
+            if self.pending.iter().any(|pending| pending.last() == Some(&leaf)) {
+                // Every walk would now end on a leaf already in the batch
+                break;
+            }
+            if let Some(value) = terminal_value(&board) {
+                self.nodes[leaf].expanded = true;
+                self.backup(&path, value);
+                continue;
+            }
+            for &i in &path {
+                self.nodes[i].virtual_loss += 1;
+            }
+            self.pending.push(path);
+            boards.push(board);
+        }
+        Ok(boards)
+    }
+
+    /// Expand the leaves returned by the last `collect` with their
+    /// evaluations, in the same order, and back the values up the tree
+    pub fn submit(&mut self, evals: Vec<LeafEval>) -> Result<(), ChessError> {
+        if evals.len() != self.pending.len() {
+            return Err(ChessError::ParseError(format!("expected {} evaluations, got {}", self.pending.len(), evals.len())));
+        }
+        if let Some(policy) = evals.iter().filter_map(|eval| eval.policy.as_ref()).find(|policy| policy.len() != POLICY_SIZE) {
+            return Err(ChessError::ParseError(format!("policy has {} entries instead of {}", policy.len(), POLICY_SIZE)));
+        }
+        for (path, eval) in std::mem::take(&mut self.pending).into_iter().zip(evals) {
+            for &i in &path {
+                self.nodes[i].virtual_loss -= 1;
+            }
+            let board = self.board_at(&path);
+            self.expand(*path.last().unwrap(), &board, eval.policy.as_deref());
+            self.backup(&path, eval.value.clamp(-1.0, 1.0));
+        }
+        Ok(())
+    }
+
+    /// Search until `playouts` playouts are done, handing each batch of
+    /// leaves to `evaluate`
+    pub fn run<E, F>(&mut self, playouts: u32, mut evaluate: F) -> Result<(), E>
+    where
+        E: From<ChessError>,
+        F: FnMut(&[Board]) -> Result<Vec<LeafEval>, E>,
+    {
+        while self.playouts() < playouts {
+            let boards = self.collect()?;
+            let evals = if boards.is_empty() { Vec::new() } else { evaluate(&boards)? };
+            self.submit(evals)?;
+        }
+        Ok(())
+    }
+
+    /// `run` with the batches scored by an `Evaluator`, values only
+    pub fn run_with_evaluator<V: Evaluator + ?Sized>(&mut self, playouts: u32, evaluator: &V) -> Result<(), ChessError> {
+        self.run(playouts, |boards| {
+            Ok(evaluator.evaluate_batch(boards)?.into_iter().map(LeafEval::from_score).collect())
+        })
+    }
+
+    /// Root moves with their visit counts and mean values for the mover,
+    /// most visited first
+    pub fn root_moves(&self) -> Vec<(Move, u32, f32)> {
+        let mut moves: Vec<(Move, u32, f32)> = self.nodes[0].children.iter()
+            .map(|&i| {
+                let node = &self.nodes[i];
+                let q = if node.visits == 0 { 0.0 } else { node.value_sum / node.visits as f32 };
+                (node.mv.clone().unwrap(), node.visits, q)
+            })
+            .collect();
+        moves.sort_by_key(|&(_, visits, _)| std::cmp::Reverse(visits));
+        moves
+    }
+
+    /// Most visited root move, None before the root is expanded or when the
+    /// game is over
+    pub fn best_move(&self) -> Option<Move> {
+        self.root_moves().into_iter().next().map(|(mv, _, _)| mv)
+    }
+
+    /// Play `mv` from the root and make its position the new root, keeping
+    /// the subtree searched below it, so that a following `run` continues
+    /// from the visits already made there. `run` counts those visits towards
+    /// its playouts. Fresh root noise is mixed in when noise is on.
+    pub fn advance(&mut self, mv: &Move) -> Result<(), ChessError> {
+        if !self.pending.is_empty() {
+            return Err(ChessError::ParseError("the previous batch has not been submitted".to_string()));
+        }
+        if !generate_legal_moves(&self.root).contains(mv) {
+            return Err(ChessError::IllegalMove(format!("{} is not legal at the root", to_uci(mv))));
+        }
+        self.root.make_move(mv);
+        let child = self.nodes[0].children.iter().copied().find(|&i| self.nodes[i].mv.as_ref() == Some(mv));
+        let Some(child) = child else {
+            self.nodes = vec![Node::new(None, 1.0)];
+            return Ok(());
+        };
+
+        // Copy the subtree breadth first, so parents precede their children
+        let mut order = vec![child];
+        let mut i = 0;
+        while i < order.len() {
+            order.extend(self.nodes[order[i]].children.iter().copied());
+            i += 1;
+        }
+        let mut new_index = vec![usize::MAX; self.nodes.len()];
+        for (new, &old) in order.iter().enumerate() {
+            new_index[old] = new;
+        }
+        let mut nodes: Vec<Node> = order.iter().map(|&old| self.nodes[old].clone()).collect();
+        for node in &mut nodes {
+            for child in &mut node.children {
+                *child = new_index[*child];
+            }
+        }
+        nodes[0].mv = None;
+        nodes[0].prior = 1.0;
+        self.nodes = nodes;
+
+        let epsilon = self.config.dirichlet_epsilon;
+        let children = self.nodes[0].children.clone();
+        if epsilon > 0.0 && !children.is_empty() {
+            let noise = dirichlet(&mut self.rng, self.config.dirichlet_alpha, children.len());
+            for (&child, noise) in children.iter().zip(noise) {
+                let prior = &mut self.nodes[child].prior;
+                *prior = (1.0 - epsilon) * *prior + epsilon * noise;
+            }
+        }
+        Ok(())
+    }
+
+    /// Move to play: the most visited one at temperature 0, otherwise drawn
+    /// with probability proportional to visits^(1 / temperature)
+    pub fn choose_move(&mut self) -> Option<Move> {
+        let moves = self.root_moves();
+        if self.config.temperature <= 0.0 || moves.is_empty() {
+            return moves.into_iter().next().map(|(mv, _, _)| mv);
+        }
+        let weights: Vec<f64> = moves.iter()
+            .map(|&(_, visits, _)| (visits as f64).powf(1.0 / self.config.temperature as f64))
+            .collect();
+        let mut pick = self.rng.gen::<f64>() * weights.iter().sum::<f64>();
+        for ((mv, _, _), weight) in moves.iter().zip(&weights) {
+            if pick < *weight {
+                return Some(mv.clone());
+            }
+            pick -= weight;
+        }
+        moves.into_iter().next().map(|(mv, _, _)| mv)
+    }
+
+    /// Share of root visits per move by `encode_move` index, `POLICY_SIZE`
+    /// long, as a policy training target
+    pub fn policy_target(&self) -> Vec<f32> {
+        let mut target = vec![0.0; POLICY_SIZE];
+        let total: u32 = self.root_moves().iter().map(|&(_, visits, _)| visits).sum();
+        for (mv, visits, _) in self.root_moves() {
+            target[encode_move(&mv) as usize] += visits as f32 / total.max(1) as f32;
+        }
+        target
+    }
+
+    /// Walk from the root by PUCT to an unexpanded or game-ending node
+    fn select(&self) -> (Vec<usize>, Board) {
+        let mut path = vec![0];
+        let mut board = self.root.clone();
+        let mut node = 0;
+        while self.nodes[node].expanded && !self.nodes[node].children.is_empty() {
+            let parent = &self.nodes[node];
+            let parent_visits = (parent.visits + parent.virtual_loss) as f32;
+            node = *parent.children.iter()
+                .max_by(|&&a, &&b| self.puct(a, parent_visits).total_cmp(&self.puct(b, parent_visits)))
+                .unwrap();
+            board.make_move(self.nodes[node].mv.as_ref().unwrap());
+            path.push(node);
+        }
+        (path, board)
+    }
+
+    /// Mean value plus exploration bonus, counting each walk in flight as a loss
+    fn puct(&self, child: usize, parent_visits: f32) -> f32 {
+        let node = &self.nodes[child];
+        let visits = (node.visits + node.virtual_loss) as f32;
+        let q = if visits == 0.0 { 0.0 } else { (node.value_sum - node.virtual_loss as f32) / visits };
+        q + self.config.c_puct * node.prior * parent_visits.sqrt() / (1.0 + visits)
+    }
+
+    fn board_at(&self, path: &[usize]) -> Board {
+        let mut board = self.root.clone();
+        for &i in &path[1..] {
+            board.make_move(self.nodes[i].mv.as_ref().unwrap());
+        }
+        board
+    }
+
+    fn expand(&mut self, node: usize, board: &Board, policy: Option<&[f32]>) {
+        if self.nodes[node].expanded {
+            return;
+        }
+        let moves = generate_legal_moves(board);
+        let weights: Vec<f32> = match policy {
+            Some(policy) => moves.iter().map(|mv| policy[encode_move(mv) as usize].max(0.0)).collect(),
+            None => vec![1.0; moves.len()],
+        };
+        let total: f32 = weights.iter().sum();
+        let uniform = 1.0 / moves.len().max(1) as f32;
+        let epsilon = if node == 0 { self.config.dirichlet_epsilon } else { 0.0 };
+        let noise = if epsilon > 0.0 { dirichlet(&mut self.rng, self.config.dirichlet_alpha, moves.len()) } else { Vec::new() };
+        for (i, (mv, weight)) in moves.into_iter().zip(weights).enumerate() {
+            let mut prior = if total > 0.0 { weight / total } else { uniform };
+            if epsilon > 0.0 {
+                prior = (1.0 - epsilon) * prior + epsilon * noise[i];
+            }
+            self.nodes.push(Node::new(Some(mv), prior));
+            let child = self.nodes.len() - 1;
+            self.nodes[node].children.push(child);
+        }
+        self.nodes[node].expanded = true;
+    }
+
+    /// Add a leaf value, for the side to move at the leaf, to every node on
+    /// the path, each from the point of view of the side that moved into it
+    fn backup(&mut self, path: &[usize], value: f32) {
+        let mut value = -value;
+        for &i in path.iter().rev() {
+            self.nodes[i].visits += 1;
+            self.nodes[i].value_sum += value;
+            value = -value;
+        }
+    }
+}
+
+/// Value for the side to move of a position that ends the game
+fn terminal_value(board: &Board) -> Option<f32> {
+    board_outcome(board).map(|outcome| match outcome.winner {
+        Some(winner) if winner == board.side_to_move => 1.0,
+        Some(_) => -1.0,
+        None => 0.0,
+    })
+}
+
+/// Sample of a symmetric Dirichlet distribution with `n` components
+fn dirichlet<R: Rng>(rng: &mut R, alpha: f32, n: usize) -> Vec<f32> {
+    let samples: Vec<f64> = (0..n).map(|_| gamma(rng, alpha.max(1e-3) as f64)).collect();
+    let total: f64 = samples.iter().sum();
+    if total <= 0.0 {
+        return vec![1.0 / n as f32; n];
+    }
+    samples.iter().map(|&x| (x / total) as f32).collect()
+}
+
+/// Gamma(alpha, 1) sample by Marsaglia and Tsang's method
+fn gamma<R: Rng>(rng: &mut R, alpha: f64) -> f64 {
+    if alpha < 1.0 {
+        return gamma(rng, alpha + 1.0) * rng.gen::<f64>().powf(1.0 / alpha);
+    }
+    let d = alpha - 1.0 / 3.0;
+    let c = 1.0 / (9.0 * d).sqrt();
+    loop {
+        // Standard normal by Box-Muller
+        let (u1, u2) = (1.0 - rng.gen::<f64>(), rng.gen::<f64>());
+        let x = (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos();
+        let v = (1.0 + c * x).powi(3);
+        if v <= 0.0 {
+            continue;
+        }
+        let u = 1.0 - rng.gen::<f64>();
+        if u.ln() < 0.5 * x * x + d - d * v + d * v.ln() {
+            return d * v;
+        }
+    }
+}
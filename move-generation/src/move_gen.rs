@@ -1,7 +1,12 @@
 //! Move generation for fast chess library
 
-use crate::types::{Board, Color, Move, Piece};
-use std::ops::BitOr;
+use crate::counters::{count, Counter};
+use crate::magic;
+use crate::types::{Board, Color, Move, Piece, Variant};
+use smallvec::SmallVec;
+use std::ops::{BitAnd, BitOr, Deref};
+
+pub use crate::magic::AttackTables;
 
 /// Bitboard representation for fast move generation
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -13,6 +18,16 @@ impl Bitboard {
         Bitboard(0)
     }
 
+    /// Bitboard with every square set
+    pub fn full() -> Self {
+        Bitboard(!0)
+    }
+
+    /// Bitboard with a single square set
+    pub fn from_square(sq: u8) -> Self {
+        Bitboard(1 << sq)
+    }
+
     /// Set a bit at a specific square
     pub fn set(&mut self, sq: u8) {
         self.0 |= 1 << sq;
@@ -53,119 +68,254 @@ impl BitOr for Bitboard {
     }
 }
 
-/// Precomputed attack tables for sliding pieces
-pub struct AttackTables {
-    pub rook_attacks: Vec<Bitboard>,
-    pub bishop_attacks: Vec<Bitboard>,
+impl BitAnd for Bitboard {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        Bitboard(self.0 & rhs.0)
+    }
+}
+
+impl From<u64> for Bitboard {
+    fn from(bits: u64) -> Self {
+        Bitboard(bits)
+    }
+}
+
+impl From<Bitboard> for u64 {
+    fn from(bb: Bitboard) -> Self {
+        bb.0
+    }
+}
+
+/// Move buffer filled by the `_into` generators. They clear it first and
+/// clearing keeps the capacity, so a list reused across calls (one per
+/// search ply, say) stops allocating once it has grown. Up to 32 moves are
+/// stored inline, enough for the moves of a single piece, so a fresh list
+/// only touches the heap when it grows past that.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MoveList {
+    moves: SmallVec<[Move; 32]>,
 }
 
-impl AttackTables {
-    /// Initialize attack tables
+impl MoveList {
     pub fn new() -> Self {
-        let mut rook_attacks = vec![Bitboard::empty(); 64];
-        let mut bishop_attacks = vec![Bitboard::empty(); 64];
+        MoveList::default()
+    }
 
-        for sq in 0..64 {
-            rook_attacks[sq] = compute_rook_attacks(sq.try_into().unwrap());
-            bishop_attacks[sq] = compute_bishop_attacks(sq.try_into().unwrap());
-        }
+    pub fn push(&mut self, mv: Move) {
+        self.moves.push(mv);
+    }
 
-        AttackTables {
-            rook_attacks,
-            bishop_attacks,
-        }
+    /// Remove every move, keeping the allocation
+    pub fn clear(&mut self) {
+        self.moves.clear();
     }
-}
 
-/// Compute rook attack mask for a given square
-fn compute_rook_attacks(sq: u8) -> Bitboard {
-    let mut attacks = Bitboard::empty();
+    /// Keep only the moves for which `keep` returns true, in order
+    pub fn retain<F: FnMut(&Move) -> bool>(&mut self, mut keep: F) {
+        self.moves.retain(|mv| keep(mv));
+    }
 
-    // Horizontal and vertical directions
-    let directions = [-8, -1, 1, 8];
+    pub fn into_vec(self) -> Vec<Move> {
+        self.moves.into_vec()
+    }
+}
 
-    for &dir in &directions {
-        let mut current_sq = sq as i8;
-        while let Some(next_sq) = step_in_direction(current_sq, dir) {
-            attacks.set(next_sq as u8);
-            current_sq = next_sq;
-        }
+impl Deref for MoveList {
+    type Target = [Move];
+
+    fn deref(&self) -> &[Move] {
+        &self.moves
     }
+}
+
+impl<'a> IntoIterator for &'a MoveList {
+    type Item = &'a Move;
+    type IntoIter = std::slice::Iter<'a, Move>;
 
-    attacks
+    fn into_iter(self) -> Self::IntoIter {
+        self.moves.iter()
+    }
 }
 
-/// Compute bishop attack mask for a given square
-fn compute_bishop_attacks(sq: u8) -> Bitboard {
-    let mut attacks = Bitboard::empty();
+impl From<MoveList> for Vec<Move> {
+    fn from(list: MoveList) -> Self {
+        list.into_vec()
+    }
+}
 
-    // Diagonal directions
-    let directions = [-9, -7, 7, 9];
+/// Which pieces pawns may promote to in generated moves
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Promotions {
+    /// Queen, rook, bishop and knight
+    #[default]
+    All,
+    /// Queen and knight, the only promotions that are ever uniquely best
+    QueenAndKnight,
+    QueenOnly,
+}
 
-    for &dir in &directions {
-        let mut current_sq = sq as i8;
-        while let Some(next_sq) = step_in_direction(current_sq, dir) {
-            attacks.set(next_sq as u8);
-            current_sq = next_sq;
+impl Promotions {
+    /// Promotion pieces emitted, most valuable first
+    pub fn pieces(&self) -> &'static [Piece] {
+        match self {
+            Promotions::All => &[Piece::Queen, Piece::Rook, Piece::Bishop, Piece::Knight],
+            Promotions::QueenAndKnight => &[Piece::Queen, Piece::Knight],
+            Promotions::QueenOnly => &[Piece::Queen],
         }
     }
+}
 
-    attacks
+/// Generate pseudo-legal moves for a given color, which must be the side
+/// to move. See `generate_pseudo_legal` for what is guaranteed.
+pub fn generate_moves(board: &Board, color: Color) -> Vec<Move> {
+    generate_moves_with_promotions(board, color, Promotions::All)
 }
 
-/// Step in a direction and return the next square, or None if out of bounds
-fn step_in_direction(sq: i8, dir: i8) -> Option<i8> {
-    let next_sq = sq + dir;
+/// `generate_moves` into a reusable buffer, replacing its contents
+pub fn generate_moves_into(board: &Board, color: Color, moves: &mut MoveList) {
+    generate_moves_into_with(board, color, moves, magic::global());
+}
 
-    if next_sq < 0 || next_sq >= 64 {
-        return None;
+/// `generate_moves` looking slider moves up in `tables` rather than the
+/// global tables
+pub fn generate_moves_with(board: &Board, color: Color, tables: &AttackTables) -> Vec<Move> {
+    let mut moves = MoveList::new();
+    generate_moves_into_with(board, color, &mut moves, tables);
+    moves.into_vec()
+}
+
+/// `generate_moves_into` looking slider moves up in `tables`
+pub fn generate_moves_into_with(board: &Board, color: Color, moves: &mut MoveList, tables: &AttackTables) {
+    moves.clear();
+    push_all_moves(board, color, Promotions::All, true, tables, moves);
+}
+
+/// Generate the pseudo-legal moves of `color`.
+///
+/// Every move follows the movement rules of its piece, and castling is only
+/// generated with the castling right, an empty path and a king that neither
+/// starts on, crosses nor lands on an attacked square. Other moves may leave
+/// the mover's own king in check; filter them with `rules::is_legal_move`,
+/// or use `rules::generate_legal_moves` to get legal moves directly.
+///
+/// `color` may be either side. For the side not to move there is no en
+/// passant capture, since the en passant square belongs to the side to move.
+pub fn generate_pseudo_legal(board: &Board, color: Color) -> Vec<Move> {
+    if color == board.side_to_move {
+        return generate_moves(board, color);
     }
+    let mut board = board.clone();
+    board.side_to_move = color;
+    board.en_passant = None;
+    generate_moves(&board, color)
+}
 
-    let same_row = (sq / 8) == (next_sq / 8);
-    let same_col = (sq % 8) == (next_sq % 8);
+/// Generate moves for a given color, emitting only the chosen promotions
+pub fn generate_moves_with_promotions(board: &Board, color: Color, promotions: Promotions) -> Vec<Move> {
+    let mut moves = MoveList::new();
+    push_all_moves(board, color, promotions, true, magic::global(), &mut moves);
+    moves.into_vec()
+}
 
-    if dir.abs() == 1 && !same_row {
-        return None;
+/// Append the moves of every piece of `color`, and its drops if asked
+fn push_all_moves(board: &Board, color: Color, promotions: Promotions, drops: bool, tables: &AttackTables, moves: &mut MoveList) {
+    for (sq, piece) in board.piece_list(color) {
+        push_piece_moves(board, piece, sq, promotions, tables, moves);
     }
 
-    if dir.abs() == 8 && !same_col {
-        return None;
+    if drops && board.variant == Variant::Crazyhouse {
+        push_drops(board, color, moves);
     }
+}
 
-    Some(next_sq)
+/// Sort moves into a canonical order, by from square, to square, promotion
+/// piece (none first) and drop piece, and remove duplicates, so that output
+/// does not depend on the order pieces were generated in
+pub fn sort_moves(moves: &mut Vec<Move>) {
+    let piece = |piece: Option<Piece>| piece.map_or(0, |p| p.index() + 1);
+    moves.sort_by_key(|mv| (mv.from, mv.to, piece(mv.promotion), piece(mv.drop)));
+    moves.dedup();
 }
 
-/// Generate all legal moves for a given color
-pub fn generate_moves(board: &Board, color: Color) -> Vec<Move> {
-    let mut moves = vec![];
+/// Whether a move captures a piece, including en passant; castling onto the
+/// player's own rook is not a capture
+pub fn is_capture(board: &Board, mv: &Move) -> bool {
+    if mv.drop.is_some() || board.is_castling(mv) {
+        return false;
+    }
+    board.squares()[mv.to as usize].is_some()
+        || (board.en_passant == Some(mv.to)
+            && matches!(board.squares()[mv.from as usize], Some((Piece::Pawn, _)))
+            && mv.from % 8 != mv.to % 8)
+}
+
+/// Generate captures only (pseudo-legal) for a given color
+pub fn generate_captures(board: &Board, color: Color) -> Vec<Move> {
+    let mut moves = MoveList::new();
+    generate_captures_into(board, color, &mut moves);
+    moves.into_vec()
+}
+
+/// `generate_captures` into a reusable buffer, replacing its contents
+pub fn generate_captures_into(board: &Board, color: Color, moves: &mut MoveList) {
+    moves.clear();
+    push_all_moves(board, color, Promotions::All, false, magic::global(), moves);
+    moves.retain(|mv| is_capture(board, mv));
+}
+
+/// Generate non-captures only (pseudo-legal) for a given color, including
+/// castling and Crazyhouse drops
+pub fn generate_quiets(board: &Board, color: Color) -> Vec<Move> {
+    let mut moves = MoveList::new();
+    generate_quiets_into(board, color, &mut moves);
+    moves.into_vec()
+}
+
+/// `generate_quiets` into a reusable buffer, replacing its contents
+pub fn generate_quiets_into(board: &Board, color: Color, moves: &mut MoveList) {
+    moves.clear();
+    push_all_moves(board, color, Promotions::All, true, magic::global(), moves);
+    moves.retain(|mv| !is_capture(board, mv));
+}
+
+/// Generate Crazyhouse drops of pocketed pieces onto empty squares.
+/// Pawns may not be dropped on the first or last rank.
+pub fn generate_drops(board: &Board, color: Color) -> Vec<Move> {
+    let mut moves = MoveList::new();
+    push_drops(board, color, &mut moves);
+    moves.into_vec()
+}
 
-    // Iterate over all squares
-    for (sq, piece) in board.squares.iter().enumerate() {
-        if let Some((p, c)) = piece {
-            if *c == color {
-                moves.extend(generate_piece_moves(board, *p, sq as u8));
+fn push_drops(board: &Board, color: Color, moves: &mut MoveList) {
+    let pocket = board.pockets[color.index()];
+    let before = moves.len();
+
+    for piece in [Piece::Pawn, Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen] {
+        if pocket[piece.index()] == 0 {
+            continue;
+        }
+        for sq in 0..64u8 {
+            if board.squares()[sq as usize].is_some() {
+                continue;
+            }
+            if piece == Piece::Pawn && !(8..56).contains(&sq) {
+                continue;
             }
+            moves.push(Move { from: sq, to: sq, promotion: None, drop: Some(piece) });
         }
     }
-
-    moves
+    count(Counter::MovesGenerated, (moves.len() - before) as u64);
 }
 
-/// Generate all valid moves for a specific piece at a square
-// pub fn generate_piece_moves(board: &Board, piece: Piece, sq: u8) -> Vec<Move> {
-//     match piece {
-//         Piece::Pawn => generate_pawn_moves(board, sq),
-//         Piece::Knight => generate_knight_moves(sq),
-//         Piece::Bishop => generate_bishop_moves(board, sq),
-//         Piece::Rook => generate_rook_moves(board, sq),
-//         Piece::Queen => generate_queen_moves(board, sq),
-//         Piece::King => generate_king_moves(board, sq),
-//     }
-// }
-
 /// Generate pawn moves
-fn generate_pawn_moves(board: &Board, sq: u8) -> Vec<Move> {
-    let mut moves = vec![];
+fn push_pawn_moves(board: &Board, sq: u8, promotions: Promotions, moves: &mut MoveList) {
+    // Rank where pawns promote
+    let last_rank = match board.side_to_move {
+        Color::White => 7,
+        Color::Black => 0,
+    };
     let direction = match board.side_to_move {
         Color::White => 8,  // White pawns move up the board
         Color::Black => -8, // Black pawns move down the board
@@ -173,26 +323,29 @@ fn generate_pawn_moves(board: &Board, sq: u8) -> Vec<Move> {
 
     // Single forward move
     let forward_sq = sq as i8 + direction;
-    if forward_sq >= 0 && forward_sq < 64 && board.squares[forward_sq as usize].is_none() {
-        moves.push(Move {
+    if (0..64).contains(&forward_sq) && board.squares()[forward_sq as usize].is_none() {
+        push_pawn_move(moves, promotions, Move {
             from: sq,
             to: forward_sq as u8,
             promotion: None,
-        });
+            drop: None,
+        }, last_rank);
 
-        // Double forward move (only from starting rank)
+        // Double forward move (only from starting rank, or the first rank in Horde)
         let starting_rank = match board.side_to_move {
             Color::White => 1,
             Color::Black => 6,
         };
-        if sq / 8 == starting_rank {
+        let horde_first_rank = board.variant == Variant::Horde && board.side_to_move == Color::White && sq / 8 == 0;
+        if sq / 8 == starting_rank || horde_first_rank {
             let double_forward_sq = forward_sq + direction;
-            if double_forward_sq >= 0 && double_forward_sq < 64 && board.squares[double_forward_sq as usize].is_none() {
-                moves.push(Move {
+            if (0..64).contains(&double_forward_sq) && board.squares()[double_forward_sq as usize].is_none() {
+                push_pawn_move(moves, promotions, Move {
                     from: sq,
                     to: double_forward_sq as u8,
                     promotion: None,
-                });
+                    drop: None,
+                }, last_rank);
             }
         }
     }
@@ -204,14 +357,16 @@ fn generate_pawn_moves(board: &Board, sq: u8) -> Vec<Move> {
     };
     for &cap_dir in &capture_directions {
         let capture_sq = sq as i8 + cap_dir;
-        if capture_sq >= 0 && capture_sq < 64 {
-            if let Some((_, color)) = board.squares[capture_sq as usize] {
+        // Diagonal steps must stay on adjacent files
+        if (0..64).contains(&capture_sq) && (capture_sq % 8 - (sq % 8) as i8).abs() == 1 {
+            if let Some((_, color)) = board.squares()[capture_sq as usize] {
                 if color != board.side_to_move {
-                    moves.push(Move {
+                    push_pawn_move(moves, promotions, Move {
                         from: sq,
                         to: capture_sq as u8,
                         promotion: None,
-                    });
+                        drop: None,
+                    }, last_rank);
                 }
             }
         }
@@ -225,17 +380,28 @@ fn generate_pawn_moves(board: &Board, sq: u8) -> Vec<Move> {
         };
         for &ep_dir in &en_passant_directions {
             let target_sq = sq as i8 + ep_dir;
-            if target_sq == en_passant_sq as i8 {
-                moves.push(Move {
+            if target_sq == en_passant_sq as i8 && (target_sq % 8 - (sq % 8) as i8).abs() == 1 {
+                push_pawn_move(moves, promotions, Move {
                     from: sq,
                     to: en_passant_sq,
                     promotion: None,
-                });
+                    drop: None,
+                }, last_rank);
             }
         }
     }
+}
 
-    moves
+/// Push a pawn move, as one move per promotion piece when it reaches the
+/// last rank
+fn push_pawn_move(moves: &mut MoveList, promotions: Promotions, mv: Move, last_rank: u8) {
+    if mv.to / 8 == last_rank {
+        for &piece in promotions.pieces() {
+            moves.push(Move { promotion: Some(piece), ..mv.clone() });
+        }
+    } else {
+        moves.push(mv);
+    }
 }
 
 /// Get the en passant square, if available
@@ -244,18 +410,17 @@ fn get_en_passant_square(board: &Board) -> Option<u8> {
 }
 
 /// Generate knight moves
-fn generate_knight_moves(board: &Board, sq: u8) -> Vec<Move> {
-    let mut moves = vec![];
+fn push_knight_moves(board: &Board, sq: u8, moves: &mut MoveList) {
     let knight_offsets = [-17, -15, -10, -6, 6, 10, 15, 17];
     let from_rank = (sq / 8) as i8;
     let from_file = (sq % 8) as i8;
     for &offset in &knight_offsets {
         let target_sq = sq as i8 + offset;
-        if target_sq < 0 || target_sq >= 64 {
+        if !(0..64).contains(&target_sq) {
             continue;
         }
-        let to_rank = (target_sq / 8) as i8;
-        let to_file = (target_sq % 8) as i8;
+        let to_rank = target_sq / 8;
+        let to_file = target_sq % 8;
         let dr = (from_rank - to_rank).abs();
         let df = (from_file - to_file).abs();
         // Must be a knight move (2,1) or (1,2)
@@ -263,7 +428,7 @@ fn generate_knight_moves(board: &Board, sq: u8) -> Vec<Move> {
             continue;
         }
         // Can't land on own piece
-        if let Some((_, color)) = board.squares[target_sq as usize] {
+        if let Some((_, color)) = board.squares()[target_sq as usize] {
             if color == board.side_to_move {
                 continue;
             }
@@ -272,46 +437,52 @@ fn generate_knight_moves(board: &Board, sq: u8) -> Vec<Move> {
             from: sq,
             to: target_sq as u8,
             promotion: None,
+            drop: None,
         });
     }
+}
+
+/// Generate all valid moves for a specific piece at a square
+pub fn generate_piece_moves(board: &Board, piece: Piece, sq: u8) -> Vec<Move> {
+    piece_move_list(board, piece, sq).into_vec()
+}
+
+/// `generate_piece_moves` kept in a MoveList, which stays off the heap
+pub(crate) fn piece_move_list(board: &Board, piece: Piece, sq: u8) -> MoveList {
+    let mut moves = MoveList::new();
+    push_piece_moves(board, piece, sq, Promotions::All, magic::global(), &mut moves);
     moves
 }
 
-/// Generate queen moves
-// fn generate_queen_moves(board: &Board, sq: u8) -> Vec<Move> {
-//     let mut moves = vec![];
-//     moves.extend(generate_rook_moves(board, sq));
-//     moves.extend(generate_bishop_moves(board, sq));
-//     moves
-// }
-    pub fn generate_piece_moves(board: &Board, piece: Piece, sq: u8) -> Vec<Move> {
+fn push_piece_moves(board: &Board, piece: Piece, sq: u8, promotions: Promotions, tables: &AttackTables, moves: &mut MoveList) {
+    let before = moves.len();
+    let occupied = board.occupied();
     match piece {
-        Piece::Pawn => generate_pawn_moves(board, sq),
-    Piece::Knight => generate_knight_moves(board, sq),
-        Piece::Bishop => generate_bishop_moves(board, sq),
-        Piece::Rook => generate_rook_moves(board, sq),
-        Piece::Queen => {
-            let mut moves = generate_rook_moves(board, sq);
-            moves.extend(generate_bishop_moves(board, sq));
-            moves
-        },
-        Piece::King => generate_king_moves(board, sq),
+        Piece::Pawn => push_pawn_moves(board, sq, promotions, moves),
+        Piece::Knight => push_knight_moves(board, sq, moves),
+        Piece::Bishop => push_slider_moves(board, sq, tables.bishop_attacks(sq, occupied), moves),
+        Piece::Rook => push_slider_moves(board, sq, tables.rook_attacks(sq, occupied), moves),
+        Piece::Queen => push_slider_moves(board, sq, tables.queen_attacks(sq, occupied), moves),
+        Piece::King => push_king_moves(board, sq, moves),
     }
+    count(Counter::MovesGenerated, (moves.len() - before) as u64);
 }
+
 /// Generate king moves
-fn generate_king_moves(board: &Board, sq: u8) -> Vec<Move> {
-    let mut moves = vec![];
+fn push_king_moves(board: &Board, sq: u8, moves: &mut MoveList) {
     let king_offsets = [-9, -8, -7, -1, 1, 7, 8, 9];
 
     for &offset in &king_offsets {
         let target_sq = sq as i8 + offset;
-        if target_sq >= 0 && target_sq < 64 {
-            if let Some((_, color)) = board.squares[target_sq as usize] {
+        // King steps must not wrap around the board edge
+        if (0..64).contains(&target_sq) && (target_sq % 8 - (sq % 8) as i8).abs() <= 1 {
+            if let Some((_, color)) = board.squares()[target_sq as usize] {
                 if color != board.side_to_move {
                     moves.push(Move {
                         from: sq,
                         to: target_sq as u8,
                         promotion: None,
+                        drop: None,
                     });
                 }
             } else {
@@ -319,221 +490,215 @@ fn generate_king_moves(board: &Board, sq: u8) -> Vec<Move> {
                     from: sq,
                     to: target_sq as u8,
                     promotion: None,
+                    drop: None,
                 });
             }
         }
     }
 
-    // Castling
-    if can_castle_kingside(board) {
-        moves.push(Move {
-            from: sq,
-            to: sq + 2, // Kingside castling
-            promotion: None,
-        });
-    }
-    if can_castle_queenside(board) {
-        moves.push(Move {
-            from: sq,
-            to: sq - 2, // Queenside castling
-            promotion: None,
-        });
+    // Castling, written as the king taking its own rook
+    for kingside in [true, false] {
+        if let Some(rook_sq) = castling_rook_if_possible(board, sq, kingside) {
+            moves.push(Move {
+                from: sq,
+                to: rook_sq,
+                promotion: None,
+                drop: None,
+            });
+        }
     }
+}
 
-    moves
+/// The rook the king on `king_sq` may castle with on one side, if castling
+/// that way is possible: the right is held, every square the king and rook
+/// cross is empty apart from the two of them, and the king neither starts
+/// in, passes through nor lands on an attacked square. The same rule covers
+/// standard chess and Chess960.
+fn castling_rook_if_possible(board: &Board, king_sq: u8, kingside: bool) -> Option<u8> {
+    let color = board.side_to_move;
+    let rook_sq = board.castling_rook(color, kingside)?;
+    let castle = Move { from: king_sq, to: rook_sq, promotion: None, drop: None };
+    let (king_to, rook_to) = castle.castling_squares();
+    let span = |a: u8, b: u8| a.min(b)..=a.max(b);
+
+    let path_clear = span(king_sq, king_to).chain(span(rook_sq, rook_to))
+        .all(|sq| sq == king_sq || sq == rook_sq || board.squares()[sq as usize].is_none());
+    if !path_clear {
+        return None;
+    }
+    let attacked = attacked_mask(board, color.opposite());
+    if span(king_sq, king_to).any(|sq| attacked.contains(sq)) {
+        return None;
+    }
+    Some(rook_sq)
 }
 
-/// Check if kingside castling is possible
-fn can_castle_kingside(board: &Board) -> bool {
-    let (king_sq, rook_sq, right_char) = match board.side_to_move {
-        Color::White => (4, 7, 'K'), // e1, h1
-        Color::Black => (60, 63, 'k'), // e8, h8
-    };
-    // Check castling rights from FEN
-    if !board.castling_rights.contains(right_char) {
-        return false;
+const NOT_A_FILE: u64 = 0xfefe_fefe_fefe_fefe;
+const NOT_H_FILE: u64 = 0x7f7f_7f7f_7f7f_7f7f;
+const NOT_AB_FILES: u64 = 0xfcfc_fcfc_fcfc_fcfc;
+const NOT_GH_FILES: u64 = 0x3f3f_3f3f_3f3f_3f3f;
+
+/// Every square attacked by the pieces of `color`, computed in one pass:
+/// pawn, knight and king attacks by shifting their bitboards and slider
+/// attacks by filling all rooks, bishops and queens along each direction at
+/// once. Squares holding pieces of either color are included when attacked.
+pub fn attacked_mask(board: &Board, color: Color) -> Bitboard {
+    let (mut pawns, mut knights, mut kings, mut orthogonal, mut diagonal) = (0u64, 0, 0, 0, 0);
+    let mut own = u64::from(board.occupied_by(color));
+    while own != 0 {
+        let sq = own.trailing_zeros();
+        own &= own - 1;
+        let Some((piece, _)) = board.squares()[sq as usize] else { continue };
+        let bit = 1u64 << sq;
+        match piece {
+            Piece::Pawn => pawns |= bit,
+            Piece::Knight => knights |= bit,
+            Piece::Bishop => diagonal |= bit,
+            Piece::Rook => orthogonal |= bit,
+            Piece::Queen => {
+                orthogonal |= bit;
+                diagonal |= bit;
+            }
+            Piece::King => kings |= bit,
+        }
     }
-    // Ensure the squares between the king and rook are empty
-    let between_squares = match board.side_to_move {
-        Color::White => [5, 6], // f1, g1
-        Color::Black => [61, 62], // f8, g8
-    };
-    if !are_squares_empty(board, &between_squares) {
-        return false;
+
+    let mut attacks = pawn_attacks(pawns, color) | knight_attacks(knights) | king_attacks(kings);
+    let empty = !u64::from(board.occupied());
+    for (shift, wrap) in [(8, !0), (-8, !0), (1, NOT_A_FILE), (-1, NOT_H_FILE)] {
+        attacks |= slide(orthogonal, empty, shift, wrap);
     }
-    // Ensure the king does not move through or into check
-    let king_path = match board.side_to_move {
-        Color::White => [4, 5, 6], // e1, f1, g1
-        Color::Black => [60, 61, 62], // e8, f8, g8
-    };
-    if is_any_square_attacked(board, &king_path, board.side_to_move) {
-        return false;
+    for (shift, wrap) in [(9, NOT_A_FILE), (7, NOT_H_FILE), (-7, NOT_A_FILE), (-9, NOT_H_FILE)] {
+        attacks |= slide(diagonal, empty, shift, wrap);
     }
-    true
+    Bitboard(attacks)
 }
 
-/// Check if queenside castling is possible
-fn can_castle_queenside(board: &Board) -> bool {
-    let (king_sq, rook_sq, right_char) = match board.side_to_move {
-        Color::White => (4, 0, 'Q'), // e1, a1
-        Color::Black => (60, 56, 'q'), // e8, a8
-    };
-    // Check castling rights from FEN
-    if !board.castling_rights.contains(right_char) {
-        return false;
+/// Squares attacked by pawns of `color` standing on `pawns`
+pub(crate) fn pawn_attacks(pawns: u64, color: Color) -> u64 {
+    match color {
+        Color::White => ((pawns << 7) & NOT_H_FILE) | ((pawns << 9) & NOT_A_FILE),
+        Color::Black => ((pawns >> 9) & NOT_H_FILE) | ((pawns >> 7) & NOT_A_FILE),
     }
-    // Ensure the squares between the king and rook are empty
-    let between_squares = match board.side_to_move {
-        Color::White => [1, 2, 3], // b1, c1, d1
-        Color::Black => [57, 58, 59], // b8, c8, d8
+}
+
+/// Squares attacked by knights standing on `knights`
+fn knight_attacks(knights: u64) -> u64 {
+    (((knights << 17) | (knights >> 15)) & NOT_A_FILE)
+        | (((knights << 15) | (knights >> 17)) & NOT_H_FILE)
+        | (((knights << 10) | (knights >> 6)) & NOT_AB_FILES)
+        | (((knights << 6) | (knights >> 10)) & NOT_GH_FILES)
+}
+
+/// Squares attacked by kings standing on `kings`
+pub(crate) fn king_attacks(kings: u64) -> u64 {
+    let sideways = ((kings << 1) & NOT_A_FILE) | ((kings >> 1) & NOT_H_FILE);
+    let row = kings | sideways;
+    sideways | (row << 8) | (row >> 8)
+}
+
+/// Squares of the pieces of `color` attacking `sq`, whoever stands on it.
+/// Only direct attacks count: a slider behind another piece on the same
+/// line is not included.
+pub fn attackers(board: &Board, sq: u8, color: Color) -> Bitboard {
+    attackers_with(board, sq, color, magic::global())
+}
+
+/// `attackers` looking slider attacks up in `tables` rather than the global
+/// tables
+pub fn attackers_with(board: &Board, sq: u8, color: Color, tables: &AttackTables) -> Bitboard {
+    let target = 1u64 << sq;
+    let occupied = board.occupied();
+    let pieces = |piece: Piece| board.piece_bits(piece);
+    let attacks = (pawn_attacks(target, color.opposite()) & pieces(Piece::Pawn))
+        | (knight_attacks(target) & pieces(Piece::Knight))
+        | (king_attacks(target) & pieces(Piece::King))
+        | (u64::from(tables.rook_attacks(sq, occupied)) & (pieces(Piece::Rook) | pieces(Piece::Queen)))
+        | (u64::from(tables.bishop_attacks(sq, occupied)) & (pieces(Piece::Bishop) | pieces(Piece::Queen)));
+    Bitboard(attacks & u64::from(board.occupied_by(color)))
+}
+
+/// Squares attacked along one direction by all of `sliders` together
+/// (Kogge-Stone fill). `shift` is the square step and `wrap` the files a
+/// step in that direction may land on without wrapping around the board.
+fn slide(sliders: u64, empty: u64, shift: i32, wrap: u64) -> u64 {
+    let step = |bits: u64, n: i32| if n > 0 { bits << n } else { bits >> -n };
+    let mut filled = sliders;
+    let mut open = empty & wrap;
+    filled |= open & step(filled, shift);
+    open &= step(open, shift);
+    filled |= open & step(filled, 2 * shift);
+    open &= step(open, 2 * shift);
+    filled |= open & step(filled, 4 * shift);
+    step(filled, shift) & wrap
+}
+
+/// Check if a square is attacked by the opponent of `color`
+pub fn is_square_attacked(board: &Board, sq: u8, color: Color) -> bool {
+    is_square_attacked_with(board, sq, color, magic::global())
+}
+
+/// `is_square_attacked` looking slider attacks up in `tables` rather than
+/// the global tables
+pub fn is_square_attacked_with(board: &Board, sq: u8, color: Color, tables: &AttackTables) -> bool {
+    count(Counter::AttackQueries, 1);
+    let enemy = color.opposite();
+    let rank = (sq / 8) as i8;
+    let file = (sq % 8) as i8;
+    let piece_at = |r: i8, f: i8| {
+        if (0..8).contains(&r) && (0..8).contains(&f) {
+            board.squares()[(r * 8 + f) as usize]
+        } else {
+            None
+        }
     };
-    if !are_squares_empty(board, &between_squares) {
-        return false;
-    }
-    // Ensure the king does not move through or into check
-    let king_path = match board.side_to_move {
-        Color::White => [4, 3, 2], // e1, d1, c1
-        Color::Black => [60, 59, 58], // e8, d8, c8
+
+    // Enemy pawns attack diagonally forward, so they sit one rank behind the target
+    let pawn_rank = match enemy {
+        Color::White => rank - 1,
+        Color::Black => rank + 1,
     };
-    if is_any_square_attacked(board, &king_path, board.side_to_move) {
-        return false;
+    if [-1, 1].iter().any(|&df| piece_at(pawn_rank, file + df) == Some((Piece::Pawn, enemy))) {
+        return true;
     }
-    true
-}
-
-/// Check if the king and rook have not moved
-fn has_king_and_rook_not_moved(_board: &Board, _king_sq: u8, _rook_sq: u8) -> bool {
-    // FEN castling rights now checked in can_castle_kingside/queenside
-    true
-}
-
-/// Check if all squares in a given list are empty
-fn are_squares_empty(board: &Board, squares: &[u8]) -> bool {
-    squares.iter().all(|&sq| board.squares[sq as usize].is_none())
-}
-
-/// Check if any square in a given list is attacked
-fn is_any_square_attacked(board: &Board, squares: &[u8], color: Color) -> bool {
-    squares.iter().any(|&sq| is_square_attacked(board, sq, color))
-}
-
-/// Check if a square is attacked by the opponent
-fn is_square_attacked(board: &Board, sq: u8, color: Color) -> bool {
-    for (i, piece) in board.squares.iter().enumerate() {
-        if let Some((p, c)) = piece {
-            if *c != color {
-                match p {
-                    Piece::Pawn => {
-                        let attack_offsets = match c {
-                            Color::White => [-7, -9],
-                            Color::Black => [7, 9],
-                        };
-                        for &offset in &attack_offsets {
-                            let target_sq = i as i8 + offset;
-                            if target_sq >= 0 && target_sq < 64 && target_sq as u8 == sq {
-                                return true;
-                            }
-                        }
-                    }
-                    Piece::Knight => {
-                        let knight_offsets = [-17, -15, -10, -6, 6, 10, 15, 17];
-                        for &offset in &knight_offsets {
-                            let target_sq = i as i8 + offset;
-                            if target_sq >= 0 && target_sq < 64 && target_sq as u8 == sq {
-                                return true;
-                            }
-                        }
-                    }
-                    Piece::Bishop | Piece::Rook | Piece::Queen => {
-                        let attacks = match p {
-                            Piece::Bishop => compute_bishop_attacks(i as u8),
-                            Piece::Rook => compute_rook_attacks(i as u8),
-                            Piece::Queen => compute_bishop_attacks(i as u8) | compute_rook_attacks(i as u8),
-                            _ => unreachable!(),
-                        };
-                        if attacks.contains(sq) {
-                            return true;
-                        }
-                    }
-                    Piece::King => {
-                        let king_offsets = [-9, -8, -7, -1, 1, 7, 8, 9];
-                        for &offset in &king_offsets {
-                            let target_sq = i as i8 + offset;
-                            if target_sq >= 0 && target_sq < 64 && target_sq as u8 == sq {
-                                return true;
-                            }
-                        }
-                    }
-                }
-            }
-        }
+
+    let knight_deltas = [(-2, -1), (-2, 1), (-1, -2), (-1, 2), (1, -2), (1, 2), (2, -1), (2, 1)];
+    if knight_deltas.iter().any(|&(dr, df)| piece_at(rank + dr, file + df) == Some((Piece::Knight, enemy))) {
+        return true;
     }
-    false
-}
 
-/// Generate bishop moves
-fn generate_bishop_moves(board: &Board, sq: u8) -> Vec<Move> {
-    let mut moves = vec![];
-    let directions = [-9i8, -7i8, 7i8, 9i8];
-    for &dir in &directions {
-        let mut current_sq = sq as i8;
-        loop {
-            let next_sq = current_sq + dir;
-            if next_sq < 0 || next_sq >= 64 {
-                break;
-            }
-            let from_rank = (current_sq / 8) as i8;
-            let from_file = (current_sq % 8) as i8;
-            let to_rank = (next_sq / 8) as i8;
-            let to_file = (next_sq % 8) as i8;
-            // Prevent wrapping: diagonal steps must change both rank and file by 1
-            if (from_rank - to_rank).abs() != 1 || (from_file - to_file).abs() != 1 {
-                break;
-            }
-            if let Some((_, color)) = board.squares[next_sq as usize] {
-                if color != board.side_to_move {
-                    moves.push(Move { from: sq, to: next_sq as u8, promotion: None });
-                }
-                break; // blocked
-            } else {
-                moves.push(Move { from: sq, to: next_sq as u8, promotion: None });
-            }
-            current_sq = next_sq;
-        }
+    let king_deltas = [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)];
+    if king_deltas.iter().any(|&(dr, df)| piece_at(rank + dr, file + df) == Some((Piece::King, enemy))) {
+        return true;
     }
-    moves
-}
 
-/// Generate rook moves
-fn generate_rook_moves(board: &Board, sq: u8) -> Vec<Move> {
-    let mut moves = vec![];
-    let directions = [-8i8, -1i8, 1i8, 8i8];
-    for &dir in &directions {
-        let mut current_sq = sq as i8;
-        loop {
-            let next_sq = current_sq + dir;
-            if next_sq < 0 || next_sq >= 64 {
-                break;
+    // Sliders: the first piece on each ray, looked up from the occupancy
+    let occupied = board.occupied();
+    let enemies = u64::from(board.occupied_by(enemy));
+    let rays = [
+        (u64::from(tables.rook_attacks(sq, occupied)), Piece::Rook),
+        (u64::from(tables.bishop_attacks(sq, occupied)), Piece::Bishop),
+    ];
+    rays.iter().any(|&(seen, slider)| {
+        let mut candidates = seen & enemies;
+        while candidates != 0 {
+            let from = candidates.trailing_zeros() as usize;
+            candidates &= candidates - 1;
+            if matches!(board.squares()[from], Some((p, _)) if p == slider || p == Piece::Queen) {
+                return true;
             }
-            let from_rank = (current_sq / 8) as i8;
-            let from_file = (current_sq % 8) as i8;
-            let to_rank = (next_sq / 8) as i8;
-            let to_file = (next_sq % 8) as i8;
-            // Prevent wrapping for horizontal moves
-            if dir == -1 && to_file > from_file { break; }
-            if dir == 1 && to_file < from_file { break; }
-            if dir == -8 && to_rank > from_rank { break; }
-            if dir == 8 && to_rank < from_rank { break; }
-            if let Some((_, color)) = board.squares[next_sq as usize] {
-                if color != board.side_to_move {
-                    moves.push(Move { from: sq, to: next_sq as u8, promotion: None });
-                }
-                break; // blocked
-            } else {
-                moves.push(Move { from: sq, to: next_sq as u8, promotion: None });
-            }
-            current_sq = next_sq;
         }
+        false
+    })
+}
+
+/// Generate the moves of a bishop, rook or queen on `sq` from its
+/// `attacks`: every attacked square not holding a piece of the side to move
+fn push_slider_moves(board: &Board, sq: u8, attacks: Bitboard, moves: &mut MoveList) {
+    let mut targets = u64::from(attacks) & !u64::from(board.occupied_by(board.side_to_move));
+    while targets != 0 {
+        let to = targets.trailing_zeros() as u8;
+        targets &= targets - 1;
+        moves.push(Move { from: sq, to, promotion: None, drop: None });
     }
-    moves
 }
@@ -0,0 +1,353 @@
+//! UCI and SAN move notation
+
+use crate::pgn::{starting_board, ChessError, Position};
+use crate::rules::{generate_legal_moves, is_checkmate, is_in_check};
+use crate::types::{Board, Move, Piece, Square, Variant};
+
+const SQUARE_NAMES: [&str; 64] = [
+    "a1", "b1", "c1", "d1", "e1", "f1", "g1", "h1",
+    "a2", "b2", "c2", "d2", "e2", "f2", "g2", "h2",
+    "a3", "b3", "c3", "d3", "e3", "f3", "g3", "h3",
+    "a4", "b4", "c4", "d4", "e4", "f4", "g4", "h4",
+    "a5", "b5", "c5", "d5", "e5", "f5", "g5", "h5",
+    "a6", "b6", "c6", "d6", "e6", "f6", "g6", "h6",
+    "a7", "b7", "c7", "d7", "e7", "f7", "g7", "h7",
+    "a8", "b8", "c8", "d8", "e8", "f8", "g8", "h8",
+];
+
+/// Algebraic name of a square, e.g. "e4" for 28; None past 63
+pub fn square_to_name(sq: Square) -> Option<&'static str> {
+    SQUARE_NAMES.get(sq as usize).copied()
+}
+
+/// Square of an algebraic name such as "e4", in lowercase
+pub fn name_to_square(name: &str) -> Option<Square> {
+    match name.as_bytes() {
+        &[file @ b'a'..=b'h', rank @ b'1'..=b'8'] => Some((rank - b'1') * 8 + (file - b'a')),
+        _ => None,
+    }
+}
+
+/// Uppercase letter of a piece, as used in SAN and drops
+fn piece_letter(piece: Piece) -> char {
+    match piece {
+        Piece::Pawn => 'P',
+        Piece::Knight => 'N',
+        Piece::Bishop => 'B',
+        Piece::Rook => 'R',
+        Piece::Queen => 'Q',
+        Piece::King => 'K',
+    }
+}
+
+fn piece_from_letter(c: char) -> Option<Piece> {
+    match c.to_ascii_uppercase() {
+        'P' => Some(Piece::Pawn),
+        'N' => Some(Piece::Knight),
+        'B' => Some(Piece::Bishop),
+        'R' => Some(Piece::Rook),
+        'Q' => Some(Piece::Queen),
+        'K' => Some(Piece::King),
+        _ => None,
+    }
+}
+
+/// UCI notation of a move: "e2e4", "e7e8n" for promotions, "N@e4" for drops.
+/// A move from or to a square off the board is the null move "0000".
+pub fn to_uci(mv: &Move) -> String {
+    let (Some(from), Some(to)) = (square_to_name(mv.from), square_to_name(mv.to)) else {
+        return "0000".to_string();
+    };
+    if let Some(piece) = mv.drop {
+        return format!("{}@{}", piece_letter(piece), to);
+    }
+    let mut uci = format!("{}{}", from, to);
+    if let Some(piece) = mv.promotion {
+        uci.push(piece_letter(piece).to_ascii_lowercase());
+    }
+    uci
+}
+
+/// UCI text of a move as standard chess GUIs expect it: castling is written
+/// as the king's two-square move ("e1g1") when the king and rook stand on
+/// their standard squares, and as the king taking its rook otherwise, as in
+/// Chess960 UCI
+pub fn to_standard_uci(board: &Board, mv: &Move) -> String {
+    if board.is_castling(mv) && mv.from % 8 == 4 && matches!(mv.to % 8, 0 | 7) {
+        let (king_to, _) = mv.castling_squares();
+        return to_uci(&Move { to: king_to, ..mv.clone() });
+    }
+    to_uci(mv)
+}
+
+/// `to_standard_uci` of each move of a line played from `board`
+pub fn line_to_standard_uci(board: &Board, moves: &[Move]) -> Vec<String> {
+    let mut board = board.clone();
+    moves.iter()
+        .map(|mv| {
+            let uci = to_standard_uci(&board, mv);
+            board.make_move(mv);
+            uci
+        })
+        .collect()
+}
+
+/// Parse a UCI move and check that it is legal in the position, including
+/// the promotion piece: a pawn reaching the last rank must name one, and
+/// underpromotions ("e7e8n") are accepted like any other. Castling may be
+/// given as the king taking its rook ("e1h1") or as the king's destination
+/// ("e1g1").
+pub fn parse_uci(board: &Board, uci: &str) -> Result<Move, ChessError> {
+    let mv = parse_uci_text(uci)?;
+    let legal = generate_legal_moves(board);
+    if legal.contains(&mv) {
+        return Ok(mv);
+    }
+    legal.into_iter()
+        .find(|castle| {
+            board.is_castling(castle) && castle.from == mv.from && castle.castling_squares().0 == mv.to
+                && mv.promotion.is_none() && mv.drop.is_none()
+        })
+        .ok_or_else(|| ChessError::IllegalMove(uci.to_string()))
+}
+
+/// `parse_uci`, filling in `promotion` when a pawn move to the last rank
+/// leaves out the piece ("e7e8"); with None such a move is an error saying
+/// the piece is missing
+pub fn parse_uci_promoting(board: &Board, uci: &str, promotion: Option<Piece>) -> Result<Move, ChessError> {
+    let mut mv = parse_uci_text(uci)?;
+    if mv.promotion.is_none() && mv.drop.is_none()
+        && generate_legal_moves(board).iter().any(|legal| legal.from == mv.from && legal.to == mv.to && legal.promotion.is_some())
+    {
+        mv.promotion = Some(promotion.ok_or_else(|| ChessError::IllegalMove(format!("{} needs a promotion piece", uci)))?);
+    }
+    parse_uci(board, &to_uci(&mv))
+}
+
+/// Read a UCI move without checking it against a position
+pub(crate) fn parse_uci_text(uci: &str) -> Result<Move, ChessError> {
+    let malformed = || ChessError::ParseError(format!("Invalid UCI move: {}", uci));
+
+    Ok(if let Some((piece, to)) = uci.split_once('@') {
+        let mut letters = piece.chars();
+        let piece = match (letters.next(), letters.next()) {
+            (Some(c), None) => piece_from_letter(c).ok_or_else(malformed)?,
+            (None, _) => Piece::Pawn,
+            _ => return Err(malformed()),
+        };
+        let to = name_to_square(to).ok_or_else(malformed)?;
+        Move { from: to, to, promotion: None, drop: Some(piece) }
+    } else {
+        if !(4..=5).contains(&uci.len()) || !uci.is_ascii() {
+            return Err(malformed());
+        }
+        let from = name_to_square(&uci[0..2]).ok_or_else(malformed)?;
+        let to = name_to_square(&uci[2..4]).ok_or_else(malformed)?;
+        let promotion = match uci[4..].chars().next() {
+            Some(c) => match piece_from_letter(c) {
+                Some(piece @ (Piece::Knight | Piece::Bishop | Piece::Rook | Piece::Queen)) => Some(piece),
+                _ => return Err(malformed()),
+            },
+            None => None,
+        };
+        Move { from, to, promotion, drop: None }
+    })
+}
+
+/// Parse the UCI move played at `ply` (counting from 1), naming the move
+/// and the ply in the error; `promotion` is as for `parse_uci_promoting`
+pub(crate) fn parse_uci_at(board: &Board, uci: &str, ply: usize, promotion: Option<Piece>) -> Result<Move, ChessError> {
+    parse_uci_promoting(board, uci, promotion).map_err(|e| match e {
+        ChessError::IllegalMove(reason) if reason.ends_with("needs a promotion piece") => {
+            ChessError::IllegalMove(format!("{} at ply {}", reason, ply))
+        }
+        ChessError::IllegalMove(_) => ChessError::IllegalMove(format!("{} at ply {}", uci, ply)),
+        ChessError::ParseError(_) => ChessError::ParseError(format!("Invalid UCI move: {} at ply {}", uci, ply)),
+        e => e,
+    })
+}
+
+/// Play a sequence of UCI moves from `board`, checking each one, and return
+/// the position after every move. The error of the first bad move names it
+/// and its ply, counting from 1.
+pub fn replay_uci<S: AsRef<str>>(board: &Board, moves: &[S]) -> Result<Vec<Board>, ChessError> {
+    let mut board = board.clone();
+    let mut positions = Vec::with_capacity(moves.len());
+    for (ply, uci) in moves.iter().enumerate() {
+        let mv = parse_uci_at(&board, uci.as_ref(), ply + 1, None)?;
+        board.make_move(&mv);
+        positions.push(board.clone());
+    }
+    Ok(positions)
+}
+
+/// Standard Algebraic Notation of a legal move: piece letter, the file or
+/// rank (or both) needed to tell it apart from another piece of the same
+/// kind, 'x' for captures, "=Q" for promotions and '+' or '#' for check and
+/// checkmate. Castling is "O-O" or "O-O-O", drops are "N@f3" and "@f3".
+pub fn to_san(board: &Board, mv: &Move) -> String {
+    let to = square_to_name(mv.to).unwrap_or_default();
+    let mut san = if let Some(piece) = mv.drop {
+        match piece {
+            Piece::Pawn => format!("@{}", to),
+            _ => format!("{}@{}", piece_letter(piece), to),
+        }
+    } else if board.is_castling(mv) {
+        if mv.to > mv.from { "O-O".to_string() } else { "O-O-O".to_string() }
+    } else {
+        let piece = board.squares()[mv.from as usize].map_or(Piece::Pawn, |(piece, _)| piece);
+        let capture = board.squares()[mv.to as usize].is_some() || (piece == Piece::Pawn && mv.from % 8 != mv.to % 8);
+        let mut san = String::new();
+        if piece == Piece::Pawn {
+            if capture {
+                san.push((b'a' + mv.from % 8) as char);
+            }
+        } else {
+            san.push(piece_letter(piece));
+            let rivals: Vec<Move> = generate_legal_moves(board).into_iter()
+                .filter(|other| {
+                    other.drop.is_none() && other.to == mv.to && other.from != mv.from
+                        && board.squares()[other.from as usize].map(|(p, _)| p) == Some(piece)
+                })
+                .collect();
+            if !rivals.is_empty() {
+                let square = square_to_name(mv.from).unwrap_or_default();
+                if rivals.iter().all(|other| other.from % 8 != mv.from % 8) {
+                    san.push_str(&square[..1]);
+                } else if rivals.iter().all(|other| other.from / 8 != mv.from / 8) {
+                    san.push_str(&square[1..]);
+                } else {
+                    san.push_str(square);
+                }
+            }
+        }
+        if capture {
+            san.push('x');
+        }
+        san.push_str(to);
+        if let Some(promotion) = mv.promotion {
+            san.push('=');
+            san.push(piece_letter(promotion));
+        }
+        san
+    };
+
+    let mut after = board.clone();
+    after.make_move(mv);
+    if is_checkmate(&after) {
+        san.push('#');
+    } else if is_in_check(&after, after.side_to_move) {
+        san.push('+');
+    }
+    san
+}
+
+/// Parse a move in Standard Algebraic Notation ("Nbd7", "exd8=N+", "O-O",
+/// "N@f3") and find the matching legal move. Promotions must name their
+/// piece, with or without '='.
+pub fn parse_san(board: &Board, san: &str) -> Result<Move, ChessError> {
+    let malformed = || ChessError::ParseError(format!("Invalid SAN move: {}", san));
+    let text = san.trim_end_matches(['+', '#', '!', '?']);
+    let legal = generate_legal_moves(board);
+    let moving_piece = |mv: &Move| board.squares()[mv.from as usize].map(|(piece, _)| piece);
+
+    let candidates: Vec<&Move> = match text {
+        "O-O" | "0-0" | "O-O-O" | "0-0-0" => {
+            let kingside = text.len() == 3;
+            legal.iter()
+                .filter(|mv| board.is_castling(mv) && (mv.to > mv.from) == kingside)
+                .collect()
+        }
+        _ if text.contains('@') => {
+            let (piece, to) = text.split_once('@').ok_or_else(malformed)?;
+            let piece = match piece.chars().next() {
+                Some(c) => piece_from_letter(c).ok_or_else(malformed)?,
+                None => Piece::Pawn,
+            };
+            let to = name_to_square(to).ok_or_else(malformed)?;
+            legal.iter().filter(|mv| mv.drop == Some(piece) && mv.to == to).collect()
+        }
+        _ => {
+            // Promotion suffix: "=Q" or a bare trailing piece letter
+            let (body, promotion) = match text.char_indices().last() {
+                Some((i, c)) if c.is_ascii_uppercase() && i >= 2 => {
+                    let body = text[..i].trim_end_matches('=');
+                    match piece_from_letter(c) {
+                        Some(piece @ (Piece::Knight | Piece::Bishop | Piece::Rook | Piece::Queen)) => (body, Some(piece)),
+                        _ => return Err(malformed()),
+                    }
+                }
+                _ => (text, None),
+            };
+            let (piece, rest) = match body.chars().next() {
+                Some(c @ ('N' | 'B' | 'R' | 'Q' | 'K')) => (piece_from_letter(c).unwrap(), &body[1..]),
+                Some(_) => (Piece::Pawn, body),
+                None => return Err(malformed()),
+            };
+            let rest = rest.replace('x', "");
+            if rest.len() < 2 || !rest.is_ascii() {
+                return Err(malformed());
+            }
+            let to = name_to_square(&rest[rest.len() - 2..]).ok_or_else(malformed)?;
+            let mut from_file = None;
+            let mut from_rank = None;
+            for c in rest[..rest.len() - 2].chars() {
+                match c {
+                    'a'..='h' => from_file = Some(c as u8 - b'a'),
+                    '1'..='8' => from_rank = Some(c as u8 - b'1'),
+                    _ => return Err(malformed()),
+                }
+            }
+            legal.iter()
+                .filter(|mv| mv.drop.is_none() && mv.to == to && moving_piece(mv) == Some(piece))
+                .filter(|mv| from_file.is_none_or(|f| mv.from % 8 == f) && from_rank.is_none_or(|r| mv.from / 8 == r))
+                .filter(|mv| mv.promotion == promotion)
+                .collect()
+        }
+    };
+
+    match candidates.as_slice() {
+        [mv] => Ok((*mv).clone()),
+        [] => Err(ChessError::IllegalMove(san.to_string())),
+        _ => Err(ChessError::ParseError(format!("Ambiguous SAN move: {}", san))),
+    }
+}
+
+/// Board of `start_fen`, or the standard start when None
+fn start_board(start_fen: Option<&str>) -> Result<Board, ChessError> {
+    match start_fen {
+        Some(fen) => Ok(Board::from(fen.parse::<Position>()?)),
+        None => Ok(starting_board(Variant::Standard)),
+    }
+}
+
+/// SAN of every move of a game given in UCI, replaying it once. The error
+/// of the first bad move names it and its ply, counting from 1.
+pub fn sans_for_game<S: AsRef<str>>(start_fen: Option<&str>, uci_moves: &[S]) -> Result<Vec<String>, ChessError> {
+    let mut board = start_board(start_fen)?;
+    let mut sans = Vec::with_capacity(uci_moves.len());
+    for (ply, uci) in uci_moves.iter().enumerate() {
+        let mv = parse_uci_at(&board, uci.as_ref(), ply + 1, None)?;
+        sans.push(to_san(&board, &mv));
+        board.make_move(&mv);
+    }
+    Ok(sans)
+}
+
+/// Standard UCI of every move of a game given in SAN, replaying it once.
+/// The error of the first bad move names it and its ply, counting from 1.
+pub fn ucis_for_game<S: AsRef<str>>(start_fen: Option<&str>, san_moves: &[S]) -> Result<Vec<String>, ChessError> {
+    let mut board = start_board(start_fen)?;
+    let mut ucis = Vec::with_capacity(san_moves.len());
+    for (ply, san) in san_moves.iter().enumerate() {
+        let san = san.as_ref();
+        let mv = parse_san(&board, san).map_err(|e| match e {
+            ChessError::IllegalMove(_) => ChessError::IllegalMove(format!("{} at ply {}", san, ply + 1)),
+            ChessError::ParseError(message) => ChessError::ParseError(format!("{} at ply {}", message, ply + 1)),
+            e => e,
+        })?;
+        ucis.push(to_standard_uci(&board, &mv));
+        board.make_move(&mv);
+    }
+    Ok(ucis)
+}
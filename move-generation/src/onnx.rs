@@ -0,0 +1,94 @@
+//! Value networks in ONNX format as evaluators (requires the `onnx` feature).
+//!
+//! The ONNX Runtime library is loaded when the first model is, from
+//! `ORT_DYLIB_PATH` or the system library path.
+
+use crate::encoding::{encode_board, PLANES};
+use crate::eval::Evaluator;
+use crate::pgn::ChessError;
+use crate::score::{Score, MATE_CP};
+use crate::types::Board;
+use ort::session::Session;
+use ort::value::Tensor;
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+
+fn onnx_error(err: ort::Error) -> ChessError {
+    ChessError::IoError(io::Error::other(err.to_string()))
+}
+
+/// What the network's single output value means, for the side to move
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ValueOutput {
+    /// Expected result from -1 (loss) to 1 (win), as trained on the value
+    /// targets of `PyPositionDataset`
+    #[default]
+    ExpectedResult,
+    Centipawns,
+}
+
+impl ValueOutput {
+    /// Score for one output value; expected results are mapped to
+    /// centipawns by inverting `accuracy::winning_chances`
+    pub fn to_score(self, value: f32) -> Score {
+        let cp = match self {
+            ValueOutput::ExpectedResult => {
+                let v = value.clamp(-0.9999, 0.9999) as f64;
+                ((1.0 + v) / (1.0 - v)).ln() / 0.00368208
+            }
+            ValueOutput::Centipawns => value as f64,
+        };
+        Score::Cp(cp.round().clamp(1.0 - MATE_CP as f64, MATE_CP as f64 - 1.0) as i32)
+    }
+}
+
+/// Evaluator running an ONNX value network on `encode_board` planes: input
+/// of shape (batch, 18, 8, 8), output of one value per position
+pub struct OnnxEvaluator {
+    session: Mutex<Session>,
+    input: String,
+    output: ValueOutput,
+}
+
+impl OnnxEvaluator {
+    /// Load a model; its first input receives the planes and its first
+    /// output is read as `ValueOutput::ExpectedResult`
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ChessError> {
+        let session = Session::builder().and_then(|builder| builder.commit_from_file(path)).map_err(onnx_error)?;
+        let input = session.inputs.first()
+            .ok_or_else(|| ChessError::ParseError("model has no inputs".to_string()))?
+            .name.clone();
+        if session.outputs.is_empty() {
+            return Err(ChessError::ParseError("model has no outputs".to_string()));
+        }
+        Ok(OnnxEvaluator { session: Mutex::new(session), input, output: ValueOutput::default() })
+    }
+
+    /// Read the output as `output` instead
+    pub fn with_output(mut self, output: ValueOutput) -> Self {
+        self.output = output;
+        self
+    }
+}
+
+impl Evaluator for OnnxEvaluator {
+    fn evaluate(&self, board: &Board) -> Result<Score, ChessError> {
+        Ok(self.evaluate_batch(std::slice::from_ref(board))?[0])
+    }
+
+    fn evaluate_batch(&self, boards: &[Board]) -> Result<Vec<Score>, ChessError> {
+        if boards.is_empty() {
+            return Ok(Vec::new());
+        }
+        let planes: Vec<f32> = boards.iter().flat_map(encode_board).collect();
+        let tensor = Tensor::from_array(([boards.len(), PLANES, 8, 8], planes)).map_err(onnx_error)?;
+        let mut session = self.session.lock().unwrap_or_else(|e| e.into_inner());
+        let outputs = session.run(ort::inputs![self.input.as_str() => tensor]).map_err(onnx_error)?;
+        let (_, values) = outputs[0].try_extract_tensor::<f32>().map_err(onnx_error)?;
+        if values.len() != boards.len() {
+            return Err(ChessError::ParseError(format!("model returned {} values for {} positions", values.len(), boards.len())));
+        }
+        Ok(values.iter().map(|&value| self.output.to_score(value)).collect())
+    }
+}
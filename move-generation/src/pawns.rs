@@ -0,0 +1,148 @@
+//! Pawn structure: doubled, isolated, backward and passed pawns and pawn
+//! islands of each side, with a table caching them by pawn key
+
+use crate::move_gen::{pawn_attacks, Bitboard};
+use crate::types::{Board, Color, Piece};
+use crate::zobrist::pawn_key;
+
+const FILE_A: u64 = 0x0101_0101_0101_0101;
+
+/// Pawn structure of one side, as squares of the pawns concerned
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PawnReport {
+    /// Pawns sharing their file with another pawn of their side
+    pub doubled: Bitboard,
+    /// Pawns with no pawn of their side on the files beside them
+    pub isolated: Bitboard,
+    /// Pawns that no pawn of their side on a neighbouring file can support,
+    /// being level with it or ahead, and whose square in front is attacked
+    /// by an enemy pawn
+    pub backward: Bitboard,
+    /// Pawns with no enemy pawn in front of them on their file or the files
+    /// beside it
+    pub passed: Bitboard,
+    /// Groups of neighbouring files holding pawns of this side
+    pub islands: u32,
+}
+
+/// Pawn structure of both sides
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PawnStructure {
+    pub white: PawnReport,
+    pub black: PawnReport,
+}
+
+impl PawnStructure {
+    pub fn side(&self, color: Color) -> &PawnReport {
+        match color {
+            Color::White => &self.white,
+            Color::Black => &self.black,
+        }
+    }
+}
+
+/// Analyse the pawns of both sides
+pub fn pawn_structure(board: &Board) -> PawnStructure {
+    PawnStructure { white: pawn_report(board, Color::White), black: pawn_report(board, Color::Black) }
+}
+
+fn pawn_report(board: &Board, color: Color) -> PawnReport {
+    let own = u64::from(board.pieces(Piece::Pawn, color));
+    let enemy = u64::from(board.pieces(Piece::Pawn, color.opposite()));
+    let mut report = PawnReport {
+        doubled: Bitboard::empty(),
+        isolated: Bitboard::empty(),
+        backward: Bitboard::empty(),
+        passed: Bitboard::empty(),
+        islands: 0,
+    };
+
+    let mut files = 0u8;
+    for sq in Bitboard::from(own).bits() {
+        let (file, rank) = (sq % 8, sq / 8);
+        files |= 1 << file;
+        let file_mask = FILE_A << file;
+        let beside = ((file_mask << 1) & !FILE_A) | ((file_mask >> 1) & !(FILE_A << 7));
+        // Ranks strictly ahead of the pawn, and ranks level with it or behind
+        let (ahead, level_or_behind) = match color {
+            Color::White if rank == 7 => (0, !0),
+            Color::White => (!0u64 << (8 * (rank + 1)), (1u64 << (8 * (rank + 1))) - 1),
+            Color::Black => ((1u64 << (8 * rank)) - 1, !0u64 << (8 * rank)),
+        };
+
+        if (own & file_mask).count_ones() > 1 {
+            report.doubled.set(sq);
+        }
+        let isolated = own & beside == 0;
+        if isolated {
+            report.isolated.set(sq);
+        }
+        if enemy & (file_mask | beside) & ahead == 0 {
+            report.passed.set(sq);
+        }
+        let stop = match color {
+            Color::White => 1u64.checked_shl(sq as u32 + 8).unwrap_or(0),
+            Color::Black => (1u64 << sq) >> 8,
+        };
+        if !isolated && own & beside & level_or_behind == 0 && pawn_attacks(stop, color) & enemy != 0 {
+            report.backward.set(sq);
+        }
+    }
+    report.islands = (files & !(files << 1)).count_ones();
+    report
+}
+
+/// Always-replace cache of pawn structures by `zobrist::pawn_key`, which
+/// changes far less often than the position does
+pub struct PawnTable {
+    entries: Vec<Option<(u64, PawnStructure)>>,
+}
+
+impl PawnTable {
+    /// Table of about `size_kb` kilobytes, with at least one slot
+    pub fn new(size_kb: usize) -> Self {
+        let len = (size_kb * 1024 / std::mem::size_of::<Option<(u64, PawnStructure)>>()).max(1);
+        PawnTable { entries: vec![None; len] }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn probe(&self, key: u64) -> Option<PawnStructure> {
+        match self.entries[self.slot(key)] {
+            Some((stored, structure)) if stored == key => Some(structure),
+            _ => None,
+        }
+    }
+
+    pub fn store(&mut self, key: u64, structure: PawnStructure) {
+        let slot = self.slot(key);
+        self.entries[slot] = Some((key, structure));
+    }
+
+    /// `pawn_structure` of `board`, from the table when its pawns were seen
+    /// before
+    pub fn pawn_structure(&mut self, board: &Board) -> PawnStructure {
+        let key = pawn_key(board);
+        if let Some(structure) = self.probe(key) {
+            return structure;
+        }
+        let structure = pawn_structure(board);
+        self.store(key, structure);
+        structure
+    }
+
+    /// Forget every entry
+    pub fn clear(&mut self) {
+        self.entries.fill(None);
+    }
+
+    fn slot(&self, key: u64) -> usize {
+        (key % self.entries.len() as u64) as usize
+    }
+}
@@ -0,0 +1,106 @@
+//! Perft: counting leaf nodes of the legal move tree
+
+use crate::batch::map_fens;
+use crate::counters::{count, Counter};
+use crate::pgn::ChessError;
+use crate::move_gen::MoveList;
+use crate::rules::{generate_legal_moves, generate_legal_moves_into};
+use crate::types::Board;
+
+/// Count the leaf nodes of the legal move tree `depth` plies deep
+pub fn perft(board: &Board, depth: u32) -> u64 {
+    let mut board = board.clone();
+    let mut lists = vec![MoveList::new(); depth as usize];
+    perft_inner(&mut board, depth, &mut lists)
+}
+
+/// `lists` holds one move buffer per remaining ply, reused across siblings
+fn perft_inner(board: &mut Board, depth: u32, lists: &mut [MoveList]) -> u64 {
+    count(Counter::Nodes, 1);
+    let Some((moves, deeper)) = lists.split_first_mut() else { return 1 };
+    generate_legal_moves_into(board, moves);
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+    let mut nodes = 0;
+    for mv in moves.iter() {
+        let undo = board.make_move(mv);
+        nodes += perft_inner(board, depth - 1, deeper);
+        board.unmake_move(mv, undo);
+    }
+    nodes
+}
+
+/// Perft of many positions at once, one position per rayon worker, for
+/// regression suites; node counts come back in input order
+pub fn perft_many<S: AsRef<str> + Sync>(fens: &[S], depth: u32) -> Vec<Result<u64, ChessError>> {
+    map_fens(fens, |board| perft(board, depth))
+}
+
+#[derive(Copy, Clone, Default)]
+struct PerftEntry {
+    key: u64,
+    depth: u32,
+    nodes: u64,
+}
+
+/// Always-replace table of (Zobrist key, depth) -> node count
+struct PerftTable {
+    entries: Vec<PerftEntry>,
+}
+
+impl PerftTable {
+    fn new(hash_mb: usize) -> Self {
+        let len = (hash_mb * 1024 * 1024 / std::mem::size_of::<PerftEntry>()).max(1);
+        PerftTable { entries: vec![PerftEntry::default(); len] }
+    }
+
+    fn probe(&self, key: u64, depth: u32) -> Option<u64> {
+        let entry = &self.entries[(key % self.entries.len() as u64) as usize];
+        // Depth 0 is never stored, so an empty slot cannot match
+        let hit = entry.key == key && entry.depth == depth;
+        count(Counter::TtProbes, 1);
+        count(Counter::TtHits, hit as u64);
+        hit.then_some(entry.nodes)
+    }
+
+    fn store(&mut self, key: u64, depth: u32, nodes: u64) {
+        let len = self.entries.len() as u64;
+        self.entries[(key % len) as usize] = PerftEntry { key, depth, nodes };
+    }
+}
+
+/// Perft with a transposition table of `hash_mb` megabytes caching node
+/// counts by (Zobrist key, depth). Gives the same result as `perft` unless
+/// two positions collide on the full 64-bit key.
+pub fn perft_hashed(board: &Board, depth: u32, hash_mb: usize) -> u64 {
+    let mut table = PerftTable::new(hash_mb);
+    let mut board = board.clone();
+    board.refresh_hash();
+    perft_hashed_inner(&mut board, depth, &mut table)
+}
+
+fn perft_hashed_inner(board: &mut Board, depth: u32, table: &mut PerftTable) -> u64 {
+    count(Counter::Nodes, 1);
+    if depth == 0 {
+        return 1;
+    }
+    let moves = generate_legal_moves(board);
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+
+    let key = board.hash;
+    if let Some(nodes) = table.probe(key, depth) {
+        return nodes;
+    }
+
+    let mut nodes = 0;
+    for mv in &moves {
+        let undo = board.make_move(mv);
+        nodes += perft_hashed_inner(board, depth - 1, table);
+        board.unmake_move(mv, undo);
+    }
+    table.store(key, depth, nodes);
+    nodes
+}
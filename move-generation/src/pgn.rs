@@ -1,12 +1,18 @@
-use crate::types::{Piece, Color};
+use crate::types::{castling_letter, square_to_name, Board, CastlingRights, Move, Piece, Color, Variant};
+use crate::rules::{has_legal_en_passant, is_checkmate, is_stalemate, validate_board};
+use crate::notation::{parse_san, parse_uci_at, to_san};
+use crate::game::board_outcome;
+use serde::Serialize;
 use std::str::FromStr;
 use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::io::{self, BufRead};
 
 #[derive(Debug)]
 pub enum ChessError {
     ParseError(String),
     IoError(io::Error),
+    IllegalMove(String),
 }
 
 impl From<io::Error> for ChessError {
@@ -20,6 +26,7 @@ impl fmt::Display for ChessError {
         match self {
             ChessError::ParseError(s) => write!(f, "Parse error: {}", s),
             ChessError::IoError(e) => write!(f, "IO error: {}", e),
+            ChessError::IllegalMove(s) => write!(f, "Illegal move: {}", s),
         }
     }
 }
@@ -28,15 +35,78 @@ impl fmt::Display for ChessError {
 pub struct Position {
     pub pieces: Vec<(Piece, Color, u8)>,  // (piece, color, square)
     pub side_to_move: Color,
-    pub castling_rights: String,
+    pub castling_rights: CastlingRights,
     pub en_passant: Option<u8>,
     pub halfmove_clock: u32,
     pub fullmove_number: u32,
+    pub pockets: Option<[[u8; 5]; 2]>, // Crazyhouse pockets, written as "[QNpp]" after the board
+    pub promoted: u64,                 // Crazyhouse promoted pieces, written with a '~' suffix
+    pub checks: Option<[u8; 2]>,       // Three-check checks given, written as remaining checks "3+3"
+}
+
+/// FEN letter of a piece
+fn piece_char(piece: Piece, color: Color) -> char {
+    let c = match piece {
+        Piece::Pawn => 'p',
+        Piece::Knight => 'n',
+        Piece::Bishop => 'b',
+        Piece::Rook => 'r',
+        Piece::Queen => 'q',
+        Piece::King => 'k',
+    };
+    match color {
+        Color::White => c.to_ascii_uppercase(),
+        Color::Black => c,
+    }
+}
+
+/// Piece and color for a FEN letter
+fn piece_from_char(c: char) -> Option<(Piece, Color)> {
+    let piece = match c.to_ascii_lowercase() {
+        'p' => Piece::Pawn,
+        'n' => Piece::Knight,
+        'b' => Piece::Bishop,
+        'r' => Piece::Rook,
+        'q' => Piece::Queen,
+        'k' => Piece::King,
+        _ => return None,
+    };
+    let color = if c.is_ascii_uppercase() { Color::White } else { Color::Black };
+    Some((piece, color))
 }
 
 impl Position {
+    /// FEN of the position. Castling rights are written as KQkq, falling
+    /// back to the rook's file when another rook stands further out on the
+    /// same side, as X-FEN does.
     pub fn to_fen(&self) -> String {
-        let mut board = vec![None; 64];
+        self.fen_with_castling(self.castling_field(false))
+    }
+
+    /// FEN with castling rights written as rook files, as Shredder-FEN does:
+    /// "HAha" for the standard start
+    pub fn to_shredder_fen(&self) -> String {
+        self.fen_with_castling(self.castling_field(true))
+    }
+
+    fn castling_field(&self, shredder: bool) -> String {
+        if self.castling_rights.is_empty() {
+            return "-".to_string();
+        }
+        self.castling_rights.iter().map(|(color, kingside, file)| {
+            if shredder {
+                let letter = (b'A' + file) as char;
+                return if color == Color::White { letter } else { letter.to_ascii_lowercase() };
+            }
+            let rank = if color == Color::White { 0 } else { 56 };
+            let rook_files = (0..8).filter(|&f| self.pieces.contains(&(Piece::Rook, color, rank + f)))
+                .fold(0u8, |files, f| files | 1 << f);
+            castling_letter(color, kingside, file, rook_files)
+        }).collect()
+    }
+
+    fn fen_with_castling(&self, castling: String) -> String {
+        let mut board = [None; 64];
         for &(piece, color, square) in &self.pieces {
             board[square as usize] = Some((piece, color));
         }
@@ -57,21 +127,10 @@ impl Position {
                             fen.push_str(&empty.to_string());
                             empty = 0;
                         }
-                        let c = match (piece, color) {
-                            (Piece::Pawn, Color::White) => 'P',
-                            (Piece::Knight, Color::White) => 'N',
-                            (Piece::Bishop, Color::White) => 'B',
-                            (Piece::Rook, Color::White) => 'R',
-                            (Piece::Queen, Color::White) => 'Q',
-                            (Piece::King, Color::White) => 'K',
-                            (Piece::Pawn, Color::Black) => 'p',
-                            (Piece::Knight, Color::Black) => 'n',
-                            (Piece::Bishop, Color::Black) => 'b',
-                            (Piece::Rook, Color::Black) => 'r',
-                            (Piece::Queen, Color::Black) => 'q',
-                            (Piece::King, Color::Black) => 'k',
-                        };
-                        fen.push(c);
+                        fen.push(piece_char(piece, color));
+                        if self.promoted & (1 << square) != 0 {
+                            fen.push('~');
+                        }
                     }
                 }
             }
@@ -81,21 +140,33 @@ impl Position {
             }
         }
 
+        if let Some(pockets) = &self.pockets {
+            fen.push('[');
+            for color in [Color::White, Color::Black] {
+                for piece in [Piece::Queen, Piece::Rook, Piece::Bishop, Piece::Knight, Piece::Pawn] {
+                    for _ in 0..pockets[color.index()][piece.index()] {
+                        fen.push(piece_char(piece, color));
+                    }
+                }
+            }
+            fen.push(']');
+        }
+
         fen.push(' ');
         fen.push(match self.side_to_move {
             Color::White => 'w',
             Color::Black => 'b',
         });
         fen.push(' ');
-        fen.push_str(&self.castling_rights);
+        fen.push_str(&castling);
         fen.push(' ');
-        if let Some(ep) = self.en_passant {
-            let file = (b'a' + (ep % 8)) as char;
-            let rank = (b'1' + (ep / 8)) as char;
-            fen.push(file);
-            fen.push(rank);
-        } else {
-            fen.push_str("-");
+        // An en passant square off the board is written as none
+        match self.en_passant.and_then(square_to_name) {
+            Some(name) => fen.push_str(name),
+            None => fen.push('-'),
+        }
+        if let Some(checks) = self.checks {
+            fen.push_str(&format!(" {}+{}", 3u8.saturating_sub(checks[0]), 3u8.saturating_sub(checks[1])));
         }
         fen.push(' ');
         fen.push_str(&self.halfmove_clock.to_string());
@@ -105,127 +176,1103 @@ impl Position {
     }
 }
 
+/// Positions compare and hash as the boards they set up: by the repetition
+/// rule, ignoring the clocks and unusable en passant squares
+impl PartialEq for Position {
+    fn eq(&self, other: &Position) -> bool {
+        Board::from(self.clone()) == Board::from(other.clone())
+    }
+}
+
+impl Eq for Position {}
+
+impl Hash for Position {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Board::from(self.clone()).hash(state);
+    }
+}
+
+impl Position {
+    /// Equality of the boards the positions set up, clocks included
+    pub fn eq_strict(&self, other: &Position) -> bool {
+        Board::from(self.clone()).eq_strict(&Board::from(other.clone()))
+    }
+}
+
 impl FromStr for Position {
     type Err = ChessError;
 
+    /// Parse a FEN strictly: exactly eight ranks of eight files, at most one
+    /// king per color, castling rights from `KQkq`, Shredder-FEN rook files
+    /// (`A`-`H` for White, `a`-`h` for Black) or `-`, and an en passant
+    /// square on the third or sixth rank. EPD-style FENs without the clocks,
+    /// or with only the halfmove clock, default them to 0 and 1.
     fn from_str(fen: &str) -> Result<Self, Self::Err> {
-        let parts: Vec<&str> = fen.split_whitespace().collect();
-        if parts.len() < 6 {
-            return Err(ChessError::ParseError("Invalid FEN: not enough fields".into()));
+        parse_fen(fen, true)
+    }
+}
+
+impl Position {
+    /// Parse a FEN as found in the wild: the clocks may be unreadable
+    /// (defaulting to 0 and 1, and a fullmove number of 0 reads as 1), short
+    /// ranks are padded with empty squares, unknown or repeated castling
+    /// letters and a malformed en passant square are dropped, and extra
+    /// kings are kept. Squares off the board are still errors.
+    pub fn parse_lenient(fen: &str) -> Result<Position, ChessError> {
+        parse_fen(fen, false)
+    }
+}
+
+fn parse_fen(fen: &str, strict: bool) -> Result<Position, ChessError> {
+    let invalid = |what: String| ChessError::ParseError(format!("Invalid FEN: {}", what));
+    let mut parts: Vec<&str> = fen.split_whitespace().collect();
+
+    // Three-check counters: remaining checks "3+2" after the en passant
+    // square, or checks given "+0+1" after the move number
+    let mut checks = None;
+    if parts.len() >= 5 && parts[4].contains('+') {
+        let remaining = parse_check_counts(parts.remove(4))?;
+        checks = Some([3u8.saturating_sub(remaining[0]), 3u8.saturating_sub(remaining[1])]);
+    } else if parts.len() >= 7 && parts[6].starts_with('+') {
+        checks = Some(parse_check_counts(&parts.remove(6)[1..])?);
+    }
+
+    if parts.len() < 4 {
+        return Err(invalid("not enough fields".into()));
+    }
+    if strict && parts.len() > 6 {
+        return Err(invalid(format!("unexpected field {:?}", parts[6])));
+    }
+
+    // Crazyhouse pockets follow the board in brackets
+    let (placement, pockets) = match parts[0].split_once('[') {
+        Some((board, pocket_str)) => {
+            let pocket_str = pocket_str.strip_suffix(']')
+                .ok_or_else(|| invalid("unterminated pocket".into()))?;
+            let mut pockets = [[0u8; 5]; 2];
+            for c in pocket_str.chars().filter(|&c| c != '-') {
+                match piece_from_char(c) {
+                    Some((piece, color)) if piece != Piece::King => {
+                        let count = &mut pockets[color.index()][piece.index()];
+                        *count = count.checked_add(1).ok_or_else(|| invalid("pocket too large".into()))?;
+                    }
+                    _ => return Err(invalid(format!("invalid pocket piece {:?}", c))),
+                }
+            }
+            (board, Some(pockets))
         }
+        None => (parts[0], None),
+    };
+
+    let (pieces, promoted) = parse_placement(placement, pockets.is_some(), strict)?;
+
+    let side_to_move = match parts[1] {
+        "w" => Color::White,
+        "b" => Color::Black,
+        other => return Err(invalid(format!("invalid side to move {:?}", other))),
+    };
+
+    let castling_rights = parse_castling(parts[2], &pieces, strict)
+        .ok_or_else(|| invalid(format!("invalid castling rights {:?}", parts[2])))?;
+
+    let en_passant = match (parse_en_passant(parts[3]), strict) {
+        (Some(square), _) => square,
+        (None, true) => return Err(invalid(format!("invalid en passant square {:?}", parts[3]))),
+        (None, false) => None,
+    };
+
+    let clock = |index: usize, default: u32| match parts.get(index).map(|field| field.parse::<u32>()) {
+        Some(Ok(value)) => Ok(value),
+        Some(Err(_)) if strict => Err(invalid(format!("invalid clock {:?}", parts[index]))),
+        _ => Ok(default),
+    };
+    let halfmove_clock = clock(4, 0)?;
+    let fullmove_number = clock(5, 1)?;
+    if strict && fullmove_number == 0 {
+        return Err(invalid("fullmove number 0".into()));
+    }
 
-        let position = parts[0];
-        let side = parts[1];
-        let castling_rights = parts[2].to_string();
-        let en_passant_str = parts[3];
-        let halfmove_clock = parts[4].parse::<u32>().unwrap_or(0);
-        let fullmove_number = parts[5].parse::<u32>().unwrap_or(1);
+    Ok(Position {
+        pieces,
+        side_to_move,
+        castling_rights,
+        en_passant,
+        halfmove_clock,
+        fullmove_number: fullmove_number.max(1),
+        pockets,
+        promoted,
+        checks,
+    })
+}
 
-        let mut pieces = Vec::new();
-        let mut rank = 7;
-        let mut file = 0;
+/// Pieces on the board, and the squares of promoted Crazyhouse pieces
+type Placement = (Vec<(Piece, Color, u8)>, u64);
 
-        for c in position.chars() {
+/// Piece placement field: ranks from the eighth down, separated by '/'.
+/// Promoted pieces are marked by a trailing '~' when there are pockets.
+fn parse_placement(placement: &str, pockets: bool, strict: bool) -> Result<Placement, ChessError> {
+    let invalid = |what: String| ChessError::ParseError(format!("Invalid FEN: {}", what));
+    let ranks: Vec<&str> = placement.split('/').collect();
+    if ranks.len() > 8 || (strict && ranks.len() != 8) {
+        return Err(invalid(format!("expected 8 ranks, found {}", ranks.len())));
+    }
+
+    let mut pieces: Vec<(Piece, Color, u8)> = Vec::new();
+    let mut promoted = 0u64;
+    let mut kings = [0; 2];
+    for (i, rank_str) in ranks.iter().enumerate() {
+        let rank = 7 - i as u8;
+        let mut file = 0u8;
+        let mut after_digit = false;
+        for c in rank_str.chars() {
             match c {
-                'P' => { pieces.push((Piece::Pawn, Color::White, rank * 8 + file)); file += 1; }
-                'N' => { pieces.push((Piece::Knight, Color::White, rank * 8 + file)); file += 1; }
-                'B' => { pieces.push((Piece::Bishop, Color::White, rank * 8 + file)); file += 1; }
-                'R' => { pieces.push((Piece::Rook, Color::White, rank * 8 + file)); file += 1; }
-                'Q' => { pieces.push((Piece::Queen, Color::White, rank * 8 + file)); file += 1; }
-                'K' => { pieces.push((Piece::King, Color::White, rank * 8 + file)); file += 1; }
-                'p' => { pieces.push((Piece::Pawn, Color::Black, rank * 8 + file)); file += 1; }
-                'n' => { pieces.push((Piece::Knight, Color::Black, rank * 8 + file)); file += 1; }
-                'b' => { pieces.push((Piece::Bishop, Color::Black, rank * 8 + file)); file += 1; }
-                'r' => { pieces.push((Piece::Rook, Color::Black, rank * 8 + file)); file += 1; }
-                'q' => { pieces.push((Piece::Queen, Color::Black, rank * 8 + file)); file += 1; }
-                'k' => { pieces.push((Piece::King, Color::Black, rank * 8 + file)); file += 1; }
-                '/' => { rank -= 1; file = 0; }
-                '1'..='8' => { file += c.to_digit(10).unwrap() as u8; }
-                _ => return Err(ChessError::ParseError("Invalid FEN character".into())),
-            }
-        }
-
-        let side_to_move = match side {
-            "w" => Color::White,
-            "b" => Color::Black,
-            _ => return Err(ChessError::ParseError("Invalid side to move".into())),
-        };
+                '1'..='8' => {
+                    if strict && after_digit {
+                        return Err(invalid(format!("adjacent empty-square counts on rank {}", rank + 1)));
+                    }
+                    file += c as u8 - b'0';
+                    after_digit = true;
+                }
+                '~' if pockets => {
+                    if let Some(&(_, _, sq)) = pieces.last() {
+                        promoted |= 1 << sq;
+                    }
+                    continue;
+                }
+                _ => {
+                    let (piece, color) = piece_from_char(c)
+                        .ok_or_else(|| invalid(format!("invalid character {:?}", c)))?;
+                    if file >= 8 {
+                        return Err(invalid(format!("rank {} has more than 8 files", rank + 1)));
+                    }
+                    pieces.push((piece, color, rank * 8 + file));
+                    if piece == Piece::King {
+                        kings[color.index()] += 1;
+                    }
+                    file += 1;
+                    after_digit = false;
+                }
+            }
+            if file > 8 {
+                return Err(invalid(format!("rank {} has more than 8 files", rank + 1)));
+            }
+        }
+        if strict && file != 8 {
+            return Err(invalid(format!("rank {} has {} files", rank + 1, file)));
+        }
+    }
+
+    if strict {
+        for (color, name) in [(Color::White, "white"), (Color::Black, "black")] {
+            if kings[color.index()] > 1 {
+                return Err(invalid(format!("{} {} kings", kings[color.index()], name)));
+            }
+        }
+    }
+    Ok((pieces, promoted))
+}
 
-        let en_passant = if en_passant_str != "-" {
-            let bytes = en_passant_str.as_bytes();
-            if bytes.len() == 2 {
-                let file = bytes[0] - b'a';
-                let rank = bytes[1] - b'1';
-                Some(rank * 8 + file)
-            } else {
-                None
+/// Castling field: '-', or at most one right per color and side written
+/// as K, Q, k and q (X-FEN: the outermost rook on that side) or as the
+/// rook's file (Shredder-FEN: A-H for White, a-h for Black). Lenient
+/// parsing drops unknown letters and repeated rights instead of failing.
+fn parse_castling(field: &str, pieces: &[(Piece, Color, u8)], strict: bool) -> Option<CastlingRights> {
+    let mut rights = CastlingRights::NONE;
+    if field == "-" {
+        return Some(rights);
+    }
+    for c in field.chars() {
+        let color = if c.is_ascii_uppercase() { Color::White } else { Color::Black };
+        let rank = if color == Color::White { 0 } else { 56 };
+        let king_file = pieces.iter()
+            .find(|&&(piece, c, sq)| piece == Piece::King && c == color && sq / 8 == rank / 8)
+            .map(|&(_, _, sq)| sq % 8);
+        let is_rook = |file: &u8| pieces.contains(&(Piece::Rook, color, rank + file));
+        let right = match c.to_ascii_lowercase() {
+            'k' => Some((true, king_file.and_then(|k| (k + 1..8).rev().find(is_rook)).unwrap_or(7))),
+            'q' => Some((false, king_file.and_then(|k| (0..k).find(is_rook)).unwrap_or(0))),
+            letter @ 'a'..='h' => {
+                let file = letter as u8 - b'a';
+                let king_file = king_file.unwrap_or(4);
+                (file != king_file).then_some((file > king_file, file))
             }
+            _ => None,
+        };
+        match right {
+            Some((kingside, file)) if !rights.has(color, kingside) => rights.set(color, kingside, file),
+            _ if strict => return None,
+            _ => {}
+        }
+    }
+    if strict && rights.is_empty() {
+        return None;
+    }
+    Some(rights)
+}
+
+/// En passant field: `Some(None)` for '-', `Some(Some(square))` for a
+/// square on the third or sixth rank and None for anything else
+fn parse_en_passant(field: &str) -> Option<Option<u8>> {
+    if field == "-" {
+        return Some(None);
+    }
+    match field.as_bytes() {
+        &[file @ b'a'..=b'h', rank @ (b'3' | b'6')] => Some(Some((rank - b'1') * 8 + file - b'a')),
+        _ => None,
+    }
+}
+
+/// Parse a pair of Three-check counters written as "W+B"
+fn parse_check_counts(field: &str) -> Result<[u8; 2], ChessError> {
+    let invalid = || ChessError::ParseError("Invalid FEN check counter".into());
+    let (white, black) = field.split_once('+').ok_or_else(invalid)?;
+    let white = white.parse::<u8>().map_err(|_| invalid())?;
+    let black = black.parse::<u8>().map_err(|_| invalid())?;
+    Ok([white.min(3), black.min(3)])
+}
+
+impl From<Position> for Board {
+    fn from(position: Position) -> Board {
+        let mut squares = [None; 64];
+        for (piece, color, square) in position.pieces {
+            squares[square as usize] = Some((piece, color));
+        }
+        let mut board = Board::from_squares(squares);
+        board.side_to_move = position.side_to_move;
+        board.castling_rights = position.castling_rights;
+        board.en_passant = position.en_passant;
+        board.halfmove_clock = position.halfmove_clock;
+        board.fullmove_number = position.fullmove_number;
+        board.variant = if position.pockets.is_some() {
+            Variant::Crazyhouse
+        } else if position.checks.is_some() {
+            Variant::ThreeCheck
         } else {
-            None
+            Variant::Standard
         };
+        board.pockets = position.pockets.unwrap_or_default();
+        board.promoted = position.promoted;
+        board.checks = position.checks.unwrap_or_default();
+        board.refresh();
+        board
+    }
+}
 
-        Ok(Position {
+impl From<&Board> for Position {
+    fn from(board: &Board) -> Position {
+        let pieces = board.squares().iter().enumerate()
+            .filter_map(|(sq, square)| square.map(|(piece, color)| (piece, color, sq as u8)))
+            .collect();
+        Position {
             pieces,
-            side_to_move,
-            castling_rights,
-            en_passant,
-            halfmove_clock,
-            fullmove_number,
-        })
+            side_to_move: board.side_to_move,
+            castling_rights: board.castling_rights,
+            en_passant: board.en_passant,
+            halfmove_clock: board.halfmove_clock,
+            fullmove_number: board.fullmove_number,
+            pockets: (board.variant == Variant::Crazyhouse).then_some(board.pockets),
+            promoted: board.promoted,
+            checks: (board.variant == Variant::ThreeCheck).then_some(board.checks),
+        }
+    }
+}
+
+/// Starting position of a variant
+pub fn starting_board(variant: Variant) -> Board {
+    let position: Position = variant.starting_fen().parse()
+        .expect("variant starting FENs are valid");
+    let mut board = Board::from(position);
+    board.variant = variant;
+    board
+}
+
+/// Normalize a FEN for deduplication.
+///
+/// The clocks are reset to "0 1", castling rights are written in KQkq order and
+/// the en passant square is kept only if a legal en passant capture exists. With
+/// `normalize_castling`, rights whose king and rook are not on their original
+/// squares are dropped as well.
+pub fn canonical_fen(fen: &str, normalize_castling: bool) -> Result<String, ChessError> {
+    let position: Position = fen.parse()?;
+    let board = Board::from(position);
+
+    let mut position = Position::from(&board);
+    if normalize_castling {
+        for (color, kingside, file) in board.castling_rights.iter() {
+            let rank = if color == Color::White { 0 } else { 56 };
+            let rook_file = if kingside { 7 } else { 0 };
+            if file != rook_file
+                || board.squares()[rank + 4] != Some((Piece::King, color))
+                || board.squares()[rank + rook_file as usize] != Some((Piece::Rook, color)) {
+                position.castling_rights.remove(color, kingside);
+            }
+        }
+    }
+    if !has_legal_en_passant(&board) {
+        position.en_passant = None;
+    }
+    position.halfmove_clock = 0;
+    position.fullmove_number = 1;
+    Ok(position.to_fen())
+}
+
+/// How a PGN reader decodes its input
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Encoding {
+    /// Strict UTF-8; anything else is a parse error
+    #[default]
+    Utf8,
+    /// UTF-8 with invalid sequences replaced by U+FFFD
+    Lossy,
+    Latin1,
+    Windows1252,
+    /// UTF-8 for lines that are valid UTF-8, Windows-1252 for the rest
+    Auto,
+}
+
+impl FromStr for Encoding {
+    type Err = ChessError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_lowercase().replace('_', "-").as_str() {
+            "utf-8" | "utf8" => Ok(Encoding::Utf8),
+            "lossy" => Ok(Encoding::Lossy),
+            "latin-1" | "latin1" | "iso-8859-1" => Ok(Encoding::Latin1),
+            "windows-1252" | "cp1252" => Ok(Encoding::Windows1252),
+            "auto" => Ok(Encoding::Auto),
+            _ => Err(ChessError::ParseError(format!("unknown encoding: {}", name))),
+        }
+    }
+}
+
+/// Windows-1252 characters for bytes 0x80-0x9F; unassigned bytes map to the
+/// C1 control of the same value, as Latin-1 does
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '\u{20AC}', '\u{81}', '\u{201A}', '\u{192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{2C6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8D}', '\u{17D}', '\u{8F}',
+    '\u{90}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{2DC}', '\u{2122}', '\u{161}', '\u{203A}', '\u{153}', '\u{9D}', '\u{17E}', '\u{178}',
+];
+
+impl Encoding {
+    /// Decode one line of input; None if it is not valid in this encoding
+    pub fn decode(self, bytes: &[u8]) -> Option<String> {
+        let single_byte = |high: fn(u8) -> char| bytes.iter()
+            .map(|&b| if b < 0x80 { b as char } else { high(b) })
+            .collect();
+        let cp1252 = |b: u8| match b {
+            0x80..=0x9F => WINDOWS_1252_HIGH[(b - 0x80) as usize],
+            _ => b as char,
+        };
+        match self {
+            Encoding::Utf8 => std::str::from_utf8(bytes).ok().map(str::to_string),
+            Encoding::Lossy => Some(String::from_utf8_lossy(bytes).into_owned()),
+            Encoding::Latin1 => Some(single_byte(|b| b as char)),
+            Encoding::Windows1252 => Some(single_byte(cp1252)),
+            Encoding::Auto => match std::str::from_utf8(bytes) {
+                Ok(text) => Some(text.to_string()),
+                Err(_) => Some(single_byte(cp1252)),
+            },
+        }
     }
 }
 
 pub struct PgnReader<R> {
     reader: io::BufReader<R>,
-    line_buffer: String,
+    line_buffer: Vec<u8>,
+    encoding: Encoding,
+    in_moves: bool,
 }
 
 impl<R: io::Read> PgnReader<R> {
     pub fn new(reader: R) -> Self {
         PgnReader {
             reader: io::BufReader::new(reader),
-            line_buffer: String::new(),
+            line_buffer: Vec::new(),
+            encoding: Encoding::default(),
+            in_moves: false,
         }
     }
 
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Starting position of the next game: its FEN tag when set up, else
+    /// the standard start
     pub fn next_position(&mut self) -> Result<Option<Position>, ChessError> {
-        let mut in_moves = false;
         let mut fen = None;
+        let mut setup = None;
 
         loop {
             self.line_buffer.clear();
-            if self.reader.read_line(&mut self.line_buffer)? == 0 {
-                break;
+            if self.reader.read_until(b'\n', &mut self.line_buffer)? == 0 {
+                return Ok(None);
             }
 
-            let trimmed = self.line_buffer.trim();
+            let line = self.encoding.decode(&self.line_buffer)
+                .ok_or_else(|| ChessError::ParseError(format!("invalid {:?} text", self.encoding)))?;
+            let trimmed = line.trim();
             if trimmed.is_empty() {
                 continue;
             }
 
-            // Check for FEN tag
-            if trimmed.starts_with("[FEN \"") {
-                fen = Some(trimmed[6..trimmed.len()-2].to_string());
+            if trimmed.starts_with('[') {
+                self.in_moves = false;
+                match parse_tag_pair(trimmed)? {
+                    (name, value) if name == "FEN" => fen = Some(value),
+                    (name, value) if name == "SetUp" => setup = Some(value),
+                    _ => {}
+                }
                 continue;
             }
 
-            // Start of moves section
-            if trimmed.starts_with("1.") {
-                in_moves = true;
+            // Remaining movetext of the game already returned
+            if self.in_moves {
+                continue;
             }
+            self.in_moves = true;
+            return match setup_position(fen.as_deref(), setup.as_deref())? {
+                Some(position) => Ok(Some(position)),
+                None => Ok(Some(Variant::Standard.starting_fen().parse()?)),
+            };
+        }
+    }
+}
+
+/// Position given by the FEN and SetUp tags, None for the standard start.
+/// A FEN without a SetUp tag is accepted, as many exporters omit it.
+fn setup_position(fen: Option<&str>, setup: Option<&str>) -> Result<Option<Position>, ChessError> {
+    match (fen, setup) {
+        (Some(_), Some("0")) => Err(ChessError::ParseError("FEN tag with SetUp \"0\"".to_string())),
+        (None, Some("1")) => Err(ChessError::ParseError("SetUp \"1\" without a FEN tag".to_string())),
+        (_, Some(other)) if other != "0" && other != "1" => {
+            Err(ChessError::ParseError(format!("Invalid SetUp tag: {}", other)))
+        }
+        (Some(fen), _) => Ok(Some(fen.parse()?)),
+        (None, _) => Ok(None),
+    }
+}
+
+/// Engine evaluation from a `[%eval ...]` comment, from White's point of view
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Eval {
+    Pawns(f32),
+    /// Mate in n moves; negative when Black mates
+    Mate(i32),
+}
+
+/// Extra movetext written after a move by `PgnGame::write_annotated`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MoveAnnotation {
+    /// Numeric Annotation Glyph, e.g. 2 for "?"
+    pub nag: Option<u8>,
+    pub comment: Option<String>,
+    /// SAN moves of a line played instead of the move
+    pub variation: Vec<String>,
+}
+
+/// A game as PGN: its tag pairs, SAN moves of the main line, the evaluation
+/// after each move when known and the termination marker
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PgnGame {
+    pub headers: Vec<(String, String)>,
+    pub moves: Vec<String>,
+    pub evals: Vec<Option<Eval>>,
+    pub result: Option<String>,
+}
 
-            // If we're in the moves section and have a FEN, we can process it
-            if in_moves {
-                if let Some(fen) = fen.take() {
-                    return Ok(Some(fen.parse()?));
+impl PgnGame {
+    /// Value of a tag pair
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+
+    /// Build a game from UCI moves played from `start_fen` (the standard
+    /// start when None), as found in engine match logs and bot APIs. The
+    /// moves are converted to SAN. The Seven Tag Roster comes first, with "?"
+    /// for missing tags, then FEN and SetUp for a set-up position and the
+    /// other headers; the result, unless given, is taken from the final
+    /// position.
+    pub fn from_uci<S: AsRef<str>>(
+        start_fen: Option<&str>,
+        uci_moves: &[S],
+        headers: Vec<(String, String)>,
+    ) -> Result<PgnGame, ChessError> {
+        let mut game = PgnGame::default();
+        for name in REQUIRED_TAGS {
+            let value = match headers.iter().find(|(key, _)| key == name) {
+                Some((_, value)) => value.clone(),
+                None if name == "Date" => "????.??.??".to_string(),
+                None => "?".to_string(),
+            };
+            game.headers.push((name.to_string(), value));
+        }
+        if let Some(fen) = start_fen {
+            game.headers.push(("SetUp".to_string(), "1".to_string()));
+            game.headers.push(("FEN".to_string(), fen.to_string()));
+        }
+        game.headers.extend(headers.into_iter()
+            .filter(|(name, _)| !REQUIRED_TAGS.contains(&name.as_str()) && name != "FEN" && name != "SetUp"));
+
+        let mut board = game.starting_board()?;
+        for (ply, uci) in uci_moves.iter().enumerate() {
+            let mv = parse_uci_at(&board, uci.as_ref(), ply + 1, None)?;
+            game.moves.push(to_san(&board, &mv));
+            board.make_move(&mv);
+        }
+
+        let result = match game.header("Result") {
+            Some("?") | None => board_outcome(&board).map_or("*", |outcome| outcome.result()).to_string(),
+            Some(result) => result.to_string(),
+        };
+        if let Some((_, value)) = game.headers.iter_mut().find(|(name, _)| name == "Result") {
+            value.clone_from(&result);
+        }
+        game.result = Some(result);
+        Ok(game)
+    }
+
+    /// Position the game starts from: the FEN tag when set up, else the
+    /// standard start. Conflicting FEN/SetUp tags and impossible positions
+    /// are errors.
+    pub fn starting_board(&self) -> Result<Board, ChessError> {
+        match setup_position(self.header("FEN"), self.header("SetUp"))? {
+            Some(position) => {
+                let board = Board::from(position);
+                if !validate_board(&board).is_valid() {
+                    return Err(ChessError::ParseError(format!("Invalid FEN position: {}", self.header("FEN").unwrap_or_default())));
+                }
+                Ok(board)
+            }
+            None => Ok(starting_board(Variant::Standard)),
+        }
+    }
+
+    /// Replay the movetext, returning the position before each move together
+    /// with the move played
+    pub fn replay(&self) -> Result<Vec<(Board, Move)>, ChessError> {
+        let mut board = self.starting_board()?;
+        let mut positions = Vec::with_capacity(self.moves.len());
+        for (ply, san) in self.moves.iter().enumerate() {
+            let mv = parse_san(&board, san).map_err(|e| match (ply, self.header("FEN"), e) {
+                (0, Some(fen), ChessError::IllegalMove(_)) => {
+                    ChessError::IllegalMove(format!("first move {} is not legal from {}", san, fen))
+                }
+                (_, _, e) => e,
+            })?;
+            positions.push((board.clone(), mv.clone()));
+            board.make_move(&mv);
+        }
+        Ok(positions)
+    }
+
+    /// Write the game in PGN export format: tag pairs in their original
+    /// order with quotes and backslashes escaped, then the movetext wrapped
+    /// at 80 columns with evaluations as `[%eval]` comments
+    pub fn write_pgn<W: io::Write>(&self, out: W) -> io::Result<()> {
+        self.write_annotated(out, &[])
+    }
+
+    /// `write_pgn` with a NAG, a comment and a variation after each move,
+    /// `annotations[i]` belonging to the `i`-th move; the comment shares the
+    /// braces of the `[%eval]` comment and loses any `}` of its own
+    pub fn write_annotated<W: io::Write>(&self, mut out: W, annotations: &[MoveAnnotation]) -> io::Result<()> {
+        for (name, value) in &self.headers {
+            writeln!(out, "[{} \"{}\"]", name, escape_tag_value(value))?;
+        }
+        if !self.headers.is_empty() {
+            writeln!(out)?;
+        }
+
+        let (mut black, mut number) = match self.header("FEN").and_then(|fen| fen.parse::<Position>().ok()) {
+            Some(position) => (position.side_to_move == Color::Black, position.fullmove_number.max(1)),
+            None => (false, 1),
+        };
+        let mut tokens = Vec::new();
+        for (i, san) in self.moves.iter().enumerate() {
+            if !black {
+                tokens.push(format!("{}.", number));
+            } else if i == 0 || annotations.get(i - 1).is_some_and(|previous| !previous.variation.is_empty()) {
+                tokens.push(format!("{}...", number));
+            }
+            tokens.push(san.clone());
+            let annotation = annotations.get(i);
+            if let Some(nag) = annotation.and_then(|annotation| annotation.nag) {
+                tokens.push(format!("${}", nag));
+            }
+            let eval = match self.evals.get(i).copied().flatten() {
+                Some(Eval::Pawns(pawns)) => Some(format!("[%eval {}]", pawns)),
+                Some(Eval::Mate(moves)) => Some(format!("[%eval #{}]", moves)),
+                None => None,
+            };
+            // A comment ends at the first closing brace, so any inside the
+            // text are dropped
+            let comment = annotation.and_then(|annotation| annotation.comment.as_deref())
+                .map(|comment| comment.replace('}', ""));
+            match (eval, comment) {
+                (Some(eval), Some(comment)) => tokens.push(format!("{{{} {}}}", eval, comment)),
+                (Some(text), None) => tokens.push(format!("{{{}}}", text)),
+                (None, Some(text)) => tokens.push(format!("{{{}}}", text)),
+                (None, None) => {}
+            }
+            if let Some(variation) = annotation.map(|annotation| &annotation.variation).filter(|v| !v.is_empty()) {
+                // The variation replaces this move, so it starts at its number
+                let start = tokens.len();
+                let (mut black, mut number) = (black, number);
+                for (j, san) in variation.iter().enumerate() {
+                    if !black {
+                        tokens.push(format!("{}.", number));
+                    } else if j == 0 {
+                        tokens.push(format!("{}...", number));
+                    }
+                    tokens.push(san.clone());
+                    if black {
+                        number += 1;
+                    }
+                    black = !black;
+                }
+                tokens[start].insert(0, '(');
+                tokens.last_mut().expect("a variation has moves").push(')');
+            }
+            if black {
+                number += 1;
+            }
+            black = !black;
+        }
+        tokens.push(self.result.clone().unwrap_or_else(|| "*".to_string()));
+        write_movetext(out, &tokens)
+    }
+
+    pub fn to_pgn(&self) -> String {
+        let mut out = Vec::new();
+        self.write_pgn(&mut out).expect("writing to a Vec cannot fail");
+        String::from_utf8(out).expect("PGN output is UTF-8")
+    }
+}
+
+/// Write movetext tokens separated by spaces in lines of at most 80
+/// columns, followed by the blank line that ends a game
+pub(crate) fn write_movetext<W: io::Write>(mut out: W, tokens: &[String]) -> io::Result<()> {
+    let mut line = String::new();
+    for token in tokens {
+        if !line.is_empty() && line.len() + 1 + token.len() > 80 {
+            writeln!(out, "{}", line)?;
+            line.clear();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(token);
+    }
+    writeln!(out, "{}", line)?;
+    writeln!(out)
+}
+
+/// Escape a tag value for export: backslashes and double quotes are
+/// preceded by a backslash
+pub fn escape_tag_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if c == '\\' || c == '"' {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+/// Tags every exported PGN game must carry (the Seven Tag Roster)
+pub const REQUIRED_TAGS: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+/// A problem found by `validate_game`
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameIssue {
+    MissingTag(&'static str),
+    /// The FEN tag does not parse
+    InvalidSetup(String),
+    /// The first move that is not legal; `ply` counts from 1
+    IllegalMove { ply: usize, san: String, fen: String },
+    /// The movetext does not end with a termination marker
+    MissingTermination,
+    /// The Result tag disagrees with the termination marker
+    ResultMismatch { header: String, movetext: String },
+    /// The game ends in checkmate or stalemate but the result says otherwise
+    ResultContradictsPosition { result: String, expected: &'static str },
+}
+
+impl fmt::Display for GameIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameIssue::MissingTag(tag) => write!(f, "missing {} tag", tag),
+            GameIssue::InvalidSetup(e) => write!(f, "invalid FEN tag: {}", e),
+            GameIssue::IllegalMove { ply, san, fen } => write!(f, "illegal move {} at ply {} in {}", san, ply, fen),
+            GameIssue::MissingTermination => write!(f, "movetext has no result"),
+            GameIssue::ResultMismatch { header, movetext } => {
+                write!(f, "Result tag {} but movetext ends with {}", header, movetext)
+            }
+            GameIssue::ResultContradictsPosition { result, expected } => {
+                write!(f, "result {} but the final position calls for {}", result, expected)
+            }
+        }
+    }
+}
+
+/// Check a game for missing tags, an unreadable setup, illegal moves and a
+/// result that disagrees with the movetext or the final position. Replaying
+/// stops at the first illegal move.
+pub fn validate_game(game: &PgnGame) -> Vec<GameIssue> {
+    let mut issues: Vec<GameIssue> = REQUIRED_TAGS.iter()
+        .filter(|tag| game.header(tag).is_none())
+        .map(|tag| GameIssue::MissingTag(tag))
+        .collect();
+
+    let final_board = match game.starting_board() {
+        Ok(mut board) => {
+            let mut complete = true;
+            for (i, san) in game.moves.iter().enumerate() {
+                match parse_san(&board, san) {
+                    Ok(mv) => { board.make_move(&mv); }
+                    Err(_) => {
+                        issues.push(GameIssue::IllegalMove {
+                            ply: i + 1,
+                            san: san.clone(),
+                            fen: Position::from(&board).to_fen(),
+                        });
+                        complete = false;
+                        break;
+                    }
                 }
-                // If no FEN was found, use the starting position
-                return Ok(Some("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".parse()?));
             }
+            complete.then_some(board)
         }
+        Err(e) => {
+            issues.push(GameIssue::InvalidSetup(e.to_string()));
+            None
+        }
+    };
+
+    issues.extend(result_issues(game, final_board.as_ref()));
+    issues
+}
+
+/// Compare the Result tag with the termination marker and, when the game
+/// replays, both with its final position: a checkmate must be scored as a
+/// win for the mating side and a stalemate as a draw
+pub fn check_result(game: &PgnGame) -> Vec<GameIssue> {
+    let final_board = game.replay().ok().map(|positions| match positions.last() {
+        Some((board, mv)) => {
+            let mut board = board.clone();
+            board.make_move(mv);
+            board
+        }
+        None => game.starting_board().expect("replay checked the setup"),
+    });
+    result_issues(game, final_board.as_ref())
+}
+
+fn result_issues(game: &PgnGame, final_board: Option<&Board>) -> Vec<GameIssue> {
+    let mut issues = Vec::new();
+    match (&game.result, game.header("Result")) {
+        (None, _) => issues.push(GameIssue::MissingTermination),
+        (Some(movetext), Some(header)) if movetext != header => issues.push(GameIssue::ResultMismatch {
+            header: header.to_string(),
+            movetext: movetext.clone(),
+        }),
+        _ => {}
+    }
 
-        Ok(None)
+    let expected = final_board.and_then(|board| {
+        if is_checkmate(board) {
+            Some(if board.side_to_move == Color::White { "0-1" } else { "1-0" })
+        } else if is_stalemate(board) {
+            Some("1/2-1/2")
+        } else {
+            None
+        }
+    });
+    if let Some(expected) = expected {
+        let mut results: Vec<&str> = game.header("Result").into_iter().chain(game.result.as_deref()).collect();
+        results.dedup();
+        for result in results.into_iter().filter(|&result| result != expected) {
+            issues.push(GameIssue::ResultContradictsPosition { result: result.to_string(), expected });
+        }
     }
+    issues
 }
 
+/// A game dropped by a lenient reader
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedGame {
+    /// Position of the game in the stream, counting from 0
+    pub index: usize,
+    /// Line on which the problem was found
+    pub line: usize,
+    pub error: String,
+}
 
+/// Counts kept by a `PgnGameReader`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseStats {
+    pub games: usize,
+    pub skipped: usize,
+}
+
+/// Reads complete games from a PGN stream.
+///
+/// Variations, NAGs and move numbers are skipped; only the main line is
+/// kept. Comments are dropped apart from `[%eval ...]` annotations.
+///
+/// By default the first malformed game ends reading with an error. In
+/// lenient mode a game with a malformed tag pair, undecodable text or a move
+/// that does not replay is recorded in `skipped()` and reading resumes at the
+/// next game; every returned game is known to replay.
+pub struct PgnGameReader<R> {
+    reader: io::BufReader<R>,
+    line_buffer: Vec<u8>,
+    pending_header: Option<String>,
+    line_number: usize,
+    in_moves: bool,
+    encoding: Encoding,
+    lenient: bool,
+    games: usize,
+    skipped: Vec<SkippedGame>,
+}
+
+impl<R: io::Read> PgnGameReader<R> {
+    pub fn new(reader: R) -> Self {
+        PgnGameReader {
+            reader: io::BufReader::new(reader),
+            line_buffer: Vec::new(),
+            pending_header: None,
+            line_number: 0,
+            in_moves: false,
+            encoding: Encoding::default(),
+            lenient: false,
+            games: 0,
+            skipped: Vec::new(),
+        }
+    }
+
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Skip malformed games instead of failing
+    pub fn lenient(mut self) -> Self {
+        self.lenient = true;
+        self
+    }
+
+    /// Games dropped so far in lenient mode
+    pub fn skipped(&self) -> &[SkippedGame] {
+        &self.skipped
+    }
+
+    pub fn stats(&self) -> ParseStats {
+        ParseStats { games: self.games, skipped: self.skipped.len() }
+    }
+
+    pub fn next_game(&mut self) -> Result<Option<PgnGame>, ChessError> {
+        loop {
+            // A game that fails to replay has already been read to its end
+            let (error, read_to_end) = match self.read_game() {
+                Ok(Some(game)) if self.lenient => match game.replay() {
+                    Ok(_) => {
+                        self.games += 1;
+                        return Ok(Some(game));
+                    }
+                    Err(e) => (e, true),
+                },
+                Ok(game) => {
+                    self.games += game.is_some() as usize;
+                    return Ok(game);
+                }
+                Err(ChessError::IoError(e)) => return Err(ChessError::IoError(e)),
+                Err(e) if self.lenient => (e, false),
+                Err(e) => return Err(e),
+            };
+            self.skipped.push(SkippedGame {
+                index: self.games + self.skipped.len(),
+                line: self.line_number,
+                error: error.to_string(),
+            });
+            if !read_to_end {
+                self.skip_to_next_game()?;
+            }
+        }
+    }
+
+    /// Read the next line into `line_buffer`; false at the end of the input
+    fn read_raw_line(&mut self) -> io::Result<bool> {
+        self.line_buffer.clear();
+        let read = self.reader.read_until(b'\n', &mut self.line_buffer)?;
+        self.line_number += (read > 0) as usize;
+        Ok(read > 0)
+    }
+
+    fn next_line(&mut self) -> Result<Option<String>, ChessError> {
+        if let Some(line) = self.pending_header.take() {
+            return Ok(Some(line));
+        }
+        if !self.read_raw_line()? {
+            return Ok(None);
+        }
+        match self.encoding.decode(&self.line_buffer) {
+            Some(line) => Ok(Some(line.trim().to_string())),
+            None => Err(ChessError::ParseError(format!("invalid {:?} text on line {}", self.encoding, self.line_number))),
+        }
+    }
+
+    /// Discard input up to the tag pairs of the next game
+    fn skip_to_next_game(&mut self) -> io::Result<()> {
+        if self.pending_header.is_some() {
+            return Ok(());
+        }
+        while self.read_raw_line()? {
+            let line = self.line_buffer.trim_ascii();
+            if line.starts_with(b"[") {
+                if self.in_moves {
+                    self.pending_header = Some(String::from_utf8_lossy(line).into_owned());
+                    return Ok(());
+                }
+            } else if !line.is_empty() {
+                self.in_moves = true;
+            }
+        }
+        Ok(())
+    }
+
+    fn read_game(&mut self) -> Result<Option<PgnGame>, ChessError> {
+        let mut game = PgnGame::default();
+        self.in_moves = false;
+        let mut comment = false;
+        let mut comment_text = String::new();
+        let mut depth = 0usize;
+
+        while let Some(line) = self.next_line()? {
+            if line.is_empty() || line.starts_with('%') {
+                continue;
+            }
+
+            if !comment && depth == 0 && line.starts_with('[') {
+                // A tag pair after movetext starts the next game
+                if self.in_moves {
+                    self.pending_header = Some(line);
+                    return Ok(Some(game));
+                }
+                game.headers.push(parse_tag_pair(&line)?);
+                continue;
+            }
+
+            self.in_moves = true;
+            let mut rest = line.as_str();
+            let mut previous = usize::MAX;
+            while !rest.is_empty() {
+                // Every pass consumes input, so no line is read forever
+                debug_assert!(rest.len() < previous, "movetext did not advance at {:?}", rest);
+                previous = rest.len();
+                if comment {
+                    match rest.find('}') {
+                        Some(end) => {
+                            comment_text.push_str(&rest[..end]);
+                            if depth == 0 && !game.evals.is_empty() {
+                                if let Some(eval) = parse_eval(&comment_text) {
+                                    *game.evals.last_mut().unwrap() = Some(eval);
+                                }
+                            }
+                            comment = false;
+                            rest = &rest[end + 1..];
+                        }
+                        None => {
+                            comment_text.push_str(rest);
+                            comment_text.push(' ');
+                            rest = "";
+                        }
+                    }
+                    continue;
+                }
+                rest = rest.trim_start();
+                let Some(c) = rest.chars().next() else { break };
+                match c {
+                    '{' => { comment = true; comment_text.clear(); rest = &rest[1..]; }
+                    ';' => rest = "",
+                    '(' => { depth += 1; rest = &rest[1..]; }
+                    ')' => { depth = depth.saturating_sub(1); rest = &rest[1..]; }
+                    '}' if self.lenient => rest = &rest[1..],
+                    '}' => {
+                        return Err(ChessError::ParseError(format!("unmatched '}}' on line {}", self.line_number)));
+                    }
+                    _ => {
+                        let end = rest.find(|c: char| c.is_whitespace() || "{};()".contains(c))
+                            .unwrap_or(rest.len());
+                        let token = &rest[..end];
+                        rest = &rest[end..];
+                        if depth > 0 || token.starts_with('$') {
+                            continue;
+                        }
+                        if RESULTS.contains(&token) {
+                            game.result = Some(token.to_string());
+                            self.in_moves = false;
+                            return Ok(Some(game));
+                        }
+                        // Strip a move number prefix such as "12." or "12..."
+                        let san = match token.rfind('.') {
+                            Some(dot) => &token[dot + 1..],
+                            None => token,
+                        };
+                        if !san.is_empty() {
+                            game.moves.push(san.to_string());
+                            game.evals.push(None);
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok((self.in_moves || !game.headers.is_empty()).then_some(game))
+    }
+}
+
+impl<R: io::Read> Iterator for PgnGameReader<R> {
+    type Item = Result<PgnGame, ChessError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_game().transpose()
+    }
+}
+
+fn parse_eval(comment: &str) -> Option<Eval> {
+    let start = comment.find("[%eval ")? + 7;
+    let value = comment[start..].split([']', ',']).next()?.trim();
+    match value.strip_prefix('#') {
+        Some(mate) => mate.parse().ok().map(Eval::Mate),
+        None => value.parse().ok().map(Eval::Pawns),
+    }
+}
+
+fn parse_tag_pair(line: &str) -> Result<(String, String), ChessError> {
+    let malformed = || ChessError::ParseError(format!("Invalid tag pair: {}", line));
+    let inner = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')).ok_or_else(malformed)?;
+    let (name, value) = inner.split_once(' ').ok_or_else(malformed)?;
+    let mut chars = value.trim().strip_prefix('"').ok_or_else(malformed)?.chars();
+
+    // Read up to the closing quote, undoing \" and \\ escapes
+    let mut unescaped = String::new();
+    loop {
+        match chars.next().ok_or_else(malformed)? {
+            '"' => break,
+            '\\' => unescaped.push(chars.next().ok_or_else(malformed)?),
+            c => unescaped.push(c),
+        }
+    }
+    if !chars.as_str().trim().is_empty() {
+        return Err(malformed());
+    }
+    Ok((name.to_string(), unescaped))
+}
@@ -0,0 +1,219 @@
+//! Whole-file PGN operations: splitting into shards and merging with
+//! duplicate removal
+
+use crate::pgn::{ChessError, PgnGame, PgnGameReader};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Reads the raw bytes of each game of a PGN stream, tag pairs through
+/// movetext and trailing blank lines, without decoding them. Lines starting
+/// with '[' inside a brace comment do not start a new game.
+pub struct RawGameReader<R> {
+    reader: io::BufReader<R>,
+    line: Vec<u8>,
+    pending: Vec<u8>,
+}
+
+impl<R: io::Read> RawGameReader<R> {
+    pub fn new(reader: R) -> Self {
+        RawGameReader { reader: io::BufReader::new(reader), line: Vec::new(), pending: Vec::new() }
+    }
+
+    pub fn next_game(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut game = std::mem::take(&mut self.pending);
+        let mut in_moves = false;
+        let mut comment = false;
+        loop {
+            self.line.clear();
+            if self.reader.read_until(b'\n', &mut self.line)? == 0 {
+                break;
+            }
+            let trimmed = self.line.trim_ascii_start();
+            if !comment && trimmed.first() == Some(&b'[') {
+                if in_moves {
+                    self.pending = self.line.clone();
+                    return Ok(Some(game));
+                }
+            } else if !trimmed.is_empty() {
+                in_moves = true;
+                for &byte in trimmed {
+                    match byte {
+                        b'{' if !comment => comment = true,
+                        b'}' if comment => comment = false,
+                        b';' if !comment => break,
+                        _ => {}
+                    }
+                }
+            }
+            game.extend_from_slice(&self.line);
+        }
+        Ok((!game.trim_ascii().is_empty()).then_some(game))
+    }
+}
+
+impl<R: io::Read> Iterator for RawGameReader<R> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_game().transpose()
+    }
+}
+
+/// How games are distributed over shards
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShardMode {
+    /// A fixed number of shards, games dealt round-robin
+    Count(usize),
+    /// Consecutive runs of at most this many games per shard
+    GamesPerShard(usize),
+}
+
+/// Output file of a shard, optionally gzip-compressed
+enum ShardWriter {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+}
+
+impl ShardWriter {
+    fn create(path: &Path, compress: bool) -> io::Result<Self> {
+        let file = BufWriter::new(File::create(path)?);
+        Ok(if compress {
+            ShardWriter::Gzip(GzEncoder::new(file, Compression::default()))
+        } else {
+            ShardWriter::Plain(file)
+        })
+    }
+
+    /// Write a game, making sure it is followed by a blank line
+    fn write_game(&mut self, game: &[u8]) -> io::Result<()> {
+        let out: &mut dyn Write = match self {
+            ShardWriter::Plain(w) => w,
+            ShardWriter::Gzip(w) => w,
+        };
+        out.write_all(game)?;
+        if !game.ends_with(b"\n") {
+            out.write_all(b"\n")?;
+        }
+        if !game.ends_with(b"\n\n") && !game.ends_with(b"\r\n\r\n") {
+            out.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> io::Result<()> {
+        match self {
+            ShardWriter::Plain(mut w) => w.flush(),
+            ShardWriter::Gzip(w) => w.finish()?.flush(),
+        }
+    }
+}
+
+/// Split a PGN stream into shard files `<stem>-00000.pgn`, ... in `dir`
+/// (`.pgn.gz` when compressing). Game bytes are copied unchanged, so the
+/// original encoding is kept. Returns the paths written.
+pub fn split_pgn<R: io::Read>(
+    input: R,
+    dir: &Path,
+    stem: &str,
+    mode: ShardMode,
+    compress: bool,
+) -> Result<Vec<PathBuf>, ChessError> {
+    if matches!(mode, ShardMode::Count(0) | ShardMode::GamesPerShard(0)) {
+        return Err(ChessError::ParseError("shard size must be positive".to_string()));
+    }
+    let extension = if compress { "pgn.gz" } else { "pgn" };
+    let shard_path = |index: usize| dir.join(format!("{}-{:05}.{}", stem, index, extension));
+
+    let mut paths = Vec::new();
+    let mut writers: Vec<Option<ShardWriter>> = Vec::new();
+    for (index, game) in RawGameReader::new(input).enumerate() {
+        let game = game?;
+        let shard = match mode {
+            ShardMode::Count(n) => index % n,
+            ShardMode::GamesPerShard(m) => index / m,
+        };
+        if shard == paths.len() {
+            // Contiguous shards are complete once the next one starts
+            if matches!(mode, ShardMode::GamesPerShard(_)) {
+                if let Some(previous) = writers.last_mut().and_then(Option::take) {
+                    previous.finish()?;
+                }
+            }
+            writers.push(Some(ShardWriter::create(&shard_path(shard), compress)?));
+            paths.push(shard_path(shard));
+        }
+        writers[shard].as_mut().expect("open shard").write_game(&game)?;
+    }
+    for writer in writers.into_iter().flatten() {
+        writer.finish()?;
+    }
+    Ok(paths)
+}
+
+/// What makes two games duplicates when merging
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DedupKey {
+    /// Same starting position and main-line moves, ignoring comments,
+    /// annotations and check marks
+    Movetext,
+    /// Same Event, Site, Date, Round, White, Black and Result tags
+    Headers,
+}
+
+/// Outcome of a merge
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MergeStats {
+    /// Games written
+    pub games: usize,
+    /// Games skipped as duplicates
+    pub duplicates: usize,
+}
+
+fn dedup_hash(game: &PgnGame, key: DedupKey) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    match key {
+        DedupKey::Movetext => {
+            game.header("FEN").hash(&mut hasher);
+            for san in &game.moves {
+                san.trim_end_matches(['+', '#', '!', '?']).hash(&mut hasher);
+            }
+        }
+        DedupKey::Headers => {
+            for tag in ["Event", "Site", "Date", "Round", "White", "Black", "Result"] {
+                game.header(tag).hash(&mut hasher);
+            }
+        }
+    }
+    hasher.finish()
+}
+
+/// Copy the games of every source to `output` in order, keeping only the
+/// first of each set of duplicates. Game bytes are copied unchanged.
+pub fn merge_pgn<R: io::Read, W: Write>(sources: Vec<R>, mut output: W, key: DedupKey) -> Result<MergeStats, ChessError> {
+    let mut seen = HashSet::new();
+    let mut stats = MergeStats::default();
+    for source in sources {
+        for raw in RawGameReader::new(source) {
+            let raw = raw?;
+            let text = String::from_utf8_lossy(&raw);
+            let game = PgnGameReader::new(text.as_bytes()).next_game()?.unwrap_or_default();
+            if !seen.insert(dedup_hash(&game, key)) {
+                stats.duplicates += 1;
+                continue;
+            }
+            output.write_all(&raw)?;
+            if !raw.ends_with(b"\n\n") && !raw.ends_with(b"\r\n\r\n") {
+                output.write_all(if raw.ends_with(b"\n") { b"\n" } else { b"\n\n" })?;
+            }
+            stats.games += 1;
+        }
+    }
+    output.flush()?;
+    Ok(stats)
+}
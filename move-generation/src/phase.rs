@@ -0,0 +1,87 @@
+//! Game phase detection
+
+use crate::types::{Board, Color, Piece};
+
+/// Phase value at which a position counts as a pure endgame
+pub const PHASE_MAX: u32 = 256;
+
+/// Non-pawn material in the starting position, in phase units
+const TOTAL_PHASE_MATERIAL: u32 = 24;
+
+/// Discrete game phase
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Phase {
+    Opening,
+    Middlegame,
+    Endgame,
+}
+
+impl Phase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Phase::Opening => "opening",
+            Phase::Middlegame => "middlegame",
+            Phase::Endgame => "endgame",
+        }
+    }
+}
+
+/// Phase weight of a piece (minor = 1, rook = 2, queen = 4)
+fn phase_weight(piece: Piece) -> u32 {
+    match piece {
+        Piece::Knight | Piece::Bishop => 1,
+        Piece::Rook => 2,
+        Piece::Queen => 4,
+        Piece::Pawn | Piece::King => 0,
+    }
+}
+
+/// Non-pawn material on the board in phase units, capped at the starting amount
+fn phase_material(board: &Board) -> u32 {
+    let material: u32 = board.squares().iter()
+        .flatten()
+        .map(|&(piece, _)| phase_weight(piece))
+        .sum();
+    material.min(TOTAL_PHASE_MATERIAL)
+}
+
+/// Continuous phase for tapered evaluation: 0 with all non-pawn material on
+/// the board, rising to `PHASE_MAX` (256) once only kings and pawns remain
+pub fn phase_value(board: &Board) -> u32 {
+    (TOTAL_PHASE_MATERIAL - phase_material(board)) * PHASE_MAX / TOTAL_PHASE_MATERIAL
+}
+
+/// Number of knights and bishops still standing on their starting squares
+fn undeveloped_minors(board: &Board) -> usize {
+    let home = [
+        (1, Piece::Knight, Color::White), (6, Piece::Knight, Color::White),
+        (2, Piece::Bishop, Color::White), (5, Piece::Bishop, Color::White),
+        (57, Piece::Knight, Color::Black), (62, Piece::Knight, Color::Black),
+        (58, Piece::Bishop, Color::Black), (61, Piece::Bishop, Color::Black),
+    ];
+    home.iter()
+        .filter(|&&(sq, piece, color)| board.squares()[sq] == Some((piece, color)))
+        .count()
+}
+
+/// Classify a position as opening, middlegame or endgame.
+///
+/// Positions with little non-pawn material left (at most a rook and a minor
+/// piece per side, or no queens and at most two rooks' worth of pieces each)
+/// are endgames. Positions with nearly full material and at least half of the
+/// minor pieces still on their home squares are openings.
+pub fn game_phase(board: &Board) -> Phase {
+    let material = phase_material(board);
+    let queens = board.squares().iter()
+        .flatten()
+        .filter(|&&(piece, _)| piece == Piece::Queen)
+        .count();
+
+    if material <= 6 || (queens == 0 && material <= 12) {
+        Phase::Endgame
+    } else if material >= 20 && undeveloped_minors(board) >= 4 {
+        Phase::Opening
+    } else {
+        Phase::Middlegame
+    }
+}
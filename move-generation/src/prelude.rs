@@ -0,0 +1,20 @@
+//! The types and entry points most callers need, for a single glob import:
+//!
+//! ```
+//! use move_generation::prelude::*;
+//!
+//! let board = Board::from("4k3/8/8/8/8/8/8/4K2R w K - 0 1".parse::<Position>().unwrap());
+//! assert_eq!(generate_legal_moves(&board).len(), 15);
+//! ```
+
+pub use crate::move_gen::{generate_moves, generate_moves_into, is_square_attacked, Bitboard, MoveList, Promotions};
+pub use crate::notation::{name_to_square, parse_san, parse_uci, square_to_name, to_san, to_uci};
+pub use crate::perft::perft;
+pub use crate::pgn::{ChessError, PgnGame, Position};
+pub use crate::rules::{
+    count_legal_moves, explain_move, generate_legal_moves, generate_legal_moves_into, is_checkmate,
+    is_in_check, is_legal_move, is_stalemate, validate_board, MoveLegality, ValidationReport,
+};
+pub use crate::eval::Evaluator;
+pub use crate::score::Score;
+pub use crate::types::{Board, CastlingRights, Color, Move, Piece, Square, Variant};
@@ -0,0 +1,296 @@
+//! Puzzle mining: positions from games where a mistake left the opponent a
+//! winning tactic with only one good move at each step, found and verified
+//! with the engine
+
+use crate::accuracy::win_percent;
+use crate::eval::Evaluator;
+use crate::game::board_outcome;
+use crate::move_gen::{generate_piece_moves, is_capture};
+use crate::notation::{line_to_standard_uci, to_san};
+use crate::pgn::{ChessError, PgnGame, PgnGameReader, Position};
+use crate::rules::{is_checkmate, is_in_check};
+use crate::score::Score;
+use crate::search::{Engine, SearchHandle, SearchLimits, SearchOptions, SearchResult};
+use crate::see::{see, see_value};
+use crate::types::{Board, Move, Piece};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{Read, Write};
+
+/// What a puzzle is about, named as on Lichess
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Theme {
+    /// The solution ends in checkmate after this many of the solver's moves
+    MateIn(u32),
+    /// The solver is at least `CRUSHING_CP` ahead
+    Crushing,
+    /// The solver is ahead, but by less than `CRUSHING_CP`
+    Advantage,
+    /// A solver's move attacks two pieces at once
+    Fork,
+    Promotion,
+    /// A solver's move loses material by static exchange
+    Sacrifice,
+    /// The first move neither captures, checks nor promotes
+    QuietMove,
+}
+
+/// Least score, for the solver, of a puzzle themed `Crushing`
+pub const CRUSHING_CP: i32 = 600;
+
+impl fmt::Display for Theme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Theme::MateIn(n) => write!(f, "mateIn{}", n),
+            Theme::Crushing => f.write_str("crushing"),
+            Theme::Advantage => f.write_str("advantage"),
+            Theme::Fork => f.write_str("fork"),
+            Theme::Promotion => f.write_str("promotion"),
+            Theme::Sacrifice => f.write_str("sacrifice"),
+            Theme::QuietMove => f.write_str("quietMove"),
+        }
+    }
+}
+
+/// A position to solve, set up by the opponent's mistake in a game
+#[derive(Clone, Debug, PartialEq)]
+pub struct Puzzle {
+    /// The position after the mistake, with the solver to move
+    pub board: Board,
+    /// SAN of the mistake
+    pub blunder: String,
+    /// Ply of the mistake in its game, 1 for White's first move
+    pub ply: usize,
+    /// The solver's moves and the opponent's best replies, starting and
+    /// ending with a solver's move
+    pub solution: Vec<Move>,
+    /// Score of `board` for the solver
+    pub score: Score,
+    pub themes: Vec<Theme>,
+    /// The game's `Site` tag, usually a link to it
+    pub site: Option<String>,
+}
+
+impl Puzzle {
+    pub fn fen(&self) -> String {
+        Position::from(&self.board).to_fen()
+    }
+
+    /// The solution in SAN
+    pub fn solution_san(&self) -> Vec<String> {
+        let mut board = self.board.clone();
+        self.solution.iter()
+            .map(|mv| {
+                let san = to_san(&board, mv);
+                board.make_move(mv);
+                san
+            })
+            .collect()
+    }
+
+    /// JSON object with the FEN, the solution in UCI, the themes and where
+    /// the puzzle comes from
+    pub fn to_json(&self) -> Value {
+        json!({
+            "fen": self.fen(),
+            "moves": line_to_standard_uci(&self.board, &self.solution),
+            "themes": self.themes.iter().map(Theme::to_string).collect::<Vec<_>>(),
+            "score": self.score.to_string(),
+            "blunder": self.blunder,
+            "ply": self.ply,
+            "site": self.site,
+        })
+    }
+}
+
+/// When a position makes a puzzle
+#[derive(Clone, Debug, PartialEq)]
+pub struct PuzzleOptions {
+    /// Search limits for every position looked at
+    pub limits: SearchLimits,
+    /// Win percentage the mistake must hand the opponent
+    pub min_swing: f64,
+    /// Least score, for the solver, at every step of the solution
+    pub winning_cp: i32,
+    /// Every other solver's move must score below `winning_cp` and at least
+    /// this much below the best
+    pub unique_margin_cp: i32,
+    /// Longest solution, in plies of both sides
+    pub max_plies: usize,
+}
+
+impl Default for PuzzleOptions {
+    fn default() -> Self {
+        PuzzleOptions {
+            limits: SearchLimits { depth: Some(8), ..Default::default() },
+            min_swing: 30.0,
+            winning_cp: 300,
+            unique_margin_cp: 200,
+            max_plies: 7,
+        }
+    }
+}
+
+/// Finds puzzles in games, remembering every search by Zobrist hash so
+/// positions shared between games are searched once
+pub struct PuzzleMiner<E> {
+    evaluator: E,
+    engine: Engine,
+    options: PuzzleOptions,
+    cache: HashMap<u64, SearchResult>,
+}
+
+impl<E: Evaluator> PuzzleMiner<E> {
+    /// The engine always searches two lines, to tell a unique best move
+    pub fn new(evaluator: E, options: PuzzleOptions, search: SearchOptions) -> Self {
+        let engine = Engine::new(SearchOptions { multipv: 2, ..search });
+        PuzzleMiner { evaluator, engine, options, cache: HashMap::new() }
+    }
+
+    fn search(&mut self, board: &Board) -> Result<SearchResult, ChessError> {
+        if let Some(result) = self.cache.get(&board.hash) {
+            return Ok(result.clone());
+        }
+        let result = self.engine.search(board, &self.options.limits, &self.evaluator, &SearchHandle::new(), |_| {})?;
+        self.cache.insert(board.hash, result.clone());
+        Ok(result)
+    }
+
+    /// Puzzles from the main line of `game`, in game order
+    pub fn mine_game(&mut self, game: &PgnGame) -> Result<Vec<Puzzle>, ChessError> {
+        let mut puzzles = Vec::new();
+        for (ply, (board, mv)) in game.replay()?.into_iter().enumerate() {
+            let mut after = board.clone();
+            after.make_move(&mv);
+            if board_outcome(&board).is_some() || board_outcome(&after).is_some() {
+                continue;
+            }
+            let before = self.search(&board)?.score;
+            let score = self.search(&after)?.score;
+            let swing = win_percent(score.to_cp()) - win_percent(-before.to_cp());
+            if swing < self.options.min_swing || score.to_cp() < self.options.winning_cp {
+                continue;
+            }
+            if let Some(solution) = self.solve(&after)? {
+                let themes = themes(&after, &solution, score);
+                puzzles.push(Puzzle {
+                    board: after,
+                    blunder: game.moves[ply].clone(),
+                    ply: ply + 1,
+                    solution,
+                    score,
+                    themes,
+                    site: game.header("Site").map(str::to_string),
+                });
+            }
+        }
+        Ok(puzzles)
+    }
+
+    /// The solver's unique winning moves and the opponent's best replies
+    /// from `board`, or None when the first move is not unique
+    pub fn solve(&mut self, board: &Board) -> Result<Option<Vec<Move>>, ChessError> {
+        let solver = board.side_to_move;
+        let mut board = board.clone();
+        let mut line = Vec::new();
+        while line.len() < self.options.max_plies && board_outcome(&board).is_none() {
+            let result = self.search(&board)?;
+            let mv = if board.side_to_move == solver {
+                let Some(best) = result.lines.first() else { break };
+                let unique = match result.lines.get(1) {
+                    Some(second) => {
+                        second.score.to_cp() < self.options.winning_cp
+                            && best.score.to_cp() - second.score.to_cp() >= self.options.unique_margin_cp
+                    }
+                    // An only move is no puzzle, but may be part of one
+                    None => !line.is_empty(),
+                };
+                if !unique || best.score.to_cp() < self.options.winning_cp {
+                    break;
+                }
+                best.pv[0].clone()
+            } else {
+                let Some(reply) = result.best_move else { break };
+                reply
+            };
+            board.make_move(&mv);
+            line.push(mv);
+        }
+        // End on the solver's move
+        if line.len() % 2 == 0 {
+            line.pop();
+        }
+        Ok((!line.is_empty()).then_some(line))
+    }
+
+    /// Number of positions searched so far
+    pub fn cache_len(&self) -> usize {
+        self.cache.len()
+    }
+}
+
+/// Whether the piece that just moved to `sq` attacks two enemy pieces that
+/// are the king or worth more than itself
+fn is_fork(after: &Board, sq: u8) -> bool {
+    let Some((piece, color)) = after.squares()[sq as usize] else { return false };
+    let mut board = after.clone();
+    board.side_to_move = color;
+    let targets = generate_piece_moves(&board, piece, sq).iter()
+        .filter_map(|mv| board.squares()[mv.to as usize])
+        .filter(|&(target, target_color)| {
+            target_color != color && (target == Piece::King || see_value(target) > see_value(piece))
+        })
+        .count();
+    targets >= 2
+}
+
+fn themes(board: &Board, solution: &[Move], score: Score) -> Vec<Theme> {
+    let mut themes = Vec::new();
+    let mut board = board.clone();
+    let (mut fork, mut promotion, mut sacrifice) = (false, false, false);
+    for (i, mv) in solution.iter().enumerate() {
+        let solver_move = i % 2 == 0;
+        if solver_move {
+            promotion |= mv.promotion.is_some();
+            sacrifice |= see(&board, mv) < 0;
+        }
+        let quiet = !is_capture(&board, mv) && mv.promotion.is_none();
+        board.make_move(mv);
+        if solver_move {
+            fork |= is_fork(&board, mv.to);
+            if i == 0 && quiet && !is_in_check(&board, board.side_to_move) {
+                themes.push(Theme::QuietMove);
+            }
+        }
+    }
+    if is_checkmate(&board) {
+        themes.insert(0, Theme::MateIn(solution.len().div_ceil(2) as u32));
+    } else {
+        themes.insert(0, if score.to_cp() >= CRUSHING_CP { Theme::Crushing } else { Theme::Advantage });
+    }
+    for (found, theme) in [(fork, Theme::Fork), (promotion, Theme::Promotion), (sacrifice, Theme::Sacrifice)] {
+        if found {
+            themes.push(theme);
+        }
+    }
+    themes
+}
+
+/// Mine every game of a PGN source and write the puzzles as JSON Lines;
+/// returns the number written
+pub fn mine_pgn<E: Evaluator, R: Read, W: Write>(
+    miner: &mut PuzzleMiner<E>,
+    games: PgnGameReader<R>,
+    mut writer: W,
+) -> Result<usize, ChessError> {
+    let mut written = 0;
+    for game in games {
+        for puzzle in miner.mine_game(&game?)? {
+            writeln!(writer, "{}", puzzle.to_json())?;
+            written += 1;
+        }
+    }
+    writer.flush()?;
+    Ok(written)
+}
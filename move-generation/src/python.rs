@@ -0,0 +1,1400 @@
+//! Python bindings: the `move_generation` extension module built with PyO3
+
+use pyo3::prelude::*;
+use pyo3::{exceptions, Bound};
+use pyo3::types::{PyList, PyTuple, PyModule};
+use std::hash::{Hash, Hasher};
+use std::os::raw::{c_char, c_int, c_void};
+use crate::{analysis, batch, chess960, eval, explorer, game, notation, pawns, perft, pgn, phase, puzzle, rules, stats, threats, zobrist};
+#[cfg(feature = "arrow")]
+use crate::arrow;
+#[cfg(feature = "cache")]
+use crate::cache;
+use crate::types::{name_to_square, square_to_name, Board, CastlingRights, Piece, Color, Variant};
+use crate::pgn::{Position, PgnGame, PgnReader, PgnGameReader, ChessError, Encoding};
+use crate::encoding::{encode_board, encode_move, PLANES};
+use crate::mcts::{LeafEval, Mcts, MctsConfig, POLICY_SIZE};
+use crate::clock::Speed;
+use crate::search::{Engine, SearchHandle, SearchLimits, SearchOptions};
+use crate::tune::EvalParams;
+use crate::move_gen::{generate_moves_with_promotions, piece_move_list, sort_moves, Bitboard, Promotions};
+use rayon::prelude::*;
+
+/// PyO3 Python API
+#[pyclass]
+pub struct PyBoard {
+    pub board: Board,
+    /// Piece a UCI pawn move to the last rank without a suffix promotes to,
+    /// or None to reject such moves
+    pub promotion: Option<Piece>,
+}
+
+#[pymethods]
+impl PyBoard {
+    #[new]
+    pub fn new() -> Self {
+        // Empty board
+        let mut board = Board::empty();
+        board.castling_rights = CastlingRights::standard();
+        board.refresh();
+        Self { board, promotion: None }
+    }
+
+    /// What `push`, `is_legal` and `replay_uci` do with a pawn move to the
+    /// last rank that names no piece ("e7e8"): "error" (the default) rejects
+    /// it, "queen", "rook", "bishop" or "knight" promotes to that piece.
+    /// Moves that name the piece ("e7e8n") are played as given either way.
+    #[getter]
+    pub fn promotion_policy(&self) -> &'static str {
+        self.promotion.map_or("error", piece_name)
+    }
+
+    #[setter]
+    pub fn set_promotion_policy(&mut self, policy: &str) -> PyResult<()> {
+        self.promotion = match policy {
+            "error" => None,
+            _ => match parse_piece(policy)? {
+                piece @ (Piece::Queen | Piece::Rook | Piece::Bishop | Piece::Knight) => Some(piece),
+                _ => return Err(PyErr::new::<exceptions::PyValueError, _>(
+                    format!("Invalid promotion policy: {}", policy)
+                )),
+            },
+        };
+        Ok(())
+    }
+
+    /// Set pieces on the board from a list of (piece_type, color, square)
+    /// tuples; squares are indexes (a1 = 0) or names such as "e4". Castling
+    /// rights without their king and rook and the en passant square are
+    /// dropped. With `validate`, a position `validate()` finds errors in
+    /// (missing kings, pawns on the back rank, the side not to move in
+    /// check, ...) raises ValueError listing them and leaves the board
+    /// unchanged.
+    #[pyo3(signature = (pieces, validate = false))]
+    pub fn set_pieces(&mut self, pieces: &Bound<'_, PyList>, validate: bool) -> PyResult<()> {
+        let mut board = self.board.clone();
+        board.clear();
+
+        for item in pieces.iter() {
+            let tuple = item.downcast::<PyTuple>()?;
+            let piece_str: String = tuple.get_item(0)?.extract()?;
+            let color_str: String = tuple.get_item(1)?.extract()?;
+            let square = extract_square(&tuple.get_item(2)?)?;
+
+            let piece = parse_piece(&piece_str)?;
+            let color = parse_color(&color_str)?;
+
+            board.put(square, piece, color);
+        }
+        for (color, kingside, _) in self.board.castling_rights.iter() {
+            if board.castling_rook(color, kingside).is_none() {
+                board.castling_rights.remove(color, kingside);
+            }
+        }
+        board.en_passant = None;
+        board.refresh();
+        if validate {
+            let report = rules::validate_board(&board);
+            let errors: Vec<String> = report.errors().map(|v| v.to_string()).collect();
+            if !errors.is_empty() {
+                return Err(PyErr::new::<exceptions::PyValueError, _>(
+                    format!("Invalid position: {}", errors.join("; "))
+                ));
+            }
+        }
+        self.board = board;
+        Ok(())
+    }
+
+    /// (piece, color) on a square given as index or name, None when empty
+    pub fn piece_at(&self, square: &Bound<'_, PyAny>) -> PyResult<Option<(&'static str, &'static str)>> {
+        let sq = extract_square(square)?;
+        Ok(self.board.squares()[sq as usize].map(|(piece, color)| (piece_name(piece), color_name(color))))
+    }
+
+    /// Set the side to move
+    pub fn set_side_to_move(&mut self, color_str: &str) -> PyResult<()> {
+        self.board.side_to_move = parse_color(color_str)?;
+        self.board.refresh_hash();
+        Ok(())
+    }
+
+    /// Occupied squares as (square, piece, color) tuples, e.g. (4, "king", "white")
+    pub fn items(&self) -> Vec<(u8, &'static str, &'static str)> {
+        occupied_items(&self.board)
+    }
+
+    /// Iterate over (square, piece, color) for every occupied square
+    pub fn __iter__(&self) -> SquareIter {
+        SquareIter { items: occupied_items(&self.board).into_iter() }
+    }
+
+    /// Bitboard of all occupied squares (bit n set for square n, a1 = 0)
+    pub fn occupied(&self) -> u64 {
+        self.board.occupied().into()
+    }
+
+    /// Bitboard of the squares occupied by "white" or "black"
+    pub fn occupied_by(&self, color: &str) -> PyResult<u64> {
+        Ok(self.board.occupied_by(parse_color(color)?).into())
+    }
+
+    /// Bitboard of one piece type of one color, e.g. pieces("knight", "white")
+    pub fn pieces(&self, piece: &str, color: &str) -> PyResult<u64> {
+        Ok(self.board.pieces(parse_piece(piece)?, parse_color(color)?).into())
+    }
+
+    /// Moves for the side to move, or for `color`, in UCI notation.
+    /// `promotions` selects the promotion pieces emitted: "all",
+    /// "queen_knight" or "queen". The side not to move gets no en passant
+    /// captures, as the en passant square belongs to the side to move.
+    /// `sorted` returns each move once, ordered by from square, to square
+    /// and promotion, as snapshot tests want.
+    #[pyo3(signature = (promotions = "all", color = None, sorted = false))]
+    pub fn generate_moves(&self, py: Python<'_>, promotions: &str, color: Option<&str>, sorted: bool) -> PyResult<PyObject> {
+        let promotions = parse_promotions(promotions)?;
+        let color = color.map_or(Ok(self.board.side_to_move), parse_color)?;
+        let board = board_for_color(&self.board, color);
+        let mut moves = generate_moves_with_promotions(&board, color, promotions);
+        if sorted {
+            sort_moves(&mut moves);
+        }
+        let mut moves_uci: Vec<String> = moves.iter()
+            .map(|mv| notation::to_standard_uci(&board, mv))
+            .collect();
+        if sorted {
+            // Castling given as king-takes-rook prints like a king move
+            let mut seen = std::collections::HashSet::new();
+            moves_uci.retain(|uci| seen.insert(uci.clone()));
+        }
+        Ok(PyList::new_bound(py, moves_uci).into())
+    }
+
+    /// Moves of the piece on `square` (an index or a name such as "e4"),
+    /// whichever side it belongs to, in UCI notation as `generate_moves`
+    /// gives them; empty for an empty square
+    #[pyo3(signature = (square, promotions = "all"))]
+    pub fn generate_moves_for_square(&self, square: &Bound<'_, PyAny>, promotions: &str) -> PyResult<Vec<String>> {
+        let sq = extract_square(square)?;
+        let promotions = parse_promotions(promotions)?;
+        let Some((_, color)) = self.board.squares()[sq as usize] else { return Ok(Vec::new()) };
+        let board = board_for_color(&self.board, color);
+        Ok(generate_moves_with_promotions(&board, color, promotions).iter()
+            .filter(|mv| mv.from == sq && mv.drop.is_none())
+            .map(|mv| notation::to_standard_uci(&board, mv))
+            .collect())
+    }
+
+    pub fn generate_moves_for_pieces_parallel(&self, py: Python<'_>, piece_sq_list: &Bound<'_, PyList>) -> PyResult<PyObject> {
+        // Convert Python list of (piece: str, square: int or name) to Rust Vec<(Piece, u8)>
+        let mut native_vec = Vec::with_capacity(piece_sq_list.len());
+        for item in piece_sq_list.iter() {
+            let tuple = item.downcast::<PyTuple>()?;
+            let piece_str: String = tuple.get_item(0)?.extract()?;
+            let sq = extract_square(&tuple.get_item(1)?)?;
+            native_vec.push((parse_piece(&piece_str)?, sq));
+        }
+        // Now parallelize over the Rust Vec
+        let results: Vec<Vec<String>> = native_vec
+            .par_iter()
+            .map(|(piece, sq)| {
+                let moves = piece_move_list(&self.board, *piece, *sq);
+                moves.iter().map(|mv| notation::to_standard_uci(&self.board, mv)).collect::<Vec<_>>()
+            })
+            .collect();
+        Ok(PyList::new_bound(py, results).into())
+    }
+
+    /// Load a position from FEN string; lenient accepts the malformed FENs
+    /// `Position::parse_lenient` does
+    #[pyo3(signature = (fen, lenient = false))]
+    pub fn load_fen(&mut self, fen: &str, lenient: bool) -> PyResult<()> {
+        let position = if lenient { Position::parse_lenient(fen) } else { fen.parse() }
+            .map_err(|e: ChessError| PyErr::new::<exceptions::PyValueError, _>(e.to_string()))?;
+
+        // Set all board fields from position
+        self.board = Board::from(position);
+        Ok(())
+    }
+
+    /// FEN of the current position; shredder writes castling rights as
+    /// rook files ("HAha") instead of KQkq
+    #[pyo3(signature = (shredder = false))]
+    pub fn fen(&self, shredder: bool) -> String {
+        let position = Position::from(&self.board);
+        if shredder { position.to_shredder_fen() } else { position.to_fen() }
+    }
+
+    /// Build a board from a python-chess Board (or anything with fen() or epd()),
+    /// keeping its variant
+    #[staticmethod]
+    pub fn from_pychess(board: &Bound<'_, PyAny>) -> PyResult<Self> {
+        let fen: String = if board.hasattr("fen")? {
+            board.call_method0("fen")?.extract()?
+        } else {
+            let epd: String = board.call_method0("epd")?.extract()?;
+            format!("{} 0 1", epd)
+        };
+        let mut py_board = PyBoard::new();
+        py_board.load_fen(&fen, false)?;
+        if board.hasattr("uci_variant")? {
+            let variant: String = board.getattr("uci_variant")?.extract()?;
+            py_board.board.variant = match variant.as_str() {
+                "crazyhouse" => Variant::Crazyhouse,
+                "3check" => Variant::ThreeCheck,
+                "kingofthehill" => Variant::KingOfTheHill,
+                "horde" => Variant::Horde,
+                "racingkings" => Variant::RacingKings,
+                _ => py_board.board.variant,
+            };
+        }
+        Ok(py_board)
+    }
+
+    /// Convert to a python-chess Board of the matching variant (requires python-chess)
+    pub fn to_pychess(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let class = match self.board.variant {
+            Variant::Standard => py.import_bound("chess")?.getattr("Board")?,
+            variant => {
+                let name = match variant {
+                    Variant::Crazyhouse => "CrazyhouseBoard",
+                    Variant::ThreeCheck => "ThreeCheckBoard",
+                    Variant::KingOfTheHill => "KingOfTheHillBoard",
+                    Variant::Horde => "HordeBoard",
+                    _ => "RacingKingsBoard",
+                };
+                py.import_bound("chess.variant")?.getattr(name)?
+            }
+        };
+        Ok(class.call1((self.fen(false),))?.into())
+    }
+
+    /// Whether a move is legal; accepts a UCI string or a python-chess Move
+    pub fn is_legal(&self, mv: &Bound<'_, PyAny>) -> PyResult<bool> {
+        Ok(notation::parse_uci_promoting(&self.board, &move_text(mv)?, self.promotion).is_ok())
+    }
+
+    /// Why a move given as a UCI string or python-chess Move cannot be
+    /// played, or None when it is legal
+    pub fn explain_move(&self, mv: &Bound<'_, PyAny>) -> PyResult<Option<String>> {
+        let mv = notation::parse_uci_text(&move_text(mv)?)
+            .map_err(|e| PyErr::new::<exceptions::PyValueError, _>(e.to_string()))?;
+        let legality = rules::explain_move(&self.board, &mv);
+        Ok((!legality.is_legal()).then(|| legality.to_string()))
+    }
+
+    /// Play a legal move given as a UCI string or a python-chess Move; a
+    /// promotion without its piece follows `promotion_policy`
+    pub fn push(&mut self, mv: &Bound<'_, PyAny>) -> PyResult<()> {
+        let mv = notation::parse_uci_promoting(&self.board, &move_text(mv)?, self.promotion)
+            .map_err(|e| PyErr::new::<exceptions::PyValueError, _>(e.to_string()))?;
+        self.board.make_move(&mv);
+        Ok(())
+    }
+
+    /// Boards after each of a sequence of UCI moves, without changing this
+    /// board; a bad move raises ValueError naming it and its ply
+    pub fn replay_uci(&self, moves: Vec<String>) -> PyResult<Vec<PyBoard>> {
+        let mut board = self.board.clone();
+        let mut positions = Vec::with_capacity(moves.len());
+        for (ply, uci) in moves.iter().enumerate() {
+            let mv = notation::parse_uci_at(&board, uci, ply + 1, self.promotion)
+                .map_err(|e| PyErr::new::<exceptions::PyValueError, _>(e.to_string()))?;
+            board.make_move(&mv);
+            positions.push(PyBoard { board: board.clone(), promotion: self.promotion });
+        }
+        Ok(positions)
+    }
+
+    /// Load Chess960 starting position number n (0..959; 518 is the standard start)
+    pub fn load_chess960(&mut self, n: u16) -> PyResult<()> {
+        if n >= chess960::CHESS960_POSITIONS {
+            return Err(PyErr::new::<exceptions::PyValueError, _>(
+                format!("Chess960 position out of range: {}", n)
+            ));
+        }
+        self.board = Board::chess960_start(n);
+        Ok(())
+    }
+
+    /// Boards are equal when they repeat: clocks and unusable en passant
+    /// squares are ignored
+    pub fn __eq__(&self, other: &PyBoard) -> bool {
+        self.board == other.board
+    }
+
+    pub fn __hash__(&self) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        self.board.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// 64-bit Zobrist hash of the current position, identical to the Rust-side key
+    pub fn transposition_key(&self) -> u64 {
+        zobrist::hash(&self.board)
+    }
+
+    /// Game phase of the current position: "opening", "middlegame" or "endgame"
+    pub fn game_phase(&self) -> &'static str {
+        phase::game_phase(&self.board).as_str()
+    }
+
+    /// Continuous game phase from 0 (all pieces on) to 256 (pawn endgame)
+    pub fn phase_value(&self) -> u32 {
+        phase::phase_value(&self.board)
+    }
+
+    /// Number of legal moves of the side to move
+    pub fn count_legal_moves(&self) -> usize {
+        rules::count_legal_moves(&self.board)
+    }
+
+    /// Whether the side to move has any legal move
+    pub fn has_legal_move(&self) -> bool {
+        rules::has_legal_move(&self.board)
+    }
+
+    /// Problems with the position as (severity, message) pairs, severity
+    /// being "error" or "warning"; empty for a sound position
+    pub fn validate(&self) -> Vec<(&'static str, String)> {
+        rules::validate_board(&self.board).violations.iter()
+            .map(|v| (if v.severity() == rules::Severity::Error { "error" } else { "warning" }, v.to_string()))
+            .collect()
+    }
+
+    /// King-safety features of White then Black (pawn shield, open files
+    /// near the king, attacks on the king zone), 7 floats each
+    pub fn king_safety_features(&self) -> Vec<f32> {
+        eval::king_safety_features(&self.board).to_vec()
+    }
+
+    /// Pawn structure as {"white": ..., "black": ...}, each a dict of the
+    /// squares of doubled, isolated, backward and passed pawns and the
+    /// number of pawn islands
+    pub fn pawn_structure(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let structure = pawns::pawn_structure(&self.board);
+        let dict = pyo3::types::PyDict::new_bound(py);
+        for color in [Color::White, Color::Black] {
+            let report = structure.side(color);
+            let side = pyo3::types::PyDict::new_bound(py);
+            side.set_item("doubled", report.doubled.bits())?;
+            side.set_item("isolated", report.isolated.bits())?;
+            side.set_item("backward", report.backward.bits())?;
+            side.set_item("passed", report.passed.bits())?;
+            side.set_item("islands", report.islands)?;
+            dict.set_item(color_name(color), side)?;
+        }
+        Ok(dict.into())
+    }
+
+    /// Pieces of the other side that `color` attacks, kings left out, as
+    /// dicts with the square, piece, attacker and defender squares, the
+    /// material the best capture wins by static exchange ("gain") and
+    /// whether the piece is hanging; the most profitable captures first
+    pub fn threats(&self, py: Python<'_>, color: &str) -> PyResult<Vec<PyObject>> {
+        threats::threats(&self.board, parse_color(color)?).into_iter()
+            .map(|threat| {
+                let dict = pyo3::types::PyDict::new_bound(py);
+                dict.set_item("square", threat.square)?;
+                dict.set_item("piece", piece_name(threat.piece))?;
+                dict.set_item("attackers", threat.attackers.bits())?;
+                dict.set_item("defenders", threat.defenders.bits())?;
+                dict.set_item("gain", threat.gain)?;
+                dict.set_item("hanging", threat.hanging)?;
+                Ok(dict.into())
+            })
+            .collect()
+    }
+
+    /// Count leaf nodes of the legal move tree; a non-zero hash_mb caches
+    /// subtree counts in a table of that many megabytes
+    #[pyo3(signature = (depth, hash_mb = 0))]
+    pub fn perft(&self, py: Python<'_>, depth: u32, hash_mb: usize) -> u64 {
+        py.allow_threads(|| match hash_mb {
+            0 => perft::perft(&self.board, depth),
+            _ => perft::perft_hashed(&self.board, depth, hash_mb),
+        })
+    }
+
+    /// Alpha-beta search with the built-in evaluation, run with the GIL
+    /// released. Stops at `depth` plies, `movetime` seconds or `nodes` nodes,
+    /// whichever comes first. `info`, if given, is called after every depth
+    /// with (depth, score, nodes, nps, pv) as in a UCI info line, e.g.
+    /// (5, "cp 35", 12000, 800000, ["e2e4", "e7e5"]). Returns the best move
+    /// in UCI (None when there is no legal move) and its score. Calling
+    /// `stop()` on `handle` from another thread ends the search early with
+    /// the best move so far, as does Ctrl-C or an exception in `info`.
+    /// `threads` above 1 searches in parallel, sharing a `hash_mb` table.
+    #[pyo3(signature = (depth = None, movetime = None, nodes = None, info = None, handle = None, threads = 1, hash_mb = 16))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn search(
+        &self,
+        py: Python<'_>,
+        depth: Option<u32>,
+        movetime: Option<f64>,
+        nodes: Option<u64>,
+        info: Option<PyObject>,
+        handle: Option<PyRef<'_, PySearchHandle>>,
+        threads: usize,
+        hash_mb: usize,
+    ) -> PyResult<(Option<String>, String)> {
+        let limits = SearchLimits {
+            depth,
+            nodes,
+            movetime: movetime_duration(movetime)?,
+        };
+        let board = self.board.clone();
+        let handle = handle.map_or_else(SearchHandle::new, |handle| handle.handle.clone());
+        let mut callback_error = None;
+        let result = py.allow_threads(|| {
+            let engine = Engine::new(SearchOptions { threads, hash_mb, ..SearchOptions::default() });
+            engine.search(&board, &limits, &EvalParams::default(), &handle, |progress| {
+                Python::with_gil(|py| {
+                    let mut outcome = py.check_signals();
+                    if let (Ok(()), Some(info)) = (&outcome, &info) {
+                        let args = (progress.depth, progress.score.to_string(), progress.nodes, progress.nps, notation::line_to_standard_uci(&board, &progress.pv));
+                        outcome = info.call1(py, args).map(drop);
+                    }
+                    if let Err(e) = outcome {
+                        callback_error.get_or_insert(e);
+                        handle.stop();
+                    }
+                });
+            })
+        });
+        if let Some(e) = callback_error {
+            return Err(e);
+        }
+        let result = result.map_err(|e| PyErr::new::<exceptions::PyValueError, _>(e.to_string()))?;
+        Ok((result.best_move.map(|mv| notation::to_standard_uci(&board, &mv)), result.score.to_string()))
+    }
+
+    /// PGN result of a finished game ("1-0", "0-1" or "1/2-1/2"), None while
+    /// the game goes on. Draws that must be claimed do not end the game, and
+    /// repetitions are not tracked since the board keeps no history.
+    pub fn result(&self) -> Option<&'static str> {
+        game::board_outcome(&self.board).map(|outcome| outcome.result())
+    }
+
+    /// How the game ended, None while it goes on
+    pub fn termination(&self) -> Option<PyTermination> {
+        game::board_outcome(&self.board).map(|outcome| outcome.termination.into())
+    }
+
+    /// Load positions from a PGN file. `encoding` is "utf-8", "lossy",
+    /// "latin-1", "windows-1252" or "auto" (Windows-1252 for non-UTF-8 lines)
+    #[pyo3(signature = (path, encoding = "utf-8"))]
+    pub fn load_pgn(&mut self, path: &str, encoding: &str) -> PyResult<Vec<(String, String)>> {
+        let encoding: Encoding = encoding.parse()
+            .map_err(|e: ChessError| PyErr::new::<exceptions::PyValueError, _>(e.to_string()))?;
+        let file = std::fs::File::open(path)
+            .map_err(|e| PyErr::new::<exceptions::PyValueError, _>(e.to_string()))?;
+        
+        let mut reader = PgnReader::new(file).with_encoding(encoding);
+        let mut positions = Vec::new();
+        
+        while let Some(position) = reader.next_position()
+            .map_err(|e| PyErr::new::<exceptions::PyValueError, _>(e.to_string()))? {
+            
+            // Convert position to FEN for storage
+            let fen = position.to_fen();
+            
+            // Load position and generate moves
+            self.load_fen(&fen, false)?;
+            let moves = Python::with_gil(|py| self.generate_moves(py, "all", None, false))?;
+            
+            positions.push((fen, format!("{:?}", moves)));
+        }
+        
+        Ok(positions)
+    }
+}
+
+/// UCI text of a move given as a string or as an object with a uci() method
+fn move_text(mv: &Bound<'_, PyAny>) -> PyResult<String> {
+    if let Ok(text) = mv.extract::<String>() {
+        return Ok(text);
+    }
+    mv.call_method0("uci")?.extract()
+}
+
+/// Why a game ended, named as in python-chess
+#[pyclass(name = "Termination")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+pub enum PyTermination {
+    CHECKMATE,
+    STALEMATE,
+    INSUFFICIENT_MATERIAL,
+    SEVENTYFIVE_MOVES,
+    FIVEFOLD_REPETITION,
+    FIFTY_MOVES,
+    THREEFOLD_REPETITION,
+    VARIANT_WIN,
+    VARIANT_DRAW,
+}
+
+impl From<game::Termination> for PyTermination {
+    fn from(termination: game::Termination) -> Self {
+        use crate::game::{DrawReason, Termination};
+        match termination {
+            Termination::Checkmate => PyTermination::CHECKMATE,
+            Termination::VariantWin => PyTermination::VARIANT_WIN,
+            Termination::Draw(DrawReason::Stalemate) => PyTermination::STALEMATE,
+            Termination::Draw(DrawReason::InsufficientMaterial) => PyTermination::INSUFFICIENT_MATERIAL,
+            Termination::Draw(DrawReason::SeventyFiveMoveRule) => PyTermination::SEVENTYFIVE_MOVES,
+            Termination::Draw(DrawReason::FivefoldRepetition) => PyTermination::FIVEFOLD_REPETITION,
+            Termination::Draw(DrawReason::FiftyMoveRule) => PyTermination::FIFTY_MOVES,
+            Termination::Draw(DrawReason::ThreefoldRepetition) => PyTermination::THREEFOLD_REPETITION,
+            Termination::Draw(DrawReason::VariantRule) => PyTermination::VARIANT_DRAW,
+        }
+    }
+}
+
+/// Iterator over the occupied squares of a PyBoard
+#[pyclass]
+pub struct SquareIter {
+    items: std::vec::IntoIter<(u8, &'static str, &'static str)>,
+}
+
+#[pymethods]
+impl SquareIter {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self) -> Option<(u8, &'static str, &'static str)> {
+        self.items.next()
+    }
+}
+
+/// Training positions streamed from a PGN file.
+///
+/// Iterating yields `(planes, move, value)` tuples: a float32 numpy array of
+/// shape (18, 8, 8), the from*64+to index of the move played and the game
+/// result from the side to move's point of view. Games are dealt round-robin
+/// to `num_workers` shards; inside a DataLoader worker the shard is taken from
+/// `torch.utils.data.get_worker_info()` unless given explicitly. Games without
+/// a result are skipped.
+#[pyclass]
+pub struct PyPositionDataset {
+    path: String,
+    worker_id: Option<usize>,
+    num_workers: Option<usize>,
+}
+
+#[pymethods]
+impl PyPositionDataset {
+    #[new]
+    #[pyo3(signature = (path, worker_id=None, num_workers=None))]
+    pub fn new(path: String, worker_id: Option<usize>, num_workers: Option<usize>) -> PyResult<Self> {
+        if num_workers == Some(0) || matches!((worker_id, num_workers), (Some(id), Some(n)) if id >= n) {
+            return Err(PyErr::new::<exceptions::PyValueError, _>("worker_id must be less than num_workers"));
+        }
+        Ok(Self { path, worker_id, num_workers })
+    }
+
+    fn __iter__(&self, py: Python<'_>) -> PyResult<PositionIter> {
+        let (mut worker_id, mut num_workers) = (0, 1);
+        if let Ok(data) = py.import_bound("torch.utils.data") {
+            let info = data.call_method0("get_worker_info")?;
+            if !info.is_none() {
+                worker_id = info.getattr("id")?.extract()?;
+                num_workers = info.getattr("num_workers")?.extract()?;
+            }
+        }
+        let file = std::fs::File::open(&self.path)?;
+        Ok(PositionIter {
+            games: PgnGameReader::new(file),
+            samples: std::collections::VecDeque::new(),
+            game_index: 0,
+            worker_id: self.worker_id.unwrap_or(worker_id),
+            num_workers: self.num_workers.unwrap_or(num_workers),
+        })
+    }
+}
+
+#[pyclass]
+pub struct PositionIter {
+    games: PgnGameReader<std::fs::File>,
+    samples: std::collections::VecDeque<(Vec<f32>, u16, f32)>,
+    game_index: usize,
+    worker_id: usize,
+    num_workers: usize,
+}
+
+#[pymethods]
+impl PositionIter {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Option<(PyObject, u16, f32)>> {
+        while self.samples.is_empty() {
+            let game = match self.games.next_game() {
+                Ok(Some(game)) => game,
+                Ok(None) => return Ok(None),
+                Err(e) => return Err(PyErr::new::<exceptions::PyValueError, _>(e.to_string())),
+            };
+            self.game_index += 1;
+            if (self.game_index - 1) % self.num_workers != self.worker_id {
+                continue;
+            }
+            let white_score = match game.result.as_deref() {
+                Some("1-0") => 1.0,
+                Some("0-1") => -1.0,
+                Some("1/2-1/2") => 0.0,
+                _ => continue,
+            };
+            let positions = py.allow_threads(|| game.replay())
+                .map_err(|e| PyErr::new::<exceptions::PyValueError, _>(e.to_string()))?;
+            self.samples.extend(positions.iter().map(|(board, mv)| {
+                let value = if board.side_to_move == Color::White { white_score } else { -white_score };
+                (encode_board(board), encode_move(mv), value)
+            }));
+        }
+
+        let (planes, mv, value) = self.samples.pop_front().unwrap();
+        let buffer = Bound::new(py, PlaneBuffer::new(planes, &[PLANES, 8, 8]))?;
+        let array = py.import_bound("numpy")?.call_method1("asarray", (buffer,))?;
+        Ok(Some((array.into(), mv, value)))
+    }
+}
+
+/// Read-only float32 tensor owned by Rust, exposed through the buffer protocol
+/// so `numpy.asarray(buf)` or `torch.frombuffer(buf)` wrap it without copying
+#[pyclass]
+pub struct PlaneBuffer {
+    data: Vec<f32>,
+    shape: Vec<isize>,
+    strides: Vec<isize>,
+}
+
+impl PlaneBuffer {
+    fn new(data: Vec<f32>, shape: &[usize]) -> Self {
+        let shape: Vec<isize> = shape.iter().map(|&dim| dim as isize).collect();
+        let mut strides = vec![std::mem::size_of::<f32>() as isize; shape.len()];
+        for i in (0..shape.len().saturating_sub(1)).rev() {
+            strides[i] = strides[i + 1] * shape[i + 1];
+        }
+        Self { data, shape, strides }
+    }
+}
+
+#[pymethods]
+impl PlaneBuffer {
+    #[getter]
+    fn shape(&self) -> Vec<isize> {
+        self.shape.clone()
+    }
+
+    fn __len__(&self) -> usize {
+        self.shape.first().map_or(0, |&dim| dim as usize)
+    }
+
+    unsafe fn __getbuffer__(slf: Bound<'_, Self>, view: *mut pyo3::ffi::Py_buffer, flags: c_int) -> PyResult<()> {
+        use pyo3::ffi;
+        if view.is_null() {
+            return Err(exceptions::PyBufferError::new_err("View is null"));
+        }
+        if (flags & ffi::PyBUF_WRITABLE) == ffi::PyBUF_WRITABLE {
+            return Err(exceptions::PyBufferError::new_err("PlaneBuffer is read-only"));
+        }
+        let buffer = slf.borrow();
+        // The pointers stay valid while the view holds a reference to the object
+        (*view).buf = buffer.data.as_ptr() as *mut c_void;
+        (*view).len = std::mem::size_of_val(buffer.data.as_slice()) as isize;
+        (*view).readonly = 1;
+        (*view).itemsize = std::mem::size_of::<f32>() as isize;
+        (*view).format = if (flags & ffi::PyBUF_FORMAT) == ffi::PyBUF_FORMAT {
+            c"f".as_ptr() as *mut c_char
+        } else {
+            std::ptr::null_mut()
+        };
+        (*view).ndim = buffer.shape.len() as c_int;
+        (*view).shape = if (flags & ffi::PyBUF_ND) == ffi::PyBUF_ND {
+            buffer.shape.as_ptr() as *mut isize
+        } else {
+            std::ptr::null_mut()
+        };
+        (*view).strides = if (flags & ffi::PyBUF_STRIDES) == ffi::PyBUF_STRIDES {
+            buffer.strides.as_ptr() as *mut isize
+        } else {
+            std::ptr::null_mut()
+        };
+        (*view).suboffsets = std::ptr::null_mut();
+        (*view).internal = std::ptr::null_mut();
+        drop(buffer);
+        (*view).obj = slf.into_any().into_ptr();
+        Ok(())
+    }
+
+    unsafe fn __releasebuffer__(&self, _view: *mut pyo3::ffi::Py_buffer) {}
+}
+
+/// (square, piece, color) for every occupied square of `board`
+fn occupied_items(board: &Board) -> Vec<(u8, &'static str, &'static str)> {
+    board.squares().iter().enumerate()
+        .filter_map(|(sq, square)| square.map(|(piece, color)| (sq as u8, piece_name(piece), color_name(color))))
+        .collect()
+}
+
+/// Name of a piece as used by the Python API, e.g. "knight"
+fn piece_name(piece: Piece) -> &'static str {
+    match piece {
+        Piece::Pawn => "pawn",
+        Piece::Knight => "knight",
+        Piece::Bishop => "bishop",
+        Piece::Rook => "rook",
+        Piece::Queen => "queen",
+        Piece::King => "king",
+    }
+}
+
+/// Name of a color as used by the Python API
+fn color_name(color: Color) -> &'static str {
+    match color {
+        Color::White => "white",
+        Color::Black => "black",
+    }
+}
+
+/// Parse a piece name such as "knight"
+fn parse_piece(name: &str) -> PyResult<Piece> {
+    match name.to_lowercase().as_str() {
+        "pawn" => Ok(Piece::Pawn),
+        "knight" => Ok(Piece::Knight),
+        "bishop" => Ok(Piece::Bishop),
+        "rook" => Ok(Piece::Rook),
+        "queen" => Ok(Piece::Queen),
+        "king" => Ok(Piece::King),
+        _ => Err(PyErr::new::<exceptions::PyValueError, _>(
+            format!("Invalid piece type: {}", name)
+        )),
+    }
+}
+
+/// Parse a color name, "white" or "black"
+fn parse_color(name: &str) -> PyResult<Color> {
+    match name.to_lowercase().as_str() {
+        "white" => Ok(Color::White),
+        "black" => Ok(Color::Black),
+        _ => Err(PyErr::new::<exceptions::PyValueError, _>(
+            format!("Invalid color: {}", name)
+        )),
+    }
+}
+
+/// Parse a promotions option, "all", "queen_knight" or "queen"
+fn parse_promotions(name: &str) -> PyResult<Promotions> {
+    match name {
+        "all" => Ok(Promotions::All),
+        "queen_knight" => Ok(Promotions::QueenAndKnight),
+        "queen" => Ok(Promotions::QueenOnly),
+        _ => Err(PyErr::new::<exceptions::PyValueError, _>(
+            format!("Invalid promotions option: {}", name)
+        )),
+    }
+}
+
+/// A movetime in seconds as a Duration; negative times count as zero, and
+/// times too long for a Duration are a ValueError rather than a panic
+fn movetime_duration(movetime: Option<f64>) -> PyResult<Option<std::time::Duration>> {
+    movetime.map(|secs| {
+        std::time::Duration::try_from_secs_f64(secs.max(0.0))
+            .map_err(|_| PyErr::new::<exceptions::PyValueError, _>(format!("Invalid movetime: {} seconds", secs)))
+    }).transpose()
+}
+
+/// `board` with `color` to move, as `generate_pseudo_legal` sees it: the
+/// en passant square is dropped when the side to move changes
+fn board_for_color(board: &Board, color: Color) -> std::borrow::Cow<'_, Board> {
+    if color == board.side_to_move {
+        return std::borrow::Cow::Borrowed(board);
+    }
+    let mut board = board.clone();
+    board.side_to_move = color;
+    board.en_passant = None;
+    board.refresh_hash();
+    std::borrow::Cow::Owned(board)
+}
+
+/// Squares set in a bitboard, in ascending order
+#[pyfunction]
+fn bitboard_to_squares(bb: u64) -> Vec<u8> {
+    Bitboard::from(bb).bits()
+}
+
+/// Bitboard with the given squares set, as indexes or names
+#[pyfunction]
+fn squares_to_bitboard(squares: Vec<Bound<'_, PyAny>>) -> PyResult<u64> {
+    let mut bb = Bitboard::empty();
+    for square in &squares {
+        bb.set(extract_square(square)?);
+    }
+    Ok(bb.into())
+}
+
+/// A square from Python: an index from 0 (a1) to 63 (h8) or a name such as
+/// "e4"
+fn extract_square(square: &Bound<'_, PyAny>) -> PyResult<u8> {
+    if let Ok(name) = square.extract::<&str>() {
+        return name_to_square(&name.to_ascii_lowercase()).ok_or_else(|| PyErr::new::<exceptions::PyValueError, _>(
+            format!("Invalid square name: {}", name)
+        ));
+    }
+    match square.extract::<u8>()? {
+        sq if sq < 64 => Ok(sq),
+        sq => Err(PyErr::new::<exceptions::PyValueError, _>(
+            format!("Invalid square index: {}", sq)
+        )),
+    }
+}
+
+/// Algebraic name of a square index, e.g. square_to_name(28) == "e4"
+#[pyfunction(name = "square_to_name")]
+fn py_square_to_name(sq: u8) -> PyResult<&'static str> {
+    square_to_name(sq).ok_or_else(|| PyErr::new::<exceptions::PyValueError, _>(
+        format!("Invalid square index: {}", sq)
+    ))
+}
+
+/// Square index of a name such as "e4", None when it is not a square
+#[pyfunction(name = "name_to_square")]
+fn py_name_to_square(name: &str) -> Option<u8> {
+    name_to_square(&name.to_ascii_lowercase())
+}
+
+impl Default for PyBoard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Normalize a FEN for deduplication (clocks, castling order, en passant)
+#[pyfunction(name = "canonical_fen")]
+#[pyo3(signature = (fen, normalize_castling = false))]
+fn py_canonical_fen(fen: &str, normalize_castling: bool) -> PyResult<String> {
+    pgn::canonical_fen(fen, normalize_castling)
+        .map_err(|e| PyErr::new::<exceptions::PyValueError, _>(e.to_string()))
+}
+
+/// Elo difference, 95% error margin, likelihood of superiority and draw
+/// ratio of a match result, as a dict; None without games or for a 0% or
+/// 100% score
+#[pyfunction]
+fn elo_stats(py: Python<'_>, wins: u32, draws: u32, losses: u32) -> PyResult<Option<PyObject>> {
+    let Some(estimate) = stats::Wdl::new(wins, draws, losses).elo() else { return Ok(None) };
+    let dict = pyo3::types::PyDict::new_bound(py);
+    dict.set_item("elo", estimate.elo)?;
+    dict.set_item("error", estimate.error)?;
+    dict.set_item("los", estimate.los)?;
+    dict.set_item("draw_ratio", estimate.draw_ratio)?;
+    Ok(Some(dict.into()))
+}
+
+/// PGN text of a game given as UCI moves from `start_fen` (None for the
+/// standard start), with the tag pairs of `headers` in their dict order
+#[pyfunction]
+#[pyo3(signature = (start_fen, moves, headers = None))]
+fn pgn_from_uci(start_fen: Option<&str>, moves: Vec<String>, headers: Option<&Bound<'_, pyo3::types::PyDict>>) -> PyResult<String> {
+    let headers = match headers {
+        Some(dict) => dict.iter()
+            .map(|(name, value)| Ok((name.extract::<String>()?, value.str()?.to_string())))
+            .collect::<PyResult<Vec<_>>>()?,
+        None => Vec::new(),
+    };
+    PgnGame::from_uci(start_fen, &moves, headers)
+        .map(|game| game.to_pgn())
+        .map_err(|e| PyErr::new::<exceptions::PyValueError, _>(e.to_string()))
+}
+
+/// SAN of every move of a game given in UCI, from `start_fen` (the
+/// standard start when None); a bad move raises ValueError naming it and
+/// its ply
+#[pyfunction]
+#[pyo3(signature = (start_fen, uci_moves))]
+fn sans_for_game(py: Python<'_>, start_fen: Option<&str>, uci_moves: Vec<String>) -> PyResult<Vec<String>> {
+    py.allow_threads(|| notation::sans_for_game(start_fen, &uci_moves))
+        .map_err(|e| PyErr::new::<exceptions::PyValueError, _>(e.to_string()))
+}
+
+/// UCI of every move of a game given in SAN, from `start_fen` (the standard
+/// start when None); a bad move raises ValueError naming it and its ply
+#[pyfunction]
+#[pyo3(signature = (start_fen, san_moves))]
+fn ucis_for_game(py: Python<'_>, start_fen: Option<&str>, san_moves: Vec<String>) -> PyResult<Vec<String>> {
+    py.allow_threads(|| notation::ucis_for_game(start_fen, &san_moves))
+        .map_err(|e| PyErr::new::<exceptions::PyValueError, _>(e.to_string()))
+}
+
+/// Puzzles from every game in `pgn`, each a dict with "fen" (the solver to
+/// move), "moves" (the solution in UCI), "san", "themes", "score",
+/// "blunder" (SAN of the move that allowed it), "ply" and "site". Every
+/// position is searched to `depth`; solutions run at most `max_plies`.
+#[pyfunction]
+#[pyo3(signature = (pgn, depth = 8, max_plies = 7, threads = 1))]
+fn mine_puzzles(py: Python<'_>, pgn: &str, depth: u32, max_plies: usize, threads: usize) -> PyResult<Vec<PyObject>> {
+    let options = puzzle::PuzzleOptions { limits: SearchLimits { depth: Some(depth), ..Default::default() }, max_plies, ..Default::default() };
+    let puzzles = py.allow_threads(|| {
+        let mut miner = puzzle::PuzzleMiner::new(EvalParams::default(), options, SearchOptions { threads, ..SearchOptions::default() });
+        let mut puzzles = Vec::new();
+        for game in PgnGameReader::new(pgn.as_bytes()) {
+            puzzles.extend(miner.mine_game(&game?)?);
+        }
+        Ok::<_, ChessError>(puzzles)
+    }).map_err(|e| PyErr::new::<exceptions::PyValueError, _>(e.to_string()))?;
+    puzzles.into_iter()
+        .map(|puzzle| {
+            let dict = pyo3::types::PyDict::new_bound(py);
+            dict.set_item("fen", puzzle.fen())?;
+            dict.set_item("moves", notation::line_to_standard_uci(&puzzle.board, &puzzle.solution))?;
+            dict.set_item("san", puzzle.solution_san())?;
+            dict.set_item("themes", puzzle.themes.iter().map(|theme| theme.to_string()).collect::<Vec<_>>())?;
+            dict.set_item("score", puzzle.score.to_string())?;
+            dict.set_item("blunder", puzzle.blunder)?;
+            dict.set_item("ply", puzzle.ply)?;
+            dict.set_item("site", puzzle.site)?;
+            Ok(dict.into())
+        })
+        .collect()
+}
+
+/// Search a position with the built-in engine and return a dict with
+/// "best_move" (UCI, None when there is no legal move), "score" ("cp 35",
+/// "mate 2") and "pv" of the best line, "depth", "nodes" and "lines", the
+/// `multipv` best lines as dicts of "move", "score" and "pv". Without
+/// `depth`, `movetime` (seconds) or `nodes` it searches for one second. The
+/// GIL is released meanwhile, and Ctrl-C ends the search early.
+#[pyfunction]
+#[pyo3(signature = (fen, depth = None, movetime = None, multipv = 1, nodes = None, threads = 1))]
+fn analyse(
+    py: Python<'_>,
+    fen: &str,
+    depth: Option<u32>,
+    movetime: Option<f64>,
+    multipv: usize,
+    nodes: Option<u64>,
+    threads: usize,
+) -> PyResult<PyObject> {
+    let board = Board::from(fen.parse::<Position>()
+        .map_err(|e| PyErr::new::<exceptions::PyValueError, _>(e.to_string()))?);
+    let movetime = match (depth, movetime, nodes) {
+        (None, None, None) => Some(1.0),
+        _ => movetime,
+    };
+    let limits = SearchLimits { depth, nodes, movetime: movetime_duration(movetime)? };
+    let engine = Engine::new(SearchOptions { threads, multipv: multipv.max(1), ..SearchOptions::default() });
+    let handle = SearchHandle::new();
+    let mut interrupt = None;
+    let result = py.allow_threads(|| {
+        engine.search(&board, &limits, &EvalParams::default(), &handle, |_| {
+            if let Err(e) = Python::with_gil(|py| py.check_signals()) {
+                interrupt.get_or_insert(e);
+                handle.stop();
+            }
+        })
+    });
+    if let Some(e) = interrupt {
+        return Err(e);
+    }
+    let result = result.map_err(|e| PyErr::new::<exceptions::PyValueError, _>(e.to_string()))?;
+
+    let lines = PyList::empty_bound(py);
+    for line in &result.lines {
+        let pv = notation::line_to_standard_uci(&board, &line.pv);
+        let entry = pyo3::types::PyDict::new_bound(py);
+        entry.set_item("move", pv.first())?;
+        entry.set_item("score", line.score.to_string())?;
+        entry.set_item("pv", pv)?;
+        lines.append(entry)?;
+    }
+    let dict = pyo3::types::PyDict::new_bound(py);
+    dict.set_item("best_move", result.best_move.map(|mv| notation::to_standard_uci(&board, &mv)))?;
+    dict.set_item("score", result.score.to_string())?;
+    dict.set_item("pv", notation::line_to_standard_uci(&board, &result.pv))?;
+    dict.set_item("depth", result.depth)?;
+    dict.set_item("nodes", result.nodes)?;
+    dict.set_item("lines", lines)?;
+    Ok(dict.into())
+}
+
+/// Perft node counts of many FENs, computed in parallel without the GIL
+#[pyfunction]
+fn perft_many(py: Python<'_>, fens: Vec<String>, depth: u32) -> PyResult<Vec<u64>> {
+    let nodes: Result<Vec<u64>, ChessError> = py.allow_threads(|| {
+        perft::perft_many(&fens, depth).into_iter().collect()
+    });
+    nodes.map_err(|e| PyErr::new::<exceptions::PyValueError, _>(e.to_string()))
+}
+
+/// Encode a batch of FENs into a (len(fens), 18, 8, 8) float32 PlaneBuffer
+#[pyfunction]
+fn encode_fens(py: Python<'_>, fens: Vec<String>) -> PyResult<PlaneBuffer> {
+    let encoded: Result<Vec<Vec<f32>>, ChessError> = py.allow_threads(|| {
+        batch::map_fens(&fens, encode_board).into_iter().collect()
+    });
+    let encoded = encoded.map_err(|e| PyErr::new::<exceptions::PyValueError, _>(e.to_string()))?;
+    Ok(PlaneBuffer::new(encoded.concat(), &[fens.len(), PLANES, 8, 8]))
+}
+
+/// Read a PGN file into a pyarrow Table with one row per position (requires
+/// pyarrow and the `arrow` feature)
+#[cfg(feature = "arrow")]
+#[pyfunction]
+#[pyo3(signature = (path, batch_size = 65536))]
+fn pgn_to_arrow(py: Python<'_>, path: &str, batch_size: usize) -> PyResult<PyObject> {
+    use arrow_array::{Array, StructArray};
+
+    let file = std::fs::File::open(path)?;
+    let batches: Result<Vec<_>, ChessError> = py.allow_threads(|| {
+        arrow::record_batches(PgnGameReader::new(file), batch_size).collect()
+    });
+    let batches = batches.map_err(|e| PyErr::new::<exceptions::PyValueError, _>(e.to_string()))?;
+
+    let pyarrow = py.import_bound("pyarrow")?;
+    let record_batch = pyarrow.getattr("RecordBatch")?;
+    let mut py_batches = Vec::with_capacity(batches.len());
+    for batch in batches {
+        // Hand the columns over through the Arrow C data interface; pyarrow
+        // takes ownership and marks the structs as released
+        let data = StructArray::from(batch).into_data();
+        let (mut array, mut schema) = arrow_array::ffi::to_ffi(&data)
+            .map_err(|e| PyErr::new::<exceptions::PyValueError, _>(e.to_string()))?;
+        py_batches.push(record_batch.call_method1(
+            "_import_from_c",
+            (std::ptr::addr_of_mut!(array) as usize, std::ptr::addr_of_mut!(schema) as usize),
+        )?);
+    }
+    let mut schema = arrow_schema::ffi::FFI_ArrowSchema::try_from(arrow::position_schema().as_ref())
+        .map_err(|e| PyErr::new::<exceptions::PyValueError, _>(e.to_string()))?;
+    let schema = pyarrow.getattr("Schema")?
+        .call_method1("_import_from_c", (std::ptr::addr_of_mut!(schema) as usize,))?;
+    Ok(pyarrow.getattr("Table")?.call_method1("from_batches", (py_batches, schema))?.into())
+}
+
+/// Monte Carlo tree search whose leaves are evaluated in batches by Python
+#[pyclass(name = "Mcts")]
+pub struct PyMcts {
+    mcts: Mcts,
+}
+
+#[pymethods]
+impl PyMcts {
+    /// `dirichlet_epsilon` above 0 mixes Dirichlet noise into the root priors
+    /// and `temperature` above 0 makes `choose_move` sample by visit counts
+    #[new]
+    #[pyo3(signature = (board, batch_size = 16, c_puct = 1.5, dirichlet_alpha = 0.3, dirichlet_epsilon = 0.0, temperature = 0.0, seed = None))]
+    pub fn new(
+        board: &PyBoard,
+        batch_size: usize,
+        c_puct: f32,
+        dirichlet_alpha: f32,
+        dirichlet_epsilon: f32,
+        temperature: f32,
+        seed: Option<u64>,
+    ) -> Self {
+        let config = MctsConfig { batch_size, c_puct, dirichlet_alpha, dirichlet_epsilon, temperature, seed };
+        Self { mcts: Mcts::new(board.board.clone(), config) }
+    }
+
+    /// Search until `playouts` playouts are done. `evaluate` is called with a
+    /// (n, 18, 8, 8) PlaneBuffer of leaf positions and returns n values in
+    /// [-1, 1] for the side to move, or a (values, policies) pair with a
+    /// 4096-entry policy per leaf indexed by from*64+to
+    pub fn run(&mut self, py: Python<'_>, playouts: u32, evaluate: &Bound<'_, PyAny>) -> PyResult<()> {
+        while self.mcts.playouts() < playouts {
+            let planes = self.collect()?;
+            let evals = if planes.__len__() == 0 {
+                Vec::new()
+            } else {
+                leaf_evals(&evaluate.call1((Bound::new(py, planes)?,))?)?
+            };
+            self.mcts.submit(evals).map_err(|e| PyErr::new::<exceptions::PyValueError, _>(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Next batch of leaves as a (n, 18, 8, 8) PlaneBuffer, for driving the
+    /// search step by step; answer it with `submit`
+    pub fn collect(&mut self) -> PyResult<PlaneBuffer> {
+        let boards = self.mcts.collect().map_err(|e| PyErr::new::<exceptions::PyValueError, _>(e.to_string()))?;
+        let planes: Vec<f32> = boards.iter().flat_map(encode_board).collect();
+        Ok(PlaneBuffer::new(planes, &[boards.len(), PLANES, 8, 8]))
+    }
+
+    /// Evaluations of the last `collect`, in the form `run` expects from its callable
+    pub fn submit(&mut self, evals: &Bound<'_, PyAny>) -> PyResult<()> {
+        self.mcts.submit(leaf_evals(evals)?).map_err(|e| PyErr::new::<exceptions::PyValueError, _>(e.to_string()))
+    }
+
+    #[getter]
+    pub fn playouts(&self) -> u32 {
+        self.mcts.playouts()
+    }
+
+    /// Most visited root move in UCI, or None
+    pub fn best_move(&self) -> Option<String> {
+        self.mcts.best_move().map(|mv| notation::to_standard_uci(self.mcts.root(), &mv))
+    }
+
+    /// Play a move (UCI string or python-chess Move) from the root, keeping
+    /// the search below it for the next `run`
+    pub fn advance(&mut self, mv: &Bound<'_, PyAny>) -> PyResult<()> {
+        notation::parse_uci(self.mcts.root(), &move_text(mv)?)
+            .and_then(|mv| self.mcts.advance(&mv))
+            .map_err(|e| PyErr::new::<exceptions::PyValueError, _>(e.to_string()))
+    }
+
+    /// Move to play in UCI, sampled by visit counts at the configured temperature
+    pub fn choose_move(&mut self) -> Option<String> {
+        self.mcts.choose_move().map(|mv| notation::to_standard_uci(self.mcts.root(), &mv))
+    }
+
+    /// Root visit shares as a 4096-entry float32 PlaneBuffer indexed by
+    /// from*64+to, the policy target for training
+    pub fn policy_target(&self) -> PlaneBuffer {
+        PlaneBuffer::new(self.mcts.policy_target(), &[POLICY_SIZE])
+    }
+
+    /// (move, visits, mean value) for each root move, most visited first
+    pub fn root_moves(&self) -> Vec<(String, u32, f32)> {
+        self.mcts.root_moves().into_iter()
+            .map(|(mv, visits, q)| (notation::to_standard_uci(self.mcts.root(), &mv), visits, q))
+            .collect()
+    }
+}
+
+fn leaf_evals(result: &Bound<'_, PyAny>) -> PyResult<Vec<LeafEval>> {
+    if let Ok((values, policies)) = result.extract::<(Vec<f32>, Vec<Vec<f32>>)>() {
+        if values.len() != policies.len() {
+            return Err(PyErr::new::<exceptions::PyValueError, _>("values and policies differ in length"));
+        }
+        return Ok(values.into_iter().zip(policies).map(|(value, policy)| LeafEval { value, policy: Some(policy) }).collect());
+    }
+    let values: Vec<f32> = result.extract()?;
+    Ok(values.into_iter().map(|value| LeafEval { value, policy: None }).collect())
+}
+
+/// Stops a running `Board.search` from another thread; one handle can be
+/// reused once `reset()`
+#[pyclass(name = "SearchHandle")]
+pub struct PySearchHandle {
+    handle: SearchHandle,
+}
+
+#[pymethods]
+impl PySearchHandle {
+    #[new]
+    pub fn new() -> Self {
+        Self { handle: SearchHandle::new() }
+    }
+
+    pub fn stop(&self) {
+        self.handle.stop();
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.handle.is_stopped()
+    }
+
+    pub fn reset(&self) {
+        self.handle.reset();
+    }
+}
+
+impl Default for PySearchHandle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Evaluates every position of games for eval graphs and `[%eval]`
+/// annotations, searching each position once per analyzer
+#[pyclass(name = "GameAnalyzer")]
+pub struct PyGameAnalyzer {
+    analyzer: analysis::GameAnalyzer<EvalParams>,
+}
+
+fn first_game(pgn: &str) -> PyResult<PgnGame> {
+    PgnGameReader::new(pgn.as_bytes()).next()
+        .unwrap_or_else(|| Err(ChessError::ParseError("no game in PGN".to_string())))
+        .map_err(|e| PyErr::new::<exceptions::PyValueError, _>(e.to_string()))
+}
+
+#[pymethods]
+impl PyGameAnalyzer {
+    /// Each position is searched to `depth` plies, for `movetime` seconds or
+    /// `nodes` nodes, whichever comes first
+    #[new]
+    #[pyo3(signature = (depth = Some(8), movetime = None, nodes = None, threads = 1, hash_mb = 16))]
+    pub fn new(depth: Option<u32>, movetime: Option<f64>, nodes: Option<u64>, threads: usize, hash_mb: usize) -> PyResult<Self> {
+        let limits = SearchLimits { depth, nodes, movetime: movetime_duration(movetime)? };
+        let options = SearchOptions { threads, hash_mb, ..SearchOptions::default() };
+        Ok(Self { analyzer: analysis::GameAnalyzer::new(EvalParams::default(), limits, options) })
+    }
+
+    /// One dict per move of the first game in `pgn`: "ply", "san", "score"
+    /// from White's point of view (None once the game is over) and
+    /// "win_percent", White's chances from 0 to 100
+    pub fn eval_graph(&mut self, py: Python<'_>, pgn: &str) -> PyResult<Vec<PyObject>> {
+        let game = first_game(pgn)?;
+        let evals = py.allow_threads(|| self.analyzer.eval_graph(&game))
+            .map_err(|e| PyErr::new::<exceptions::PyValueError, _>(e.to_string()))?;
+        evals.into_iter()
+            .map(|eval| {
+                let dict = pyo3::types::PyDict::new_bound(py);
+                dict.set_item("ply", eval.ply)?;
+                dict.set_item("san", eval.san)?;
+                dict.set_item("score", eval.score.map(|score| score.to_string()))?;
+                dict.set_item("win_percent", eval.white_win_percent)?;
+                Ok(dict.into())
+            })
+            .collect()
+    }
+
+    /// The first game in `pgn` written back with a `[%eval]` comment after
+    /// every move
+    pub fn annotate(&mut self, py: Python<'_>, pgn: &str) -> PyResult<String> {
+        let mut game = first_game(pgn)?;
+        py.allow_threads(|| self.analyzer.annotate(&mut game))
+            .map_err(|e| PyErr::new::<exceptions::PyValueError, _>(e.to_string()))?;
+        Ok(game.to_pgn())
+    }
+
+    /// Number of positions searched so far
+    fn __len__(&self) -> usize {
+        self.analyzer.cache_len()
+    }
+}
+
+/// Moves played from positions of a game collection with their results,
+/// filtered by rating band and speed at query time
+#[pyclass(name = "OpeningExplorer")]
+pub struct PyOpeningExplorer {
+    explorer: explorer::OpeningExplorer,
+}
+
+#[pymethods]
+impl PyOpeningExplorer {
+    /// An empty explorer indexing the first `max_ply` moves of each game
+    #[new]
+    #[pyo3(signature = (max_ply = 30))]
+    pub fn new(max_ply: usize) -> Self {
+        Self { explorer: explorer::OpeningExplorer::new(max_ply) }
+    }
+
+    /// Index every game of a PGN file; returns the number of games added
+    pub fn load_pgn(&mut self, py: Python<'_>, path: &str) -> PyResult<usize> {
+        let file = std::fs::File::open(path)
+            .map_err(|e| PyErr::new::<exceptions::PyIOError, _>(e.to_string()))?;
+        let before = self.explorer.len();
+        py.allow_threads(|| {
+            PgnGameReader::new(file).try_for_each(|game| self.explorer.add_game(&game?))
+        }).map_err(|e| PyErr::new::<exceptions::PyValueError, _>(e.to_string()))?;
+        Ok(self.explorer.len() - before)
+    }
+
+    /// One dict per move played from `fen`, most played first: "move"
+    /// (UCI), "san", "games", "white_wins", "draws", "black_wins" and
+    /// "avg_elo". `speeds` takes names such as "blitz" or "rapid".
+    #[pyo3(signature = (fen, min_elo = None, max_elo = None, speeds = None))]
+    pub fn explore(&self, py: Python<'_>, fen: &str, min_elo: Option<u32>, max_elo: Option<u32>, speeds: Option<Vec<String>>) -> PyResult<Vec<PyObject>> {
+        let speeds = speeds.unwrap_or_default().iter()
+            .map(|speed| speed.parse::<Speed>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| PyErr::new::<exceptions::PyValueError, _>(e.to_string()))?;
+        let filter = explorer::ExplorerFilter { min_elo, max_elo, speeds };
+        let board = Board::from(fen.parse::<Position>()
+            .map_err(|e| PyErr::new::<exceptions::PyValueError, _>(e.to_string()))?);
+        self.explorer.explore(&board, &filter).into_iter()
+            .map(|mv| {
+                let dict = pyo3::types::PyDict::new_bound(py);
+                dict.set_item("move", notation::to_standard_uci(&board, &mv.mv))?;
+                dict.set_item("san", mv.san)?;
+                dict.set_item("games", mv.stats.games)?;
+                dict.set_item("white_wins", mv.stats.white_wins)?;
+                dict.set_item("draws", mv.stats.draws)?;
+                dict.set_item("black_wins", mv.stats.black_wins)?;
+                dict.set_item("avg_elo", mv.avg_elo)?;
+                Ok(dict.into())
+            })
+            .collect()
+    }
+
+    /// Number of games indexed
+    fn __len__(&self) -> usize {
+        self.explorer.len()
+    }
+}
+
+/// Persistent position cache keyed by Zobrist hash (requires the `cache` feature)
+#[cfg(feature = "cache")]
+#[pyclass(name = "PositionCache")]
+pub struct PyPositionCache {
+    cache: cache::PositionCache,
+}
+
+#[cfg(feature = "cache")]
+#[pymethods]
+impl PyPositionCache {
+    #[new]
+    pub fn new(path: &str) -> PyResult<Self> {
+        let cache = cache::PositionCache::open(path)
+            .map_err(|e| PyErr::new::<exceptions::PyIOError, _>(e.to_string()))?;
+        Ok(Self { cache })
+    }
+
+    /// Stored bytes for a key (e.g. from `PyBoard.transposition_key()`), or None
+    pub fn get(&self, py: Python<'_>, key: u64) -> PyResult<Option<PyObject>> {
+        let value = self.cache.get(key)
+            .map_err(|e| PyErr::new::<exceptions::PyIOError, _>(e.to_string()))?;
+        Ok(value.map(|bytes| pyo3::types::PyBytes::new_bound(py, &bytes).into()))
+    }
+
+    pub fn put(&self, key: u64, value: &[u8]) -> PyResult<()> {
+        self.cache.put(key, value)
+            .map_err(|e| PyErr::new::<exceptions::PyIOError, _>(e.to_string()))
+    }
+
+    pub fn flush(&self) -> PyResult<()> {
+        self.cache.flush()
+            .map_err(|e| PyErr::new::<exceptions::PyIOError, _>(e.to_string()))
+    }
+
+    fn __len__(&self) -> usize {
+        self.cache.len()
+    }
+}
+
+#[pymodule]
+fn move_generation(_py: Python<'_>, m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyBoard>()?;
+    m.add_class::<PyTermination>()?;
+    m.add_class::<SquareIter>()?;
+    m.add_class::<PyPositionDataset>()?;
+    m.add_class::<PositionIter>()?;
+    m.add_class::<PlaneBuffer>()?;
+    m.add_class::<PyMcts>()?;
+    m.add_class::<PySearchHandle>()?;
+    m.add_class::<PyGameAnalyzer>()?;
+    m.add_class::<PyOpeningExplorer>()?;
+    m.add_function(wrap_pyfunction!(encode_fens, m)?)?;
+    #[cfg(feature = "arrow")]
+    m.add_function(wrap_pyfunction!(pgn_to_arrow, m)?)?;
+    #[cfg(feature = "cache")]
+    m.add_class::<PyPositionCache>()?;
+    m.add_function(wrap_pyfunction!(py_canonical_fen, m)?)?;
+    m.add_function(wrap_pyfunction!(elo_stats, m)?)?;
+    m.add_function(wrap_pyfunction!(analyse, m)?)?;
+    m.add_function(wrap_pyfunction!(mine_puzzles, m)?)?;
+    m.add_function(wrap_pyfunction!(sans_for_game, m)?)?;
+    m.add_function(wrap_pyfunction!(ucis_for_game, m)?)?;
+    m.add_function(wrap_pyfunction!(pgn_from_uci, m)?)?;
+    m.add_function(wrap_pyfunction!(perft_many, m)?)?;
+    m.add_function(wrap_pyfunction!(bitboard_to_squares, m)?)?;
+    m.add_function(wrap_pyfunction!(squares_to_bitboard, m)?)?;
+    m.add_function(wrap_pyfunction!(py_square_to_name, m)?)?;
+    m.add_function(wrap_pyfunction!(py_name_to_square, m)?)?;
+    Ok(())
+}
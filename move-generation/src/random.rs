@@ -0,0 +1,81 @@
+//! Random legal positions for fuzzing and testing
+
+use crate::rules::validate_board;
+use crate::types::{Board, Color, Piece};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::ops::RangeInclusive;
+
+/// Material a side may be given, drawn without replacement
+const PIECE_SET: [Piece; 15] = [
+    Piece::Queen, Piece::Rook, Piece::Rook, Piece::Bishop, Piece::Bishop,
+    Piece::Knight, Piece::Knight, Piece::Pawn, Piece::Pawn, Piece::Pawn,
+    Piece::Pawn, Piece::Pawn, Piece::Pawn, Piece::Pawn, Piece::Pawn,
+];
+
+/// Constraints on positions produced by `generate_random_position`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PositionConstraints {
+    /// Number of White pieces besides the king (at most 15)
+    pub white_pieces: RangeInclusive<usize>,
+    /// Number of Black pieces besides the king (at most 15)
+    pub black_pieces: RangeInclusive<usize>,
+    /// Side to move, or random if `None`
+    pub side_to_move: Option<Color>,
+}
+
+impl Default for PositionConstraints {
+    fn default() -> Self {
+        PositionConstraints {
+            white_pieces: 0..=15,
+            black_pieces: 0..=15,
+            side_to_move: None,
+        }
+    }
+}
+
+/// Generate a random legal position.
+///
+/// Each side gets a king plus a random subset of the standard army sized
+/// within its range. Pawns never stand on the first or last rank, the kings
+/// are not adjacent and the side not to move is not in check, so the result
+/// passes `validate_board`. There are no castling rights or en passant
+/// square.
+pub fn generate_random_position<R: Rng + ?Sized>(rng: &mut R, constraints: &PositionConstraints) -> Board {
+    loop {
+        let side_to_move = constraints.side_to_move
+            .unwrap_or_else(|| if rng.gen() { Color::White } else { Color::Black });
+        let mut board = Board::empty();
+        board.side_to_move = side_to_move;
+
+        let mut free: Vec<u8> = (0..64).collect();
+        free.shuffle(rng);
+
+        let white_king = free.pop().unwrap();
+        let far_from_white_king = free.iter()
+            .position(|&sq| (sq / 8).abs_diff(white_king / 8) > 1 || (sq % 8).abs_diff(white_king % 8) > 1)
+            .unwrap();
+        let black_king = free.swap_remove(far_from_white_king);
+        board.put(white_king, Piece::King, Color::White);
+        board.put(black_king, Piece::King, Color::Black);
+
+        for (color, range) in [(Color::White, &constraints.white_pieces), (Color::Black, &constraints.black_pieces)] {
+            let count = rng.gen_range(*range.start().min(&15)..=*range.end().min(&15));
+            for &piece in PIECE_SET.choose_multiple(rng, count) {
+                let pos = match piece {
+                    Piece::Pawn => free.iter().position(|sq| (8..56).contains(sq)),
+                    _ => (!free.is_empty()).then_some(0),
+                };
+                if let Some(pos) = pos {
+                    let sq = free.swap_remove(pos);
+                    board.put(sq, piece, color);
+                }
+            }
+        }
+
+        board.refresh();
+        if validate_board(&board).is_valid() {
+            return board;
+        }
+    }
+}
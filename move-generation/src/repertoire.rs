@@ -0,0 +1,165 @@
+//! Opening trees built from games, exported as PGN repertoires
+
+use crate::pgn::{escape_tag_value, write_movetext, ChessError, PgnGame, PgnGameReader};
+use crate::types::Move;
+use std::io::{self, Read, Write};
+
+/// Games through a position, by result from White's point of view
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TreeStats {
+    pub games: u32,
+    pub white_wins: u32,
+    pub draws: u32,
+    pub black_wins: u32,
+}
+
+impl TreeStats {
+    pub(crate) fn add(&mut self, result: Option<&str>) {
+        self.games += 1;
+        match result {
+            Some("1-0") => self.white_wins += 1,
+            Some("0-1") => self.black_wins += 1,
+            Some("1/2-1/2") => self.draws += 1,
+            _ => {}
+        }
+    }
+}
+
+/// A move of the opening tree with the games that played it and their
+/// continuations
+#[derive(Debug, Clone)]
+pub struct TreeNode {
+    pub mv: Move,
+    /// SAN as first seen in the games, without annotation glyphs
+    pub san: String,
+    pub stats: TreeStats,
+    pub children: Vec<TreeNode>,
+}
+
+/// Tree of the opening moves of games played from the standard start
+#[derive(Debug, Clone, Default)]
+pub struct OpeningTree {
+    max_ply: usize,
+    stats: TreeStats,
+    children: Vec<TreeNode>,
+}
+
+impl OpeningTree {
+    /// An empty tree keeping the first `max_ply` moves of each game
+    pub fn new(max_ply: usize) -> Self {
+        OpeningTree { max_ply, ..Default::default() }
+    }
+
+    /// Games entered so far
+    pub fn stats(&self) -> TreeStats {
+        self.stats
+    }
+
+    /// First moves, in the order they were first seen
+    pub fn moves(&self) -> &[TreeNode] {
+        &self.children
+    }
+
+    /// Enter a game; games set up from a FEN are skipped
+    pub fn add_game(&mut self, game: &PgnGame) -> Result<(), ChessError> {
+        if game.header("FEN").is_some() {
+            return Ok(());
+        }
+        let positions = game.replay()?;
+        let result = game.result.as_deref();
+        self.stats.add(result);
+
+        let mut children = &mut self.children;
+        for ((_, mv), san) in positions.into_iter().zip(&game.moves).take(self.max_ply) {
+            let index = match children.iter().position(|node| node.mv == mv) {
+                Some(index) => index,
+                None => {
+                    children.push(TreeNode {
+                        mv,
+                        san: san.trim_end_matches(['!', '?']).to_string(),
+                        stats: TreeStats::default(),
+                        children: Vec::new(),
+                    });
+                    children.len() - 1
+                }
+            };
+            children[index].stats.add(result);
+            children = &mut children[index].children;
+        }
+        Ok(())
+    }
+
+    /// Write the tree as a repertoire: one PGN game per first move, its most
+    /// played continuation as the main line and the others as nested
+    /// variations, each move annotated with its game count and results.
+    /// Moves played in fewer than `min_games` games are left out. Returns
+    /// the number of games written.
+    pub fn write_repertoire<W: Write>(&self, mut out: W, min_games: u32) -> io::Result<usize> {
+        let mut written = 0;
+        for first in popular(&self.children, min_games) {
+            let event = format!("Repertoire: 1. {}", first.san);
+            for (name, value) in [
+                ("Event", event.as_str()),
+                ("Site", "?"),
+                ("Date", "????.??.??"),
+                ("Round", "?"),
+                ("White", "?"),
+                ("Black", "?"),
+                ("Result", "*"),
+            ] {
+                writeln!(out, "[{} \"{}\"]", name, escape_tag_value(value))?;
+            }
+            writeln!(out)?;
+
+            let mut tokens = Vec::new();
+            push_move(&mut tokens, first, 0);
+            push_continuations(&mut tokens, first, 1, min_games);
+            tokens.push("*".to_string());
+            write_movetext(&mut out, &tokens)?;
+            written += 1;
+        }
+        Ok(written)
+    }
+}
+
+/// Build a tree from every game of a PGN source
+pub fn build_tree<R: Read>(games: PgnGameReader<R>, max_ply: usize) -> Result<OpeningTree, ChessError> {
+    let mut tree = OpeningTree::new(max_ply);
+    for game in games {
+        tree.add_game(&game?)?;
+    }
+    Ok(tree)
+}
+
+/// Moves played in at least `min_games` games, most played first
+fn popular(nodes: &[TreeNode], min_games: u32) -> Vec<&TreeNode> {
+    let mut popular: Vec<&TreeNode> = nodes.iter().filter(|node| node.stats.games >= min_games).collect();
+    popular.sort_by_key(|node| std::cmp::Reverse(node.stats.games));
+    popular
+}
+
+/// Numbered move followed by its statistics; Black's moves are numbered too
+/// since a comment always comes before them
+fn push_move(tokens: &mut Vec<String>, node: &TreeNode, ply: usize) {
+    let number = ply / 2 + 1;
+    tokens.push(if ply % 2 == 1 { format!("{}...", number) } else { format!("{}.", number) });
+    tokens.push(node.san.clone());
+    let stats = node.stats;
+    let games = if stats.games == 1 { "game" } else { "games" };
+    tokens.push(format!("{{{} {}, +{} ={} -{}}}", stats.games, games, stats.white_wins, stats.draws, stats.black_wins));
+}
+
+/// Continue the line after `node`, alternatives first as variations
+fn push_continuations(tokens: &mut Vec<String>, node: &TreeNode, ply: usize, min_games: u32) {
+    let children = popular(&node.children, min_games);
+    let Some((main, alternatives)) = children.split_first() else { return };
+    push_move(tokens, main, ply);
+    for alternative in alternatives {
+        let start = tokens.len();
+        push_move(tokens, alternative, ply);
+        push_continuations(tokens, alternative, ply + 1, min_games);
+        tokens[start].insert(0, '(');
+        tokens.last_mut().expect("a variation has moves").push(')');
+    }
+    push_continuations(tokens, main, ply + 1, min_games);
+}
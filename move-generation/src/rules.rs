@@ -1,125 +1,706 @@
 //! Chess rules and validation
 
-use crate::{types::{Board, Move, Piece, Color}, move_gen::generate_piece_moves};
+use std::fmt;
+use crate::{types::{castling_letter, square_to_name, Board, Move, Piece, Color, Variant}, move_gen::{attacked_mask, generate_drops, generate_moves, generate_moves_into, generate_moves_with_promotions, piece_move_list, is_square_attacked, Bitboard, MoveList, Promotions}};
+use crate::magic;
 
-/// Check if a move is legal
+/// Check if a move is legal: a move of a piece of the side to move that
+/// the piece can make, or an allowed drop, which leaves the mover's king
+/// safe
 pub fn is_legal_move(board: &Board, mv: &Move) -> bool {
-    // Ensure the move adheres to the rules of the piece
-    if !is_valid_piece_move(board, mv) {
+    if mv.from >= 64 || mv.to >= 64 {
         return false;
     }
+    let pseudo_legal = match (mv.drop, board.squares()[mv.from as usize]) {
+        (Some(piece), _) => is_valid_drop(board, mv, piece),
+        (None, Some((piece, color))) if color == board.side_to_move => {
+            piece_move_list(board, piece, mv.from).contains(mv)
+        }
+        _ => false,
+    };
+    pseudo_legal && keeps_king_safe(board, mv)
+}
 
-    // Ensure the move does not leave the king in check
+/// Whether a pseudo-legal move, as the generators produce it, is legal:
+/// it does not leave the mover's king in check, nor give check in Racing
+/// Kings
+pub(crate) fn keeps_king_safe(board: &Board, mv: &Move) -> bool {
     if leaves_king_in_check(board, mv) {
         return false;
     }
 
+    // Racing Kings forbids giving check as well
+    if board.variant == Variant::RacingKings {
+        let mut new_board = board.clone();
+        new_board.make_move(mv);
+        if is_in_check(&new_board, new_board.side_to_move) {
+            return false;
+        }
+    }
+
     true
 }
 
-/// Validate the board state
-pub fn validate_board(board: &Board) -> bool {
-    // Ensure there is exactly one king of each color
-    let white_king_count = board.squares.iter().filter(|&sq| {
-        matches!(sq, Some((Piece::King, Color::White)))
-    }).count();
+/// Why a move is or is not legal, as `explain_move` finds it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveLegality {
+    Legal,
+    /// A square of the move is not on the board
+    OffBoard,
+    /// The game is already over under the variant's rules
+    GameOver,
+    NoPieceOnFrom,
+    /// The piece belongs to the side not to move
+    WrongColor,
+    /// The piece does not move that way, even on an empty board
+    CannotMoveThatWay,
+    /// A piece stands between the from and to squares
+    BlockedPath,
+    /// The target square holds a piece of the mover's own color
+    OwnPieceOnTarget,
+    /// A pawn moving diagonally needs a piece to capture
+    NothingToCapture,
+    /// A pawn reaching the last rank must promote
+    MissingPromotion,
+    /// Only a pawn reaching the last rank promotes, and only to a knight,
+    /// bishop, rook or queen
+    BadPromotion,
+    /// The king or rook has moved, or the rook is gone
+    NoCastlingRight,
+    CastlingOutOfCheck,
+    /// The king would pass over or land on an attacked square
+    CastlingThroughCheck,
+    LeavesKingInCheck,
+    /// Racing Kings forbids giving check
+    GivesCheck,
+    /// Not Crazyhouse, no such piece in the pocket, an occupied square or a
+    /// pawn dropped on the first or last rank
+    BadDrop,
+}
 
-    let black_king_count = board.squares.iter().filter(|&sq| {
-        matches!(sq, Some((Piece::King, Color::Black)))
-    }).count();
+impl MoveLegality {
+    pub fn is_legal(&self) -> bool {
+        *self == MoveLegality::Legal
+    }
+}
 
-    white_king_count == 1 && black_king_count == 1
+impl fmt::Display for MoveLegality {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            MoveLegality::Legal => "the move is legal",
+            MoveLegality::OffBoard => "the move leaves the board",
+            MoveLegality::GameOver => "the game is over",
+            MoveLegality::NoPieceOnFrom => "there is no piece on the starting square",
+            MoveLegality::WrongColor => "that piece belongs to the other side",
+            MoveLegality::CannotMoveThatWay => "that piece does not move that way",
+            MoveLegality::BlockedPath => "another piece is in the way",
+            MoveLegality::OwnPieceOnTarget => "you cannot capture your own piece",
+            MoveLegality::NothingToCapture => "a pawn only moves diagonally to capture",
+            MoveLegality::MissingPromotion => "a pawn reaching the last rank must promote",
+            MoveLegality::BadPromotion => "only a pawn reaching the last rank promotes, to a knight, bishop, rook or queen",
+            MoveLegality::NoCastlingRight => "castling is no longer allowed on that side",
+            MoveLegality::CastlingOutOfCheck => "you cannot castle out of check",
+            MoveLegality::CastlingThroughCheck => "the king cannot castle through or into an attacked square",
+            MoveLegality::LeavesKingInCheck => "the move leaves your king in check",
+            MoveLegality::GivesCheck => "giving check is not allowed in Racing Kings",
+            MoveLegality::BadDrop => "that piece cannot be dropped there",
+        })
+    }
 }
 
-/// Check if a move leaves the king in check
-fn leaves_king_in_check(board: &Board, mv: &Move) -> bool {
-    let mut new_board = board.clone();
+/// Check a move like `is_legal_move`, but say what is wrong with it. A move
+/// is legal when `generate_legal_moves` produces it; castling is written as
+/// the king taking its rook, though a king moving two squares towards a
+/// castling rook is explained as that castling.
+pub fn explain_move(board: &Board, mv: &Move) -> MoveLegality {
+    if mv.from >= 64 || mv.to >= 64 {
+        return MoveLegality::OffBoard;
+    }
+    if variant_winner(board).is_some() || is_variant_draw(board) {
+        return MoveLegality::GameOver;
+    }
+    if generate_legal_moves(board).contains(mv) {
+        return MoveLegality::Legal;
+    }
+    let color = board.side_to_move;
+    if let Some(piece) = mv.drop {
+        return if is_valid_drop(board, mv, piece) { explain_king_safety(board, mv) } else { MoveLegality::BadDrop };
+    }
+    let piece = match board.squares()[mv.from as usize] {
+        None => return MoveLegality::NoPieceOnFrom,
+        Some((_, c)) if c != color => return MoveLegality::WrongColor,
+        Some((piece, _)) => piece,
+    };
+
+    let (from_rank, from_file) = ((mv.from / 8) as i8, (mv.from % 8) as i8);
+    let (to_rank, to_file) = ((mv.to / 8) as i8, (mv.to % 8) as i8);
+    let (dr, df) = (to_rank - from_rank, to_file - from_file);
+    if piece == Piece::King && mv.promotion.is_none() {
+        let back_rank = if color == Color::White { 0 } else { 7 };
+        let onto_own_rook = board.squares()[mv.to as usize] == Some((Piece::Rook, color));
+        let two_squares = dr == 0 && df.abs() == 2;
+        if from_rank == back_rank && to_rank == back_rank && (onto_own_rook || two_squares) {
+            if let Some(reason) = explain_castling(board, mv.from, df > 0) {
+                return reason;
+            }
+            if let (true, Some(rook_sq)) = (two_squares, board.castling_rook(color, df > 0)) {
+                return explain_move(board, &Move { from: mv.from, to: rook_sq, promotion: None, drop: None });
+            }
+        }
+    }
 
-    // Make the move on a cloned board
-    new_board.squares[mv.to as usize] = new_board.squares[mv.from as usize];
-    new_board.squares[mv.from as usize] = None;
+    let target = board.squares()[mv.to as usize];
+    if mv.from == mv.to || target.is_some_and(|(_, c)| c == color) {
+        return MoveLegality::OwnPieceOnTarget;
+    }
+    let occupied = board.occupied();
+    let reachable = match piece {
+        Piece::Pawn => return explain_pawn_move(board, mv, color),
+        Piece::Knight => (dr.abs() == 1 && df.abs() == 2) || (dr.abs() == 2 && df.abs() == 1),
+        Piece::King => dr.abs() <= 1 && df.abs() <= 1,
+        Piece::Bishop => dr.abs() == df.abs(),
+        Piece::Rook => dr == 0 || df == 0,
+        Piece::Queen => dr == 0 || df == 0 || dr.abs() == df.abs(),
+    };
+    if !reachable {
+        return MoveLegality::CannotMoveThatWay;
+    }
+    if matches!(piece, Piece::Bishop | Piece::Rook | Piece::Queen) && !magic::queen_attacks(mv.from, occupied).contains(mv.to) {
+        return MoveLegality::BlockedPath;
+    }
+    if mv.promotion.is_some() {
+        return MoveLegality::BadPromotion;
+    }
+    explain_king_safety(board, mv)
+}
 
-    // Find the king's position
-    let king_pos = new_board.squares.iter().position(|&sq| {
-        matches!(sq, Some((Piece::King, color)) if color == new_board.side_to_move)
-    });
+/// Castling rules in the order a beginner would check them, or None when
+/// the castling itself is allowed
+fn explain_castling(board: &Board, king_sq: u8, kingside: bool) -> Option<MoveLegality> {
+    let color = board.side_to_move;
+    let Some(rook_sq) = board.castling_rook(color, kingside) else {
+        return Some(MoveLegality::NoCastlingRight);
+    };
+    if is_in_check(board, color) {
+        return Some(MoveLegality::CastlingOutOfCheck);
+    }
+    let castle = Move { from: king_sq, to: rook_sq, promotion: None, drop: None };
+    let (king_to, rook_to) = castle.castling_squares();
+    let span = |a: u8, b: u8| a.min(b)..=a.max(b);
+    let blocked = span(king_sq, king_to).chain(span(rook_sq, rook_to))
+        .any(|sq| sq != king_sq && sq != rook_sq && board.squares()[sq as usize].is_some());
+    if blocked {
+        return Some(MoveLegality::BlockedPath);
+    }
+    let attacked = attacked_mask(board, color.opposite());
+    if span(king_sq, king_to).any(|sq| attacked.contains(sq)) {
+        return Some(MoveLegality::CastlingThroughCheck);
+    }
+    None
+}
 
-    if let Some(king_sq) = king_pos {
-        // Check if the king is attacked
-        is_square_attacked(&new_board, king_sq as u8, new_board.side_to_move)
-    } else {
-        false
+fn explain_pawn_move(board: &Board, mv: &Move, color: Color) -> MoveLegality {
+    let forward: i8 = if color == Color::White { 1 } else { -1 };
+    let start_rank = if color == Color::White { 1 } else { 6 };
+    let last_rank = if color == Color::White { 7 } else { 0 };
+    let (dr, df) = ((mv.to / 8) as i8 - (mv.from / 8) as i8, (mv.to % 8) as i8 - (mv.from % 8) as i8);
+    let target = board.squares()[mv.to as usize];
+
+    let single = dr == forward && df == 0;
+    let double = dr == 2 * forward && df == 0 && mv.from / 8 == start_rank;
+    let capture = dr == forward && df.abs() == 1;
+    if !(single || double || capture) {
+        return MoveLegality::CannotMoveThatWay;
+    }
+    if single && target.is_some() {
+        return MoveLegality::BlockedPath;
+    }
+    if double {
+        let between = (mv.from as i8 + 8 * forward) as u8;
+        if board.squares()[between as usize].is_some() || target.is_some() {
+            return MoveLegality::BlockedPath;
+        }
+    }
+    if capture && target.is_none() && board.en_passant != Some(mv.to) {
+        return MoveLegality::NothingToCapture;
+    }
+    match mv.promotion {
+        None if mv.to / 8 == last_rank => return MoveLegality::MissingPromotion,
+        Some(Piece::Pawn | Piece::King) => return MoveLegality::BadPromotion,
+        Some(_) if mv.to / 8 != last_rank => return MoveLegality::BadPromotion,
+        _ => {}
+    }
+    explain_king_safety(board, mv)
+}
+
+/// Whatever remains once the move itself is well formed: the mover's king
+/// must be safe afterwards, and in Racing Kings the other king too
+fn explain_king_safety(board: &Board, mv: &Move) -> MoveLegality {
+    if leaves_king_in_check(board, mv) {
+        return MoveLegality::LeavesKingInCheck;
+    }
+    if board.variant == Variant::RacingKings {
+        let mut new_board = board.clone();
+        new_board.make_move(mv);
+        if is_in_check(&new_board, new_board.side_to_move) {
+            return MoveLegality::GivesCheck;
+        }
+    }
+    // Well formed and safe but still not generated
+    MoveLegality::CannotMoveThatWay
+}
+
+/// How serious a validation problem is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The position cannot be played: move generation or engines may
+    /// misbehave on it
+    Error,
+    /// The position can be played but cannot arise in a game
+    Warning,
+}
+
+/// A problem found by `validate_board`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// A side has the wrong number of kings: one, or none for the Horde
+    KingCount { color: Color, count: usize },
+    /// The kings stand next to each other
+    KingsAdjacent,
+    /// The side that just moved is in check
+    OpponentInCheck,
+    /// A pawn on the first or eighth rank, where it can never stand
+    PawnOnBackRank(u8),
+    /// No enemy pawn can just have double-pushed across the en passant square
+    InvalidEnPassant(u8),
+    /// A castling right whose king or rook is not in place
+    InvalidCastlingRight(char),
+    /// More than eight pawns
+    TooManyPawns { color: Color, count: usize },
+    /// More pieces beyond the starting set than missing pawns could have
+    /// promoted into
+    ImpossibleMaterial(Color),
+}
+
+impl Violation {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Violation::TooManyPawns { .. } | Violation::ImpossibleMaterial(_) => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = |color: &Color| if *color == Color::White { "White" } else { "Black" };
+        match self {
+            Violation::KingCount { color, count } => write!(f, "{} has {} kings", name(color), count),
+            Violation::KingsAdjacent => write!(f, "the kings are adjacent"),
+            Violation::OpponentInCheck => write!(f, "the side not to move is in check"),
+            Violation::PawnOnBackRank(sq) => write!(f, "pawn on back rank square {}", square_to_name(*sq).unwrap_or("?")),
+            Violation::InvalidEnPassant(sq) => match square_to_name(*sq) {
+                Some(name) => write!(f, "no pawn can have double-pushed across en passant square {}", name),
+                None => write!(f, "en passant square {} is off the board", sq),
+            },
+            Violation::InvalidCastlingRight(right) => write!(f, "castling right {} without its king and rook", right),
+            Violation::TooManyPawns { color, count } => write!(f, "{} has {} pawns", name(color), count),
+            Violation::ImpossibleMaterial(color) => write!(f, "{} has more promoted pieces than missing pawns", name(color)),
+        }
+    }
+}
+
+/// Every problem `validate_board` found with a position
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    pub violations: Vec<Violation>,
+}
+
+impl ValidationReport {
+    /// Whether the position can be played: nothing of `Severity::Error`
+    pub fn is_valid(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &Violation> {
+        self.violations.iter().filter(|v| v.severity() == Severity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &Violation> {
+        self.violations.iter().filter(|v| v.severity() == Severity::Warning)
+    }
+}
+
+/// Validate the board state, listing every problem found
+pub fn validate_board(board: &Board) -> ValidationReport {
+    let mut violations = Vec::new();
+    let king_squares = |color: Color| {
+        board.squares().iter().enumerate()
+            .filter(move |&(_, &sq)| sq == Some((Piece::King, color)))
+            .map(|(i, _)| i as u8)
+            .collect::<Vec<u8>>()
+    };
+    let (white_kings, black_kings) = (king_squares(Color::White), king_squares(Color::Black));
+
+    // Exactly one king of each color, but the Horde has no king
+    let white_kings_expected = if board.variant == Variant::Horde { 0 } else { 1 };
+    for (color, kings, expected) in [(Color::White, &white_kings, white_kings_expected), (Color::Black, &black_kings, 1)] {
+        if kings.len() != expected {
+            violations.push(Violation::KingCount { color, count: kings.len() });
+        }
+    }
+    if let ([white], [black]) = (white_kings.as_slice(), black_kings.as_slice()) {
+        if (white / 8).abs_diff(black / 8) <= 1 && (white % 8).abs_diff(black % 8) <= 1 {
+            violations.push(Violation::KingsAdjacent);
+        }
+    }
+    if is_in_check(board, board.side_to_move.opposite()) {
+        violations.push(Violation::OpponentInCheck);
+    }
+    // Horde pawns start on White's first rank
+    for (sq, square) in board.squares().iter().enumerate() {
+        let back_rank = matches!(sq / 8, 0 | 7);
+        let horde_pawn = board.variant == Variant::Horde && sq < 8 && *square == Some((Piece::Pawn, Color::White));
+        if back_rank && !horde_pawn && matches!(square, Some((Piece::Pawn, _))) {
+            violations.push(Violation::PawnOnBackRank(sq as u8));
+        }
     }
+
+    if let Some(ep) = board.en_passant.filter(|_| !valid_en_passant(board)) {
+        violations.push(Violation::InvalidEnPassant(ep));
+    }
+    for (color, kingside, file) in board.castling_rights.iter() {
+        if board.castling_rook(color, kingside).is_none() {
+            let rank = if color == Color::White { 0 } else { 56 };
+            let rook_files = (0..8).filter(|&f| board.squares()[rank + f] == Some((Piece::Rook, color)))
+                .fold(0u8, |files, f| files | 1 << f);
+            violations.push(Violation::InvalidCastlingRight(castling_letter(color, kingside, file, rook_files)));
+        }
+    }
+
+    // Pieces can return to the board from a Crazyhouse pocket, and the
+    // Horde starts with 36 pawns
+    if board.variant != Variant::Crazyhouse {
+        for color in [Color::White, Color::Black] {
+            if board.variant == Variant::Horde && color == Color::White {
+                continue;
+            }
+            violations.extend(material_violations(board, color));
+        }
+    }
+
+    ValidationReport { violations }
+}
+
+/// An en passant square must be the one a double-pushed enemy pawn just
+/// crossed: on the sixth rank from the mover's side, empty, with the pawn
+/// in front of it and the square it came from empty
+fn valid_en_passant(board: &Board) -> bool {
+    let Some(ep) = board.en_passant else { return true };
+    let ep_rank = match board.side_to_move {
+        Color::White => 5,
+        Color::Black => 2,
+    };
+    // Checked first, so the squares either side of it are on the board
+    if ep / 8 != ep_rank {
+        return false;
+    }
+    let (pawn_sq, from_sq) = match board.side_to_move {
+        Color::White => (ep - 8, ep + 8),
+        Color::Black => (ep + 8, ep - 8),
+    };
+    board.squares()[ep as usize].is_none()
+        && board.squares()[from_sq as usize].is_none()
+        && board.squares()[pawn_sq as usize] == Some((Piece::Pawn, board.side_to_move.opposite()))
+}
+
+/// Pawn count and promotions: every piece beyond the starting set needs a
+/// pawn that promoted
+fn material_violations(board: &Board, color: Color) -> Vec<Violation> {
+    let count = |piece: Piece| board.squares().iter().filter(|&&sq| sq == Some((piece, color))).count();
+    let pawns = count(Piece::Pawn);
+    let promoted = count(Piece::Queen).saturating_sub(1)
+        + count(Piece::Rook).saturating_sub(2)
+        + count(Piece::Bishop).saturating_sub(2)
+        + count(Piece::Knight).saturating_sub(2);
+    let mut violations = Vec::new();
+    if pawns > 8 {
+        violations.push(Violation::TooManyPawns { color, count: pawns });
+    } else if pawns + promoted > 8 {
+        violations.push(Violation::ImpossibleMaterial(color));
+    }
+    violations
+}
+
+/// Generate the legal moves of the side to move
+pub fn generate_legal_moves(board: &Board) -> Vec<Move> {
+    generate_legal_moves_with_promotions(board, Promotions::All)
+}
+
+/// Generate the legal moves of the side to move, emitting only the chosen
+/// promotions
+pub fn generate_legal_moves_with_promotions(board: &Board, promotions: Promotions) -> Vec<Move> {
+    if variant_winner(board).is_some() || is_variant_draw(board) {
+        return Vec::new();
+    }
+    generate_moves_with_promotions(board, board.side_to_move, promotions)
+        .into_iter()
+        .filter(|mv| keeps_king_safe(board, mv))
+        .collect()
+}
+
+/// `generate_legal_moves` into a reusable buffer, replacing its contents
+pub fn generate_legal_moves_into(board: &Board, moves: &mut MoveList) {
+    if variant_winner(board).is_some() || is_variant_draw(board) {
+        moves.clear();
+        return;
+    }
+    generate_moves_into(board, board.side_to_move, moves);
+    moves.retain(|mv| keeps_king_safe(board, mv));
 }
 
-/// Check if a square is attacked by the opponent
-fn is_square_attacked(board: &Board, sq: u8, color: Color) -> bool {
-    for (i, piece) in board.squares.iter().enumerate() {
-        if let Some((p, c)) = piece {
-            if *c != color {
-                let moves = generate_piece_moves(board, *p, i as u8);
-                if moves.iter().any(|m| m.to == sq) {
-                    return true;
+/// Legal moves of the side to move from a square in `from_mask` to a square
+/// in `to_mask`. Only pieces on `from_mask` are generated, and legality is
+/// checked only for moves that pass both masks. Crazyhouse drops count as
+/// moving from their target square.
+pub fn generate_moves_masked(board: &Board, from_mask: Bitboard, to_mask: Bitboard) -> Vec<Move> {
+    if variant_winner(board).is_some() || is_variant_draw(board) {
+        return Vec::new();
+    }
+    let color = board.side_to_move;
+    let mut moves = Vec::new();
+    for sq in from_mask.bits() {
+        if let Some((piece, c)) = board.squares()[sq as usize] {
+            if c == color {
+                moves.extend(piece_move_list(board, piece, sq).into_vec());
+            }
+        }
+    }
+    if board.variant == Variant::Crazyhouse {
+        moves.extend(generate_drops(board, color).into_iter().filter(|mv| from_mask.contains(mv.to)));
+    }
+    moves.retain(|mv| to_mask.contains(mv.to) && keeps_king_safe(board, mv));
+    moves
+}
+
+/// Number of legal moves of the side to move, without collecting them.
+/// Each piece's moves are generated into a stack buffer and tried with
+/// make/unmake on one copy of the board; drops are tried square by square.
+pub fn count_legal_moves(board: &Board) -> usize {
+    if variant_winner(board).is_some() || is_variant_draw(board) {
+        return 0;
+    }
+    let color = board.side_to_move;
+    let mut scratch = board.clone();
+    let mut count = 0;
+    for (sq, piece) in board.piece_list(color) {
+        for mv in &piece_move_list(board, piece, sq) {
+            count += usize::from(is_legal_after(&mut scratch, mv, color));
+        }
+    }
+    if board.variant == Variant::Crazyhouse {
+        for piece in [Piece::Pawn, Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen] {
+            if board.pockets[color.index()][piece.index()] == 0 {
+                continue;
+            }
+            for to in (0..64).filter(|&to| board.squares()[to as usize].is_none()) {
+                if piece == Piece::Pawn && !(8..56).contains(&to) {
+                    continue;
                 }
+                let mv = Move { from: to, to, promotion: None, drop: Some(piece) };
+                count += usize::from(is_legal_after(&mut scratch, &mv, color));
             }
         }
     }
-    false
+    count
+}
+
+/// Whether the pseudo-legal `mv` by `color` is legal: it is made and
+/// unmade on `board`, which ends up as it was
+fn is_legal_after(board: &mut Board, mv: &Move, color: Color) -> bool {
+    let undo = board.make_move(mv);
+    let legal = !is_in_check(board, color)
+        && (board.variant != Variant::RacingKings || !is_in_check(board, color.opposite()));
+    board.unmake_move(mv, undo);
+    legal
+}
+
+/// Whether the side to move has any legal move. Pieces are tried one at a
+/// time, so this stops at the first legal move found.
+pub fn has_legal_move(board: &Board) -> bool {
+    if variant_winner(board).is_some() || is_variant_draw(board) {
+        return false;
+    }
+    let color = board.side_to_move;
+    let piece_can_move = board.piece_list(color)
+        .any(|(sq, piece)| piece_move_list(board, piece, sq).iter().any(|mv| keeps_king_safe(board, mv)));
+    piece_can_move
+        || (board.variant == Variant::Crazyhouse
+            && generate_drops(board, color).iter().any(|mv| keeps_king_safe(board, mv)))
+}
+
+/// Check if the king of the given color is attacked
+pub fn is_in_check(board: &Board, color: Color) -> bool {
+    board.king_square(color)
+        .is_some_and(|king_sq| is_square_attacked(board, king_sq, color))
 }
 
-/// Check if a move adheres to the rules of the piece
-fn is_valid_piece_move(board: &Board, mv: &Move) -> bool {
-    match board.squares[mv.from as usize] {
-        Some((piece, color)) => match piece {
-            Piece::Pawn => is_valid_pawn_move(board, mv, color),
-            Piece::Knight => is_valid_knight_move(mv),
-            Piece::Bishop => is_valid_bishop_move(board, mv, color),
-            Piece::Rook => is_valid_rook_move(board, mv, color),
-            Piece::Queen => is_valid_queen_move(board, mv, color),
-            Piece::King => is_valid_king_move(board, mv, color),
-        },
-        None => false,
+/// Check if the side to move is checkmated
+pub fn is_checkmate(board: &Board) -> bool {
+    variant_winner(board).is_none()
+        && is_in_check(board, board.side_to_move)
+        && !has_legal_move(board)
+}
+
+/// Check if the side to move is stalemated
+pub fn is_stalemate(board: &Board) -> bool {
+    variant_winner(board).is_none()
+        && !is_variant_draw(board)
+        && !is_in_check(board, board.side_to_move)
+        && !has_legal_move(board)
+}
+
+/// Squares a king must reach to win King of the Hill (d4, e4, d5, e5)
+const HILL: [u8; 4] = [27, 28, 35, 36];
+
+/// Winner by a variant-specific rule: the third check in Three-check, a
+/// king on a centre square in King of the Hill, Black wiping out the Horde
+/// or a king reaching the eighth rank in Racing Kings. Checkmate is not
+/// included.
+pub fn variant_winner(board: &Board) -> Option<Color> {
+    match board.variant {
+        Variant::ThreeCheck => [Color::White, Color::Black].into_iter()
+            .find(|color| board.checks[color.index()] >= 3),
+        Variant::KingOfTheHill => HILL.iter()
+            .find_map(|&sq| match board.squares()[sq as usize] {
+                Some((Piece::King, color)) => Some(color),
+                _ => None,
+            }),
+        Variant::Horde => {
+            let horde_left = board.squares().iter().flatten().any(|&(_, color)| color == Color::White);
+            (!horde_left).then_some(Color::Black)
+        }
+        Variant::RacingKings => racing_kings_winner(board),
+        _ => None,
     }
 }
 
-/// Validate pawn moves
-fn is_valid_pawn_move(board: &Board, mv: &Move, color: Color) -> bool {
-    // TODO: Implement pawn movement rules (including en passant and promotion)
-    true
+/// Whether the game is drawn by a variant-specific rule: in Racing Kings,
+/// both kings reaching the eighth rank
+pub fn is_variant_draw(board: &Board) -> bool {
+    board.variant == Variant::RacingKings
+        && king_on_eighth_rank(board, Color::White)
+        && king_on_eighth_rank(board, Color::Black)
 }
 
-/// Validate knight moves
-fn is_valid_knight_move(mv: &Move) -> bool {
-    // TODO: Implement knight movement rules
-    true
+fn king_on_eighth_rank(board: &Board, color: Color) -> bool {
+    board.squares()[56..64].contains(&Some((Piece::King, color)))
 }
 
-/// Validate bishop moves
-fn is_valid_bishop_move(board: &Board, mv: &Move, color: Color) -> bool {
-    // TODO: Implement bishop movement rules
-    true
+/// Racing Kings winner. When White's king arrives first, Black still gets
+/// one move to draw by reaching the eighth rank as well.
+fn racing_kings_winner(board: &Board) -> Option<Color> {
+    match (king_on_eighth_rank(board, Color::White), king_on_eighth_rank(board, Color::Black)) {
+        (false, true) => Some(Color::Black),
+        (true, false) => {
+            let black_can_follow = board.side_to_move == Color::Black
+                && generate_moves(board, Color::Black).iter().any(|mv| {
+                    board.squares()[mv.from as usize] == Some((Piece::King, Color::Black))
+                        && mv.to >= 56
+                        && keeps_king_safe(board, mv)
+                });
+            (!black_can_follow).then_some(Color::White)
+        }
+        _ => None,
+    }
 }
 
-/// Validate rook moves
-fn is_valid_rook_move(board: &Board, mv: &Move, color: Color) -> bool {
-    // TODO: Implement rook movement rules
-    true
+/// Check if neither side has enough material to deliver mate
+/// (bare kings, a single minor piece, or bishops all on one square color)
+pub fn is_insufficient_material(board: &Board) -> bool {
+    // A bare king can still march to the centre or the eighth rank
+    if matches!(board.variant, Variant::KingOfTheHill | Variant::RacingKings) {
+        return false;
+    }
+    let mut knights = 0;
+    let mut bishop_square_colors = [false; 2];
+    for (sq, square) in board.squares().iter().enumerate() {
+        match square {
+            Some((Piece::King, _)) | None => {}
+            Some((Piece::Knight, _)) => knights += 1,
+            Some((Piece::Bishop, _)) => bishop_square_colors[(sq / 8 + sq % 8) % 2] = true,
+            Some(_) => return false,
+        }
+    }
+    let bishops_on_both_colors = bishop_square_colors[0] && bishop_square_colors[1];
+    let any_bishop = bishop_square_colors[0] || bishop_square_colors[1];
+    match knights {
+        0 => !bishops_on_both_colors,
+        1 => !any_bishop,
+        _ => false,
+    }
 }
 
-/// Validate queen moves
-fn is_valid_queen_move(board: &Board, mv: &Move, color: Color) -> bool {
-    // TODO: Implement queen movement rules
-    true
+/// Check if a move leaves the king in check
+fn leaves_king_in_check(board: &Board, mv: &Move) -> bool {
+    let color = match (mv.drop, board.squares()[mv.from as usize]) {
+        (Some(_), _) => board.side_to_move,
+        (None, Some((_, color))) => color,
+        (None, None) => return false,
+    };
+    // Make the move on a cloned board, so en passant, castling and
+    // promotions change the squares they really do
+    let mut new_board = board.clone();
+    new_board.make_move(mv);
+
+    if let Some(king_sq) = new_board.king_square(color) {
+        // Check if the king is attacked
+        is_square_attacked(&new_board, king_sq, color)
+    } else {
+        false
+    }
 }
 
-/// Validate king moves
-fn is_valid_king_move(board: &Board, mv: &Move, color: Color) -> bool {
-    // TODO: Implement king movement rules (including castling)
-    true
+/// Check if the side to move has a legal en passant capture
+pub fn has_legal_en_passant(board: &Board) -> bool {
+    let ep = match board.en_passant {
+        Some(ep) => ep,
+        None => return false,
+    };
+    let color = board.side_to_move;
+    // The double-pushed pawn stands just past the en passant square
+    let (victim_sq, from_rank) = match color {
+        Color::White if ep / 8 == 5 => (ep - 8, 4),
+        Color::Black if ep / 8 == 2 => (ep + 8, 3),
+        _ => return false,
+    };
+    if board.squares()[victim_sq as usize] != Some((Piece::Pawn, color.opposite())) {
+        return false;
+    }
+
+    let file = (ep % 8) as i8;
+    [file - 1, file + 1].iter()
+        .filter(|f| (0..8).contains(*f))
+        .map(|&f| from_rank * 8 + f as u8)
+        .filter(|&from| board.squares()[from as usize] == Some((Piece::Pawn, color)))
+        .any(|from| {
+            let mut new_board = board.clone();
+            new_board.put(ep, Piece::Pawn, color);
+            new_board.remove(from);
+            new_board.remove(victim_sq);
+            match new_board.king_square(color) {
+                Some(king_sq) => !is_square_attacked(&new_board, king_sq, color),
+                None => true,
+            }
+        })
 }
 
-/// Validate castling rules
-fn is_valid_castling(board: &Board, mv: &Move) -> bool {
-    // TODO: Implement castling rules
-    true
-}
\ No newline at end of file
+/// Validate Crazyhouse drops: a pocketed piece onto an empty square,
+/// pawns not on the first or last rank
+fn is_valid_drop(board: &Board, mv: &Move, piece: Piece) -> bool {
+    board.variant == Variant::Crazyhouse
+        && piece != Piece::King
+        && board.pockets[board.side_to_move.index()][piece.index()] > 0
+        && board.squares()[mv.to as usize].is_none()
+        && (piece != Piece::Pawn || (8..56).contains(&mv.to))
+}
@@ -0,0 +1,141 @@
+//! Engine scores: centipawn evaluations and mate distances
+
+use crate::pgn::{ChessError, Eval};
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::Neg;
+use std::str::FromStr;
+
+/// Centipawn value standing in for a mate when a score must be a plain
+/// number; a mate in n is `MATE_CP - n`
+pub const MATE_CP: i32 = 32_000;
+
+/// An evaluation from one side's point of view: centipawns, or a forced mate.
+///
+/// `Mate(n)` with n > 0 means this side mates in n moves; n < 0 means it is
+/// mated in -n moves and `Mate(0)` that it is already checkmated. Any mate
+/// for this side beats every centipawn value, sooner mates beat later ones,
+/// and being mated later beats being mated sooner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Score {
+    Cp(i32),
+    Mate(i32),
+}
+
+impl Score {
+    /// Score of a checkmated side
+    pub const MATED: Score = Score::Mate(0);
+
+    pub fn is_mate(self) -> bool {
+        matches!(self, Score::Mate(_))
+    }
+
+    /// The score as a single number, mates mapped near `±MATE_CP` so that
+    /// numeric order matches the order of scores
+    pub fn to_cp(self) -> i32 {
+        match self {
+            Score::Cp(cp) => cp,
+            Score::Mate(n) if n > 0 => MATE_CP - n,
+            Score::Mate(n) => -MATE_CP - n,
+        }
+    }
+
+    /// Score of a move whose resulting position the opponent scores as
+    /// `self`, when backing up a search: if the opponent is mated in n
+    /// the mover mates in n + 1, and if the opponent mates in n the mover is
+    /// mated in n
+    pub fn from_child(self) -> Score {
+        match self {
+            Score::Cp(cp) => Score::Cp(-cp),
+            Score::Mate(n) if n > 0 => Score::Mate(-n),
+            Score::Mate(n) => Score::Mate(-n + 1),
+        }
+    }
+
+    fn sort_key(self) -> (i8, i32) {
+        match self {
+            Score::Mate(n) if n > 0 => (2, -n),
+            Score::Cp(cp) => (1, cp),
+            Score::Mate(n) => (0, -n),
+        }
+    }
+}
+
+impl Ord for Score {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.sort_key().cmp(&other.sort_key())
+    }
+}
+
+impl PartialOrd for Score {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The same evaluation from the other side's point of view
+impl Neg for Score {
+    type Output = Score;
+
+    fn neg(self) -> Score {
+        match self {
+            Score::Cp(cp) => Score::Cp(-cp),
+            Score::Mate(n) => Score::Mate(-n),
+        }
+    }
+}
+
+impl From<i32> for Score {
+    fn from(cp: i32) -> Score {
+        Score::Cp(cp)
+    }
+}
+
+/// A `[%eval]` annotation, from White's point of view
+impl From<Eval> for Score {
+    fn from(eval: Eval) -> Score {
+        match eval {
+            Eval::Pawns(pawns) => Score::Cp((pawns * 100.0).round() as i32),
+            Eval::Mate(moves) => Score::Mate(moves),
+        }
+    }
+}
+
+/// A `[%eval]` annotation for a score from White's point of view
+impl From<Score> for Eval {
+    fn from(score: Score) -> Eval {
+        match score {
+            Score::Cp(cp) => Eval::Pawns(cp as f32 / 100.0),
+            Score::Mate(moves) => Eval::Mate(moves),
+        }
+    }
+}
+
+/// UCI form, as after `score` in an `info` line: "cp 35" or "mate -3"
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Score::Cp(cp) => write!(f, "cp {}", cp),
+            Score::Mate(n) => write!(f, "mate {}", n),
+        }
+    }
+}
+
+impl FromStr for Score {
+    type Err = ChessError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ChessError::ParseError(format!("Invalid score: {}", s));
+        let mut words = s.split_whitespace();
+        let (kind, value) = (words.next().ok_or_else(invalid)?, words.next().ok_or_else(invalid)?);
+        if words.next().is_some() {
+            return Err(invalid());
+        }
+        let value = value.parse().map_err(|_| invalid())?;
+        match kind {
+            "cp" => Ok(Score::Cp(value)),
+            "mate" => Ok(Score::Mate(value)),
+            _ => Err(invalid()),
+        }
+    }
+}
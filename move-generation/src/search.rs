@@ -0,0 +1,587 @@
+//! Alpha-beta search: iterative deepening principal variation search with a
+//! transposition table and a quiescence search, on one or more threads,
+//! reporting progress after every completed depth
+
+use crate::eval::Evaluator;
+use crate::game::board_outcome;
+use crate::move_gen::generate_captures;
+use crate::notation::to_uci;
+use crate::pgn::ChessError;
+use crate::rules::{is_in_check, keeps_king_safe};
+use crate::score::{Score, MATE_CP};
+use crate::see::{see, see_value};
+use crate::stager::MoveStager;
+use crate::tt::{Bound, TranspositionTable, TtEntry};
+use crate::types::{Board, Move, Piece, Variant};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Deepest iteration searched when no other limit applies
+pub const MAX_DEPTH: u32 = 64;
+
+/// Scores this close to `MATE_CP` are mates, counted in plies
+const MATE_BOUND: i32 = MATE_CP - 1000;
+
+/// Shallowest node that tries a singular extension
+const SINGULAR_DEPTH: i32 = 6;
+
+/// When to stop searching; the first limit reached ends the search, and
+/// without any the search runs to `MAX_DEPTH`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SearchLimits {
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub movetime: Option<Duration>,
+}
+
+/// Thread-safe switch for interrupting a search from outside, e.g. from a
+/// GUI thread or on a UCI `stop`. Clones share the switch. A stopped search
+/// returns the best move of its last completed depth.
+#[derive(Clone, Debug, Default)]
+pub struct SearchHandle {
+    stopped: Arc<AtomicBool>,
+}
+
+impl SearchHandle {
+    pub fn new() -> Self {
+        SearchHandle::default()
+    }
+
+    /// Ask the searches using this handle to finish as soon as possible
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed)
+    }
+
+    /// Clear a stop, so the handle can serve another search
+    pub fn reset(&self) {
+        self.stopped.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Progress after a completed iteration, as in a UCI `info` line
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchInfo {
+    pub depth: u32,
+    /// Line number from 1 when searching several lines
+    pub multipv: Option<usize>,
+    /// Score for the side to move at the root
+    pub score: Score,
+    pub nodes: u64,
+    /// Nodes per second so far
+    pub nps: u64,
+    pub time: Duration,
+    /// Principal variation, starting with the best move
+    pub pv: Vec<Move>,
+}
+
+/// UCI form without the leading "info", e.g.
+/// "depth 4 score cp 35 nodes 5120 nps 400000 time 12 pv e2e4 e7e5"
+impl fmt::Display for SearchInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "depth {}", self.depth)?;
+        if let Some(multipv) = self.multipv {
+            write!(f, " multipv {}", multipv)?;
+        }
+        write!(f, " score {} nodes {} nps {} time {} pv", self.score, self.nodes, self.nps, self.time.as_millis())?;
+        for mv in &self.pv {
+            write!(f, " {}", to_uci(mv))?;
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of a search: the last completed iteration, or the best move of
+/// an interrupted first iteration
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchResult {
+    /// None when the side to move has no legal move or the game is over
+    pub best_move: Option<Move>,
+    pub score: Score,
+    pub depth: u32,
+    pub nodes: u64,
+    pub pv: Vec<Move>,
+    /// Every line of the last completed depth, best first: the main line
+    /// alone unless `SearchOptions::multipv` asks for more
+    pub lines: Vec<PvLine>,
+}
+
+/// One root move's line in a multi-PV search
+#[derive(Clone, Debug, PartialEq)]
+pub struct PvLine {
+    pub score: Score,
+    pub pv: Vec<Move>,
+}
+
+/// Engine configuration that outlives a single search
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchOptions {
+    /// Search threads; more than one searches Lazy SMP style, every thread
+    /// on the whole tree and sharing what it finds through the hash table
+    pub threads: usize,
+    /// Size of the transposition table in megabytes
+    pub hash_mb: usize,
+    /// Number of best root moves to search lines for, each reported by
+    /// its own `SearchInfo`
+    pub multipv: usize,
+    /// Search moves that give check one ply deeper
+    pub check_extensions: bool,
+    /// Search the hash move one ply deeper when a reduced search shows
+    /// every other move to be clearly worse
+    pub singular_extensions: bool,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        SearchOptions { threads: 1, hash_mb: 16, multipv: 1, check_extensions: true, singular_extensions: true }
+    }
+}
+
+/// A searcher keeping its transposition table between searches, as a game
+/// or analysis session wants
+pub struct Engine {
+    options: SearchOptions,
+    tt: TranspositionTable,
+}
+
+impl Engine {
+    pub fn new(options: SearchOptions) -> Self {
+        Engine { tt: TranspositionTable::new(options.hash_mb), options }
+    }
+
+    pub fn options(&self) -> &SearchOptions {
+        &self.options
+    }
+
+    /// Change the options; a new hash size also empties the table
+    pub fn set_options(&mut self, options: SearchOptions) {
+        if options.hash_mb != self.options.hash_mb {
+            self.tt = TranspositionTable::new(options.hash_mb);
+        }
+        self.options = options;
+    }
+
+    /// Forget earlier searches, e.g. before a new game
+    pub fn clear(&self) {
+        self.tt.clear();
+    }
+
+    /// Search `board` within `limits` until done or `handle` is stopped.
+    /// Only the calling thread reports `on_info` and picks the result; the
+    /// helper threads start at alternating depths so they explore ahead of
+    /// it, and stop when it does.
+    pub fn search<E: Evaluator + ?Sized>(
+        &self,
+        board: &Board,
+        limits: &SearchLimits,
+        evaluator: &E,
+        handle: &SearchHandle,
+        mut on_info: impl FnMut(&SearchInfo),
+    ) -> Result<SearchResult, ChessError> {
+        let start = Instant::now();
+        let nodes = AtomicU64::new(0);
+        let helpers = SearchHandle::new();
+        let result = thread::scope(|scope| {
+            for index in 1..self.options.threads.max(1) {
+                let mut helper = Searcher::new(evaluator, limits, &self.options, &self.tt, &nodes, helpers.clone(), start);
+                scope.spawn(move || {
+                    // A failing evaluator fails the main thread as well
+                    let _ = helper.iterate(board, 1 + index as u32 % 2, &mut |_| {});
+                });
+            }
+            let mut main = Searcher::new(evaluator, limits, &self.options, &self.tt, &nodes, handle.clone(), start);
+            let result = main.iterate(board, 1, &mut on_info);
+            helpers.stop();
+            result
+        });
+        result.map(|result| SearchResult { nodes: nodes.load(Ordering::Relaxed), ..result })
+    }
+}
+
+/// Search `board` within `limits`, scoring leaves with `evaluator` and
+/// calling `on_info` after each completed depth
+pub fn search<E: Evaluator + ?Sized>(
+    board: &Board,
+    limits: &SearchLimits,
+    evaluator: &E,
+    on_info: impl FnMut(&SearchInfo),
+) -> Result<SearchResult, ChessError> {
+    search_with_handle(board, limits, evaluator, &SearchHandle::new(), on_info)
+}
+
+/// `search` that also ends when `handle` is stopped, which may happen from
+/// another thread or from `on_info`
+pub fn search_with_handle<E: Evaluator + ?Sized>(
+    board: &Board,
+    limits: &SearchLimits,
+    evaluator: &E,
+    handle: &SearchHandle,
+    on_info: impl FnMut(&SearchInfo),
+) -> Result<SearchResult, ChessError> {
+    Engine::new(SearchOptions::default()).search(board, limits, evaluator, handle, on_info)
+}
+
+/// Engine-internal value, mates as `MATE_CP` minus the distance in plies, as
+/// a `Score` with the distance in moves
+fn to_score(value: i32) -> Score {
+    if value >= MATE_BOUND {
+        Score::Mate((MATE_CP - value + 1) / 2)
+    } else if value <= -MATE_BOUND {
+        Score::Mate(-(MATE_CP + value) / 2)
+    } else {
+        Score::Cp(value)
+    }
+}
+
+/// Mate values are stored relative to the position rather than the root
+fn value_to_tt(value: i32, ply: usize) -> i32 {
+    match value {
+        v if v >= MATE_BOUND => v + ply as i32,
+        v if v <= -MATE_BOUND => v - ply as i32,
+        v => v,
+    }
+}
+
+fn value_from_tt(value: i32, ply: usize) -> i32 {
+    match value {
+        v if v >= MATE_BOUND => v - ply as i32,
+        v if v <= -MATE_BOUND => v + ply as i32,
+        v => v,
+    }
+}
+
+/// One search thread
+struct Searcher<'a, E: ?Sized> {
+    evaluator: &'a E,
+    limits: SearchLimits,
+    options: SearchOptions,
+    tt: &'a TranspositionTable,
+    /// Nodes of all threads, added to every 1024 nodes
+    shared_nodes: &'a AtomicU64,
+    handle: SearchHandle,
+    start: Instant,
+    nodes: u64,
+    /// Part of `nodes` already added to `shared_nodes`
+    flushed: u64,
+    stopped: bool,
+    /// Depth of the current iteration; lines are extended to at most twice it
+    root_depth: i32,
+    /// Two quiet moves per ply that caused a cutoff
+    killers: Vec<[Option<Move>; 2]>,
+    /// Move left out per ply while testing the hash move for singularity
+    excluded: Vec<Option<Move>>,
+    /// Root moves of the lines already found at this depth
+    root_excluded: Vec<Move>,
+    /// Hashes of the positions on the current line, for repetitions
+    history: Vec<u64>,
+}
+
+impl<'a, E: Evaluator + ?Sized> Searcher<'a, E> {
+    fn new(
+        evaluator: &'a E,
+        limits: &SearchLimits,
+        options: &SearchOptions,
+        tt: &'a TranspositionTable,
+        shared_nodes: &'a AtomicU64,
+        handle: SearchHandle,
+        start: Instant,
+    ) -> Self {
+        Searcher {
+            evaluator,
+            limits: limits.clone(),
+            options: options.clone(),
+            tt,
+            shared_nodes,
+            handle,
+            start,
+            nodes: 0,
+            flushed: 0,
+            stopped: false,
+            root_depth: 0,
+            killers: Vec::new(),
+            excluded: Vec::new(),
+            root_excluded: Vec::new(),
+            history: Vec::new(),
+        }
+    }
+
+    /// Iterative deepening from `first_depth`
+    fn iterate(&mut self, board: &Board, first_depth: u32, on_info: &mut dyn FnMut(&SearchInfo)) -> Result<SearchResult, ChessError> {
+        let mut board = board.clone();
+        let mut result = SearchResult { best_move: None, score: Score::Cp(0), depth: 0, nodes: 0, pv: Vec::new(), lines: Vec::new() };
+        let multipv = self.options.multipv.max(1);
+
+        for depth in first_depth..=self.limits.depth.unwrap_or(MAX_DEPTH).min(MAX_DEPTH) {
+            self.root_depth = depth as i32;
+            let mut lines = Vec::new();
+            let mut best_value = 0;
+            let mut partial = Vec::new();
+            for index in 0..multipv {
+                let previous = result.lines.get(index).map(|line| line.pv.clone()).unwrap_or_default();
+                let mut pv = Vec::new();
+                let value = self.negamax(&mut board, depth as i32, 0, -MATE_CP, MATE_CP, &previous, &mut pv);
+                self.flush_nodes();
+                let value = value?;
+                if self.stopped {
+                    partial = pv;
+                    break;
+                }
+                if index == 0 {
+                    best_value = value;
+                }
+                // Fewer legal moves than lines
+                let Some(mv) = pv.first().cloned() else { break };
+                self.root_excluded.push(mv);
+                lines.push(PvLine { score: to_score(value), pv });
+            }
+            self.root_excluded.clear();
+            if self.stopped {
+                // A partial first iteration still beats having no move at all
+                if result.best_move.is_none() {
+                    result.pv = lines.into_iter().next().map_or(partial, |line| line.pv);
+                    result.best_move = result.pv.first().cloned();
+                }
+                break;
+            }
+            let nodes = self.shared_nodes.load(Ordering::Relaxed);
+            let pv = lines.first().map(|line| line.pv.clone()).unwrap_or_default();
+            result = SearchResult { best_move: pv.first().cloned(), score: to_score(best_value), depth, nodes, pv, lines };
+            let time = self.start.elapsed();
+            let nps = (nodes as f64 / time.as_secs_f64().max(1e-6)) as u64;
+            if result.lines.is_empty() {
+                on_info(&SearchInfo { depth, multipv: None, score: result.score, nodes, nps, time, pv: Vec::new() });
+            }
+            for (index, line) in result.lines.iter().enumerate() {
+                let multipv = (multipv > 1).then_some(index + 1);
+                on_info(&SearchInfo { depth, multipv, score: line.score, nodes, nps, time, pv: line.pv.clone() });
+            }
+            if result.best_move.is_none() || best_value.abs() >= MATE_BOUND || self.handle.is_stopped() {
+                break;
+            }
+        }
+        Ok(result)
+    }
+
+    fn flush_nodes(&mut self) {
+        self.shared_nodes.fetch_add(self.nodes - self.flushed, Ordering::Relaxed);
+        self.flushed = self.nodes;
+    }
+
+    fn out_of_budget(&mut self) -> bool {
+        if !self.stopped && self.nodes.is_multiple_of(1024) {
+            self.flush_nodes();
+            let nodes_spent = self.limits.nodes.is_some_and(|limit| self.shared_nodes.load(Ordering::Relaxed) >= limit);
+            let time_spent = self.limits.movetime.is_some_and(|limit| self.start.elapsed() >= limit);
+            self.stopped = nodes_spent || time_spent || self.handle.is_stopped();
+        }
+        self.stopped
+    }
+
+    /// Value of `board` for the side to move within (alpha, beta); `line`
+    /// is the previous principal variation while still on it, `pv` receives
+    /// the best line found
+    #[allow(clippy::too_many_arguments)]
+    fn negamax(
+        &mut self,
+        board: &mut Board,
+        depth: i32,
+        ply: usize,
+        mut alpha: i32,
+        beta: i32,
+        line: &[Move],
+        pv: &mut Vec<Move>,
+    ) -> Result<i32, ChessError> {
+        self.nodes += 1;
+        if ply > 0 {
+            if self.out_of_budget() {
+                return Ok(0);
+            }
+            if board.halfmove_clock >= 100 || self.history.contains(&board.hash) {
+                return Ok(0);
+            }
+        }
+        if board.variant != Variant::Standard {
+            if let Some(outcome) = board_outcome(board) {
+                return Ok(match outcome.winner {
+                    Some(winner) if winner == board.side_to_move => MATE_CP - ply as i32,
+                    Some(_) => -(MATE_CP - ply as i32),
+                    None => 0,
+                });
+            }
+        }
+        let in_check = is_in_check(board, board.side_to_move);
+        if depth <= 0 && !in_check {
+            return self.quiescence(board, alpha, beta);
+        }
+
+        if self.killers.len() <= ply {
+            self.killers.resize(ply + 1, [None, None]);
+            self.excluded.resize(ply + 1, None);
+        }
+        // A search without one move must neither use nor overwrite the
+        // entry of the full position
+        let excluded = self.excluded[ply].clone();
+        let entry = self.tt.probe(board.hash).filter(|_| excluded.is_none());
+        // Only null-window nodes take hash cutoffs, so principal variations
+        // stay whole
+        if let Some(entry) = entry.as_ref().filter(|entry| ply > 0 && beta - alpha == 1 && entry.depth >= depth) {
+            let value = value_from_tt(entry.value, ply);
+            let cutoff = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => value >= beta,
+                Bound::Upper => value <= alpha,
+            };
+            if cutoff {
+                return Ok(value);
+            }
+        }
+
+        let singular = match &entry {
+            Some(entry) if self.options.singular_extensions && ply > 0 && depth >= SINGULAR_DEPTH => {
+                self.singular_move(board, depth, ply, entry)?
+            }
+            _ => None,
+        };
+
+        let follow = line.first().cloned();
+        let hash_move = follow.clone().or_else(|| entry.and_then(|entry| entry.best_move));
+        // The stager reads a copy while the board is played on
+        let position = board.clone();
+        let moves = MoveStager::new(&position, hash_move, self.killers[ply].clone());
+        let original_alpha = alpha;
+        let mut best_move = None;
+        let mut any_move = false;
+        self.history.push(board.hash);
+        for mv in moves {
+            if excluded.as_ref() == Some(&mv) || ply == 0 && self.root_excluded.contains(&mv) {
+                continue;
+            }
+            let rest = if follow.as_ref() == Some(&mv) { &line[1..] } else { &[] };
+            let capture = board.squares()[mv.to as usize].is_some();
+            let mut child_pv = Vec::new();
+            let undo = board.make_move(&mv);
+            let extend = ply < 2 * self.root_depth as usize
+                && (singular.as_ref() == Some(&mv) || self.options.check_extensions && is_in_check(board, board.side_to_move));
+            let value = self.search_child(board, depth - 1 + extend as i32, ply + 1, alpha, beta, !any_move, rest, &mut child_pv);
+            board.unmake_move(&mv, undo);
+            let value = value?;
+            any_move = true;
+            if self.stopped {
+                break;
+            }
+            if value > alpha {
+                alpha = value;
+                best_move = Some(mv.clone());
+                pv.clear();
+                pv.push(mv.clone());
+                pv.extend(child_pv);
+            }
+            if alpha >= beta {
+                if !capture && mv.promotion.is_none() && self.killers[ply][0].as_ref() != Some(&mv) {
+                    self.killers[ply] = [Some(mv), self.killers[ply][0].take()];
+                }
+                break;
+            }
+        }
+        self.history.pop();
+
+        // Searches leaving moves out say nothing about the whole position
+        let partial = excluded.is_some() || ply == 0 && !self.root_excluded.is_empty();
+        if !any_move {
+            if partial {
+                return Ok(alpha);
+            }
+            return Ok(if in_check { -(MATE_CP - ply as i32) } else { 0 });
+        }
+        if !self.stopped && !partial {
+            let bound = match alpha {
+                a if a >= beta => Bound::Lower,
+                a if a > original_alpha => Bound::Exact,
+                _ => Bound::Upper,
+            };
+            self.tt.store(TtEntry { key: board.hash, best_move, value: value_to_tt(alpha, ply), depth, bound });
+        }
+        Ok(alpha)
+    }
+
+    /// The hash move, when a reduced search of the other moves fails low
+    /// against a margin below its stored value
+    fn singular_move(&mut self, board: &mut Board, depth: i32, ply: usize, entry: &TtEntry) -> Result<Option<Move>, ChessError> {
+        let Some(mv) = entry.best_move.clone() else { return Ok(None) };
+        let value = value_from_tt(entry.value, ply);
+        if entry.bound == Bound::Upper || entry.depth < depth - 3 || value.abs() >= MATE_BOUND {
+            return Ok(None);
+        }
+        let singular_beta = value - 2 * depth;
+        self.excluded[ply] = Some(mv.clone());
+        let result = self.negamax(board, (depth - 1) / 2, ply, singular_beta - 1, singular_beta, &[], &mut Vec::new());
+        self.excluded[ply] = None;
+        Ok((result? < singular_beta).then_some(mv))
+    }
+
+    /// Principal variation search of a child, from the parent's point of
+    /// view: the first move gets the full window, the rest a null window
+    /// and a full re-search only when they beat alpha
+    #[allow(clippy::too_many_arguments)]
+    fn search_child(
+        &mut self,
+        board: &mut Board,
+        depth: i32,
+        ply: usize,
+        alpha: i32,
+        beta: i32,
+        first: bool,
+        line: &[Move],
+        pv: &mut Vec<Move>,
+    ) -> Result<i32, ChessError> {
+        if !first && beta - alpha > 1 {
+            let value = -self.negamax(board, depth, ply, -alpha - 1, -alpha, line, pv)?;
+            if value <= alpha || self.stopped {
+                return Ok(value);
+            }
+            pv.clear();
+        }
+        Ok(-self.negamax(board, depth, ply, -beta, -alpha, line, pv)?)
+    }
+
+    /// Captures only, until the position is quiet, standing pat on the
+    /// static evaluation
+    fn quiescence(&mut self, board: &mut Board, mut alpha: i32, beta: i32) -> Result<i32, ChessError> {
+        let stand_pat = self.evaluator.evaluate(board)?.to_cp().clamp(-MATE_BOUND + 1, MATE_BOUND - 1);
+        if stand_pat >= beta {
+            return Ok(stand_pat);
+        }
+        alpha = alpha.max(stand_pat);
+
+        let mut captures: Vec<Move> = generate_captures(board, board.side_to_move)
+            .into_iter()
+            .filter(|mv| keeps_king_safe(board, mv) && see(board, mv) >= 0)
+            .collect();
+        captures.sort_by_key(|mv| {
+            let victim = board.squares()[mv.to as usize].map_or(see_value(Piece::Pawn), |(p, _)| see_value(p));
+            let attacker = board.squares()[mv.from as usize].map_or(0, |(p, _)| see_value(p));
+            (-victim, attacker)
+        });
+        for mv in captures {
+            self.nodes += 1;
+            if self.out_of_budget() {
+                break;
+            }
+            let undo = board.make_move(&mv);
+            let value = self.quiescence(board, -beta, -alpha);
+            board.unmake_move(&mv, undo);
+            let value = -value?;
+            if value >= beta {
+                return Ok(value);
+            }
+            alpha = alpha.max(value);
+        }
+        Ok(alpha)
+    }
+}
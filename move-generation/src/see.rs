@@ -0,0 +1,116 @@
+//! Static exchange evaluation
+
+use crate::types::{Board, Color, Move, Piece};
+
+/// Piece values used by the exchange evaluation, in centipawns
+pub fn see_value(piece: Piece) -> i32 {
+    match piece {
+        Piece::Pawn => 100,
+        Piece::Knight => 320,
+        Piece::Bishop => 330,
+        Piece::Rook => 500,
+        Piece::Queen => 900,
+        Piece::King => 20000,
+    }
+}
+
+type Squares = [Option<(Piece, Color)>; 64];
+
+/// Square and type of the cheapest piece of `color` attacking `sq`
+fn least_valuable_attacker(squares: &Squares, sq: u8, color: Color) -> Option<(u8, Piece)> {
+    let rank = (sq / 8) as i8;
+    let file = (sq % 8) as i8;
+    let at = |r: i8, f: i8| -> Option<(u8, Piece)> {
+        if !(0..8).contains(&r) || !(0..8).contains(&f) {
+            return None;
+        }
+        let idx = (r * 8 + f) as u8;
+        match squares[idx as usize] {
+            Some((piece, c)) if c == color => Some((idx, piece)),
+            _ => None,
+        }
+    };
+
+    let mut attackers: Vec<(u8, Piece)> = Vec::new();
+
+    let pawn_rank = match color {
+        Color::White => rank - 1,
+        Color::Black => rank + 1,
+    };
+    attackers.extend([-1, 1].iter().filter_map(|&df| at(pawn_rank, file + df)).filter(|&(_, p)| p == Piece::Pawn));
+
+    let knight_deltas = [(-2, -1), (-2, 1), (-1, -2), (-1, 2), (1, -2), (1, 2), (2, -1), (2, 1)];
+    attackers.extend(knight_deltas.iter().filter_map(|&(dr, df)| at(rank + dr, file + df)).filter(|&(_, p)| p == Piece::Knight));
+
+    let rays = [
+        ((1, 0), Piece::Rook), ((-1, 0), Piece::Rook), ((0, 1), Piece::Rook), ((0, -1), Piece::Rook),
+        ((1, 1), Piece::Bishop), ((1, -1), Piece::Bishop), ((-1, 1), Piece::Bishop), ((-1, -1), Piece::Bishop),
+    ];
+    for &((dr, df), slider) in &rays {
+        let (mut r, mut f) = (rank + dr, file + df);
+        while (0..8).contains(&r) && (0..8).contains(&f) {
+            let idx = (r * 8 + f) as usize;
+            if let Some((p, c)) = squares[idx] {
+                if c == color && (p == slider || p == Piece::Queen) {
+                    attackers.push((idx as u8, p));
+                }
+                break;
+            }
+            r += dr;
+            f += df;
+        }
+    }
+
+    let king_deltas = [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)];
+    attackers.extend(king_deltas.iter().filter_map(|&(dr, df)| at(rank + dr, file + df)).filter(|&(_, p)| p == Piece::King));
+
+    attackers.into_iter().min_by_key(|&(_, p)| see_value(p))
+}
+
+/// Static exchange evaluation of a move: the material the mover expects to
+/// gain on the target square if both sides keep recapturing with their least
+/// valuable attacker and either may stop when continuing would lose material.
+/// Pinned pieces are not taken into account.
+pub fn see(board: &Board, mv: &Move) -> i32 {
+    let mut squares = *board.squares();
+    let (piece, color) = match (mv.drop, squares[mv.from as usize]) {
+        (Some(piece), _) => (piece, board.side_to_move),
+        (None, Some(moving)) => moving,
+        (None, None) => return 0,
+    };
+    if board.is_castling(mv) {
+        return 0;
+    }
+
+    let mut captured = squares[mv.to as usize].map_or(0, |(p, _)| see_value(p));
+    if piece == Piece::Pawn && Some(mv.to) == board.en_passant && mv.from % 8 != mv.to % 8 && mv.drop.is_none() {
+        let victim_sq = if color == Color::White { mv.to - 8 } else { mv.to + 8 };
+        squares[victim_sq as usize] = None;
+        captured = see_value(Piece::Pawn);
+    }
+
+    let placed = mv.promotion.unwrap_or(piece);
+    let mut gain = vec![captured + mv.promotion.map_or(0, |p| see_value(p) - see_value(Piece::Pawn))];
+    let mut on_square = see_value(placed);
+    if mv.drop.is_none() {
+        squares[mv.from as usize] = None;
+    }
+    squares[mv.to as usize] = Some((placed, color));
+
+    let mut side = color.opposite();
+    while let Some((sq, attacker)) = least_valuable_attacker(&squares, mv.to, side) {
+        gain.push(on_square - gain[gain.len() - 1]);
+        on_square = see_value(attacker);
+        squares[sq as usize] = None;
+        squares[mv.to as usize] = Some((attacker, side));
+        side = side.opposite();
+    }
+
+    // Each side may decline to recapture
+    while gain.len() > 1 {
+        let last = gain.pop().unwrap();
+        let prev = gain.last_mut().unwrap();
+        *prev = -(-*prev).max(last);
+    }
+    gain[0]
+}
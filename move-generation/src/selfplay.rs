@@ -0,0 +1,223 @@
+//! Self-play training data: games of the engine against itself, played in
+//! parallel on the rayon pool, written out as (position, policy, outcome)
+//! examples for an AlphaZero-style training loop
+
+use crate::eval::Evaluator;
+use crate::game::{can_claim_draw, Game};
+use crate::mcts::{Mcts, MctsConfig};
+use crate::notation::to_standard_uci;
+use crate::pgn::{starting_board, ChessError, Position};
+use crate::search::{Engine, SearchHandle, SearchLimits, SearchOptions};
+use crate::stats::Wdl;
+use crate::types::{Board, Color, Move, Variant};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+use serde_json::{json, Map, Value};
+use std::io::Write;
+use std::sync::Mutex;
+
+/// Centipawn difference that divides a move's search policy weight by e
+const POLICY_SCALE_CP: f32 = 100.0;
+
+/// How moves are chosen and where their policy comes from
+#[derive(Clone, Debug, PartialEq)]
+pub enum SelfPlayEngine {
+    /// Alpha-beta search. The policy spreads over the `options.multipv`
+    /// best moves by score, so with one line it is the best move alone.
+    Search { limits: SearchLimits, options: SearchOptions },
+    /// Monte Carlo tree search with `playouts` per move; the policy is the
+    /// share of root visits. Root noise is set by `config.dirichlet_epsilon`;
+    /// `config.temperature` is unused, the self-play temperature applies.
+    Mcts { playouts: u32, config: MctsConfig },
+}
+
+/// Settings of a self-play run
+#[derive(Clone, Debug, PartialEq)]
+pub struct SelfPlayConfig {
+    pub games: usize,
+    pub engine: SelfPlayEngine,
+    /// Moves are drawn with probability proportional to policy^(1 /
+    /// temperature) for the first `temperature_plies` plies and the most
+    /// likely one is played after that; 0 always plays the most likely
+    pub temperature: f32,
+    pub temperature_plies: usize,
+    /// Game length in plies at which it is scored a draw
+    pub max_plies: usize,
+    /// Start position as FEN, the standard one when None
+    pub start_fen: Option<String>,
+    /// Seed of game `i` is `seed + i`, or random when None
+    pub seed: Option<u64>,
+}
+
+impl Default for SelfPlayConfig {
+    fn default() -> Self {
+        SelfPlayConfig {
+            games: 1,
+            engine: SelfPlayEngine::Mcts { playouts: 100, config: MctsConfig { dirichlet_epsilon: 0.25, ..MctsConfig::default() } },
+            temperature: 1.0,
+            temperature_plies: 30,
+            max_plies: 400,
+            start_fen: None,
+            seed: None,
+        }
+    }
+}
+
+/// One training example
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrainingExample {
+    pub board: Board,
+    /// Search policy over the legal moves, summing to 1
+    pub policy: Vec<(Move, f32)>,
+    /// Result of the game for the side to move: 1 win, 0 draw, -1 loss
+    pub outcome: f32,
+}
+
+impl TrainingExample {
+    /// `{"fen": ..., "policy": {"e2e4": 0.6, ...}, "outcome": 1.0}`, moves
+    /// in standard UCI
+    pub fn to_json(&self) -> Value {
+        let policy: Map<String, Value> = self.policy.iter()
+            .map(|(mv, p)| (to_standard_uci(&self.board, mv), json!(p)))
+            .collect();
+        json!({
+            "fen": Position::from(&self.board).to_fen(),
+            "policy": policy,
+            "outcome": self.outcome,
+        })
+    }
+}
+
+/// Totals of a self-play run
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SelfPlayStats {
+    /// Results from White's point of view
+    pub wdl: Wdl,
+    pub examples: usize,
+}
+
+/// Play `config.games` games with `evaluator` over the rayon pool and write
+/// each game's examples to `writer` as JSON Lines as soon as it is over, so
+/// games appear in the order they finish
+pub fn generate<E, W>(config: &SelfPlayConfig, evaluator: &E, writer: W) -> Result<SelfPlayStats, ChessError>
+where
+    E: Evaluator + ?Sized,
+    W: Write + Send,
+{
+    let start = match &config.start_fen {
+        Some(fen) => Board::from(fen.parse::<Position>()?),
+        None => starting_board(Variant::Standard),
+    };
+    let output = Mutex::new((writer, SelfPlayStats::default()));
+    (0..config.games).into_par_iter().try_for_each(|index| {
+        let rng = match config.seed {
+            Some(seed) => StdRng::seed_from_u64(seed.wrapping_add(index as u64)),
+            None => StdRng::from_entropy(),
+        };
+        let (examples, winner) = play_game(config, evaluator, start.clone(), rng)?;
+        let mut output = output.lock().unwrap();
+        let (writer, stats) = &mut *output;
+        for example in &examples {
+            writeln!(writer, "{}", example.to_json())?;
+        }
+        match winner {
+            Some(Color::White) => stats.wdl.wins += 1,
+            Some(Color::Black) => stats.wdl.losses += 1,
+            None => stats.wdl.draws += 1,
+        }
+        stats.examples += examples.len();
+        Ok::<_, ChessError>(())
+    })?;
+    let (mut writer, stats) = output.into_inner().unwrap();
+    writer.flush()?;
+    Ok(stats)
+}
+
+/// Play one game and return its examples and winner
+pub fn play_game<E: Evaluator + ?Sized>(
+    config: &SelfPlayConfig,
+    evaluator: &E,
+    start: Board,
+    mut rng: StdRng,
+) -> Result<(Vec<TrainingExample>, Option<Color>), ChessError> {
+    let mut game = Game::new(start.clone());
+    let mut searcher = match &config.engine {
+        SelfPlayEngine::Search { limits, options } => Searcher::Search(Engine::new(options.clone()), limits),
+        SelfPlayEngine::Mcts { playouts, config } => {
+            Searcher::Mcts(Box::new(Mcts::new(start, MctsConfig { seed: Some(rng.gen()), ..config.clone() })), *playouts)
+        }
+    };
+    let mut examples = Vec::new();
+    let winner = loop {
+        if let Some(outcome) = game.outcome() {
+            break outcome.winner;
+        }
+        if can_claim_draw(&game).is_some() || game.moves().len() >= config.max_plies {
+            break None;
+        }
+        let policy = match &mut searcher {
+            Searcher::Search(engine, limits) => {
+                let result = engine.search(game.board(), limits, evaluator, &SearchHandle::new(), |_| {})?;
+                let lines: Vec<(Move, i32)> = result.lines.iter()
+                    .filter_map(|line| Some((line.pv.first()?.clone(), line.score.to_cp())))
+                    .collect();
+                search_policy(&lines)
+            }
+            Searcher::Mcts(mcts, playouts) => {
+                mcts.run_with_evaluator(mcts.playouts() + *playouts, evaluator)?;
+                let moves = mcts.root_moves();
+                let total = moves.iter().map(|&(_, visits, _)| visits).sum::<u32>().max(1) as f32;
+                moves.into_iter().map(|(mv, visits, _)| (mv, visits as f32 / total)).collect()
+            }
+        };
+        let temperature = if game.moves().len() < config.temperature_plies { config.temperature } else { 0.0 };
+        let Some(mv) = sample(&policy, temperature, &mut rng) else {
+            return Err(ChessError::IllegalMove("no move found".to_string()));
+        };
+        examples.push(TrainingExample { board: game.board().clone(), policy, outcome: 0.0 });
+        if let Searcher::Mcts(mcts, _) = &mut searcher {
+            mcts.advance(&mv)?;
+        }
+        game.push(mv)?;
+    };
+    for example in &mut examples {
+        example.outcome = match winner {
+            Some(color) if color == example.board.side_to_move => 1.0,
+            Some(_) => -1.0,
+            None => 0.0,
+        };
+    }
+    Ok((examples, winner))
+}
+
+enum Searcher<'a> {
+    Search(Engine, &'a SearchLimits),
+    Mcts(Box<Mcts>, u32),
+}
+
+/// Softmax over the scores of the searched lines
+fn search_policy(lines: &[(Move, i32)]) -> Vec<(Move, f32)> {
+    let best = lines.iter().map(|&(_, cp)| cp).max().unwrap_or(0);
+    let weights: Vec<f32> = lines.iter().map(|&(_, cp)| ((cp - best) as f32 / POLICY_SCALE_CP).exp()).collect();
+    let total: f32 = weights.iter().sum();
+    lines.iter().zip(weights).map(|((mv, _), weight)| (mv.clone(), weight / total)).collect()
+}
+
+/// Draw a move with probability proportional to policy^(1 / temperature),
+/// or take the most likely one at temperature 0
+fn sample(policy: &[(Move, f32)], temperature: f32, rng: &mut StdRng) -> Option<Move> {
+    let best = policy.iter().max_by(|a, b| a.1.total_cmp(&b.1)).map(|(mv, _)| mv.clone());
+    if temperature <= 0.0 {
+        return best;
+    }
+    let weights: Vec<f64> = policy.iter().map(|&(_, p)| (p as f64).powf(1.0 / temperature as f64)).collect();
+    let mut pick = rng.gen::<f64>() * weights.iter().sum::<f64>();
+    for ((mv, _), weight) in policy.iter().zip(&weights) {
+        if pick < *weight {
+            return Some(mv.clone());
+        }
+        pick -= weight;
+    }
+    best
+}
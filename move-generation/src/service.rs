@@ -0,0 +1,116 @@
+//! JSON request handling shared by the HTTP service and the stdio server
+
+use crate::notation::{parse_uci, to_standard_uci};
+use crate::perft::perft;
+use crate::pgn::{ChessError, Position};
+use crate::phase::game_phase;
+use crate::rules::{generate_legal_moves, is_checkmate, is_in_check, is_stalemate, validate_board};
+use crate::score::Score;
+use crate::see::see_value;
+use crate::types::{Board, Color, Piece};
+use serde_json::{json, Value};
+use std::io::{self, BufRead, Write};
+
+/// Deepest perft a request may ask for
+pub const MAX_PERFT_DEPTH: u32 = 6;
+
+/// Answer a request such as `{"cmd": "legal_moves", "fen": "..."}`.
+///
+/// Commands: `legal_moves`, `validate_move` (with `move` in UCI), `perft`
+/// (with `depth`) and `evaluate`, a static material count with the game status
+/// and a UCI score for the side to move such as "cp 300" or "mate 0".
+pub fn handle_request(request: &Value) -> Result<Value, ChessError> {
+    let cmd = request["cmd"].as_str()
+        .ok_or_else(|| ChessError::ParseError("missing \"cmd\"".to_string()))?;
+    let board = board_param(request)?;
+
+    match cmd {
+        "legal_moves" => {
+            let moves: Vec<String> = generate_legal_moves(&board).iter().map(|mv| to_standard_uci(&board, mv)).collect();
+            Ok(json!({ "moves": moves }))
+        }
+        "validate_move" => {
+            let uci = request["move"].as_str()
+                .ok_or_else(|| ChessError::ParseError("missing \"move\"".to_string()))?;
+            match parse_uci(&board, uci) {
+                Ok(mv) => {
+                    let mut after = board.clone();
+                    after.make_move(&mv);
+                    Ok(json!({ "legal": true, "fen": Position::from(&after).to_fen() }))
+                }
+                Err(ChessError::IllegalMove(_)) => Ok(json!({ "legal": false })),
+                Err(e) => Err(e),
+            }
+        }
+        "perft" => {
+            let depth = request["depth"].as_u64()
+                .ok_or_else(|| ChessError::ParseError("missing \"depth\"".to_string()))?;
+            if depth > MAX_PERFT_DEPTH as u64 {
+                return Err(ChessError::ParseError(format!("depth is limited to {}", MAX_PERFT_DEPTH)));
+            }
+            Ok(json!({ "nodes": perft(&board, depth as u32) }))
+        }
+        "evaluate" => {
+            let material = material_balance(&board);
+            let (status, score) = if is_checkmate(&board) {
+                ("checkmate", Score::MATED)
+            } else if is_stalemate(&board) {
+                ("stalemate", Score::Cp(0))
+            } else if board.side_to_move == Color::White {
+                ("ongoing", Score::Cp(material))
+            } else {
+                ("ongoing", -Score::Cp(material))
+            };
+            Ok(json!({
+                "material": material,
+                "score": score.to_string(),
+                "phase": game_phase(&board).as_str(),
+                "in_check": is_in_check(&board, board.side_to_move),
+                "status": status,
+            }))
+        }
+        _ => Err(ChessError::ParseError(format!("unknown command: {}", cmd))),
+    }
+}
+
+fn board_param(request: &Value) -> Result<Board, ChessError> {
+    let fen = request["fen"].as_str()
+        .ok_or_else(|| ChessError::ParseError("missing \"fen\"".to_string()))?;
+    let board = Board::from(fen.parse::<Position>()?);
+    if !validate_board(&board).is_valid() {
+        return Err(ChessError::ParseError(format!("invalid position: {}", fen)));
+    }
+    Ok(board)
+}
+
+/// Material difference in centipawns from White's point of view, kings excluded
+fn material_balance(board: &Board) -> i32 {
+    board.squares().iter().flatten()
+        .filter(|(piece, _)| *piece != Piece::King)
+        .map(|&(piece, color)| if color == Color::White { see_value(piece) } else { -see_value(piece) })
+        .sum()
+}
+
+/// Answer line-delimited JSON requests until `input` ends, writing one JSON
+/// line per request. A request's `id` is echoed back; failures are reported
+/// as `{"error": "..."}`.
+pub fn serve_lines<R: BufRead, W: Write>(input: R, mut output: W) -> io::Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let request: Value = serde_json::from_str(&line).unwrap_or(Value::Null);
+        let mut reply = match request {
+            Value::Object(_) => handle_request(&request)
+                .unwrap_or_else(|e| json!({ "error": e.to_string() })),
+            _ => json!({ "error": "request must be a JSON object" }),
+        };
+        if let Some(id) = request.get("id") {
+            reply["id"] = id.clone();
+        }
+        writeln!(output, "{}", reply)?;
+        output.flush()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,149 @@
+//! Staged move generation for search
+
+use crate::move_gen::{generate_captures, generate_moves, generate_quiets, piece_move_list, is_capture};
+use crate::rules::{is_variant_draw, keeps_king_safe, variant_winner};
+use crate::see::{see, see_value};
+use crate::types::{Board, Move, Piece};
+
+/// Phase a `MoveStager` is in
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stage {
+    HashMove,
+    GoodCaptures,
+    Killers,
+    Quiets,
+    BadCaptures,
+    Done,
+}
+
+/// Lazily yields the legal moves of the side to move in search order: the
+/// hash move, captures that do not lose material (most valuable victim
+/// first), killer moves, quiet moves and finally losing captures.
+///
+/// Each phase is only generated once the previous one is exhausted, so a
+/// search that cuts off early never pays for the later phases. Every legal
+/// move is yielded exactly once; hash and killer moves that are not legal
+/// here are ignored.
+pub struct MoveStager<'a> {
+    board: &'a Board,
+    stage: Stage,
+    hash_move: Option<Move>,
+    killers: [Option<Move>; 2],
+    /// Killers that were yielded, so the quiet phase can skip them
+    tried_killers: Vec<Move>,
+    /// Moves of the current phase, in reverse order of preference
+    pending: Vec<Move>,
+    bad_captures: Vec<Move>,
+}
+
+impl<'a> MoveStager<'a> {
+    pub fn new(board: &'a Board, hash_move: Option<Move>, killers: [Option<Move>; 2]) -> Self {
+        let mut stager = MoveStager {
+            board,
+            stage: Stage::HashMove,
+            hash_move: None,
+            killers,
+            tried_killers: Vec::new(),
+            pending: Vec::new(),
+            bad_captures: Vec::new(),
+        };
+        if let Some(mv) = hash_move.filter(|mv| stager.is_playable(mv)) {
+            stager.pending.push(mv.clone());
+            stager.hash_move = Some(mv);
+        }
+        stager
+    }
+
+    /// Phase of the moves currently being yielded
+    pub fn stage(&self) -> Stage {
+        self.stage
+    }
+
+    /// Whether a move from outside the generator (hash or killer) can be played
+    fn is_playable(&self, mv: &Move) -> bool {
+        let board = self.board;
+        if variant_winner(board).is_some() || is_variant_draw(board) {
+            return false;
+        }
+        let generated = match (mv.drop, board.squares()[mv.from as usize]) {
+            (Some(_), _) => generate_moves(board, board.side_to_move).contains(mv),
+            (None, Some((piece, color))) if color == board.side_to_move => {
+                piece_move_list(board, piece, mv.from).contains(mv)
+            }
+            _ => false,
+        };
+        generated && keeps_king_safe(board, mv)
+    }
+
+    /// Generate the moves of the next phase into `pending` and enter it
+    fn advance(&mut self) {
+        let board = self.board;
+        let color = board.side_to_move;
+        self.stage = match self.stage {
+            Stage::HashMove => {
+                let mut captures: Vec<Move> = generate_captures(board, color)
+                    .into_iter()
+                    .filter(|mv| self.hash_move.as_ref() != Some(mv) && keeps_king_safe(board, mv))
+                    .collect();
+                // Most valuable victim first, then least valuable attacker;
+                // sorted worst first since `pending` is popped from the back
+                captures.sort_by_key(|mv| {
+                    let victim = board.squares()[mv.to as usize].map_or(see_value(Piece::Pawn), |(p, _)| see_value(p));
+                    let attacker = board.squares()[mv.from as usize].map_or(0, |(p, _)| see_value(p));
+                    (victim, -attacker)
+                });
+                for mv in captures.into_iter().rev() {
+                    if see(board, &mv) >= 0 {
+                        self.pending.insert(0, mv);
+                    } else {
+                        self.bad_captures.insert(0, mv);
+                    }
+                }
+                Stage::GoodCaptures
+            }
+            Stage::GoodCaptures => {
+                for killer in self.killers.clone().into_iter().flatten() {
+                    let fresh = self.hash_move.as_ref() != Some(&killer) && !self.tried_killers.contains(&killer);
+                    if fresh && !is_capture(board, &killer) && self.is_playable(&killer) {
+                        self.tried_killers.push(killer);
+                    }
+                }
+                self.pending = self.tried_killers.iter().rev().cloned().collect();
+                Stage::Killers
+            }
+            Stage::Killers => {
+                self.pending = generate_quiets(board, color)
+                    .into_iter()
+                    .rev()
+                    .filter(|mv| {
+                        self.hash_move.as_ref() != Some(mv)
+                            && !self.tried_killers.contains(mv)
+                            && keeps_king_safe(board, mv)
+                    })
+                    .collect();
+                Stage::Quiets
+            }
+            Stage::Quiets => {
+                self.pending = std::mem::take(&mut self.bad_captures);
+                Stage::BadCaptures
+            }
+            Stage::BadCaptures | Stage::Done => Stage::Done,
+        };
+    }
+}
+
+impl Iterator for MoveStager<'_> {
+    type Item = Move;
+
+    fn next(&mut self) -> Option<Move> {
+        loop {
+            if let Some(mv) = self.pending.pop() {
+                return Some(mv);
+            }
+            if self.stage == Stage::Done {
+                return None;
+            }
+            self.advance();
+        }
+    }
+}
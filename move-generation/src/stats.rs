@@ -0,0 +1,223 @@
+//! Statistics for engine testing
+
+use std::fmt;
+
+/// Win/draw/loss counts from the point of view of the engine under test
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Wdl {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl Wdl {
+    pub fn new(wins: u32, draws: u32, losses: u32) -> Self {
+        Wdl { wins, draws, losses }
+    }
+
+    /// Total number of games
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Mean score per game (1 for a win, 0.5 for a draw), or None without games
+    pub fn score(&self) -> Option<f64> {
+        let n = self.games();
+        if n == 0 {
+            return None;
+        }
+        Some((self.wins as f64 + 0.5 * self.draws as f64) / n as f64)
+    }
+
+    /// Share of games drawn, or None without games
+    pub fn draw_ratio(&self) -> Option<f64> {
+        let n = self.games();
+        (n > 0).then(|| self.draws as f64 / n as f64)
+    }
+
+    /// Likelihood of superiority: the probability that the engine is
+    /// stronger, from wins and losses alone. None before any decisive game.
+    pub fn los(&self) -> Option<f64> {
+        let decisive = (self.wins + self.losses) as f64;
+        if decisive == 0.0 {
+            return None;
+        }
+        let z = (self.wins as f64 - self.losses as f64) / (2.0 * decisive).sqrt();
+        Some(0.5 * (1.0 + erf(z)))
+    }
+
+    /// Elo difference with its 95% error margin, from the score and its
+    /// standard error. None without games or with a 0% or 100% score, where
+    /// the difference is unbounded.
+    pub fn elo(&self) -> Option<EloEstimate> {
+        let score = self.score()?;
+        if score <= 0.0 || score >= 1.0 {
+            return None;
+        }
+        let margin = 1.959964 * (self.variance(score) / self.games() as f64).sqrt();
+        let lower = score_to_elo((score - margin).max(f64::MIN_POSITIVE));
+        let upper = score_to_elo((score + margin).min(1.0 - f64::EPSILON));
+        Some(EloEstimate {
+            elo: score_to_elo(score),
+            error: (upper - lower) / 2.0,
+            los: self.los().unwrap_or(0.5),
+            draw_ratio: self.draw_ratio()?,
+        })
+    }
+
+    /// Per-game variance of the score around its mean
+    fn variance(&self, score: f64) -> f64 {
+        let n = self.games() as f64;
+        (self.wins as f64 * (1.0 - score).powi(2)
+            + self.draws as f64 * (0.5 - score).powi(2)
+            + self.losses as f64 * score.powi(2))
+            / n
+    }
+}
+
+/// Expected score for a given Elo difference under the logistic model
+pub fn elo_to_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+/// Elo difference for a given expected score under the logistic model
+pub fn score_to_elo(score: f64) -> f64 {
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+/// Error function, Abramowitz and Stegun 7.1.26 (absolute error below 1.5e-7)
+fn erf(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.3275911 * x.abs());
+    let poly = t * (0.254829592 + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    (1.0 - poly * (-x * x).exp()).copysign(x)
+}
+
+/// Match result summary in the style of cutechess-cli
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct EloEstimate {
+    pub elo: f64,
+    /// Half-width of the 95% confidence interval
+    pub error: f64,
+    pub los: f64,
+    pub draw_ratio: f64,
+}
+
+impl fmt::Display for EloEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Elo difference: {:.1} +/- {:.1}, LOS: {:.1} %, DrawRatio: {:.1} %",
+            self.elo,
+            self.error,
+            self.los * 100.0,
+            self.draw_ratio * 100.0,
+        )
+    }
+}
+
+/// Outcome of a sequential probability ratio test
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SprtStatus {
+    /// Neither bound reached, keep playing
+    Continue,
+    /// Lower bound reached: the patch is no better than elo0
+    AcceptH0,
+    /// Upper bound reached: the patch is at least elo1 better
+    AcceptH1,
+}
+
+/// Sequential probability ratio test between H0: elo = elo0 and H1: elo = elo1
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+impl Sprt {
+    pub fn new(elo0: f64, elo1: f64, alpha: f64, beta: f64) -> Self {
+        Sprt { elo0, elo1, alpha, beta }
+    }
+
+    /// Lower and upper LLR bounds at which H0 respectively H1 is accepted
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1.0 - self.alpha)).ln(),
+            ((1.0 - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    /// Log-likelihood ratio of H1 against H0, using the normal approximation
+    /// of the trinomial (W/D/L) score distribution
+    pub fn llr(&self, wdl: &Wdl) -> f64 {
+        let score = match wdl.score() {
+            Some(score) => score,
+            None => return 0.0,
+        };
+        let variance = wdl.variance(score);
+        if variance <= 0.0 {
+            return 0.0;
+        }
+
+        let s0 = elo_to_score(self.elo0);
+        let s1 = elo_to_score(self.elo1);
+        wdl.games() as f64 * (s1 - s0) * (2.0 * score - s0 - s1) / (2.0 * variance)
+    }
+
+    /// Decide the test given the current results
+    pub fn status(&self, wdl: &Wdl) -> SprtStatus {
+        let llr = self.llr(wdl);
+        let (lower, upper) = self.bounds();
+        if llr >= upper {
+            SprtStatus::AcceptH1
+        } else if llr <= lower {
+            SprtStatus::AcceptH0
+        } else {
+            SprtStatus::Continue
+        }
+    }
+
+    /// Snapshot of the test for live progress reporting
+    pub fn report(&self, wdl: &Wdl) -> SprtReport {
+        SprtReport {
+            sprt: *self,
+            wdl: *wdl,
+            llr: self.llr(wdl),
+            status: self.status(wdl),
+        }
+    }
+}
+
+/// Current state of an SPRT run
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SprtReport {
+    pub sprt: Sprt,
+    pub wdl: Wdl,
+    pub llr: f64,
+    pub status: SprtStatus,
+}
+
+impl fmt::Display for SprtReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (lower, upper) = self.sprt.bounds();
+        write!(
+            f,
+            "Games: {} W: {} D: {} L: {} LLR: {:.2} ({:.2}, {:.2}) [{:.2}, {:.2}]",
+            self.wdl.games(),
+            self.wdl.wins,
+            self.wdl.draws,
+            self.wdl.losses,
+            self.llr,
+            lower,
+            upper,
+            self.sprt.elo0,
+            self.sprt.elo1,
+        )?;
+        match self.status {
+            SprtStatus::Continue => Ok(()),
+            SprtStatus::AcceptH0 => write!(f, " H0 accepted"),
+            SprtStatus::AcceptH1 => write!(f, " H1 accepted"),
+        }
+    }
+}
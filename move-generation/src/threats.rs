@@ -0,0 +1,60 @@
+//! Threat report: which enemy pieces a side attacks, who attacks and
+//! defends each one and which of them are hanging
+
+use crate::move_gen::{attackers, Bitboard};
+use crate::see::see;
+use crate::types::{Board, Color, Move, Piece};
+
+/// An enemy piece under attack
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Threat {
+    pub square: u8,
+    pub piece: Piece,
+    /// Squares of the pieces attacking it
+    pub attackers: Bitboard,
+    /// Squares of its own pieces defending it
+    pub defenders: Bitboard,
+    /// Material the attacker wins by the best capture of the piece, by
+    /// static exchange; 0 or less when no capture gains anything
+    pub gain: i32,
+    /// Whether leaving the piece where it is loses material, that is
+    /// `gain > 0`
+    pub hanging: bool,
+}
+
+/// Every piece of the opponent of `color` that `color` attacks, kings left
+/// out, the ones whose capture wins the most first. Attacks are counted
+/// whoever is to move, and pins are not taken into account.
+pub fn threats(board: &Board, color: Color) -> Vec<Threat> {
+    let mut threats: Vec<Threat> = board.piece_list(color.opposite())
+        .filter(|&(_, piece)| piece != Piece::King)
+        .filter_map(|(square, piece)| {
+            let attacking = attackers(board, square, color);
+            if attacking == Bitboard::empty() {
+                return None;
+            }
+            let gain = attacking.bits().into_iter()
+                .map(|from| see(board, &capture(board, from, square)))
+                .max()
+                .unwrap_or(0);
+            Some(Threat {
+                square,
+                piece,
+                attackers: attacking,
+                defenders: attackers(board, square, color.opposite()),
+                gain,
+                hanging: gain > 0,
+            })
+        })
+        .collect();
+    threats.sort_by_key(|threat| (std::cmp::Reverse(threat.gain), threat.square));
+    threats
+}
+
+/// The capture of the piece on `to` by the one on `from`, promoting to a
+/// queen when a pawn takes on the last rank
+fn capture(board: &Board, from: u8, to: u8) -> Move {
+    let pawn = matches!(board.squares()[from as usize], Some((Piece::Pawn, _)));
+    let promotion = (pawn && matches!(to / 8, 0 | 7)).then_some(Piece::Queen);
+    Move { from, to, promotion, drop: None }
+}
@@ -0,0 +1,128 @@
+//! Transposition table for the alpha-beta search, shared by its threads.
+//!
+//! The table takes no locks. Each slot is two atomic words, the packed
+//! entry and its key XORed with it, written and read independently with
+//! relaxed ordering. Threads storing to the same slot at once may leave one
+//! thread's key word next to another's data word; such a slot then fails
+//! the key check on probe and reads as empty, so a race loses an entry but
+//! never returns a wrong one. Which of two racing stores survives is
+//! unspecified, and an entry may be missed right after another thread
+//! stored it. The search treats every entry as a hint, so this costs a
+//! little work at most.
+
+use crate::types::{Move, Piece};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// How a stored value relates to the true value of the position
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Bound {
+    Exact,
+    /// The search failed high: the true value is at least this
+    Lower,
+    /// The search failed low: the true value is at most this
+    Upper,
+}
+
+/// A stored search result. Depths are kept within -128..=127.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TtEntry {
+    pub key: u64,
+    pub best_move: Option<Move>,
+    pub value: i32,
+    pub depth: i32,
+    pub bound: Bound,
+}
+
+const PIECES: [Piece; 6] = [Piece::Pawn, Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen, Piece::King];
+
+/// Set in every stored data word, so an empty slot never matches
+const OCCUPIED: u64 = 1 << 63;
+
+/// Data word: value in bits 0-31, depth in 32-39, bound in 40-41 and the
+/// move in 42-60 (a presence bit, from, to, promotion and drop piece)
+fn pack(entry: &TtEntry) -> u64 {
+    let bound = match entry.bound {
+        Bound::Exact => 0,
+        Bound::Lower => 1,
+        Bound::Upper => 2,
+    };
+    let piece = |piece: Option<Piece>| piece.map_or(0, |p| p.index() as u64 + 1);
+    let mv = entry.best_move.as_ref().map_or(0, |mv| {
+        1 | (mv.from as u64) << 1 | (mv.to as u64) << 7 | piece(mv.promotion) << 13 | piece(mv.drop) << 16
+    });
+    let depth = entry.depth.clamp(i8::MIN as i32, i8::MAX as i32) as i8 as u8;
+    OCCUPIED | entry.value as u32 as u64 | (depth as u64) << 32 | bound << 40 | mv << 42
+}
+
+fn unpack(key: u64, data: u64) -> TtEntry {
+    let bound = match (data >> 40) & 3 {
+        0 => Bound::Exact,
+        1 => Bound::Lower,
+        _ => Bound::Upper,
+    };
+    let mv = (data >> 42) & 0x7ffff;
+    let piece = |bits: u64| (bits & 7).checked_sub(1).map(|i| PIECES[i as usize]);
+    let best_move = (mv & 1 == 1).then(|| Move {
+        from: (mv >> 1 & 63) as u8,
+        to: (mv >> 7 & 63) as u8,
+        promotion: piece(mv >> 13),
+        drop: piece(mv >> 16),
+    });
+    TtEntry { key, best_move, value: data as u32 as i32, depth: (data >> 32) as u8 as i8 as i32, bound }
+}
+
+/// Fixed-size table of search results keyed by Zobrist hash. A slot keeps
+/// the deeper of its old and new entry, unless the old one is for another
+/// position.
+pub struct TranspositionTable {
+    /// (key ^ data, data) per slot
+    slots: Vec<[AtomicU64; 2]>,
+}
+
+impl TranspositionTable {
+    /// Table of about `hash_mb` megabytes (16 bytes a slot), with at least
+    /// one slot
+    pub fn new(hash_mb: usize) -> Self {
+        let len = (hash_mb * 1024 * 1024 / 16).max(1);
+        TranspositionTable { slots: (0..len).map(|_| [AtomicU64::new(0), AtomicU64::new(0)]).collect() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    pub fn probe(&self, key: u64) -> Option<TtEntry> {
+        let [check, data] = self.slot(key);
+        let data = data.load(Ordering::Relaxed);
+        let check = check.load(Ordering::Relaxed);
+        (data & OCCUPIED != 0 && check ^ data == key).then(|| unpack(key, data))
+    }
+
+    pub fn store(&self, entry: TtEntry) {
+        if let Some(old) = self.probe(entry.key) {
+            if old.depth > entry.depth && entry.bound != Bound::Exact {
+                return;
+            }
+        }
+        let [check, data] = self.slot(entry.key);
+        let packed = pack(&entry);
+        check.store(entry.key ^ packed, Ordering::Relaxed);
+        data.store(packed, Ordering::Relaxed);
+    }
+
+    /// Forget every entry, e.g. before a new game
+    pub fn clear(&self) {
+        for slot in &self.slots {
+            slot[0].store(0, Ordering::Relaxed);
+            slot[1].store(0, Ordering::Relaxed);
+        }
+    }
+
+    fn slot(&self, key: u64) -> &[AtomicU64; 2] {
+        &self.slots[(key % self.slots.len() as u64) as usize]
+    }
+}
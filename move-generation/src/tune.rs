@@ -0,0 +1,297 @@
+//! Texel tuning of material, piece-square and king-safety weights against
+//! game results
+
+use crate::eval::{king_safety, KING_SAFETY_FEATURES};
+use crate::pgn::{ChessError, PgnGameReader};
+use crate::phase::{phase_value, PHASE_MAX};
+use crate::rules::is_in_check;
+use crate::types::{Board, Color};
+use rayon::prelude::*;
+use std::fmt::Write;
+use std::io::Read;
+
+/// Weights per game phase: a material value and 64 square bonuses per piece
+const PHASE_PARAMS: usize = 6 * 65;
+
+/// Index of the first king-safety weight, after the middlegame and endgame
+/// tables
+const KING_SAFETY_START: usize = 2 * PHASE_PARAMS;
+
+/// Number of tunable weights: middlegame then endgame tables, then the
+/// king-safety weights
+pub const PARAMS: usize = KING_SAFETY_START + KING_SAFETY_FEATURES;
+
+/// Material and piece-square tables of a tapered evaluation, in centipawns.
+/// The outer index is the phase (0 = middlegame, 1 = endgame), the next one
+/// `Piece::index`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EvalParams {
+    pub material: [[i32; 6]; 2],
+    /// Bonus by square from White's side (a1 = 0); Black's squares are mirrored
+    pub pst: [[[i32; 64]; 6]; 2],
+    /// Middlegame weight of each `eval::KingSafety` feature, in feature order
+    pub king_safety: [i32; KING_SAFETY_FEATURES],
+}
+
+impl Default for EvalParams {
+    /// Conventional piece values, flat tables, and king-safety weights that
+    /// reward a pawn shield and punish open files and attackers near the king
+    fn default() -> Self {
+        EvalParams {
+            material: [[100, 320, 330, 500, 900, 0], [100, 300, 320, 520, 950, 0]],
+            pst: [[[0; 64]; 6]; 2],
+            king_safety: [8, -10, -12, -6, -4, -3, -2],
+        }
+    }
+}
+
+impl EvalParams {
+    /// Evaluation from White's point of view, blending the middlegame and
+    /// endgame scores by `phase_value`
+    pub fn evaluate(&self, board: &Board) -> i32 {
+        let phase = phase_value(board) as i32;
+        let mut score = [0; 2];
+        for (sq, &(piece, color)) in board.squares().iter().enumerate().filter_map(|(sq, p)| Some((sq, p.as_ref()?))) {
+            let (sign, sq) = if color == Color::White { (1, sq) } else { (-1, sq ^ 56) };
+            for (phase, score) in score.iter_mut().enumerate() {
+                *score += sign * (self.material[phase][piece.index()] + self.pst[phase][piece.index()][sq]);
+            }
+        }
+        if self.king_safety.iter().any(|&weight| weight != 0) {
+            for (color, sign) in [(Color::White, 1), (Color::Black, -1)] {
+                let features = king_safety(board, color).features();
+                score[0] += sign * self.king_safety.iter().zip(features).map(|(&weight, value)| weight * value as i32).sum::<i32>();
+            }
+        }
+        (score[0] * (PHASE_MAX as i32 - phase) + score[1] * phase) / PHASE_MAX as i32
+    }
+
+    /// Weight `i` of the `PARAMS` tunable ones
+    pub fn param(&self, i: usize) -> i32 {
+        if i >= KING_SAFETY_START {
+            return self.king_safety[i - KING_SAFETY_START];
+        }
+        let (phase, piece, slot) = (i / PHASE_PARAMS, i % PHASE_PARAMS / 65, i % 65);
+        if slot == 0 { self.material[phase][piece] } else { self.pst[phase][piece][slot - 1] }
+    }
+
+    fn param_mut(&mut self, i: usize) -> &mut i32 {
+        if i >= KING_SAFETY_START {
+            return &mut self.king_safety[i - KING_SAFETY_START];
+        }
+        let (phase, piece, slot) = (i / PHASE_PARAMS, i % PHASE_PARAMS / 65, i % 65);
+        if slot == 0 { &mut self.material[phase][piece] } else { &mut self.pst[phase][piece][slot - 1] }
+    }
+
+    /// The tables as Rust constants, ready to paste into an evaluation
+    pub fn to_rust_source(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "/// Piece values by phase (middlegame, endgame) and piece").unwrap();
+        writeln!(out, "pub const MATERIAL: [[i32; 6]; 2] = {:?};", self.material).unwrap();
+        writeln!(out, "\n/// Square bonuses by phase and piece, a1 first, from White's side").unwrap();
+        writeln!(out, "pub const PST: [[[i32; 64]; 6]; 2] = [").unwrap();
+        for tables in &self.pst {
+            writeln!(out, "    [").unwrap();
+            for table in tables {
+                writeln!(out, "        [").unwrap();
+                for rank in table.chunks(8) {
+                    let row: Vec<String> = rank.iter().map(|v| format!("{:4}", v)).collect();
+                    writeln!(out, "           {},", row.join(",")).unwrap();
+                }
+                writeln!(out, "        ],").unwrap();
+            }
+            writeln!(out, "    ],").unwrap();
+        }
+        writeln!(out, "];").unwrap();
+        writeln!(out, "\n/// Middlegame weights of the king-safety features").unwrap();
+        writeln!(out, "pub const KING_SAFETY: [i32; {}] = {:?};", KING_SAFETY_FEATURES, self.king_safety).unwrap();
+        out
+    }
+}
+
+/// A position reduced to the weights its evaluation uses, with the result of
+/// its game
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sample {
+    /// Middlegame weight index and net count (White minus Black); the
+    /// endgame weight is `PHASE_PARAMS` further on
+    features: Vec<(u16, i8)>,
+    /// King-safety features, White's minus Black's
+    king_safety: [i32; KING_SAFETY_FEATURES],
+    phase: i32,
+    /// 1 for a White win, 0.5 for a draw, 0 for a Black win
+    result: f64,
+}
+
+impl Sample {
+    pub fn new(board: &Board, result: f64) -> Sample {
+        let mut features: Vec<(u16, i8)> = Vec::with_capacity(64);
+        for (sq, &(piece, color)) in board.squares().iter().enumerate().filter_map(|(sq, p)| Some((sq, p.as_ref()?))) {
+            let (sign, sq) = if color == Color::White { (1, sq) } else { (-1, sq ^ 56) };
+            for index in [piece.index() * 65, piece.index() * 65 + 1 + sq] {
+                match features.iter_mut().find(|(i, _)| *i as usize == index) {
+                    Some((_, count)) => *count += sign,
+                    None => features.push((index as u16, sign)),
+                }
+            }
+        }
+        features.retain(|&(_, count)| count != 0);
+        let (white, black) = (king_safety(board, Color::White).features(), king_safety(board, Color::Black).features());
+        let king_safety = std::array::from_fn(|i| white[i] as i32 - black[i] as i32);
+        Sample { features, king_safety, phase: phase_value(board) as i32, result }
+    }
+
+    fn evaluate(&self, params: &[i32]) -> f64 {
+        let (mut mg, eg) = self.features.iter().fold((0, 0), |(mg, eg), &(i, count)| {
+            let count = count as i32;
+            (mg + count * params[i as usize], eg + count * params[i as usize + PHASE_PARAMS])
+        });
+        mg += self.king_safety.iter().zip(&params[KING_SAFETY_START..]).map(|(&value, &weight)| value * weight).sum::<i32>();
+        (mg * (PHASE_MAX as i32 - self.phase) + eg * self.phase) as f64 / PHASE_MAX as f64
+    }
+}
+
+/// Collect tuning samples from games with a decisive or drawn result: every
+/// position before a move, except those with the side to move in check
+pub fn samples_from_games<R: Read>(games: PgnGameReader<R>) -> Result<Vec<Sample>, ChessError> {
+    let mut samples = Vec::new();
+    for game in games {
+        let game = game?;
+        let result = match game.result.as_deref() {
+            Some("1-0") => 1.0,
+            Some("0-1") => 0.0,
+            Some("1/2-1/2") => 0.5,
+            _ => continue,
+        };
+        for (board, _) in game.replay()? {
+            if !is_in_check(&board, board.side_to_move) {
+                samples.push(Sample::new(&board, result));
+            }
+        }
+    }
+    Ok(samples)
+}
+
+/// Settings of the local search in `Tuner::tune`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TuneConfig {
+    /// Passes over all weights before giving up on convergence
+    pub max_passes: usize,
+    /// Amount a weight is moved by in each trial
+    pub step: i32,
+    /// Keep the material values as given and tune only the tables
+    pub fix_material: bool,
+}
+
+impl Default for TuneConfig {
+    fn default() -> Self {
+        TuneConfig { max_passes: 100, step: 1, fix_material: false }
+    }
+}
+
+/// Fits evaluation weights to game results by minimising the mean squared
+/// error between each result and the win probability
+/// `1 / (1 + 10^(-k * eval / 400))` predicted from the evaluation
+pub struct Tuner {
+    samples: Vec<Sample>,
+    /// Scaling of evaluations to win probabilities
+    pub k: f64,
+}
+
+impl Tuner {
+    pub fn new(samples: Vec<Sample>) -> Tuner {
+        Tuner { samples, k: 1.0 }
+    }
+
+    pub fn samples(&self) -> &[Sample] {
+        &self.samples
+    }
+
+    /// Mean squared error of the predictions made with `params`
+    pub fn error(&self, params: &EvalParams) -> f64 {
+        let flat: Vec<i32> = (0..PARAMS).map(|i| params.param(i)).collect();
+        self.flat_error(&flat, self.k)
+    }
+
+    fn flat_error(&self, params: &[i32], k: f64) -> f64 {
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+        let total: f64 = self.samples.par_iter()
+            .map(|sample| {
+                let predicted = 1.0 / (1.0 + 10f64.powf(-k * sample.evaluate(params) / 400.0));
+                (sample.result - predicted).powi(2)
+            })
+            .sum();
+        total / self.samples.len() as f64
+    }
+
+    /// Set `k` to the scaling that best fits `params` as they are, so that
+    /// tuning changes the weights rather than their scale
+    pub fn fit_k(&mut self, params: &EvalParams) -> f64 {
+        let flat: Vec<i32> = (0..PARAMS).map(|i| params.param(i)).collect();
+        let (mut low, mut high) = (0.0, 10.0);
+        for _ in 0..100 {
+            let (a, b) = (low + (high - low) / 3.0, high - (high - low) / 3.0);
+            if self.flat_error(&flat, a) < self.flat_error(&flat, b) {
+                high = b;
+            } else {
+                low = a;
+            }
+        }
+        self.k = (low + high) / 2.0;
+        self.k
+    }
+
+    /// Texel local search: move each weight up or down by `step` while that
+    /// lowers the error, pass after pass until no weight improves or
+    /// `max_passes` is reached. Weights that no sample uses, and the king's
+    /// material value, are left alone; king-safety weights are tuned with
+    /// the tables. `on_pass` receives the pass number and
+    /// the error after it.
+    pub fn tune(&self, params: &EvalParams, config: &TuneConfig, mut on_pass: impl FnMut(usize, f64)) -> EvalParams {
+        let mut used = vec![false; PARAMS];
+        for &(i, _) in self.samples.iter().flat_map(|sample| &sample.features) {
+            used[i as usize] = true;
+            used[i as usize + PHASE_PARAMS] = true;
+        }
+        for sample in &self.samples {
+            for (feature, &value) in sample.king_safety.iter().enumerate() {
+                used[KING_SAFETY_START + feature] |= value != 0;
+            }
+        }
+        let king_material = [5 * 65, PHASE_PARAMS + 5 * 65];
+        let is_material = |i: usize| i < KING_SAFETY_START && i.is_multiple_of(65);
+        let tunable: Vec<usize> = (0..PARAMS)
+            .filter(|&i| used[i] && !king_material.contains(&i) && !(config.fix_material && is_material(i)))
+            .collect();
+
+        let mut flat: Vec<i32> = (0..PARAMS).map(|i| params.param(i)).collect();
+        let mut best = self.flat_error(&flat, self.k);
+        for pass in 1..=config.max_passes {
+            let mut improved = false;
+            for &i in &tunable {
+                for delta in [config.step, -config.step] {
+                    flat[i] += delta;
+                    let error = self.flat_error(&flat, self.k);
+                    if error < best {
+                        best = error;
+                        improved = true;
+                        break;
+                    }
+                    flat[i] -= delta;
+                }
+            }
+            on_pass(pass, best);
+            if !improved {
+                break;
+            }
+        }
+
+        let mut tuned = params.clone();
+        for (i, &value) in flat.iter().enumerate() {
+            *tuned.param_mut(i) = value;
+        }
+        tuned
+    }
+}
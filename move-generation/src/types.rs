@@ -1,12 +1,39 @@
 //! Types for fast chess move generation
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+use crate::move_gen::Bitboard;
+use crate::notation::to_uci;
+pub use crate::notation::{name_to_square, square_to_name};
+use crate::pgn::Position;
+use crate::rules::{has_legal_en_passant, is_in_check};
+use crate::zobrist;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Color {
     White,
     Black,
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+impl Color {
+    /// The other color
+    pub fn opposite(self) -> Color {
+        match self {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        }
+    }
+
+    /// Index for per-color tables (White = 0, Black = 1)
+    pub fn index(self) -> usize {
+        match self {
+            Color::White => 0,
+            Color::Black => 1,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Piece {
     Pawn,
     Knight,
@@ -16,20 +43,737 @@ pub enum Piece {
     King,
 }
 
+impl Piece {
+    /// Index for per-piece tables (Pawn = 0 .. King = 5)
+    pub fn index(self) -> usize {
+        match self {
+            Piece::Pawn => 0,
+            Piece::Knight => 1,
+            Piece::Bishop => 2,
+            Piece::Rook => 3,
+            Piece::Queen => 4,
+            Piece::King => 5,
+        }
+    }
+}
+
+/// Rule set the board is played under
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Variant {
+    #[default]
+    Standard,
+    /// Captured pieces go to the capturer's pocket and can be dropped back
+    Crazyhouse,
+    /// Giving check for the third time wins
+    ThreeCheck,
+    /// Bringing the king to d4, e4, d5 or e5 wins
+    KingOfTheHill,
+    /// White has 36 pawns and no king, and wins by mating; Black wins by
+    /// capturing every White piece
+    Horde,
+    /// Checks are not allowed; the first king to reach the eighth rank wins
+    RacingKings,
+}
+
+impl Variant {
+    /// FEN of the variant's starting position
+    pub fn starting_fen(&self) -> &'static str {
+        match self {
+            Variant::Standard | Variant::KingOfTheHill => {
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+            }
+            Variant::Crazyhouse => "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1",
+            Variant::ThreeCheck => "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 3+3 0 1",
+            Variant::Horde => "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1",
+            Variant::RacingKings => "8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1",
+        }
+    }
+}
+
+/// Board square index: 0 is a1, 7 is h1, 63 is h8
+pub type Square = u8;
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Move {
     pub from: u8, // 0..63
     pub to: u8,   // 0..63
     pub promotion: Option<Piece>,
+    pub drop: Option<Piece>, // Crazyhouse drop of a pocketed piece; `from` equals `to`
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+impl Move {
+    /// Destinations of the king and rook when this move is castling, which
+    /// is written as the king taking its own rook: the king lands on the g
+    /// or c file and the rook beside it, in standard chess and Chess960 alike
+    pub fn castling_squares(&self) -> (u8, u8) {
+        let rank = self.from - self.from % 8;
+        if self.to > self.from {
+            (rank + 6, rank + 5)
+        } else {
+            (rank + 2, rank + 3)
+        }
+    }
+}
+
+/// Castling rights: for each color and side of the king, the file of the
+/// rook the king may castle with. Keeping the file rather than just the
+/// side tells apart the rooks of Chess960 positions with two on one side.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CastlingRights {
+    // Indexed by color, then 0 for the kingside and 1 for the queenside
+    rooks: [[Option<u8>; 2]; 2],
+}
+
+impl CastlingRights {
+    /// No castling rights
+    pub const NONE: CastlingRights = CastlingRights { rooks: [[None; 2]; 2] };
+
+    /// All four rights of the standard start, with the rooks on the h and a
+    /// files
+    pub fn standard() -> CastlingRights {
+        CastlingRights { rooks: [[Some(7), Some(0)]; 2] }
+    }
+
+    /// File of the rook `color` may castle with on one side
+    pub fn rook_file(&self, color: Color, kingside: bool) -> Option<u8> {
+        self.rooks[color.index()][usize::from(!kingside)]
+    }
+
+    pub fn has(&self, color: Color, kingside: bool) -> bool {
+        self.rook_file(color, kingside).is_some()
+    }
+
+    /// Grant the right to castle with the rook on `file`
+    pub fn set(&mut self, color: Color, kingside: bool, file: u8) {
+        self.rooks[color.index()][usize::from(!kingside)] = Some(file);
+    }
+
+    pub fn remove(&mut self, color: Color, kingside: bool) {
+        self.rooks[color.index()][usize::from(!kingside)] = None;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == CastlingRights::NONE
+    }
+
+    /// Rights held as (color, kingside, rook file), in KQkq order
+    pub fn iter(&self) -> impl Iterator<Item = (Color, bool, u8)> + '_ {
+        [(Color::White, true), (Color::White, false), (Color::Black, true), (Color::Black, false)]
+            .into_iter()
+            .filter_map(|(color, kingside)| self.rook_file(color, kingside).map(|file| (color, kingside, file)))
+    }
+}
+
+/// KQkq letters for rooks on the h and a files and Shredder-FEN file
+/// letters for any other, or "-" without rights
+impl fmt::Display for CastlingRights {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "-");
+        }
+        for (color, kingside, file) in self.iter() {
+            write!(f, "{}", castling_letter(color, kingside, file, u8::MAX))?;
+        }
+        Ok(())
+    }
+}
+
+/// Letter of one castling right, as X-FEN writes it: K or Q when no other
+/// rook of the color stands further out on that side of its back rank, else
+/// the rook's file; lowercase for Black. `rook_files` has a bit per file
+/// holding one of the color's rooks there; without a board, `u8::MAX`
+/// keeps K and Q for the h and a files alone.
+pub(crate) fn castling_letter(color: Color, kingside: bool, file: u8, rook_files: u8) -> char {
+    let outside = if kingside { !((2u16 << file) - 1) as u8 } else { (1u8 << file) - 1 };
+    let letter = match (rook_files & outside == 0, kingside) {
+        (true, true) => 'K',
+        (true, false) => 'Q',
+        (false, _) => (b'A' + file) as char,
+    };
+    if color == Color::White { letter } else { letter.to_ascii_lowercase() }
+}
+
+#[derive(Clone)]
 pub struct Board {
-    // 0..63 squares, None if empty, Some((Piece, Color)) if occupied
-    pub squares: [Option<(Piece, Color)>; 64],
+    // 0..63 squares, None if empty, Some((Piece, Color)) if occupied; read
+    // with `squares`, written with `put`/`remove` so the bitboards follow
+    squares: [Option<(Piece, Color)>; 64],
     pub side_to_move: Color,
-    pub castling_rights: String, // e.g. "KQkq"
+    pub castling_rights: CastlingRights,
     pub en_passant: Option<u8>,  // Square index or None
     pub halfmove_clock: u32,
     pub fullmove_number: u32,
-}
\ No newline at end of file
+    pub variant: Variant,
+    pub pockets: [[u8; 5]; 2], // Crazyhouse pocket counts per color, Pawn..Queen
+    pub promoted: u64,         // Crazyhouse: squares holding promoted pieces
+    pub checks: [u8; 2],       // Three-check: checks given by each color
+    /// Zobrist hash, updated incrementally by `make_move`/`unmake_move`; call
+    /// `refresh_hash` after editing the other fields directly
+    pub hash: u64,
+    /// Squares of each color's pieces, indexed by `Color::index`; kept in
+    /// step with `squares` by `make_move`/`unmake_move` and `put`/`remove`,
+    /// the only ways to change a square
+    occupancy: [u64; 2],
+    /// Squares of each piece type, both colors together, indexed by
+    /// `Piece::index`; kept in step with `squares` like `occupancy`
+    piece_occupancy: [u64; 6],
+}
+
+/// State needed to take back a move made with `Board::make_move`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Undo {
+    /// Piece taken by the move; for castling, the player's own rook
+    pub captured: Option<(Piece, Color)>,
+    pub castling_rights: CastlingRights,
+    pub en_passant: Option<u8>,
+    pub halfmove_clock: u32,
+    pub pockets: [[u8; 5]; 2],
+    pub promoted: u64,
+    pub checks: [u8; 2],
+    pub hash: u64,
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Board::empty()
+    }
+}
+
+impl Board {
+    /// A standard board with no pieces, White to move, no castling rights
+    /// and move 1; call `refresh_hash` after placing pieces with `put`
+    pub fn empty() -> Board {
+        Board {
+            squares: [None; 64],
+            side_to_move: Color::White,
+            castling_rights: CastlingRights::NONE,
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            variant: Variant::Standard,
+            pockets: [[0; 5]; 2],
+            promoted: 0,
+            checks: [0; 2],
+            hash: 0,
+            occupancy: [0; 2],
+            piece_occupancy: [0; 6],
+        }
+    }
+
+    /// Recompute `hash` from scratch
+    pub fn refresh_hash(&mut self) {
+        self.hash = zobrist::hash(self);
+    }
+
+    /// A board like `empty` with the given pieces on it, bitboards and
+    /// hash included
+    pub fn from_squares(squares: [Option<(Piece, Color)>; 64]) -> Board {
+        let mut board = Board::empty();
+        for (sq, square) in squares.into_iter().enumerate() {
+            if let Some((piece, color)) = square {
+                board.put(sq as Square, piece, color);
+            }
+        }
+        board.refresh_hash();
+        board
+    }
+
+    /// Bring the cached state in line after fields were edited directly.
+    /// The occupancy bitboards already follow every square change, so this
+    /// recomputes `hash`.
+    pub fn refresh(&mut self) {
+        self.refresh_hash();
+    }
+
+    /// What stands on each square, a1 = 0 to h8 = 63
+    pub fn squares(&self) -> &[Option<(Piece, Color)>; 64] {
+        &self.squares
+    }
+
+    fn color_bits(&self, color: Color) -> u64 {
+        self.occupancy[color.index()]
+    }
+
+    /// Squares holding `piece` of either color
+    pub(crate) fn piece_bits(&self, piece: Piece) -> u64 {
+        self.piece_occupancy[piece.index()]
+    }
+
+    /// Squares holding any piece
+    pub fn occupied(&self) -> Bitboard {
+        Bitboard::from(self.color_bits(Color::White) | self.color_bits(Color::Black))
+    }
+
+    /// Squares holding a piece of `color`
+    pub fn occupied_by(&self, color: Color) -> Bitboard {
+        Bitboard::from(self.color_bits(color))
+    }
+
+    /// Bring the occupancy bits of the given squares in line with `squares`
+    fn sync_occupancy(&mut self, squares: &[u8]) {
+        for &sq in squares {
+            let bit = 1u64 << sq;
+            self.occupancy[0] &= !bit;
+            self.occupancy[1] &= !bit;
+            for bb in &mut self.piece_occupancy {
+                *bb &= !bit;
+            }
+            if let Some((piece, color)) = self.squares[sq as usize] {
+                self.occupancy[color.index()] |= bit;
+                self.piece_occupancy[piece.index()] |= bit;
+            }
+        }
+    }
+
+    /// Place a piece of `color` on `sq`, replacing whatever stood there and
+    /// keeping `occupancy` in step; the hash is left alone, call
+    /// `refresh_hash` once done
+    pub fn put(&mut self, sq: Square, piece: Piece, color: Color) {
+        self.set_square(sq, Some((piece, color)));
+    }
+
+    /// Empty `sq`, returning what stood there; like `put`, the hash is left
+    /// alone
+    pub fn remove(&mut self, sq: Square) -> Option<(Piece, Color)> {
+        let square = self.squares[sq as usize];
+        self.set_square(sq, None);
+        square
+    }
+
+    /// Empty every square; like `put`, the hash is left alone
+    pub fn clear(&mut self) {
+        self.squares = [None; 64];
+        self.occupancy = [0; 2];
+        self.piece_occupancy = [0; 6];
+    }
+
+    fn set_square(&mut self, sq: Square, square: Option<(Piece, Color)>) {
+        self.squares[sq as usize] = square;
+        self.sync_occupancy(&[sq]);
+    }
+
+    /// Squares holding the given piece of `color`
+    pub fn pieces(&self, piece: Piece, color: Color) -> Bitboard {
+        Bitboard::from(self.piece_bits(piece) & self.color_bits(color))
+    }
+
+    /// Square of the king of `color`, the lowest one should there be several
+    pub fn king_square(&self, color: Color) -> Option<u8> {
+        let kings = self.piece_bits(Piece::King) & self.color_bits(color);
+        (kings != 0).then(|| kings.trailing_zeros() as u8)
+    }
+
+    /// The pieces of `color` and their squares, in square order, found from
+    /// the occupancy bits rather than by scanning the board
+    pub fn piece_list(&self, color: Color) -> impl Iterator<Item = (u8, Piece)> + '_ {
+        let mut own = self.color_bits(color);
+        std::iter::from_fn(move || {
+            while own != 0 {
+                let sq = own.trailing_zeros() as u8;
+                own &= own - 1;
+                if let Some((piece, _)) = self.squares[sq as usize] {
+                    return Some((sq, piece));
+                }
+            }
+            None
+        })
+    }
+
+    /// Play a move, updating castling rights, en passant square and clocks.
+    ///
+    /// Handles castling (the king moving onto its own rook), en passant captures,
+    /// promotions, Crazyhouse drops and pockets, and Three-check counters. The move is not checked
+    /// for legality.
+    pub fn make_move(&mut self, mv: &Move) -> Undo {
+        let undo = Undo { captured: self.squares[mv.to as usize], ..self.undo_state() };
+
+        if let Some(piece) = mv.drop {
+            let color = self.side_to_move;
+            let count = &mut self.pockets[color.index()][piece.index()];
+            *count = count.saturating_sub(1);
+            self.squares[mv.to as usize] = Some((piece, color));
+            self.en_passant = None;
+            if piece == Piece::Pawn {
+                self.halfmove_clock = 0;
+            } else {
+                self.halfmove_clock += 1;
+            }
+            if color == Color::Black {
+                self.fullmove_number += 1;
+            }
+            self.side_to_move = color.opposite();
+            self.sync_occupancy(&[mv.to]);
+            self.update_hash(&undo, &[(mv.to, None)]);
+            return undo;
+        }
+
+        let (piece, color) = match self.squares[mv.from as usize] {
+            Some(moving) => moving,
+            None => return undo,
+        };
+        let castling = self.is_castling(mv);
+        let rights = self.rights_after(mv, piece, color);
+        let mut captured = if castling { None } else { undo.captured };
+        // Squares the move changes, with their previous contents
+        let mut touched = [(mv.from, Some((piece, color))), (mv.to, undo.captured), (0, None), (0, None)];
+        let mut touched_len = 2;
+
+        if castling {
+            let (king_to, rook_to) = mv.castling_squares();
+            for sq in [king_to, rook_to] {
+                if !touched[..touched_len].iter().any(|&(seen, _)| seen == sq) {
+                    touched[touched_len] = (sq, self.squares[sq as usize]);
+                    touched_len += 1;
+                }
+            }
+            let rook = self.squares[mv.to as usize].take();
+            self.squares[mv.from as usize] = None;
+            self.squares[rook_to as usize] = rook;
+            self.squares[king_to as usize] = Some((piece, color));
+        } else {
+            // En passant: the captured pawn is beside the target square
+            if piece == Piece::Pawn && Some(mv.to) == self.en_passant && mv.from % 8 != mv.to % 8 && captured.is_none() {
+                let victim_sq = if color == Color::White { mv.to - 8 } else { mv.to + 8 };
+                captured = self.squares[victim_sq as usize].take();
+                touched[touched_len] = (victim_sq, captured);
+                touched_len += 1;
+            }
+            self.squares[mv.from as usize] = None;
+            self.squares[mv.to as usize] = Some((mv.promotion.unwrap_or(piece), color));
+        }
+
+        if self.variant == Variant::Crazyhouse {
+            // Captured pieces join the capturer's pocket, promoted ones as
+            // pawns; a king taken in an illegal position has no pocket
+            if let Some((captured_piece, _)) = captured.filter(|&(piece, _)| piece != Piece::King) {
+                let demoted = self.promoted & (1 << mv.to) != 0;
+                let pocket_piece = if demoted { Piece::Pawn } else { captured_piece };
+                let count = &mut self.pockets[color.index()][pocket_piece.index()];
+                *count = count.saturating_add(1);
+            }
+            let was_promoted = self.promoted & (1 << mv.from) != 0;
+            self.promoted &= !((1 << mv.from) | (1 << mv.to));
+            if was_promoted || mv.promotion.is_some() {
+                self.promoted |= 1 << mv.to;
+            }
+        }
+
+        self.castling_rights = rights;
+
+        // Horde pawns pushed two squares from the first rank give no en passant
+        self.en_passant = if piece == Piece::Pawn && mv.from.abs_diff(mv.to) == 16 && matches!(mv.from / 8, 1 | 6) {
+            Some((mv.from + mv.to) / 2)
+        } else {
+            None
+        };
+        if piece == Piece::Pawn || captured.is_some() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+        if color == Color::Black {
+            self.fullmove_number += 1;
+        }
+        self.side_to_move = color.opposite();
+
+        let touched = &touched[..touched_len];
+        for &(sq, _) in touched {
+            self.sync_occupancy(&[sq]);
+        }
+        if self.variant == Variant::ThreeCheck && is_in_check(self, self.side_to_move) {
+            self.checks[color.index()] += 1;
+        }
+
+        self.update_hash(&undo, touched);
+        if castling {
+            undo
+        } else {
+            Undo { captured, ..undo }
+        }
+    }
+
+    /// Whether a move is castling, i.e. a king moving onto its own rook
+    pub fn is_castling(&self, mv: &Move) -> bool {
+        match (self.squares[mv.from as usize], self.squares[mv.to as usize]) {
+            (Some((Piece::King, color)), Some((Piece::Rook, rook_color))) => mv.drop.is_none() && color == rook_color,
+            _ => false,
+        }
+    }
+
+    /// Rook a player may castle with on one side of the king: the one on
+    /// the file the right names, provided the king stands on the back rank
+    /// and the rook is on that side of it
+    pub fn castling_rook(&self, color: Color, kingside: bool) -> Option<u8> {
+        let file = self.castling_rights.rook_file(color, kingside)?;
+        let rank = if color == Color::White { 0 } else { 56 };
+        let king = (rank..rank + 8).find(|&sq| self.squares[sq as usize] == Some((Piece::King, color)))?;
+        let rook = rank + file;
+        let on_side = if kingside { rook > king } else { rook < king };
+        (on_side && self.squares[rook as usize] == Some((Piece::Rook, color))).then_some(rook)
+    }
+
+    /// Castling rights left after a move: moving the king loses both of its
+    /// rights, and moving or capturing a castling rook loses that side's
+    fn rights_after(&self, mv: &Move, piece: Piece, color: Color) -> CastlingRights {
+        let mut rights = self.castling_rights;
+        for (owner, kingside, file) in self.castling_rights.iter() {
+            let rook = if owner == Color::White { file } else { 56 + file };
+            let king_moves = piece == Piece::King && color == owner;
+            if king_moves || rook == mv.from || rook == mv.to {
+                rights.remove(owner, kingside);
+            }
+        }
+        rights
+    }
+
+    /// Pass the turn: the side to move flips and the en passant square is
+    /// cleared. Pieces, castling rights and pockets are unchanged; the clocks
+    /// advance as for any move.
+    pub fn make_null_move(&mut self) -> Undo {
+        let undo = self.undo_state();
+        self.en_passant = None;
+        self.halfmove_clock += 1;
+        if self.side_to_move == Color::Black {
+            self.fullmove_number += 1;
+        }
+        self.side_to_move = self.side_to_move.opposite();
+        self.update_hash(&undo, &[]);
+        undo
+    }
+
+    /// Take back a null move played with `make_null_move`
+    pub fn unmake_null_move(&mut self, undo: Undo) {
+        self.side_to_move = self.side_to_move.opposite();
+        if self.side_to_move == Color::Black {
+            self.fullmove_number -= 1;
+        }
+        self.en_passant = undo.en_passant;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.hash = undo.hash;
+    }
+
+    /// Irreversible state to restore on unmake, with nothing captured
+    fn undo_state(&self) -> Undo {
+        Undo {
+            captured: None,
+            castling_rights: self.castling_rights,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            pockets: self.pockets,
+            promoted: self.promoted,
+            checks: self.checks,
+            hash: self.hash,
+        }
+    }
+
+    /// Apply to `hash` the difference between the state saved in `undo` and
+    /// the current one, given the squares a move touched and what they held
+    fn update_hash(&mut self, undo: &Undo, touched: &[(u8, Option<(Piece, Color)>)]) {
+        let mut hash = self.hash ^ zobrist::side_key();
+        for &(sq, before) in touched {
+            let after = self.squares[sq as usize];
+            if before != after {
+                if let Some((piece, color)) = before {
+                    hash ^= zobrist::piece_key(piece, color, sq);
+                }
+                if let Some((piece, color)) = after {
+                    hash ^= zobrist::piece_key(piece, color, sq);
+                }
+            }
+        }
+        if undo.castling_rights != self.castling_rights {
+            for (color, kingside, _) in undo.castling_rights.iter().chain(self.castling_rights.iter()) {
+                hash ^= zobrist::castling_key(color, kingside);
+            }
+        }
+        if undo.en_passant != self.en_passant {
+            for ep in undo.en_passant.into_iter().chain(self.en_passant) {
+                hash ^= zobrist::en_passant_key(ep);
+            }
+        }
+        for color in [Color::White, Color::Black] {
+            let (c, before) = (color.index(), &undo.pockets[color.index()]);
+            for piece in [Piece::Pawn, Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen] {
+                let (old, new) = (before[piece.index()], self.pockets[c][piece.index()]);
+                if old != new {
+                    hash ^= zobrist::pocket_key(piece, color, old) ^ zobrist::pocket_key(piece, color, new);
+                }
+            }
+            if undo.checks[c] != self.checks[c] {
+                hash ^= zobrist::checks_key(color, undo.checks[c]) ^ zobrist::checks_key(color, self.checks[c]);
+            }
+        }
+        self.hash = hash;
+    }
+
+    /// Take back a move previously played with `make_move`
+    pub fn unmake_move(&mut self, mv: &Move, undo: Undo) {
+        self.hash = undo.hash;
+        self.pockets = undo.pockets;
+        self.promoted = undo.promoted;
+        self.checks = undo.checks;
+
+        if mv.drop.is_some() {
+            let color = self.side_to_move.opposite();
+            self.remove(mv.to);
+            self.en_passant = undo.en_passant;
+            self.halfmove_clock = undo.halfmove_clock;
+            if color == Color::Black {
+                self.fullmove_number -= 1;
+            }
+            self.side_to_move = color;
+            return;
+        }
+
+        let mover = self.side_to_move.opposite();
+        if undo.captured == Some((Piece::Rook, mover)) {
+            // Castling: only here is the "captured" piece the player's own
+            let (king_to, rook_to) = mv.castling_squares();
+            self.squares[king_to as usize] = None;
+            self.squares[rook_to as usize] = None;
+            self.squares[mv.from as usize] = Some((Piece::King, mover));
+            self.squares[mv.to as usize] = undo.captured;
+            self.sync_occupancy(&[king_to, rook_to, mv.from, mv.to]);
+        } else if !self.unmake_piece_move(mv, &undo) {
+            return;
+        }
+
+        self.castling_rights = undo.castling_rights;
+        self.en_passant = undo.en_passant;
+        self.halfmove_clock = undo.halfmove_clock;
+        if mover == Color::Black {
+            self.fullmove_number -= 1;
+        }
+        self.side_to_move = mover;
+    }
+
+    /// Put back the pieces of a move other than castling or a drop; false if
+    /// no piece stands on the target square
+    fn unmake_piece_move(&mut self, mv: &Move, undo: &Undo) -> bool {
+        let (piece, color) = match self.squares[mv.to as usize] {
+            Some(moved) => moved,
+            None => return false,
+        };
+        let original = if mv.promotion.is_some() { Piece::Pawn } else { piece };
+
+        self.squares[mv.from as usize] = Some((original, color));
+        self.squares[mv.to as usize] = None;
+
+        if original == Piece::Pawn && Some(mv.to) == undo.en_passant && mv.from % 8 != mv.to % 8 {
+            let victim_sq = if color == Color::White { mv.to - 8 } else { mv.to + 8 };
+            self.squares[victim_sq as usize] = undo.captured;
+            self.sync_occupancy(&[victim_sq]);
+        } else {
+            self.squares[mv.to as usize] = undo.captured;
+        }
+        self.sync_occupancy(&[mv.from, mv.to]);
+        true
+    }
+}
+
+/// UCI notation, e.g. "e2e4" or "e7e8q"
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&to_uci(self))
+    }
+}
+
+/// ASCII diagram from White's side followed by the FEN
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for rank in (0..8).rev() {
+            write!(f, "{}", rank + 1)?;
+            for file in 0..8 {
+                let c = match self.squares[rank * 8 + file] {
+                    Some((piece, color)) => {
+                        let c = ['p', 'n', 'b', 'r', 'q', 'k'][piece.index()];
+                        if color == Color::White { c.to_ascii_uppercase() } else { c }
+                    }
+                    None => '.',
+                };
+                write!(f, " {}", c)?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(f, "  a b c d e f g h")?;
+        writeln!(f)?;
+        write!(f, "FEN: {}", Position::from(self).to_fen())
+    }
+}
+
+/// What makes two positions the same for the repetition rule
+#[derive(PartialEq, Eq, Hash)]
+struct RepetitionKey<'a> {
+    squares: &'a [Option<(Piece, Color)>; 64],
+    side_to_move: Color,
+    castling_rights: CastlingRights,
+    en_passant: Option<u8>,
+    variant: Variant,
+    pockets: [[u8; 5]; 2],
+    promoted: u64,
+    checks: [u8; 2],
+}
+
+impl Board {
+    fn repetition_key(&self) -> RepetitionKey<'_> {
+        RepetitionKey {
+            squares: &self.squares,
+            side_to_move: self.side_to_move,
+            castling_rights: self.castling_rights,
+            en_passant: self.en_passant.filter(|_| has_legal_en_passant(self)),
+            variant: self.variant,
+            pockets: self.pockets,
+            promoted: self.promoted,
+            checks: self.checks,
+        }
+    }
+
+    /// Equality of every field, move clocks and the cached hash and
+    /// occupancy bitboards included
+    pub fn eq_strict(&self, other: &Board) -> bool {
+        self.squares == other.squares
+            && self.side_to_move == other.side_to_move
+            && self.castling_rights == other.castling_rights
+            && self.en_passant == other.en_passant
+            && self.halfmove_clock == other.halfmove_clock
+            && self.fullmove_number == other.fullmove_number
+            && self.variant == other.variant
+            && self.pockets == other.pockets
+            && self.promoted == other.promoted
+            && self.checks == other.checks
+            && self.hash == other.hash
+            && self.occupancy == other.occupancy
+            && self.piece_occupancy == other.piece_occupancy
+    }
+}
+
+/// Boards are equal when the repetition rule counts them as the same
+/// position: same pieces on the same squares, side to move, castling rights
+/// and en passant capture, plus pockets and check counts in the variants
+/// that have them. An en passant square no pawn can take on and the move
+/// clocks are ignored. `eq_strict` compares every field.
+impl PartialEq for Board {
+    fn eq(&self, other: &Board) -> bool {
+        self.repetition_key() == other.repetition_key()
+    }
+}
+
+impl Eq for Board {}
+
+/// Consistent with `PartialEq`, so boards that repeat share a hash entry
+impl Hash for Board {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.repetition_key().hash(state);
+    }
+}
+
+/// Compact form showing the FEN and variant instead of the square array
+impl fmt::Debug for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let fen = Position::from(self).to_fen();
+        match self.variant {
+            Variant::Standard => write!(f, "Board({:?})", fen),
+            variant => write!(f, "Board({:?}, {:?})", fen, variant),
+        }
+    }
+}
@@ -0,0 +1,346 @@
+//! UCI front-end for the alpha-beta search: the protocol loop, time
+//! management and the engine options a GUI can set with `setoption`
+
+use crate::notation::{line_to_standard_uci, parse_uci};
+use crate::pgn::{starting_board, ChessError, Position};
+use crate::search::{Engine, SearchHandle, SearchInfo, SearchLimits, SearchOptions};
+use crate::tune::EvalParams;
+use crate::types::{Board, Color, Variant};
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, Scope, ScopedJoinHandle};
+use std::time::{Duration, Instant};
+
+/// Type and default of an engine option, as announced after `uci`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OptionType {
+    Check { default: bool },
+    Spin { default: i64, min: i64, max: i64 },
+    String { default: &'static str },
+    Button,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UciOption {
+    pub name: &'static str,
+    pub kind: OptionType,
+}
+
+/// "option name Hash type spin default 16 min 1 max 1048576"
+impl fmt::Display for UciOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "option name {} type ", self.name)?;
+        match &self.kind {
+            OptionType::Check { default } => write!(f, "check default {}", default),
+            OptionType::Spin { default, min, max } => write!(f, "spin default {} min {} max {}", default, min, max),
+            OptionType::String { default } => write!(f, "string default {}", if default.is_empty() { "<empty>" } else { default }),
+            OptionType::Button => write!(f, "button"),
+        }
+    }
+}
+
+/// Every option the engine announces
+pub const OPTIONS: &[UciOption] = &[
+    UciOption { name: "Hash", kind: OptionType::Spin { default: 16, min: 1, max: 1 << 20 } },
+    UciOption { name: "Threads", kind: OptionType::Spin { default: 1, min: 1, max: 1024 } },
+    UciOption { name: "MultiPV", kind: OptionType::Spin { default: 1, min: 1, max: 256 } },
+    UciOption { name: "Move Overhead", kind: OptionType::Spin { default: 10, min: 0, max: 5000 } },
+    UciOption { name: "Ponder", kind: OptionType::Check { default: false } },
+    UciOption { name: "SyzygyPath", kind: OptionType::String { default: "" } },
+    UciOption { name: "Clear Hash", kind: OptionType::Button },
+    UciOption { name: "CheckExtensions", kind: OptionType::Check { default: true } },
+    UciOption { name: "SingularExtensions", kind: OptionType::Check { default: true } },
+];
+
+/// Current values of the `OPTIONS`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UciOptions {
+    /// Hash, Threads, MultiPV and the extension switches
+    pub search: SearchOptions,
+    /// Time kept back from every move for the GUI and the connection
+    pub move_overhead: Duration,
+    /// Whether the GUI may send `go ponder`; while off, `go ponder` is
+    /// searched as a plain `go`
+    pub ponder: bool,
+    /// Accepted for GUIs that always send it; tablebases are not probed
+    pub syzygy_path: Option<String>,
+}
+
+impl Default for UciOptions {
+    fn default() -> Self {
+        UciOptions {
+            search: SearchOptions::default(),
+            move_overhead: Duration::from_millis(10),
+            ponder: false,
+            syzygy_path: None,
+        }
+    }
+}
+
+impl UciOptions {
+    /// Apply `setoption name <name> [value <value>]`. Names are matched
+    /// without regard to case; values out of range or of the wrong type are
+    /// errors. Buttons have no value to keep, so "Clear Hash" is left to the
+    /// caller.
+    pub fn set(&mut self, name: &str, value: Option<&str>) -> Result<(), ChessError> {
+        let option = OPTIONS.iter().find(|option| option.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| ChessError::ParseError(format!("unknown option: {}", name)))?;
+        let missing = || ChessError::ParseError(format!("option {} needs a value", option.name));
+        match &option.kind {
+            OptionType::Spin { min, max, .. } => {
+                let value: i64 = value.ok_or_else(missing)?.parse()
+                    .map_err(|_| ChessError::ParseError(format!("option {} takes a number", option.name)))?;
+                if value < *min || value > *max {
+                    return Err(ChessError::ParseError(format!("option {} ranges from {} to {}", option.name, min, max)));
+                }
+                match option.name {
+                    "Hash" => self.search.hash_mb = value as usize,
+                    "Threads" => self.search.threads = value as usize,
+                    "MultiPV" => self.search.multipv = value as usize,
+                    _ => self.move_overhead = Duration::from_millis(value as u64),
+                }
+            }
+            OptionType::Check { .. } => {
+                let value = match value.ok_or_else(missing)? {
+                    v if v.eq_ignore_ascii_case("true") => true,
+                    v if v.eq_ignore_ascii_case("false") => false,
+                    _ => return Err(ChessError::ParseError(format!("option {} is true or false", option.name))),
+                };
+                match option.name {
+                    "Ponder" => self.ponder = value,
+                    "CheckExtensions" => self.search.check_extensions = value,
+                    _ => self.search.singular_extensions = value,
+                }
+            }
+            OptionType::String { .. } => {
+                self.syzygy_path = value.filter(|path| !path.is_empty() && *path != "<empty>").map(str::to_string);
+            }
+            OptionType::Button => {}
+        }
+        Ok(())
+    }
+}
+
+/// Time to spend on a move: an even share of `clock` over `moves_to_go`
+/// moves (30 when unknown) plus most of the increment, at most half the
+/// clock, less `overhead`
+pub fn move_time(clock: Duration, increment: Duration, moves_to_go: Option<u32>, overhead: Duration) -> Duration {
+    let share = clock / moves_to_go.unwrap_or(30).max(1) + increment * 3 / 4;
+    share.min(clock / 2).saturating_sub(overhead).max(Duration::from_millis(1))
+}
+
+/// A parsed `go` command
+struct Go {
+    limits: SearchLimits,
+    /// Time for the move once a ponder search becomes a real one
+    budget: Option<Duration>,
+    infinite: bool,
+    ponder: bool,
+}
+
+fn parse_go<'a>(mut words: impl Iterator<Item = &'a str>, side: Color, options: &UciOptions) -> Go {
+    let mut go = Go { limits: SearchLimits::default(), budget: None, infinite: false, ponder: false };
+    let (mut clock, mut increment, mut moves_to_go) = (None, Duration::ZERO, None);
+    let millis = |word: Option<&str>| word.and_then(|w| w.parse::<i64>().ok()).map(|ms| Duration::from_millis(ms.max(0) as u64));
+    while let Some(word) = words.next() {
+        match (word, side) {
+            ("depth", _) => go.limits.depth = words.next().and_then(|w| w.parse().ok()),
+            ("nodes", _) => go.limits.nodes = words.next().and_then(|w| w.parse().ok()),
+            ("movetime", _) => go.budget = millis(words.next()).map(|time| time.saturating_sub(options.move_overhead)),
+            ("movestogo", _) => moves_to_go = words.next().and_then(|w| w.parse().ok()),
+            ("wtime", Color::White) | ("btime", Color::Black) => clock = millis(words.next()),
+            ("winc", Color::White) | ("binc", Color::Black) => increment = millis(words.next()).unwrap_or_default(),
+            ("infinite", _) => go.infinite = true,
+            ("ponder", _) => go.ponder = options.ponder,
+            _ => {}
+        }
+    }
+    if let (None, Some(clock)) = (go.budget, clock) {
+        go.budget = Some(move_time(clock, increment, moves_to_go, options.move_overhead));
+    }
+    if !go.infinite && !go.ponder {
+        go.limits.movetime = go.budget;
+    }
+    go
+}
+
+/// Board after `position startpos|fen <fen> [moves ...]`
+fn parse_position<'a>(mut words: impl Iterator<Item = &'a str>) -> Result<Board, ChessError> {
+    let mut board = match words.next() {
+        Some("startpos") => starting_board(Variant::Standard),
+        Some("fen") => {
+            let fen: Vec<&str> = words.by_ref().take_while(|&word| word != "moves").collect();
+            Board::from(fen.join(" ").parse::<Position>()?)
+        }
+        _ => return Err(ChessError::ParseError("position needs startpos or fen".to_string())),
+    };
+    for uci in words.skip_while(|&word| word == "moves") {
+        let mv = parse_uci(&board, uci)?;
+        board.make_move(&mv);
+    }
+    Ok(board)
+}
+
+/// A search running on its own thread
+struct Running<'scope> {
+    handle: SearchHandle,
+    /// Set while the result must be held back, during `go infinite` and
+    /// `go ponder`
+    waiting: Arc<AtomicBool>,
+    budget: Option<Duration>,
+    thread: ScopedJoinHandle<'scope, ()>,
+}
+
+impl Running<'_> {
+    /// Stop the search and wait for its `bestmove`
+    fn finish(self) {
+        self.handle.stop();
+        let _ = self.thread.join();
+    }
+}
+
+fn send<W: Write>(output: &Mutex<W>, line: &str) -> io::Result<()> {
+    let mut output = output.lock().unwrap_or_else(|e| e.into_inner());
+    writeln!(output, "{}", line)?;
+    output.flush()
+}
+
+fn info_line(board: &Board, info: &SearchInfo) -> String {
+    // Moves are written the way GUIs expect castling, not as `to_uci`
+    let mut line = format!("info {}", SearchInfo { pv: Vec::new(), ..info.clone() });
+    for uci in line_to_standard_uci(board, &info.pv) {
+        line.push(' ');
+        line.push_str(&uci);
+    }
+    line
+}
+
+fn start_search<'scope, 'env: 'scope, W: Write + Send>(
+    scope: &'scope Scope<'scope, 'env>,
+    engine: &'env Mutex<Engine>,
+    output: &'env Mutex<W>,
+    board: Board,
+    go: Go,
+) -> Running<'scope> {
+    let handle = SearchHandle::new();
+    let waiting = Arc::new(AtomicBool::new(go.infinite || go.ponder));
+    let thread = {
+        let (handle, waiting) = (handle.clone(), waiting.clone());
+        scope.spawn(move || {
+            let engine = engine.lock().unwrap_or_else(|e| e.into_inner());
+            let result = engine.search(&board, &go.limits, &EvalParams::default(), &handle, |info| {
+                let _ = send(output, &info_line(&board, info));
+            });
+            while waiting.load(Ordering::Relaxed) && !handle.is_stopped() {
+                thread::sleep(Duration::from_millis(1));
+            }
+            let reply = match result {
+                Ok(result) => match line_to_standard_uci(&board, &result.pv).as_slice() {
+                    [] => "bestmove 0000".to_string(),
+                    [best] => format!("bestmove {}", best),
+                    [best, ponder, ..] => format!("bestmove {} ponder {}", best, ponder),
+                },
+                Err(e) => format!("info string {}\nbestmove 0000", e),
+            };
+            let _ = send(output, &reply);
+        })
+    };
+    Running { handle, waiting, budget: go.budget, thread }
+}
+
+/// Speak UCI on `input` and `output` until `quit` or the end of input.
+/// Searches run on a second thread, so `stop`, `ponderhit` and `isready`
+/// are answered while they do; `setoption`, `position` and `go` first stop
+/// a search still running.
+pub fn run<R: BufRead, W: Write + Send>(input: R, output: W) -> io::Result<()> {
+    let output = Mutex::new(output);
+    let engine = Mutex::new(Engine::new(SearchOptions::default()));
+    let mut options = UciOptions::default();
+    let mut board = starting_board(Variant::Standard);
+
+    thread::scope(|scope| {
+        let mut running: Option<Running> = None;
+        for line in input.lines() {
+            let line = line?;
+            let mut words = line.split_whitespace();
+            let command = words.next();
+            if matches!(command, Some("setoption" | "ucinewgame" | "position" | "go" | "stop" | "quit")) {
+                if let Some(search) = running.take() {
+                    search.finish();
+                }
+            }
+            match command {
+                Some("uci") => {
+                    send(&output, &format!("id name move_generation {}", env!("CARGO_PKG_VERSION")))?;
+                    send(&output, "id author the move_generation authors")?;
+                    for option in OPTIONS {
+                        send(&output, &option.to_string())?;
+                    }
+                    send(&output, "uciok")?;
+                }
+                Some("isready") => send(&output, "readyok")?,
+                Some("setoption") => {
+                    // setoption name <name words> [value <value words>]
+                    let rest: Vec<&str> = words.skip_while(|&word| word == "name").collect();
+                    let split = rest.iter().position(|&word| word == "value");
+                    let name = rest[..split.unwrap_or(rest.len())].join(" ");
+                    let value = split.map(|i| rest[i + 1..].join(" "));
+                    match options.set(&name, value.as_deref()) {
+                        Ok(()) if name.eq_ignore_ascii_case("Clear Hash") => engine.lock().unwrap_or_else(|e| e.into_inner()).clear(),
+                        Ok(()) => {
+                            engine.lock().unwrap_or_else(|e| e.into_inner()).set_options(options.search.clone());
+                            if name.eq_ignore_ascii_case("SyzygyPath") && options.syzygy_path.is_some() {
+                                send(&output, "info string tablebases are not supported; SyzygyPath is kept but not probed")?;
+                            }
+                        }
+                        Err(e) => send(&output, &format!("info string {}", e))?,
+                    }
+                }
+                Some("ucinewgame") => {
+                    engine.lock().unwrap_or_else(|e| e.into_inner()).clear();
+                    board = starting_board(Variant::Standard);
+                }
+                Some("position") => match parse_position(words) {
+                    Ok(position) => board = position,
+                    Err(e) => send(&output, &format!("info string {}", e))?,
+                },
+                Some("go") => {
+                    let words: Vec<&str> = words.collect();
+                    if !options.ponder && words.contains(&"ponder") {
+                        send(&output, "info string Ponder is off; searching this as a normal go")?;
+                    }
+                    let go = parse_go(words.into_iter(), board.side_to_move, &options);
+                    running = Some(start_search(scope, &engine, &output, board.clone(), go));
+                }
+                Some("ponderhit") => {
+                    if let Some(search) = &running {
+                        search.waiting.store(false, Ordering::Relaxed);
+                        // The move's time starts now
+                        if let Some(budget) = search.budget {
+                            let handle = search.handle.clone();
+                            let start = Instant::now();
+                            scope.spawn(move || {
+                                while start.elapsed() < budget && !handle.is_stopped() {
+                                    thread::sleep(Duration::from_millis(1));
+                                }
+                                handle.stop();
+                            });
+                        }
+                    }
+                }
+                Some("quit") => return Ok(()),
+                _ => {}
+            }
+        }
+        // At the end of input a search with limits may still finish
+        if let Some(search) = running.take() {
+            if search.waiting.load(Ordering::Relaxed) {
+                search.handle.stop();
+            }
+            let _ = search.thread.join();
+        }
+        Ok(())
+    })
+}
@@ -0,0 +1,199 @@
+//! Zobrist hashing of board positions
+
+use crate::types::{Board, Color, Piece};
+
+/// Random keys, generated at compile time from a fixed seed so hashes are
+/// stable across runs, processes and the Python bindings
+struct ZobristKeys {
+    pieces: [[[u64; 64]; 6]; 2],
+    castling: [u64; 4],
+    en_passant: [u64; 8],
+    black_to_move: u64,
+    /// Crazyhouse pocket keys, indexed by color, piece and count - 1
+    pockets: [[[u64; 16]; 5]; 2],
+    /// Three-check keys, indexed by color and checks given - 1
+    checks: [[u64; 3]; 2],
+}
+
+const KEYS: ZobristKeys = ZobristKeys::generate(0x9E37_79B9_7F4A_7C15);
+
+impl ZobristKeys {
+    const fn generate(seed: u64) -> Self {
+        let mut state = seed;
+        let mut keys = ZobristKeys {
+            pieces: [[[0; 64]; 6]; 2],
+            castling: [0; 4],
+            en_passant: [0; 8],
+            black_to_move: 0,
+            pockets: [[[0; 16]; 5]; 2],
+            checks: [[0; 3]; 2],
+        };
+
+        let mut color = 0;
+        while color < 2 {
+            let mut piece = 0;
+            while piece < 6 {
+                let mut sq = 0;
+                while sq < 64 {
+                    state = splitmix64(state);
+                    keys.pieces[color][piece][sq] = state;
+                    sq += 1;
+                }
+                piece += 1;
+            }
+            color += 1;
+        }
+
+        let mut i = 0;
+        while i < 4 {
+            state = splitmix64(state);
+            keys.castling[i] = state;
+            i += 1;
+        }
+
+        let mut file = 0;
+        while file < 8 {
+            state = splitmix64(state);
+            keys.en_passant[file] = state;
+            file += 1;
+        }
+
+        state = splitmix64(state);
+        keys.black_to_move = state;
+
+        let mut color = 0;
+        while color < 2 {
+            let mut piece = 0;
+            while piece < 5 {
+                let mut count = 0;
+                while count < 16 {
+                    state = splitmix64(state);
+                    keys.pockets[color][piece][count] = state;
+                    count += 1;
+                }
+                piece += 1;
+            }
+            color += 1;
+        }
+
+        let mut color = 0;
+        while color < 2 {
+            let mut count = 0;
+            while count < 3 {
+                state = splitmix64(state);
+                keys.checks[color][count] = state;
+                count += 1;
+            }
+            color += 1;
+        }
+
+        keys
+    }
+}
+
+/// One step of the SplitMix64 generator, returning the next output
+const fn splitmix64(state: u64) -> u64 {
+    let mut z = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Key for a piece of a given color standing on a square
+pub fn piece_key(piece: Piece, color: Color, sq: u8) -> u64 {
+    let color_idx = match color {
+        Color::White => 0,
+        Color::Black => 1,
+    };
+    let piece_idx = match piece {
+        Piece::Pawn => 0,
+        Piece::Knight => 1,
+        Piece::Bishop => 2,
+        Piece::Rook => 3,
+        Piece::Queen => 4,
+        Piece::King => 5,
+    };
+    KEYS.pieces[color_idx][piece_idx][sq as usize]
+}
+
+/// Key for the right of `color` to castle on one side, whichever rook it
+/// castles with
+pub fn castling_key(color: Color, kingside: bool) -> u64 {
+    KEYS.castling[2 * color.index() + usize::from(!kingside)]
+}
+
+/// Key for an en passant square, which depends on its file only
+pub fn en_passant_key(sq: u8) -> u64 {
+    KEYS.en_passant[(sq % 8) as usize]
+}
+
+/// Key toggled when Black is to move
+pub fn side_key() -> u64 {
+    KEYS.black_to_move
+}
+
+/// Key for holding `count` pieces of a kind in a Crazyhouse pocket; an
+/// empty pocket contributes nothing
+pub fn pocket_key(piece: Piece, color: Color, count: u8) -> u64 {
+    if count == 0 || piece == Piece::King {
+        return 0;
+    }
+    KEYS.pockets[color.index()][piece.index()][(count.min(16) - 1) as usize]
+}
+
+/// Key for `count` checks given by a color in Three-check; none contributes
+/// nothing
+pub fn checks_key(color: Color, count: u8) -> u64 {
+    if count == 0 {
+        return 0;
+    }
+    KEYS.checks[color.index()][(count.min(3) - 1) as usize]
+}
+
+/// Zobrist key of the pawns alone, the same for every position with the
+/// same pawns; keys `pawns::PawnTable`
+pub fn pawn_key(board: &Board) -> u64 {
+    let mut key = 0;
+    for color in [Color::White, Color::Black] {
+        for sq in board.pieces(Piece::Pawn, color).bits() {
+            key ^= piece_key(Piece::Pawn, color, sq);
+        }
+    }
+    key
+}
+
+/// Compute the Zobrist hash of a board from scratch.
+///
+/// The hash covers piece placement, side to move, castling rights and the
+/// en passant square (whenever one is set), Crazyhouse pockets and Three-check
+/// counters; move clocks are not included.
+pub fn hash(board: &Board) -> u64 {
+    let mut key = 0;
+
+    for (sq, square) in board.squares().iter().enumerate() {
+        if let Some((piece, color)) = square {
+            key ^= piece_key(*piece, *color, sq as u8);
+        }
+    }
+
+    for (color, kingside, _) in board.castling_rights.iter() {
+        key ^= castling_key(color, kingside);
+    }
+
+    if let Some(ep) = board.en_passant {
+        key ^= en_passant_key(ep);
+    }
+
+    if board.side_to_move == Color::Black {
+        key ^= side_key();
+    }
+
+    for color in [Color::White, Color::Black] {
+        for piece in [Piece::Pawn, Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen] {
+            key ^= pocket_key(piece, color, board.pockets[color.index()][piece.index()]);
+        }
+        key ^= checks_key(color, board.checks[color.index()]);
+    }
+
+    key
+}
@@ -0,0 +1,32 @@
+use move_generation::accuracy::{game_accuracy, move_accuracy};
+use move_generation::types::Color;
+
+#[test]
+fn test_flawless_game() {
+    let result = game_accuracy(&[20, 25, 20, 30, 25], Color::White);
+    assert_eq!(result.white.moves, 2);
+    assert_eq!(result.black.moves, 2);
+    assert!(result.white.acpl < 1e-9);
+    assert!(result.white.accuracy > 99.0);
+    assert_eq!(result.black.blunders + result.black.mistakes + result.black.inaccuracies, 0);
+    assert_eq!(move_accuracy(60.0, 40.0).round(), 40.0);
+}
+
+#[test]
+fn test_blunder_is_counted_against_the_mover() {
+    let result = game_accuracy(&[20, 20, 20, -500, -500], Color::White);
+    assert_eq!(result.white.blunders, 1);
+    assert!((result.white.acpl - 260.0).abs() < 1e-9);
+    assert!(result.white.accuracy < result.black.accuracy);
+    assert_eq!(result.black.blunders, 0);
+    assert!(result.black.acpl < 1e-9);
+
+    // Starting with Black flips the attribution
+    let flipped = game_accuracy(&[20, 20, -500], Color::Black);
+    assert_eq!(flipped.black.moves, 1);
+    assert_eq!(flipped.white.blunders, 1);
+
+    let tags = result.to_pgn_tags();
+    assert!(tags.contains(&("WhiteACPL".to_string(), "260".to_string())));
+    assert!(tags.contains(&("WhiteBlunders".to_string(), "1".to_string())));
+}
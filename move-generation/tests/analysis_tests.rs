@@ -0,0 +1,34 @@
+use move_generation::analysis::GameAnalyzer;
+use move_generation::pgn::{Eval, PgnGameReader};
+use move_generation::score::Score;
+use move_generation::search::{SearchLimits, SearchOptions};
+use move_generation::tune::EvalParams;
+
+fn analyzer() -> GameAnalyzer<EvalParams> {
+    let limits = SearchLimits { depth: Some(2), ..Default::default() };
+    GameAnalyzer::new(EvalParams::default(), limits, SearchOptions { hash_mb: 1, ..SearchOptions::default() })
+}
+
+#[test]
+fn test_eval_graph() {
+    // Scholar's mate
+    let pgn = "1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0\n\n";
+    let mut game = PgnGameReader::new(pgn.as_bytes()).next().unwrap().unwrap();
+    let mut analyzer = analyzer();
+    let evals = analyzer.eval_graph(&game).unwrap();
+    assert_eq!(evals.len(), 7);
+    assert_eq!((evals[0].ply, evals[0].san.as_str()), (1, "e4"));
+    // After 3...Nf6 White mates at once, and the mate itself ends the game
+    assert_eq!(evals[5].score, Some(Score::Mate(1)));
+    assert!(evals[5].white_win_percent > 95.0);
+    assert_eq!((evals[6].score, evals[6].white_win_percent), (None, 100.0));
+    assert_eq!(analyzer.cache_len(), 6);
+
+    // A second pass is answered from the cache
+    let evals = analyzer.annotate(&mut game).unwrap();
+    assert_eq!(analyzer.cache_len(), 6);
+    assert_eq!(game.evals[5], Some(Eval::Mate(1)));
+    assert_eq!(game.evals[6], None);
+    assert_eq!(game.evals[0], evals[0].score.map(Eval::from));
+    assert!(game.to_pgn().contains("Nf6 {[%eval #1]} 4. Qxf7# 1-0"));
+}
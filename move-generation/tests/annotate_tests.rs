@@ -0,0 +1,85 @@
+use move_generation::accuracy::Judgement;
+use move_generation::annotate::annotate_game;
+use move_generation::match_runner::{SearchPlayer, UciEngine};
+use move_generation::pgn::{MoveAnnotation, PgnGame, PgnGameReader};
+use move_generation::search::{SearchLimits, SearchOptions};
+use move_generation::tune::EvalParams;
+
+fn scholars_mate() -> PgnGame {
+    let moves = ["e2e4", "e7e5", "d1h5", "b8c6", "f1c4", "g8f6", "h5f7"];
+    PgnGame::from_uci(None, &moves, Vec::new()).unwrap()
+}
+
+#[test]
+fn test_write_annotated_numbers_variations() {
+    let game = PgnGame::from_uci(None, &["e2e4", "e7e5", "g1f3"], Vec::new()).unwrap();
+    let annotations = [
+        MoveAnnotation::default(),
+        MoveAnnotation {
+            nag: Some(2),
+            comment: Some("Mistake. c5 was best.".to_string()),
+            variation: vec!["c5".to_string(), "Nf3".to_string(), "d6".to_string()],
+        },
+        MoveAnnotation { nag: None, comment: Some("Developing.".to_string()), variation: Vec::new() },
+    ];
+    let mut out = Vec::new();
+    game.write_annotated(&mut out, &annotations).unwrap();
+    let pgn = String::from_utf8(out).unwrap();
+    let movetext = pgn.split("\n\n").nth(1).unwrap().replace('\n', " ");
+    assert_eq!(movetext.trim(), "1. e4 e5 $2 {Mistake. c5 was best.} (1... c5 2. Nf3 d6) 2. Nf3 {Developing.} *");
+    assert_eq!(game.to_pgn(), {
+        let mut out = Vec::new();
+        game.write_annotated(&mut out, &[]).unwrap();
+        String::from_utf8(out).unwrap()
+    });
+}
+
+#[test]
+fn test_write_annotated_drops_closing_braces_from_comments() {
+    let game = PgnGame::from_uci(None, &["e2e4", "e7e5", "g1f3"], Vec::new()).unwrap();
+    let annotations = [
+        MoveAnnotation { nag: None, comment: Some("Best} 2. Qh5 {by test".to_string()), variation: Vec::new() },
+    ];
+    let mut out = Vec::new();
+    game.write_annotated(&mut out, &annotations).unwrap();
+    let pgn = String::from_utf8(out).unwrap();
+    assert!(pgn.contains("1. e4 {Best 2. Qh5 {by test} e5 2. Nf3 *"), "{}", pgn);
+
+    let read = PgnGameReader::new(pgn.as_bytes()).next().unwrap().unwrap();
+    assert_eq!(read.moves, game.moves);
+    assert_eq!(read.headers, game.headers);
+}
+
+#[test]
+fn test_annotate_game_marks_the_blunder() {
+    let mut player = SearchPlayer::new("builtin", EvalParams::default(), SearchOptions::default());
+    let limits = SearchLimits { depth: Some(3), ..SearchLimits::default() };
+    let annotated = annotate_game(&scholars_mate(), &mut player, &limits).unwrap();
+
+    assert_eq!(annotated.judgements.len(), 7);
+    assert_eq!(annotated.judgements[5], Some(Judgement::Blunder));
+    assert!(annotated.judgements[..5].iter().all(|judgement| *judgement != Some(Judgement::Blunder)));
+    assert_eq!(annotated.judgements[6], None);
+    let blunder = &annotated.annotations[5];
+    assert_eq!(blunder.nag, Some(4));
+    assert!(blunder.comment.as_deref().unwrap().starts_with("Blunder. "));
+    assert!(blunder.variation.len() > 1);
+    assert_ne!(blunder.variation[0], "Nf6");
+    assert_eq!(annotated.game.evals.len(), 7);
+    assert_eq!(annotated.game.evals[6], None);
+
+    let pgn = annotated.to_pgn().replace('\n', " ");
+    assert!(pgn.contains("3. Bc4 {[%eval "), "{}", pgn);
+    assert!(pgn.contains("]} Nf6 $4 {[%eval #1] Blunder. "), "{}", pgn);
+    assert!(pgn.contains("(3... "));
+    assert!(pgn.contains(") 4. Qxf7# 1-0"));
+}
+
+#[test]
+fn test_annotate_game_with_a_uci_engine() {
+    let mut engine = UciEngine::spawn(env!("CARGO_BIN_EXE_uci"), &[]).unwrap();
+    let limits = SearchLimits { depth: Some(3), ..SearchLimits::default() };
+    let annotated = annotate_game(&scholars_mate(), &mut engine, &limits).unwrap();
+    assert_eq!(annotated.judgements[5], Some(Judgement::Blunder));
+    assert!(annotated.annotations[5].variation.len() > 1);
+}
@@ -0,0 +1,55 @@
+#![cfg(feature = "arrow")]
+
+use arrow_array::cast::AsArray;
+use arrow_array::Array;
+use arrow_array::types::{Float32Type, Int32Type, Int8Type, UInt16Type};
+use move_generation::arrow::record_batches;
+use move_generation::pgn::PgnGameReader;
+
+const PGN: &str = r#"[WhiteElo "2100"]
+[Result "1-0"]
+
+1. e4 { [%eval 0.3] } e5 { [%eval 0.25] } 2. Bc4 Nc6 3. Qh5 Nf6 { [%eval #1] } 4. Qxf7# 1-0
+
+[Result "*"]
+
+1. d4 d5 *
+"#;
+
+#[test]
+fn test_position_batches() {
+    let batches: Vec<_> = record_batches(PgnGameReader::new(PGN.as_bytes()), 1000)
+        .collect::<Result<_, _>>().unwrap();
+    assert_eq!(batches.len(), 1);
+    let batch = &batches[0];
+    assert_eq!(batch.num_rows(), 9);
+
+    let fen = batch.column_by_name("fen").unwrap().as_string::<i32>();
+    assert_eq!(fen.value(0), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    let result = batch.column_by_name("result").unwrap().as_primitive::<Int8Type>();
+    assert_eq!(result.value(0), 1);
+    assert!(result.is_null(7));
+    let elo = batch.column_by_name("white_elo").unwrap().as_primitive::<UInt16Type>();
+    assert_eq!(elo.value(0), 2100);
+    assert!(batch.column_by_name("black_elo").unwrap().is_null(0));
+
+    // Evaluations are attached to the position they annotate
+    let eval = batch.column_by_name("eval").unwrap().as_primitive::<Float32Type>();
+    assert!(eval.is_null(0));
+    assert_eq!(eval.value(1), 0.3);
+    assert_eq!(eval.value(2), 0.25);
+    let mate = batch.column_by_name("mate").unwrap().as_primitive::<Int32Type>();
+    assert_eq!(mate.value(6), 1);
+    assert!(eval.is_null(6));
+
+    let mv = batch.column_by_name("move").unwrap().as_primitive::<UInt16Type>();
+    assert_eq!(mv.value(0), 12 * 64 + 28);
+}
+
+#[test]
+fn test_batches_split_on_game_boundaries() {
+    let sizes: Vec<usize> = record_batches(PgnGameReader::new(PGN.as_bytes()), 3)
+        .map(|batch| batch.unwrap().num_rows())
+        .collect();
+    assert_eq!(sizes, [7, 2]);
+}
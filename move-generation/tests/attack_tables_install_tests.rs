@@ -0,0 +1,18 @@
+//! Installing the global attack tables needs a process that has not looked
+//! up any attack yet, so this test has a binary of its own
+
+use move_generation::magic::{global, install, AttackTables};
+use move_generation::pgn::Position;
+use move_generation::rules::generate_legal_moves;
+use move_generation::types::Board;
+
+#[test]
+fn test_install_global_tables() {
+    let tables = AttackTables::new();
+    install(tables.clone()).unwrap();
+    assert!(global().ptr_eq(&tables));
+
+    let board = Board::from("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1".parse::<Position>().unwrap());
+    assert_eq!(generate_legal_moves(&board).len(), 26);
+    assert!(install(AttackTables::new()).is_err());
+}
@@ -0,0 +1,15 @@
+use move_generation::bench::{run, BenchConfig};
+
+#[test]
+fn test_bench_counts() {
+    let stats = run(&BenchConfig { iterations: 2, perft_depth: 1 }).unwrap();
+    assert_eq!(stats.movegen.count, 12);
+    // 20 + 48 + 14 + 6 + 44 + 46 legal moves in the six positions
+    assert_eq!(stats.perft.count, 178);
+    assert_eq!(stats.make_unmake.count, 2 * 178);
+    assert_eq!(stats.fen_parsing.count, 12);
+    // The test binary does not install CountingAllocator
+    assert_eq!(stats.movegen.allocations, None);
+    assert!(stats.perft.per_second() > 0.0);
+    assert_eq!(stats.to_string().lines().count(), 4);
+}
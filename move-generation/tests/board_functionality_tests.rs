@@ -1,30 +1,22 @@
-use move_generation::types::{Board, Color, Piece};
+use move_generation::types::{Board, CastlingRights, Color, Piece};
 use move_generation::move_gen::generate_moves;
-use std::process::{Command, Stdio};
+use move_generation::notation::to_standard_uci;
 use std::io::Write;
-use serde_json::json;
-
-fn square_idx_to_uci(idx: u8) -> String {
-    let file = (b'a' + (idx % 8) as u8) as char;
-    let rank = (b'1' + (idx / 8) as u8) as char;
-    format!("{}{}", file, rank)
-}
 
 #[test]
 fn test_random_board_position() {
-    let board = Board {
-        squares: [
-            Some((Piece::Rook, Color::White)), None, None, None, Some((Piece::King, Color::White)), None, None, Some((Piece::Rook, Color::White)),
-            None, None, None, None, None, None, None, None,
-            None, None, None, None, None, None, None, None,
-            None, None, None, None, None, None, None, None,
-            None, None, None, None, None, None, None, None,
-            None, None, None, None, None, None, None, None,
-            None, None, None, None, None, None, None, None,
-            Some((Piece::Rook, Color::Black)), None, None, None, Some((Piece::King, Color::Black)), None, None, Some((Piece::Rook, Color::Black)),
-        ],
-        side_to_move: Color::White,
-    };
+    let mut board = Board::from_squares([
+        Some((Piece::Rook, Color::White)), None, None, None, Some((Piece::King, Color::White)), None, None, Some((Piece::Rook, Color::White)),
+        None, None, None, None, None, None, None, None,
+        None, None, None, None, None, None, None, None,
+        None, None, None, None, None, None, None, None,
+        None, None, None, None, None, None, None, None,
+        None, None, None, None, None, None, None, None,
+        None, None, None, None, None, None, None, None,
+        Some((Piece::Rook, Color::Black)), None, None, None, Some((Piece::King, Color::Black)), None, None, Some((Piece::Rook, Color::Black)),
+    ]);
+    board.castling_rights = CastlingRights::standard();
+    board.refresh();
 
     let moves = generate_moves(&board, Color::White);
 
@@ -37,23 +29,22 @@ fn test_random_board_position() {
 
 #[test]
 fn test_validate_with_pychess() {
-    let board = Board {
-        squares: [
-            Some((Piece::Rook, Color::White)), None, None, None, Some((Piece::King, Color::White)), None, None, Some((Piece::Rook, Color::White)),
-            None, None, None, None, None, None, None, None,
-            None, None, None, None, None, None, None, None,
-            None, None, None, None, None, None, None, None,
-            None, None, None, None, None, None, None, None,
-            None, None, None, None, None, None, None, None,
-            None, None, None, None, None, None, None, None,
-            Some((Piece::Rook, Color::Black)), None, None, None, Some((Piece::King, Color::Black)), None, None, Some((Piece::Rook, Color::Black)),
-        ],
-        side_to_move: Color::White,
-    };
+    let mut board = Board::from_squares([
+        Some((Piece::Rook, Color::White)), None, None, None, Some((Piece::King, Color::White)), None, None, Some((Piece::Rook, Color::White)),
+        None, None, None, None, None, None, None, None,
+        None, None, None, None, None, None, None, None,
+        None, None, None, None, None, None, None, None,
+        None, None, None, None, None, None, None, None,
+        None, None, None, None, None, None, None, None,
+        None, None, None, None, None, None, None, None,
+        Some((Piece::Rook, Color::Black)), None, None, None, Some((Piece::King, Color::Black)), None, None, Some((Piece::Rook, Color::Black)),
+    ]);
+    board.castling_rights = CastlingRights::standard();
+    board.refresh();
 
     let rust_moves = generate_moves(&board, Color::White);
     let rust_moves_uci: Vec<String> = rust_moves.iter()
-        .map(|m| format!("{}{}", square_idx_to_uci(m.from), square_idx_to_uci(m.to)))
+        .map(|m| to_standard_uci(&board, m))
         .collect();
 
     let board_fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
@@ -80,3 +71,166 @@ fn test_validate_with_pychess() {
 
     assert!(result["valid"].as_bool().unwrap(), "Moves validation failed: {:?}", result);
 }
+
+#[test]
+fn test_is_move_legal_detects_check() {
+    use move_generation::{is_move_legal, types::Move};
+
+    let mut board = Board::empty();
+    // White king e1, white bishop e2 pinned by a black rook on e8
+    board.put(4, Piece::King, Color::White);
+    board.put(12, Piece::Bishop, Color::White);
+    board.put(60, Piece::Rook, Color::Black);
+    board.put(63, Piece::King, Color::Black);
+    board.refresh();
+
+    assert!(!is_move_legal(&board, &Move { from: 12, to: 21, promotion: None, drop: None }));
+    assert!(is_move_legal(&board, &Move { from: 4, to: 5, promotion: None, drop: None }));
+}
+
+#[test]
+fn test_make_unmake_special_moves() {
+    use move_generation::{pgn::Position, types::Move};
+
+    let cases = [
+        // Castling kingside and queenside, written as the king taking its rook
+        ("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 3 10", Move { from: 4, to: 7, promotion: None, drop: None }),
+        ("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 3 10", Move { from: 60, to: 56, promotion: None, drop: None }),
+        // Chess960 castling where the king stays put and the rook jumps over it
+        ("1r4kr/8/8/8/8/8/8/1R4KR w KQkq - 0 1", Move { from: 6, to: 7, promotion: None, drop: None }),
+        // En passant capture
+        ("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2", Move { from: 36, to: 43, promotion: None, drop: None }),
+        // Capturing promotion
+        ("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1", Move { from: 48, to: 57, promotion: Some(Piece::Queen), drop: None }),
+    ];
+    for (fen, mv) in cases {
+        let original = Board::from(fen.parse::<Position>().unwrap());
+        let mut board = original.clone();
+        let undo = board.make_move(&mv);
+        assert_ne!(board, original);
+        board.unmake_move(&mv, undo);
+        assert!(board.eq_strict(&original), "make/unmake changed {}", fen);
+    }
+
+    let mut board = Board::from("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 3 10".parse::<Position>().unwrap());
+    board.make_move(&Move { from: 4, to: 7, promotion: None, drop: None });
+    assert_eq!(board.squares()[5], Some((Piece::Rook, Color::White)));
+    assert_eq!(board.squares()[6], Some((Piece::King, Color::White)));
+    assert_eq!(board.castling_rights.to_string(), "kq");
+    assert_eq!(board.side_to_move, Color::Black);
+}
+
+#[test]
+fn test_null_move() {
+    use move_generation::{pgn::Position, zobrist};
+
+    let original = Board::from("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 3".parse::<Position>().unwrap());
+    let mut board = original.clone();
+    let undo = board.make_null_move();
+    assert_eq!(Position::from(&board).to_fen(), "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR w KQkq - 1 4");
+    assert_eq!(board.hash, zobrist::hash(&board));
+
+    board.unmake_null_move(undo);
+    assert!(board.eq_strict(&original));
+}
+
+#[test]
+fn test_occupancy_follows_make_unmake() {
+    use move_generation::{pgn::Position, rules::generate_legal_moves};
+
+    for fen in [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+        "1r4kr/8/8/8/8/8/8/1R4KR w KQkq - 0 1",
+        "r3k2r/pPpp1ppp/8/8/8/8/PpPP1PPP/R3K2R[QNp] w KQkq - 0 1",
+    ] {
+        let mut board = Board::from(fen.parse::<Position>().unwrap());
+        for mv in generate_legal_moves(&board) {
+            let undo = board.make_move(&mv);
+            let mut fresh = board.clone();
+            fresh.refresh();
+            assert!(board.eq_strict(&fresh), "after {:?} in {}", mv, fen);
+            for reply in generate_legal_moves(&board) {
+                let reply_undo = board.make_move(&reply);
+                let mut fresh = board.clone();
+                fresh.refresh();
+                assert!(board.eq_strict(&fresh), "after {:?} {:?} in {}", mv, reply, fen);
+                board.unmake_move(&reply, reply_undo);
+            }
+            board.unmake_move(&mv, undo);
+        }
+        let mut fresh = board.clone();
+        fresh.refresh();
+        assert!(board.eq_strict(&fresh), "make/unmake left {} out of step", fen);
+    }
+}
+
+#[test]
+fn test_piece_list() {
+    use move_generation::pgn::Position;
+
+    let board = Board::from("4k3/8/8/8/3n4/8/1P6/R3K3 w Q - 0 1".parse::<Position>().unwrap());
+    assert_eq!(board.piece_list(Color::White).collect::<Vec<_>>(), [(0, Piece::Rook), (4, Piece::King), (9, Piece::Pawn)]);
+    assert_eq!(board.piece_list(Color::Black).collect::<Vec<_>>(), [(27, Piece::Knight), (60, Piece::King)]);
+    assert_eq!((board.king_square(Color::White), board.king_square(Color::Black)), (Some(4), Some(60)));
+    assert_eq!(u64::from(board.pieces(Piece::Knight, Color::Black)), 1 << 27);
+    assert!(board.pieces(Piece::Knight, Color::White).bits().is_empty());
+}
+
+#[test]
+fn test_is_move_legal_en_passant() {
+    use move_generation::{is_move_legal, pgn::Position, types::Move};
+
+    // Taking en passant would empty the rank between the king and the rook
+    let board = Board::from("8/8/8/K2pP2r/8/8/8/4k3 w - d6 0 2".parse::<Position>().unwrap());
+    assert!(!is_move_legal(&board, &Move { from: 36, to: 43, promotion: None, drop: None }));
+    assert!(is_move_legal(&board, &Move { from: 36, to: 44, promotion: None, drop: None }));
+
+    // Taking en passant removes the pawn giving check
+    let board = Board::from("8/8/8/3pP3/4K3/8/8/4k3 w - d6 0 2".parse::<Position>().unwrap());
+    assert!(is_move_legal(&board, &Move { from: 36, to: 43, promotion: None, drop: None }));
+}
+
+#[test]
+fn test_is_move_legal_checks_how_pieces_move() {
+    use move_generation::{is_move_legal, notation::parse_uci, pgn::Position, types::{name_to_square, Move}};
+
+    let board = Board::from("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".parse::<Position>().unwrap());
+    assert!(is_move_legal(&board, &parse_uci(&board, "e2e4").unwrap()));
+    for uci in ["e2e6", "b1b3", "a1a3", "e1e2", "e7e5", "g8f6"] {
+        let mv = Move { from: name_to_square(&uci[..2]).unwrap(), to: name_to_square(&uci[2..]).unwrap(), promotion: None, drop: None };
+        assert!(!is_move_legal(&board, &mv), "{}", uci);
+    }
+    assert!(!is_move_legal(&board, &Move { from: 12, to: 64, promotion: None, drop: None }));
+    assert!(!is_move_legal(&board, &Move { from: 28, to: 28, promotion: None, drop: Some(Piece::Knight) }));
+}
+
+#[test]
+fn test_put_and_remove_keep_bitboards_in_step() {
+    use move_generation::{rules::generate_legal_moves, types::Move};
+
+    let mut board = Board::empty();
+    board.put(4, Piece::King, Color::White);
+    board.put(60, Piece::King, Color::Black);
+    board.put(0, Piece::Rook, Color::White);
+    board.refresh_hash();
+    assert_eq!(board.king_square(Color::Black), Some(60));
+    assert_eq!(board.pieces(Piece::Rook, Color::White).bits(), vec![0]);
+    assert_eq!(generate_legal_moves(&board).len(), 15);
+
+    assert_eq!(board.remove(0), Some((Piece::Rook, Color::White)));
+    assert_eq!(board.remove(0), None);
+    assert!(board.pieces(Piece::Rook, Color::White).bits().is_empty());
+    assert_eq!(u64::from(board.occupied()), 1 << 4 | 1 << 60);
+
+    // Squares changed after a move are seen by the generators
+    board.make_move(&Move { from: 4, to: 12, promotion: None, drop: None });
+    board.put(0, Piece::Rook, Color::Black);
+    board.remove(60);
+    board.put(63, Piece::King, Color::Black);
+    assert_eq!(u64::from(board.occupied_by(Color::Black)), 1 | 1 << 63);
+    assert_eq!(board.king_square(Color::Black), Some(63));
+    assert_eq!(generate_legal_moves(&board).len(), 17);
+    assert_eq!(Board::from_squares(*board.squares()).occupied(), board.occupied());
+}
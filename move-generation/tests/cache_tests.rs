@@ -0,0 +1,37 @@
+#![cfg(feature = "cache")]
+
+use move_generation::cache::PositionCache;
+use move_generation::pgn::starting_board;
+use move_generation::types::Variant;
+use move_generation::zobrist;
+use std::path::PathBuf;
+
+fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("move_generation_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&path);
+    path
+}
+
+#[test]
+fn test_values_persist_across_opens() {
+    let path = temp_path("persist");
+    let board = starting_board(Variant::Standard);
+    {
+        let cache = PositionCache::open(&path).unwrap();
+        assert!(cache.is_empty());
+        cache.put_board(&board, b"+0.25").unwrap();
+        cache.put(42, b"annotation").unwrap();
+        cache.flush().unwrap();
+    }
+
+    let cache = PositionCache::open(&path).unwrap();
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.get(zobrist::hash(&board)).unwrap().as_deref(), Some(&b"+0.25"[..]));
+    assert_eq!(cache.get_board(&board).unwrap(), Some(b"+0.25".to_vec()));
+    assert_eq!(cache.get(7).unwrap(), None);
+
+    cache.remove(42).unwrap();
+    assert_eq!(cache.get(42).unwrap(), None);
+    drop(cache);
+    std::fs::remove_dir_all(&path).unwrap();
+}
@@ -0,0 +1,34 @@
+use move_generation::pgn::Position;
+use move_generation::rules::validate_board;
+use move_generation::types::Board;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+fn placement(board: &Board) -> String {
+    let fen = Position::from(board).to_fen();
+    fen.split_whitespace().next().unwrap().to_string()
+}
+
+#[test]
+fn test_chess960_numbering() {
+    assert_eq!(
+        Position::from(&Board::chess960_start(518)).to_fen(),
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+    );
+    assert_eq!(placement(&Board::chess960_start(0)), "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR");
+    assert_eq!(placement(&Board::chess960_start(959)), "rkrnnqbb/pppppppp/8/8/8/8/PPPPPPPP/RKRNNQBB");
+    assert!(Board::chess960_start(0).castling_rights.is_empty());
+}
+
+#[test]
+fn test_chess960_positions_are_distinct_and_valid() {
+    let mut seen = std::collections::HashSet::new();
+    for n in 0..960 {
+        let board = Board::chess960_start(n);
+        assert!(validate_board(&board).is_valid());
+        assert!(seen.insert(placement(&board)));
+    }
+
+    let mut rng = StdRng::seed_from_u64(7);
+    assert!(validate_board(&Board::chess960_random(&mut rng)).is_valid());
+}
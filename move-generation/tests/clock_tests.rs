@@ -0,0 +1,82 @@
+use move_generation::clock::{parse_clk, Clock, Speed, TimeControl};
+use move_generation::types::Color;
+use std::time::Duration;
+
+#[test]
+fn test_parse_time_control_tags() {
+    assert_eq!(
+        "300".parse::<TimeControl>().unwrap(),
+        TimeControl::SuddenDeath { base: Duration::from_secs(300) }
+    );
+    assert_eq!(
+        "180+2".parse::<TimeControl>().unwrap(),
+        TimeControl::Increment { base: Duration::from_secs(180), increment: Duration::from_secs(2) }
+    );
+    assert_eq!(
+        "40/7200".parse::<TimeControl>().unwrap(),
+        TimeControl::MovesIn { moves: 40, base: Duration::from_secs(7200), increment: Duration::ZERO }
+    );
+    assert!("-".parse::<TimeControl>().is_err());
+    assert!("0/60".parse::<TimeControl>().is_err());
+}
+
+#[test]
+fn test_parse_clk_comment() {
+    assert_eq!(parse_clk("[%clk 0:05:23]"), Some(Duration::from_secs(323)));
+    assert_eq!(parse_clk("good move [%clk 1:00:00.5] [%eval 0.3]"), Some(Duration::from_millis(3_600_500)));
+    assert_eq!(parse_clk("[%eval 0.3]"), None);
+}
+
+#[test]
+fn test_clock_increment_and_flag() {
+    let mut clock = Clock::new("10+5".parse().unwrap());
+
+    // White spends 3s and gets the 5s increment back
+    assert_eq!(clock.advance(Duration::from_secs(3)), Duration::from_secs(12));
+    assert_eq!(clock.side_to_move(), Color::Black);
+
+    // Black oversteps the clock
+    assert_eq!(clock.advance(Duration::from_secs(11)), Duration::ZERO);
+    assert_eq!(clock.flagged(), Some(Color::Black));
+    assert_eq!(clock.remaining(Color::White), Duration::from_secs(12));
+}
+
+#[test]
+fn test_clock_moves_in_period_and_clk_readings() {
+    let mut clock = Clock::new("2/60".parse().unwrap());
+
+    // Time spent is recovered from successive %clk readings
+    assert_eq!(clock.advance_to(Duration::from_secs(50)), Duration::from_secs(10));
+    assert_eq!(clock.advance_to(Duration::from_secs(55)), Duration::from_secs(5));
+
+    // White's second move completes the period and tops the clock up
+    assert_eq!(clock.advance(Duration::from_secs(20)), Duration::from_secs(90));
+    assert_eq!(clock.moves_made(Color::White), 2);
+}
+
+#[test]
+fn test_speed_from_tag() {
+    assert_eq!(Speed::from_tag("15"), Some(Speed::UltraBullet));
+    assert_eq!(Speed::from_tag("60+1"), Some(Speed::Bullet));
+    assert_eq!(Speed::from_tag("180+2"), Some(Speed::Blitz));
+    assert_eq!(Speed::from_tag("600+5"), Some(Speed::Rapid));
+    assert_eq!(Speed::from_tag("1800"), Some(Speed::Classical));
+    assert_eq!(Speed::from_tag("-"), Some(Speed::Correspondence));
+    assert_eq!(Speed::from_tag("?"), None);
+    assert_eq!("Blitz".parse::<Speed>().unwrap(), Speed::Blitz);
+    assert!("hyperbullet".parse::<Speed>().is_err());
+}
+
+#[test]
+fn test_huge_times_do_not_panic() {
+    assert!("2e69".parse::<TimeControl>().is_err());
+    assert!("60+5e244".parse::<TimeControl>().is_err());
+    assert_eq!(parse_clk("[%clk 2e69]"), None);
+    assert_eq!(parse_clk("[%clk 1e300:00:00]"), None);
+    // Fits in a Duration, but 40 increments do not
+    let tc = "1.8e19+1e18".parse::<TimeControl>().unwrap();
+    assert_eq!(tc.speed(), Speed::Classical);
+    let mut clock = Clock::new(tc);
+    clock.advance(Duration::ZERO);
+    assert_eq!(clock.remaining(Color::White), Duration::MAX);
+}
@@ -0,0 +1,9 @@
+//! Fixtures shared by the integration tests
+
+use move_generation::pgn::Position;
+use move_generation::types::Board;
+
+/// Board of a FEN that must parse
+pub fn board_from_fen(fen: &str) -> Board {
+    Board::from(fen.parse::<Position>().unwrap())
+}
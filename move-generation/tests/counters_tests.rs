@@ -0,0 +1,27 @@
+#![cfg(feature = "counters")]
+
+use move_generation::counters::Stats;
+use move_generation::perft::{perft, perft_hashed};
+use move_generation::pgn::Position;
+use move_generation::types::Board;
+
+#[test]
+fn test_counters() {
+    let board = Board::from("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".parse::<Position>().unwrap());
+    Stats::reset();
+    let before = Stats::snapshot();
+    assert_eq!(perft(&board, 2), 400);
+    let work = Stats::snapshot().since(&before);
+    // The root and its 20 children
+    assert_eq!(work.nodes, 21);
+    assert_eq!(work.moves_generated, 20 + 20 * 20);
+    assert!(work.attack_queries > 0);
+    assert_eq!(work.tt_probes, 0);
+
+    // Rook and king moves transpose three plies in
+    let board = Board::from("4k3/8/8/8/8/8/8/R3K3 w - - 0 1".parse::<Position>().unwrap());
+    let before = Stats::snapshot();
+    assert_eq!(perft_hashed(&board, 5, 1), perft(&board, 5));
+    let work = Stats::snapshot().since(&before);
+    assert!(work.tt_hits > 0 && work.tt_hits < work.tt_probes);
+}
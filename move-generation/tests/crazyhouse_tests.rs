@@ -0,0 +1,81 @@
+use move_generation::move_gen::generate_drops;
+use move_generation::pgn::Position;
+use move_generation::rules::generate_legal_moves;
+use move_generation::types::{Color, Move, Piece, Variant};
+
+mod common;
+
+use common::board_from_fen;
+
+#[test]
+fn test_crazyhouse_fen_round_trip() {
+    let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQ~KBNR[Qnp] w KQkq - 0 1";
+    let board = board_from_fen(fen);
+    assert_eq!(board.variant, Variant::Crazyhouse);
+    assert_eq!(board.pockets[Color::White.index()][Piece::Queen.index()], 1);
+    assert_eq!(board.pockets[Color::Black.index()][Piece::Knight.index()], 1);
+    assert_eq!(board.pockets[Color::Black.index()][Piece::Pawn.index()], 1);
+    assert_eq!(board.promoted, 1 << 3);
+    assert_eq!(Position::from(&board).to_fen(), fen);
+
+    let standard = board_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    assert_eq!(standard.variant, Variant::Standard);
+    assert!(!Position::from(&standard).to_fen().contains('['));
+}
+
+#[test]
+fn test_no_pawn_drops_on_back_ranks() {
+    let board = board_from_fen("4k3/8/8/8/8/8/8/4K3[PN] w - - 0 1");
+    let drops = generate_drops(&board, Color::White);
+
+    let pawn_drops: Vec<&Move> = drops.iter().filter(|m| m.drop == Some(Piece::Pawn)).collect();
+    assert_eq!(pawn_drops.len(), 48);
+    assert!(pawn_drops.iter().all(|m| (8..56).contains(&m.to)));
+    assert_eq!(drops.iter().filter(|m| m.drop == Some(Piece::Knight)).count(), 62);
+
+    assert!(generate_drops(&board, Color::Black).is_empty());
+    assert_eq!(generate_legal_moves(&board).iter().filter(|m| m.drop.is_some()).count(), 110);
+}
+
+#[test]
+fn test_captures_go_to_pocket() {
+    // The knight on d5 is a promoted pawn, so capturing it gains a pawn
+    let mut board = board_from_fen("4k3/8/8/3n~4/4P3/8/8/4K3[] w - - 0 1");
+    let capture = Move { from: 28, to: 35, promotion: None, drop: None };
+    let undo = board.make_move(&capture);
+    assert_eq!(board.pockets[Color::White.index()][Piece::Pawn.index()], 1);
+    assert_eq!(board.pockets[Color::White.index()][Piece::Knight.index()], 0);
+    assert_eq!(board.promoted, 0);
+
+    board.unmake_move(&capture, undo);
+    assert_eq!(board.pockets, [[0; 5]; 2]);
+    assert_eq!(board.promoted, 1 << 35);
+    assert_eq!(board.squares()[35], Some((Piece::Knight, Color::Black)));
+}
+
+#[test]
+fn test_king_capture_skips_the_pocket() {
+    // Black's king is left in check with White to move, so fxe8 takes it
+    let mut board = board_from_fen("rnbqkbnr/pppppPpp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1");
+    let capture = Move { from: 53, to: 60, promotion: Some(Piece::Queen), drop: None };
+    let undo = board.make_move(&capture);
+    assert_eq!(board.pockets, [[0; 5]; 2]);
+    board.unmake_move(&capture, undo);
+    assert_eq!(board.squares()[60], Some((Piece::King, Color::Black)));
+
+    let mut board = board_from_fen("4k3/8/8/3q4/4P3/8/8/4K3[QQQQQQQQQQQQQQQQ] w - - 0 1");
+    board.pockets[Color::White.index()][Piece::Queen.index()] = u8::MAX;
+    board.make_move(&Move { from: 28, to: 35, promotion: None, drop: None });
+    assert_eq!(board.pockets[Color::White.index()][Piece::Queen.index()], u8::MAX);
+}
+
+#[test]
+fn test_drop_blocks_check() {
+    // Only drops between the rook and the king, or king moves, get out of check
+    let board = board_from_fen("4k3/8/8/8/8/8/8/r3K3[N] w - - 0 1");
+    let drops: Vec<u8> = generate_legal_moves(&board).iter()
+        .filter(|m| m.drop.is_some())
+        .map(|m| m.to)
+        .collect();
+    assert_eq!(drops, vec![1, 2, 3]);
+}
@@ -0,0 +1,36 @@
+use move_generation::types::{Move, Piece, Variant};
+
+mod common;
+
+use common::board_from_fen;
+
+#[test]
+fn test_board_display_and_debug() {
+    let fen = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1";
+    let board = board_from_fen(fen);
+    let expected = "\
+8 r n b q k b n r
+7 p p p p p p p p
+6 . . . . . . . .
+5 . . . . . . . .
+4 . . . . P . . .
+3 . . . . . . . .
+2 P P P P . P P P
+1 R N B Q K B N R
+  a b c d e f g h
+
+FEN: rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1";
+    assert_eq!(board.to_string(), expected);
+    assert_eq!(format!("{:?}", board), format!("Board({:?})", fen));
+
+    let mut koth = board.clone();
+    koth.variant = Variant::KingOfTheHill;
+    assert!(format!("{:?}", koth).ends_with(", KingOfTheHill)"));
+}
+
+#[test]
+fn test_move_display() {
+    assert_eq!(Move { from: 12, to: 28, promotion: None, drop: None }.to_string(), "e2e4");
+    assert_eq!(Move { from: 52, to: 60, promotion: Some(Piece::Knight), drop: None }.to_string(), "e7e8n");
+    assert_eq!(Move { from: 28, to: 28, promotion: None, drop: Some(Piece::Knight) }.to_string(), "N@e4");
+}
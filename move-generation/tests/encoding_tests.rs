@@ -0,0 +1,19 @@
+use move_generation::encoding::{encode_board, encode_move, PLANES};
+use move_generation::pgn::Position;
+use move_generation::types::{Board, Move};
+
+#[test]
+fn test_encode_board() {
+    let position: Position = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1".parse().unwrap();
+    let planes = encode_board(&Board::from(position));
+    assert_eq!(planes.len(), PLANES * 64);
+    assert_eq!(planes[36], 1.0); // White pawn on e5
+    assert_eq!(planes[6 * 64 + 35], 1.0); // Black pawn on d5
+    assert_eq!(planes[11 * 64 + 60], 1.0); // Black king on e8
+    assert!(planes[12 * 64..13 * 64].iter().all(|&v| v == 1.0));
+    assert!(planes[13 * 64..17 * 64].iter().all(|&v| v == 0.0));
+    assert_eq!(planes[17 * 64 + 43], 1.0);
+    assert_eq!(planes.iter().sum::<f32>(), 4.0 + 64.0 + 1.0);
+
+    assert_eq!(encode_move(&Move { from: 12, to: 28, promotion: None, drop: None }), 12 * 64 + 28);
+}
@@ -0,0 +1,54 @@
+use move_generation::epd::{parse_epd, read_epd, run_suite};
+use move_generation::match_runner::SearchPlayer;
+use move_generation::notation::parse_san;
+use move_generation::search::{SearchLimits, SearchOptions};
+use move_generation::tune::EvalParams;
+
+#[test]
+fn test_parse_epd_operations() {
+    let entry = parse_epd(
+        "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - bm Bb5 Bc4; am b1c3 Ng5; \
+         id \"test; 1\"; c0 \"Bb5=10, Bc4=8\";",
+    )
+    .unwrap();
+    assert_eq!(entry.id(), Some("test; 1"));
+    assert_eq!(entry.operation("c0"), Some("Bb5=10, Bc4=8"));
+    assert_eq!(entry.operations.len(), 4);
+    assert_eq!(entry.best_moves, [parse_san(&entry.board, "Bb5").unwrap(), parse_san(&entry.board, "Bc4").unwrap()]);
+    assert_eq!(entry.avoid_moves.len(), 2);
+    assert!(entry.accepts(&entry.best_moves[1]));
+    assert!(!entry.accepts(&entry.avoid_moves[0]));
+    assert!(!entry.accepts(&parse_san(&entry.board, "d4").unwrap()));
+
+    assert!(parse_epd("8/8/8 w -").is_err());
+    assert!(parse_epd("4k3/8/8/8/8/8/8/4K3 w - - bm Qh5;").is_err());
+    assert!(parse_epd("4k3/8/8/8/8/8/8/4K3 w - -").unwrap().operations.is_empty());
+}
+
+#[test]
+fn test_run_suite_scores_bm_and_am() {
+    let suite = "# mates and blunders\n\
+        6k1/5ppp/8/8/8/8/8/R5K1 w - - bm Ra8#; id \"mate\";\n\
+        \n\
+        k7/p7/1p6/8/8/8/8/KQ6 w - - am Qxb6; id \"avoid\";\n\
+        4k3/8/8/8/8/8/8/4K3 w - - id \"no key\";\n\
+        6k1/5ppp/8/8/8/8/8/R5K1 w - - bm Kf2;\n";
+    let entries = read_epd(suite.as_bytes()).unwrap();
+    assert_eq!(entries.len(), 4);
+
+    let mut player = SearchPlayer::new("builtin", EvalParams::default(), SearchOptions::default());
+    let limits = SearchLimits { depth: Some(3), ..SearchLimits::default() };
+    let mut seen = Vec::new();
+    let report = run_suite(&entries, &mut player, &limits, |result| seen.push(result.to_string())).unwrap();
+
+    assert_eq!((report.solved(), report.total()), (2, 3));
+    let ids: Vec<&str> = report.results.iter().map(|result| result.id.as_str()).collect();
+    assert_eq!(ids, ["mate", "avoid", "4"]);
+    assert_eq!(report.results[0].played, "Ra8#");
+    assert_eq!(seen[0], "mate: Ra8# bm Ra8# (mate 1) ok");
+    assert!(seen[2].starts_with("4: Ra8# bm Kf2 "));
+    assert!(seen[2].ends_with("FAILED"));
+    let summary = report.to_string();
+    assert!(summary.starts_with("Solved 2/3 (66.7%) in "));
+    assert!(summary.ends_with("\nFailed: 4"));
+}
@@ -0,0 +1,56 @@
+use move_generation::eval::{evaluate_fens, king_safety, king_safety_features, Evaluator, KingSafety};
+use move_generation::pgn::{ChessError, Position};
+use move_generation::score::Score;
+use move_generation::tune::EvalParams;
+use move_generation::types::{Board, Color, Piece};
+
+/// Counts the side to move's pieces, to check the trait is usable from outside
+struct PieceCount;
+
+impl Evaluator for PieceCount {
+    fn evaluate(&self, board: &Board) -> Result<Score, ChessError> {
+        let own = board.squares().iter().flatten().filter(|&&(piece, color)| color == board.side_to_move && piece != Piece::King).count();
+        Ok(Score::Cp(own as i32))
+    }
+}
+
+#[test]
+fn test_evaluators_score_for_the_side_to_move() {
+    let fens = ["4k3/8/8/8/8/2N5/8/4K3 w - - 0 1", "4k3/8/8/8/8/2N5/8/4K3 b - - 0 1", "4k3/pp6/8/8/8/8/8/4K3 b - - 0 1"];
+    let params = EvalParams::default();
+    let scores = evaluate_fens(&fens, &params).unwrap();
+    assert!(scores[0] > Score::Cp(250));
+    assert_eq!(scores[1], -scores[0]);
+
+    let evaluator: &dyn Evaluator = &PieceCount;
+    assert_eq!(evaluate_fens(&fens, evaluator).unwrap(), [Score::Cp(1), Score::Cp(0), Score::Cp(2)]);
+    assert!(evaluate_fens(&["not a fen"], evaluator).is_err());
+}
+
+#[test]
+fn test_king_safety() {
+    // Black's g-pawn is gone and the white queen bears down the g-file
+    let board = Board::from("6k1/5p1p/8/8/6Q1/8/5PPP/6K1 w - - 0 1".parse::<Position>().unwrap());
+    assert_eq!(king_safety(&board, Color::White), KingSafety { shield_pawns: 3, ..Default::default() });
+    assert_eq!(king_safety(&board, Color::Black), KingSafety {
+        shield_pawns: 2,
+        shield_gaps: 1,
+        open_files: 0,
+        half_open_files: 1,
+        zone_attackers: 1,
+        zone_attack_weight: 5,
+        attacked_zone_squares: 2,
+    });
+    let features = king_safety_features(&board);
+    assert_eq!(features[..7], [3.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
+    assert_eq!(features[7..], [2.0, 1.0, 0.0, 1.0, 1.0, 5.0, 2.0]);
+
+    let kingless = Board::from("4k3/8/8/8/8/8/8/8 w - - 0 1".parse::<Position>().unwrap());
+    assert_eq!(king_safety(&kingless, Color::White), KingSafety::default());
+
+    // The default weights count against Black's open king
+    let unweighted = EvalParams { king_safety: [0; 7], ..Default::default() };
+    assert!(EvalParams::default().evaluate(&board) > unweighted.evaluate(&board));
+    let params = EvalParams { king_safety: [0, -30, 0, 0, 0, 0, 0], ..unweighted.clone() };
+    assert!(params.evaluate(&board) > unweighted.evaluate(&board));
+}
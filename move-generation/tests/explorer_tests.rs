@@ -0,0 +1,87 @@
+use move_generation::clock::Speed;
+use move_generation::explorer::{build_explorer, ExplorerFilter};
+use move_generation::pgn::PgnGameReader;
+
+const PGN: &str = r#"[WhiteElo "2000"]
+[BlackElo "2200"]
+[TimeControl "180+2"]
+[Result "1-0"]
+
+1. e4 e5 2. Nf3 Nc6 3. Bb5 1-0
+
+[WhiteElo "1500"]
+[BlackElo "1500"]
+[TimeControl "600+5"]
+[Result "1/2-1/2"]
+
+1. Nf3 Nc6 2. e4 e5 3. Bb5 1/2-1/2
+
+[TimeControl "180+0"]
+[Result "0-1"]
+
+1. e4 c5 0-1
+
+"#;
+
+const AFTER_E4_E5_NF3_NC6: &str = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3";
+
+#[test]
+fn test_explore_counts_moves_and_results() {
+    let explorer = build_explorer(PgnGameReader::new(PGN.as_bytes()), 30).unwrap();
+    assert_eq!(explorer.len(), 3);
+    let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    let moves = explorer.explore_fen(start, &ExplorerFilter::default()).unwrap();
+    assert_eq!(moves.iter().map(|mv| mv.san.as_str()).collect::<Vec<_>>(), ["e4", "Nf3"]);
+    let e4 = &moves[0].stats;
+    assert_eq!((e4.games, e4.white_wins, e4.draws, e4.black_wins), (2, 1, 0, 1));
+    assert_eq!(moves[0].avg_elo, Some(2100));
+    assert_eq!(moves[1].avg_elo, Some(1500));
+}
+
+#[test]
+fn test_explore_meets_transpositions() {
+    let explorer = build_explorer(PgnGameReader::new(PGN.as_bytes()), 30).unwrap();
+    let moves = explorer.explore_fen(AFTER_E4_E5_NF3_NC6, &ExplorerFilter::default()).unwrap();
+    assert_eq!(moves.len(), 1);
+    assert_eq!((moves[0].san.as_str(), moves[0].stats.games), ("Bb5", 2));
+
+    // Queried without the en passant square that 2. e4 leaves on the board
+    let after_nf3_nc6_e4 = "r1bqkbnr/pppppppp/2n5/8/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 0 2";
+    let moves = explorer.explore_fen(after_nf3_nc6_e4, &ExplorerFilter::default()).unwrap();
+    assert_eq!(moves.len(), 1);
+    assert_eq!((moves[0].san.as_str(), moves[0].stats.draws), ("e5", 1));
+}
+
+#[test]
+fn test_explore_filters() {
+    let explorer = build_explorer(PgnGameReader::new(PGN.as_bytes()), 30).unwrap();
+    let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    let rated = ExplorerFilter { min_elo: Some(1800), ..Default::default() };
+    let moves = explorer.explore_fen(start, &rated).unwrap();
+    assert_eq!(moves.len(), 1);
+    assert_eq!((moves[0].san.as_str(), moves[0].stats.games), ("e4", 1));
+
+    let blitz = ExplorerFilter { speeds: vec![Speed::Blitz], ..Default::default() };
+    let moves = explorer.explore_fen(start, &blitz).unwrap();
+    assert_eq!(moves.len(), 1);
+    assert_eq!((moves[0].stats.games, moves[0].avg_elo), (2, Some(2100)));
+
+    let low = ExplorerFilter { max_elo: Some(1400), ..Default::default() };
+    assert!(explorer.explore_fen(start, &low).unwrap().is_empty());
+}
+
+#[test]
+fn test_max_ply() {
+    let explorer = build_explorer(PgnGameReader::new(PGN.as_bytes()), 1).unwrap();
+    let after_e4 = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1";
+    assert!(explorer.explore_fen(after_e4, &ExplorerFilter::default()).unwrap().is_empty());
+}
+
+#[test]
+fn test_huge_ratings_do_not_overflow() {
+    let pgn = "[WhiteElo \"4000000000\"]\n[BlackElo \"4000000000\"]\n[Result \"1-0\"]\n\n1. e4 1-0\n";
+    let explorer = build_explorer(PgnGameReader::new(pgn.as_bytes()), 30).unwrap();
+    let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    let moves = explorer.explore_fen(start, &ExplorerFilter::default()).unwrap();
+    assert_eq!(moves[0].avg_elo, Some(4_000_000_000));
+}
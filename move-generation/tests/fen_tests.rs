@@ -0,0 +1,127 @@
+use move_generation::pgn::canonical_fen;
+
+#[test]
+fn test_canonical_fen_clocks_and_castling_order() {
+    assert_eq!(
+        canonical_fen("r3k2r/8/8/8/8/8/8/R3K2R w qkQK - 12 40", false).unwrap(),
+        "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1"
+    );
+    assert_eq!(
+        canonical_fen("4k3/8/8/8/8/8/8/4K2R w KQkq - 0 1", false).unwrap(),
+        "4k3/8/8/8/8/8/8/4K2R w KQkq - 0 1"
+    );
+    assert_eq!(
+        canonical_fen("4k3/8/8/8/8/8/8/4K2R w KQkq - 0 1", true).unwrap(),
+        "4k3/8/8/8/8/8/8/4K2R w K - 0 1"
+    );
+}
+
+#[test]
+fn test_canonical_fen_en_passant() {
+    // No black pawn can capture on e3
+    assert_eq!(
+        canonical_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1", false).unwrap(),
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1"
+    );
+    // d4xe3 is available
+    assert_eq!(
+        canonical_fen("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 3", false).unwrap(),
+        "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+    );
+    // b5xc6 would expose the king on a5 to the rook on h5
+    assert_eq!(
+        canonical_fen("8/8/8/KPp4r/8/8/8/7k w - c6 0 1", false).unwrap(),
+        "8/8/8/KPp4r/8/8/8/7k w - - 0 1"
+    );
+}
+
+#[test]
+fn test_canonical_fen_rejects_garbage() {
+    assert!(canonical_fen("not a fen", false).is_err());
+}
+
+#[test]
+fn test_strict_fen_rejects_malformed_fields() {
+    use move_generation::pgn::Position;
+
+    for fen in [
+        "rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "rnbqkbnr/ppppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR/8 w KQkq - 0 1",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP w KQkq - 0 1",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR//// w KQkq - 0 1",
+        "rnbqkbnr/pppppppp/44/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBKKBNR w KQkq - 0 1",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkx - 0 1",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KKkq - 0 1",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e4 0 1",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq A1 0 1",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - x 1",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 0",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 extra",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq",
+    ] {
+        assert!(fen.parse::<Position>().is_err(), "{}", fen);
+    }
+
+    let err = "rnbqkbnr/ppppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".parse::<Position>().unwrap_err();
+    assert_eq!(err.to_string(), "Parse error: Invalid FEN: rank 7 has more than 8 files");
+}
+
+#[test]
+fn test_lenient_fen() {
+    use move_generation::pgn::Position;
+
+    let position = Position::parse_lenient("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkqx e4").unwrap();
+    assert_eq!(position.to_fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    assert_eq!(Position::parse_lenient("4k3/8/8/8/8/8/8/4K w - - 5 0").unwrap().to_fen(), "4k3/8/8/8/8/8/8/4K3 w - - 5 1");
+
+    // Squares off the board are errors in either mode
+    assert!(Position::parse_lenient("rnbqkbnr/ppppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").is_err());
+    assert!(Position::parse_lenient("8/8/8/8/8/8/8/8/8 w - - 0 1").is_err());
+}
+
+#[test]
+fn test_fen_without_clocks() {
+    use move_generation::pgn::Position;
+
+    let position: Position = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3".parse().unwrap();
+    assert_eq!(position.to_fen(), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+    // An en passant square set off the board is not written
+    let mut off_board = position.clone();
+    off_board.en_passant = Some(64);
+    assert_eq!(off_board.to_fen(), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1");
+    let position: Position = "4k3/8/8/8/8/8/8/4K3 w - - 7".parse().unwrap();
+    assert_eq!((position.halfmove_clock, position.fullmove_number), (7, 1));
+    // Three-check counters after the en passant square
+    let position: Position = "4k3/8/8/8/8/8/8/4K3 w - - 3+2".parse().unwrap();
+    assert_eq!(position.checks, Some([0, 1]));
+}
+
+#[test]
+fn test_shredder_fen_castling() {
+    use move_generation::pgn::Position;
+    use move_generation::rules::generate_legal_moves;
+    use move_generation::types::{Board, Color};
+
+    let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1".parse::<Position>().unwrap();
+    assert_eq!(start.to_fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    assert_eq!(start.to_shredder_fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1");
+
+    // Two rooks on the kingside: B castles with the b-file rook, G with the
+    // g-file one, which KQkq cannot tell apart from the outermost
+    let fen = "1r2k1rr/pppppppp/8/8/8/8/PPPPPPPP/1R2K1RR w Gb - 0 1";
+    let position = fen.parse::<Position>().unwrap();
+    assert_eq!(position.to_fen(), "1r2k1rr/pppppppp/8/8/8/8/PPPPPPPP/1R2K1RR w Gq - 0 1");
+    assert_eq!(position.to_shredder_fen(), fen);
+    let board = Board::from(position);
+    assert_eq!(board.castling_rook(Color::White, true), Some(6));
+    assert_eq!(board.castling_rook(Color::White, false), None);
+    assert_eq!(board.castling_rook(Color::Black, false), Some(57));
+    assert!(generate_legal_moves(&board).iter().any(|m| m.from == 4 && m.to == 6));
+    assert!(!generate_legal_moves(&board).iter().any(|m| m.from == 4 && m.to == 7));
+
+    // A letter on the king's file names no side
+    assert!("4k3/8/8/8/8/8/8/R3K2R w E - 0 1".parse::<Position>().is_err());
+}
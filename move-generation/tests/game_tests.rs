@@ -0,0 +1,124 @@
+use move_generation::game::{board_outcome, can_claim_draw, DrawClaim, DrawReason, Game, Outcome, Termination};
+use move_generation::pgn::Position;
+use move_generation::types::{Board, Color, Move};
+
+fn game(fen: &str) -> Game {
+    Game::new(Board::from(fen.parse::<Position>().unwrap()))
+}
+
+fn mv(from: u8, to: u8) -> Move {
+    Move { from, to, promotion: None, drop: None }
+}
+
+#[test]
+fn test_repetition_claims() {
+    let mut game = game("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    let shuffle = [mv(6, 21), mv(62, 45), mv(21, 6), mv(45, 62)];
+
+    for m in &shuffle {
+        game.push(m.clone()).unwrap();
+    }
+    assert_eq!(game.repetition_count(), 2);
+    for m in &shuffle[..3] {
+        game.push(m.clone()).unwrap();
+    }
+    // Black can claim by announcing Ng8, which repeats the start position a third time
+    assert_eq!(can_claim_draw(&game), Some(DrawClaim::Claimable(DrawReason::ThreefoldRepetition)));
+    game.push(shuffle[3].clone()).unwrap();
+    assert_eq!(game.repetition_count(), 3);
+    assert_eq!(can_claim_draw(&game), Some(DrawClaim::Claimable(DrawReason::ThreefoldRepetition)));
+
+    for _ in 0..2 {
+        for m in &shuffle {
+            game.push(m.clone()).unwrap();
+        }
+    }
+    assert_eq!(can_claim_draw(&game), Some(DrawClaim::Automatic(DrawReason::FivefoldRepetition)));
+}
+
+#[test]
+fn test_move_rules() {
+    assert_eq!(can_claim_draw(&game("4k3/8/8/8/8/8/8/4K2R w - - 10 80")), None);
+    assert_eq!(
+        can_claim_draw(&game("4k3/8/8/8/8/8/8/4K2R w - - 99 80")),
+        Some(DrawClaim::Claimable(DrawReason::FiftyMoveRule))
+    );
+    assert_eq!(
+        can_claim_draw(&game("4k3/8/8/8/8/8/8/4K2R w - - 100 80")),
+        Some(DrawClaim::Claimable(DrawReason::FiftyMoveRule))
+    );
+    assert_eq!(
+        can_claim_draw(&game("4k3/8/8/8/8/8/8/4K2R w - - 150 80")),
+        Some(DrawClaim::Automatic(DrawReason::SeventyFiveMoveRule))
+    );
+    // Checkmate on the 75th move stands
+    assert_eq!(can_claim_draw(&game("7k/6Q1/6K1/8/8/8/8/8 b - - 150 100")), None);
+}
+
+#[test]
+fn test_dead_positions() {
+    assert_eq!(
+        can_claim_draw(&game("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1")),
+        Some(DrawClaim::Automatic(DrawReason::Stalemate))
+    );
+    assert_eq!(
+        can_claim_draw(&game("8/8/4k3/8/8/3KB3/8/8 w - - 0 1")),
+        Some(DrawClaim::Automatic(DrawReason::InsufficientMaterial))
+    );
+}
+
+#[test]
+fn test_push_and_pop() {
+    let mut game = game("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    let start = game.board().clone();
+    game.push(mv(12, 28)).unwrap();
+    assert!(game.push(mv(0, 8)).is_err());
+    assert_eq!(game.pop(), Some(mv(12, 28)));
+    assert_eq!(game.board(), &start);
+}
+
+#[test]
+fn test_upcoming_repetition() {
+    let mut game = game("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    for m in [mv(6, 21), mv(62, 45), mv(21, 6)] {
+        assert!(!game.has_upcoming_repetition());
+        game.push(m).unwrap();
+    }
+    // Ng8 returns to the start position
+    assert!(game.has_upcoming_repetition());
+    game.push(mv(45, 62)).unwrap();
+    assert!(game.has_upcoming_repetition());
+
+    // Nc3 leaves two knights away from home: no single move repeats
+    game.pop();
+    game.pop();
+    game.push(mv(1, 18)).unwrap();
+    assert!(!game.has_upcoming_repetition());
+}
+
+#[test]
+fn test_upcoming_repetition_needs_clear_path() {
+    // The rook walks a1-b1-b4-a4 while the king circles back to h8; Ra1 would
+    // repeat the start unless a2 is blocked
+    for (fen, expected) in [("7k/8/8/8/8/8/8/R6K b - - 0 1", true), ("7k/8/8/8/8/8/P7/R6K b - - 0 1", false)] {
+        let mut game = game(fen);
+        for m in [mv(63, 62), mv(0, 1), mv(62, 54), mv(1, 25), mv(54, 55), mv(25, 24), mv(55, 63)] {
+            game.push(m).unwrap();
+        }
+        assert_eq!(game.has_upcoming_repetition(), expected, "{}", fen);
+    }
+}
+
+#[test]
+fn test_outcome() {
+    let mated = game("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3");
+    let outcome = mated.outcome().unwrap();
+    assert_eq!(outcome, Outcome { termination: Termination::Checkmate, winner: Some(Color::Black) });
+    assert_eq!(outcome.result(), "0-1");
+
+    let stalemate = board_outcome(game("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").board()).unwrap();
+    assert_eq!(stalemate.termination, Termination::Draw(DrawReason::Stalemate));
+    assert_eq!(stalemate.result(), "1/2-1/2");
+
+    assert_eq!(game("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").outcome(), None);
+}
@@ -0,0 +1,43 @@
+use move_generation::jsonl::pgn_to_jsonl;
+use serde_json::Value;
+
+const PGN: &str = r#"[White "Alice"]
+[Result "1-0"]
+
+1. e4 e5 2. Bc4 Nc6 3. Qh5 Nf6 { [%eval #1] } 4. Qxf7# 1-0
+
+[FEN "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"]
+[Result "*"]
+
+1. e4 *
+"#;
+
+#[test]
+fn test_pgn_to_jsonl() {
+    let mut output = Vec::new();
+    assert_eq!(pgn_to_jsonl(PGN.as_bytes(), &mut output).unwrap(), 2);
+
+    let lines: Vec<Value> = String::from_utf8(output).unwrap().lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2);
+
+    let first = &lines[0];
+    assert_eq!(first["headers"]["White"], "Alice");
+    assert_eq!(first["moves"][0], "e2e4");
+    assert_eq!(first["moves"][6], "h5f7");
+    assert_eq!(first["result"], "1-0");
+    assert_eq!(first["final_fen"], "r1bqkb1r/pppp1Qpp/2n2n2/4p3/2B1P3/8/PPPP1PPP/RNB1K1NR b KQkq - 0 4");
+    assert_eq!(first["evals"][5]["mate"], 1);
+    assert!(first["evals"][0].is_null());
+
+    let second = &lines[1];
+    assert_eq!(second["final_fen"], "4k3/8/8/8/4P3/8/8/4K3 b - e3 0 1");
+    assert!(second.get("evals").is_none());
+}
+
+#[test]
+fn test_illegal_game_is_an_error() {
+    let mut output = Vec::new();
+    assert!(pgn_to_jsonl("1. e5 *".as_bytes(), &mut output).is_err());
+}
@@ -0,0 +1,75 @@
+#![cfg(feature = "lichess")]
+
+use move_generation::lichess::{ExportOptions, LichessClient};
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::thread;
+use std::time::Duration;
+
+/// Answer each connection with the next canned response
+fn serve(responses: Vec<String>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = format!("http://{}", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        for response in responses {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+    });
+    address
+}
+
+fn ok(body: &str) -> String {
+    format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body)
+}
+
+const RATE_LIMITED: &str = "HTTP/1.1 429 Too Many Requests\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+
+#[test]
+fn test_export_url() {
+    let client = LichessClient::new();
+    let options = ExportOptions {
+        since: Some(1700000000000),
+        max: Some(50),
+        perf_type: Some("blitz,rapid".to_string()),
+        evals: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        client.export_url("DrNykterstein", &options).unwrap(),
+        "https://lichess.org/api/games/user/DrNykterstein?since=1700000000000&max=50&perfType=blitz,rapid&evals=true&clocks=false"
+    );
+    assert!(client.export_url("../account", &options).is_err());
+}
+
+#[test]
+fn test_user_games_retries_after_rate_limit() {
+    let pgn = "[Event \"Rated blitz game\"]\n[Result \"1-0\"]\n\n1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0\n\n\
+               [Event \"Rated blitz game\"]\n[Result \"*\"]\n\n1. d4 *\n";
+    let server = serve(vec![RATE_LIMITED.to_string(), ok(pgn)]);
+    let client = LichessClient::new().with_base_url(&server).with_retries(1, Duration::from_millis(10));
+    let games: Vec<_> = client.user_games("someone", &ExportOptions::default()).unwrap()
+        .collect::<Result<_, _>>().unwrap();
+    assert_eq!(games.len(), 2);
+    assert_eq!(games[0].moves.len(), 7);
+
+    let server = serve(vec![RATE_LIMITED.to_string(), RATE_LIMITED.to_string()]);
+    let client = LichessClient::new().with_base_url(&server).with_retries(1, Duration::from_millis(10));
+    assert!(client.user_games("someone", &ExportOptions::default()).is_err());
+}
+
+#[test]
+fn test_user_games_json() {
+    let ndjson = "{\"id\":\"abc\",\"moves\":\"e4 e5\"}\n\n{\"id\":\"def\",\"moves\":\"d4\"}\n";
+    let server = serve(vec![ok(ndjson)]);
+    let client = LichessClient::new().with_base_url(&server);
+    let games: Vec<_> = client.user_games_json("someone", &ExportOptions::default()).unwrap()
+        .collect::<Result<_, _>>().unwrap();
+    assert_eq!(games.len(), 2);
+    assert_eq!(games[1]["id"], "def");
+}
@@ -0,0 +1,100 @@
+use move_generation::magic::{bishop_attacks, global, init, install, queen_attacks, rook_attacks, AttackTables};
+use move_generation::move_gen::{
+    attackers, attackers_with, generate_moves, generate_moves_with, is_square_attacked, is_square_attacked_with, Bitboard,
+};
+use move_generation::pgn::Position;
+use move_generation::types::{Board, Color};
+
+/// Attacks by walking the rays square by square
+fn ray_attacks(sq: u8, occupied: u64, directions: &[(i8, i8)]) -> u64 {
+    let mut attacks = 0;
+    for &(dr, df) in directions {
+        let (mut r, mut f) = ((sq / 8) as i8 + dr, (sq % 8) as i8 + df);
+        while (0..8).contains(&r) && (0..8).contains(&f) {
+            attacks |= 1 << (r * 8 + f);
+            if occupied & (1 << (r * 8 + f)) != 0 {
+                break;
+            }
+            r += dr;
+            f += df;
+        }
+    }
+    attacks
+}
+
+#[test]
+fn test_magic_attacks_match_ray_walk() {
+    let rook = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+    let bishop = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+    let mut state = 0x1234_5678_9abc_def0u64;
+    for _ in 0..2000 {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        let occupied = state & (state >> 3);
+        for sq in 0..64u8 {
+            let bb = Bitboard::from(occupied);
+            assert_eq!(u64::from(rook_attacks(sq, bb)), ray_attacks(sq, occupied, &rook));
+            assert_eq!(u64::from(bishop_attacks(sq, bb)), ray_attacks(sq, occupied, &bishop));
+        }
+    }
+}
+
+#[test]
+fn test_magic_attacks_examples() {
+    // Rook on a1 of an empty board sees the a-file and the first rank
+    assert_eq!(u64::from(rook_attacks(0, Bitboard::empty())), 0x0101_0101_0101_01fe);
+    // Blockers on a3 and c1 are attacked and stop the rays
+    let blockers = Bitboard::from_square(16) | Bitboard::from_square(2);
+    assert_eq!(u64::from(rook_attacks(0, blockers)), 0x1_0106);
+    // Queen on d4 of an empty board: 14 rook squares and 13 bishop squares
+    assert_eq!(u64::from(queen_attacks(27, Bitboard::empty())).count_ones(), 27);
+}
+
+#[test]
+fn test_attack_tables_handle() {
+    let tables = AttackTables::new();
+    let shared = tables.clone();
+    assert!(tables.ptr_eq(&shared));
+    assert!(!tables.ptr_eq(global()));
+    assert_eq!(tables.size_bytes(), 107648 * 8);
+    let blockers = Bitboard::from_square(16) | Bitboard::from_square(2);
+    assert_eq!(shared.rook_attacks(0, blockers), rook_attacks(0, blockers));
+    assert_eq!(shared.queen_attacks(27, blockers), queen_attacks(27, blockers));
+
+    let board = Board::from("4k3/8/8/8/8/8/1q6/4K3 w - - 0 1".parse::<Position>().unwrap());
+    for sq in 0..64 {
+        assert_eq!(is_square_attacked_with(&board, sq, Color::White, &tables), is_square_attacked(&board, sq, Color::White));
+        assert_eq!(attackers_with(&board, sq, Color::Black, &tables), attackers(&board, sq, Color::Black));
+    }
+    let board = Board::from("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1".parse::<Position>().unwrap());
+    assert_eq!(generate_moves_with(&board, Color::White, &tables), generate_moves(&board, Color::White));
+
+    // The global tables exist once anything has looked an attack up
+    init();
+    assert!(install(tables).is_err());
+}
+
+#[cfg(feature = "magic_finder")]
+#[test]
+fn test_find_magics() {
+    use move_generation::magic_search::{find_magic_for, find_magics, to_bytes, to_rust_source, Rng};
+
+    let magics = find_magics(7);
+    assert_eq!(magics.len(), 128);
+    assert_eq!(magics, find_magics(7));
+    // Rook and bishop tables packed one after the other
+    let last = magics.last().unwrap();
+    assert!(last.bishop && last.offset + (1 << (64 - last.shift)) == 107648);
+    assert!(to_rust_source(&magics).ends_with("pub(crate) const TABLE_SIZE: usize = 107648;\n"));
+    assert_eq!(to_bytes(&magics).len(), 128 * 21);
+
+    // Attacks that differ for every occupancy of d4 and f4 need four slots
+    let mask = (1 << 27) | (1 << 29);
+    let magic = find_magic_for(mask, |occupied| occupied ^ mask, &mut Rng::new(1));
+    let index = |occupied: u64| occupied.wrapping_mul(magic) >> 62;
+    let mut seen: Vec<u64> = [0, 1 << 27, 1 << 29, mask].into_iter().map(index).collect();
+    seen.sort();
+    seen.dedup();
+    assert_eq!(seen.len(), 4);
+}
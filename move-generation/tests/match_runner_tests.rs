@@ -0,0 +1,215 @@
+use move_generation::clock::TimeControl;
+use move_generation::match_runner::{
+    openings_from_pgn, play_game, run_match, Adjudication, MatchConfig, MoveChoice, MoveRequest, Opening, Player,
+    SearchPlayer, UciEngine,
+};
+use move_generation::pgn::{ChessError, PgnGameReader};
+use move_generation::rules::generate_legal_moves;
+use move_generation::score::Score;
+use move_generation::search::{SearchLimits, SearchOptions};
+use move_generation::stats::{Sprt, SprtStatus};
+use move_generation::tune::EvalParams;
+use move_generation::types::Move;
+
+/// Plays the first legal move, or forfeits when `forfeit(game, moves)` says
+/// so, `game` and `moves` counting from 0
+struct Scripted {
+    name: &'static str,
+    score: Option<Score>,
+    forfeit: fn(usize, usize) -> bool,
+    games: usize,
+    moves: usize,
+}
+
+impl Scripted {
+    fn new(name: &'static str, forfeit: fn(usize, usize) -> bool) -> Self {
+        Scripted { name, score: None, forfeit, games: 0, moves: 0 }
+    }
+}
+
+impl Player for Scripted {
+    fn name(&self) -> String {
+        self.name.to_string()
+    }
+
+    fn new_game(&mut self) -> Result<(), ChessError> {
+        self.games += 1;
+        self.moves = 0;
+        Ok(())
+    }
+
+    fn choose_move(&mut self, request: &MoveRequest) -> Result<MoveChoice, ChessError> {
+        self.moves += 1;
+        if (self.forfeit)(self.games - 1, self.moves - 1) {
+            return Err(ChessError::IllegalMove("forfeit".to_string()));
+        }
+        let mv = generate_legal_moves(request.board)[0].clone();
+        Ok(MoveChoice { mv, score: self.score, pv: Vec::new() })
+    }
+}
+
+fn search_player(name: &str) -> SearchPlayer<EvalParams> {
+    SearchPlayer::new(name, EvalParams::default(), SearchOptions::default())
+}
+
+#[test]
+fn test_match_alternates_colors_and_openings() {
+    let config = MatchConfig {
+        games: 4,
+        limits: SearchLimits { depth: Some(1), ..SearchLimits::default() },
+        openings: vec![
+            Opening { fen: None, moves: vec!["d2d4".to_string()] },
+            Opening::from_epd("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - bm e5;").unwrap(),
+        ],
+        adjudication: Adjudication { max_plies: Some(10), ..Adjudication::default() },
+        event: "Test".to_string(),
+        ..MatchConfig::default()
+    };
+    let (mut first, mut second) = (search_player("first"), search_player("second"));
+    let mut games = Vec::new();
+    let progress = run_match(&config, &mut first, &mut second, |game, _| games.push(game.clone())).unwrap();
+
+    assert_eq!(progress.wdl.games(), 4);
+    assert_eq!(progress.sprt, None);
+    let players: Vec<_> = games.iter().map(|g| (g.game.header("White").unwrap(), g.first_is_white)).collect();
+    assert_eq!(players, [("first", true), ("second", false), ("first", true), ("second", false)]);
+    assert_eq!(games[0].game.moves[0], "d4");
+    assert_eq!(games[2].game.header("FEN"), Some("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1"));
+    for game in &games {
+        assert_eq!(game.game.header("Event"), Some("Test"));
+        assert_eq!(game.game.header("Round"), Some(game.round.to_string().as_str()));
+        assert_eq!(game.game.header("Termination"), Some("adjudication"));
+        assert_eq!(game.game.result.as_deref(), Some("1/2-1/2"));
+        assert_eq!(game.game.evals.len(), game.game.moves.len());
+        assert!(game.game.evals[1..].iter().all(Option::is_some));
+        assert!(game.game.replay().is_ok());
+    }
+    assert_eq!(games[1].game.moves.len(), 10);
+    assert_eq!(games[2].game.moves.len(), 10);
+    assert!(games[2].game.to_pgn().contains("\n\n1... "));
+}
+
+#[test]
+fn test_forfeits_and_illegal_moves_lose() {
+    let config = MatchConfig { games: 1, ..MatchConfig::default() };
+    let mut white = Scripted::new("white", |_, moves| moves == 1);
+    let mut black = Scripted::new("black", |_, _| false);
+    let game = play_game(&config, &mut white, &mut black, &Opening::default(), 1).unwrap();
+    assert_eq!(game.result.as_deref(), Some("0-1"));
+    assert_eq!(game.header("Termination"), Some("rules infraction"));
+    assert_eq!(game.moves.len(), 2);
+
+    struct Illegal;
+    impl Player for Illegal {
+        fn name(&self) -> String {
+            "illegal".to_string()
+        }
+        fn choose_move(&mut self, _: &MoveRequest) -> Result<MoveChoice, ChessError> {
+            let mv = Move { from: 12, to: 44, promotion: None, drop: None };
+            Ok(MoveChoice { mv, score: None, pv: Vec::new() })
+        }
+    }
+    let game = play_game(&config, &mut Illegal, &mut black, &Opening::default(), 1).unwrap();
+    assert_eq!(game.result.as_deref(), Some("0-1"));
+    assert_eq!(game.header("Termination"), Some("rules infraction"));
+}
+
+#[test]
+fn test_resign_adjudication() {
+    let config = MatchConfig {
+        games: 1,
+        adjudication: Adjudication { resign_cp: Some(400), resign_moves: 2, ..Adjudication::default() },
+        ..MatchConfig::default()
+    };
+    let mut white = Scripted { score: Some(Score::Cp(-500)), ..Scripted::new("white", |_, _| false) };
+    let mut black = Scripted { score: Some(Score::Cp(500)), ..Scripted::new("black", |_, _| false) };
+    let game = play_game(&config, &mut white, &mut black, &Opening::default(), 1).unwrap();
+    assert_eq!(game.result.as_deref(), Some("0-1"));
+    assert_eq!(game.header("Termination"), Some("adjudication"));
+    assert_eq!(game.moves.len(), 4);
+}
+
+#[test]
+fn test_sprt_stops_the_match() {
+    let config = MatchConfig { games: 1000, sprt: Some(Sprt::new(0.0, 100.0, 0.05, 0.05)), ..MatchConfig::default() };
+    // The first player loses every third game and wins the others
+    let mut first = Scripted::new("first", |game, _| game % 3 == 0);
+    let mut second = Scripted::new("second", |_, moves| moves == 1);
+    let mut reports = 0;
+    let progress = run_match(&config, &mut first, &mut second, |_, _| reports += 1).unwrap();
+    let sprt = progress.sprt.unwrap();
+    assert_eq!(sprt.status, SprtStatus::AcceptH1);
+    assert_eq!(reports, progress.wdl.games());
+    assert!(progress.wdl.games() < 1000);
+    assert!(progress.wdl.wins > progress.wdl.losses && progress.wdl.losses > 0);
+}
+
+#[test]
+fn test_games_end_on_mate_and_repetition() {
+    let config = MatchConfig { games: 1, ..MatchConfig::default() };
+    let mate = Opening { fen: None, moves: ["f2f3", "e7e5", "g2g4", "d8h4"].map(String::from).to_vec() };
+    let (mut white, mut black) = (Scripted::new("white", |_, _| false), Scripted::new("black", |_, _| false));
+    let game = play_game(&config, &mut white, &mut black, &mate, 1).unwrap();
+    assert_eq!(game.result.as_deref(), Some("0-1"));
+    assert_eq!(game.header("Termination"), Some("normal"));
+    assert_eq!(game.moves.last().map(String::as_str), Some("Qh4#"));
+
+    let shuffle = ["g1f3", "g8f6", "f3g1", "f6g8", "g1f3", "g8f6", "f3g1", "f6g8"];
+    let repetition = Opening { fen: None, moves: shuffle.map(String::from).to_vec() };
+    let game = play_game(&config, &mut white, &mut black, &repetition, 1).unwrap();
+    assert_eq!(game.result.as_deref(), Some("1/2-1/2"));
+    assert_eq!(game.moves.len(), 8);
+}
+
+#[test]
+fn test_openings_from_pgn() {
+    let pgn = "[Event \"a\"]\n\n1. e4 e5 2. Nf3 Nc6 *\n\n\
+        [FEN \"4k3/8/8/8/8/8/4P3/4K3 w - - 0 1\"]\n[SetUp \"1\"]\n\n1. e4 *\n";
+    let openings = openings_from_pgn(PgnGameReader::new(pgn.as_bytes()), 3).unwrap();
+    assert_eq!(openings[0], Opening { fen: None, moves: vec!["e2e4".into(), "e7e5".into(), "g1f3".into()] });
+    assert_eq!(openings[1].fen.as_deref(), Some("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"));
+    assert_eq!(openings[1].moves, ["e2e4"]);
+    assert!(Opening::from_epd("8/8 w").is_err());
+}
+
+#[test]
+fn test_uci_engine_plays_a_clocked_game() {
+    let mut engine = UciEngine::spawn(env!("CARGO_BIN_EXE_uci"), &[]).unwrap();
+    assert!(engine.name().starts_with("move_generation"));
+    engine.set_option("Hash", "1").unwrap();
+    let config = MatchConfig {
+        games: 2,
+        time_control: Some("2+0.05".parse::<TimeControl>().unwrap()),
+        adjudication: Adjudication { max_plies: Some(8), ..Adjudication::default() },
+        ..MatchConfig::default()
+    };
+    let mut builtin = search_player("builtin");
+    let mut games = Vec::new();
+    run_match(&config, &mut engine, &mut builtin, |game, _| games.push(game.game.clone())).unwrap();
+    for game in &games {
+        assert_eq!(game.header("TimeControl"), Some("2+0.05"));
+        assert_eq!(game.header("Termination"), Some("adjudication"));
+        assert_eq!(game.moves.len(), 8);
+        assert!(game.evals.iter().all(Option::is_some));
+    }
+}
+
+#[test]
+fn test_uci_engine_is_stopped_at_its_deadline() {
+    use move_generation::pgn::starting_board;
+    use move_generation::types::Variant;
+    use std::time::{Duration, Instant};
+
+    // Answers the handshake, then only moves once told to stop
+    let script = "while read line; do case \"$line\" in \
+        uci) echo uciok;; isready) echo readyok;; go*) echo 'info depth 1';; stop) echo 'bestmove e2e4';; \
+        esac; done";
+    let mut engine = UciEngine::spawn("sh", &["-c", script]).unwrap().with_timeout(Duration::from_millis(100));
+    let board = starting_board(Variant::Standard);
+    let limits = SearchLimits { depth: Some(30), ..SearchLimits::default() };
+    let request = MoveRequest { start: &board, moves: &[], board: &board, limits: &limits, clock: None };
+    let start = Instant::now();
+    let choice = engine.choose_move(&request).unwrap();
+    assert_eq!(choice.mv, move_generation::notation::parse_uci(&board, "e2e4").unwrap());
+    assert!(start.elapsed() < Duration::from_secs(10));
+}
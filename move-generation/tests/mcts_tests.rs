@@ -0,0 +1,127 @@
+use move_generation::encoding::encode_move;
+use move_generation::mcts::{LeafEval, Mcts, MctsConfig, POLICY_SIZE};
+use move_generation::notation::parse_uci;
+use move_generation::pgn::ChessError;
+use move_generation::tune::EvalParams;
+use move_generation::types::{Board, Move};
+
+mod common;
+
+use common::board_from_fen;
+
+#[test]
+fn test_mcts_finds_mate_with_batches() {
+    let root = board_from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+    let mut mcts = Mcts::new(root.clone(), MctsConfig { batch_size: 8, ..Default::default() });
+    let mut batches = Vec::new();
+    mcts.run(400, |boards| {
+        batches.push(boards.len());
+        Ok::<_, ChessError>(boards.iter().map(|_| LeafEval { value: 0.0, policy: None }).collect())
+    }).unwrap();
+
+    assert!(mcts.playouts() >= 400);
+    assert!(batches.iter().all(|&n| (1..=8).contains(&n)));
+    assert!(batches.iter().any(|&n| n > 1), "leaves were never batched: {:?}", batches);
+    assert_eq!(mcts.best_move(), Some(parse_uci(&root, "a1a8").unwrap()));
+    assert!(mcts.root_moves()[0].2 > 0.9);
+}
+
+#[test]
+fn test_mcts_collect_and_submit() {
+    let root = board_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    let favourite = parse_uci(&root, "g1f3").unwrap();
+    let mut policy = vec![0.0; POLICY_SIZE];
+    policy[encode_move(&favourite) as usize] = 1.0;
+
+    let mut mcts = Mcts::new(root, MctsConfig { batch_size: 4, ..Default::default() });
+    let leaves = mcts.collect().unwrap();
+    assert_eq!(leaves.len(), 1);
+    assert!(mcts.collect().is_err());
+    assert!(mcts.submit(Vec::new()).is_err());
+    assert!(mcts.submit(vec![LeafEval { value: 0.0, policy: Some(vec![0.0; 10]) }]).is_err());
+    mcts.submit(vec![LeafEval { value: 0.0, policy: Some(policy.clone()) }]).unwrap();
+
+    for _ in 0..10 {
+        let leaves = mcts.collect().unwrap();
+        let evals = leaves.iter().map(|_| LeafEval { value: 0.0, policy: Some(policy.clone()) }).collect();
+        mcts.submit(evals).unwrap();
+    }
+    assert_eq!(mcts.best_move(), Some(favourite));
+}
+
+#[test]
+fn test_mcts_with_evaluator() {
+    let root = board_from_fen("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1");
+    let mut mcts = Mcts::new(root.clone(), MctsConfig::default());
+    mcts.run_with_evaluator(200, &EvalParams::default()).unwrap();
+    assert_eq!(mcts.best_move(), Some(parse_uci(&root, "d2d5").unwrap()));
+}
+
+#[test]
+fn test_mcts_root_noise_and_temperature() {
+    let root = board_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    let search = |seed, dirichlet_epsilon, temperature| {
+        let config = MctsConfig { dirichlet_epsilon, temperature, seed: Some(seed), ..Default::default() };
+        let mut mcts = Mcts::new(root.clone(), config);
+        mcts.run(200, |boards| Ok::<_, ChessError>(boards.iter().map(|_| LeafEval { value: 0.0, policy: None }).collect())).unwrap();
+        mcts
+    };
+
+    // Without noise every root move gets the same prior and close visit counts
+    let visits = |mcts: &Mcts| mcts.root_moves().iter().map(|&(_, visits, _)| visits).collect::<Vec<u32>>();
+    let plain = visits(&search(1, 0.0, 0.0));
+    assert!(plain[0] - plain[19] <= 2, "{:?}", plain);
+    let noisy = visits(&search(1, 0.5, 0.0));
+    assert!(noisy[0] - noisy[19] > 5, "{:?}", noisy);
+    assert_eq!(noisy, visits(&search(1, 0.5, 0.0)));
+
+    let mut sampling = search(2, 0.5, 1.0);
+    let first = sampling.choose_move().unwrap();
+    assert!((0..50).any(|_| sampling.choose_move().unwrap() != first));
+    let mut greedy = search(2, 0.5, 0.0);
+    assert_eq!(greedy.choose_move(), greedy.best_move());
+
+    let target = sampling.policy_target();
+    assert!((target.iter().sum::<f32>() - 1.0).abs() < 1e-4);
+    assert_eq!(target.iter().filter(|&&p| p > 0.0).count(), 20);
+}
+
+#[test]
+fn test_mcts_advance_keeps_subtree() {
+    let root = board_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    let mut policy = vec![0.0; POLICY_SIZE];
+    let e4 = parse_uci(&root, "e2e4").unwrap();
+    policy[encode_move(&e4) as usize] = 1.0;
+    let evaluate = |boards: &[Board]| Ok::<_, ChessError>(boards.iter().map(|_| LeafEval { value: 0.0, policy: Some(policy.clone()) }).collect());
+
+    let mut mcts = Mcts::new(root.clone(), MctsConfig::default());
+    mcts.run(300, evaluate).unwrap();
+    let (_, e4_visits, _) = mcts.root_moves()[0].clone();
+    mcts.advance(&e4).unwrap();
+    assert_eq!(mcts.playouts(), e4_visits);
+    let mut after = root.clone();
+    after.make_move(&e4);
+    assert!(mcts.root().eq_strict(&after));
+    assert_eq!(mcts.root_moves().iter().map(|&(_, visits, _)| visits).sum::<u32>(), e4_visits - 1);
+
+    mcts.run(e4_visits + 100, evaluate).unwrap();
+    let reply = mcts.best_move().unwrap();
+    mcts.advance(&reply).unwrap();
+    assert!(mcts.advance(&e4).is_err());
+
+    // A move that was never expanded starts a fresh tree
+    let mut cold = Mcts::new(root.clone(), MctsConfig::default());
+    cold.advance(&parse_uci(&root, "g1f3").unwrap()).unwrap();
+    assert_eq!(cold.playouts(), 0);
+}
+
+#[test]
+fn test_mcts_advance_rejects_illegal_moves() {
+    let root = board_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    let mut mcts = Mcts::new(root.clone(), MctsConfig::default());
+    let e2e6 = Move { from: 12, to: 44, promotion: None, drop: None };
+    let e7e5 = Move { from: 52, to: 36, promotion: None, drop: None };
+    assert!(matches!(mcts.advance(&e2e6), Err(ChessError::IllegalMove(_))));
+    assert!(matches!(mcts.advance(&e7e5), Err(ChessError::IllegalMove(_))));
+    assert!(mcts.root().eq_strict(&root));
+}
@@ -1,15 +1,17 @@
-use move_generation::types::{Board, Color, Piece};
-use move_generation::move_gen::{generate_moves};
+use move_generation::types::{Board, Color, Move, Piece};
+use move_generation::move_gen::{
+    attacked_mask, generate_captures, generate_captures_into, generate_moves, generate_moves_into, generate_pseudo_legal,
+    generate_quiets, generate_quiets_into, is_square_attacked, sort_moves, MoveList,
+};
+use move_generation::rules::{generate_legal_moves, generate_legal_moves_into};
+use move_generation::pgn::Position;
 
 #[test]
 fn test_pawn_moves() {
-    let mut board = Board {
-        squares: [None; 64],
-        side_to_move: Color::White,
-    };
+    let mut board = Board::empty();
 
     // Place a white pawn at e2 (square 12)
-    board.squares[12] = Some((Piece::Pawn, Color::White));
+    board.put(12, Piece::Pawn, Color::White);
 
     let moves = generate_moves(&board, Color::White);
 
@@ -29,13 +31,10 @@ fn test_pawn_moves() {
 
 #[test]
 fn test_knight_moves() {
-    let mut board = Board {
-        squares: [None; 64],
-        side_to_move: Color::White,
-    };
+    let mut board = Board::empty();
 
     // Place a white knight at b1 (square 1)
-    board.squares[1] = Some((Piece::Knight, Color::White));
+    board.put(1, Piece::Knight, Color::White);
 
     let moves = generate_moves(&board, Color::White);
 
@@ -52,13 +51,10 @@ fn test_knight_moves() {
 
 #[test]
 fn test_rook_moves() {
-    let mut board = Board {
-        squares: [None; 64],
-        side_to_move: Color::White,
-    };
+    let mut board = Board::empty();
 
     // Place a white rook at a1 (square 0)
-    board.squares[0] = Some((Piece::Rook, Color::White));
+    board.put(0, Piece::Rook, Color::White);
 
     let moves = generate_moves(&board, Color::White);
 
@@ -84,3 +80,107 @@ fn test_rook_moves() {
         assert!(moves.iter().any(|m| m.from == from && m.to == to));
     }
 }
+
+#[test]
+fn test_captures_and_quiets_partition_moves() {
+    let position: Position = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1".parse().unwrap();
+    let board = Board::from(position);
+
+    let captures = generate_captures(&board, Color::White);
+    let quiets = generate_quiets(&board, Color::White);
+    assert!(captures.iter().all(|m| board.squares()[m.to as usize].is_some()));
+    assert!(quiets.iter().all(|m| board.squares()[m.to as usize].is_none() || board.is_castling(m)));
+
+    let mut combined: Vec<(u8, u8)> = captures.iter().chain(&quiets).map(|m| (m.from, m.to)).collect();
+    let mut all: Vec<(u8, u8)> = generate_moves(&board, Color::White).iter().map(|m| (m.from, m.to)).collect();
+    combined.sort();
+    all.sort();
+    assert_eq!(combined, all);
+}
+
+#[test]
+fn test_generate_into_reused_buffer() {
+    let kiwipete = Board::from("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1".parse::<Position>().unwrap());
+    let start = Board::from("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".parse::<Position>().unwrap());
+    let mut list = MoveList::new();
+
+    // Each call replaces what the buffer held before
+    for board in [&kiwipete, &start, &kiwipete] {
+        generate_moves_into(board, Color::White, &mut list);
+        assert_eq!(list.to_vec(), generate_moves(board, Color::White));
+        generate_captures_into(board, Color::White, &mut list);
+        assert_eq!(list.to_vec(), generate_captures(board, Color::White));
+        generate_quiets_into(board, Color::White, &mut list);
+        assert_eq!(list.to_vec(), generate_quiets(board, Color::White));
+        generate_legal_moves_into(board, &mut list);
+        assert_eq!(list.to_vec(), generate_legal_moves(board));
+    }
+    assert_eq!(list.len(), 48);
+}
+
+#[test]
+fn test_pseudo_legal_for_either_side() {
+    // The pinned knight on e2 still has pseudo-legal moves
+    let position: Position = "4r1k1/8/8/8/8/8/4N3/4K3 w - - 0 1".parse().unwrap();
+    let board = Board::from(position);
+    let white = generate_pseudo_legal(&board, Color::White);
+    assert!(white.iter().any(|m| m.from == 12));
+
+    // Black pieces move correctly even though White is to move
+    let position: Position = "4k3/4p3/8/8/8/8/8/4K3 w - - 0 1".parse().unwrap();
+    let board = Board::from(position);
+    let mut black: Vec<(u8, u8)> = generate_pseudo_legal(&board, Color::Black).iter().map(|m| (m.from, m.to)).collect();
+    black.sort();
+    assert_eq!(black, vec![(52, 36), (52, 44), (60, 51), (60, 53), (60, 59), (60, 61)]);
+}
+
+#[test]
+fn test_board_bitboards() {
+    let position: Position = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".parse().unwrap();
+    let board = Board::from(position);
+    assert_eq!(u64::from(board.occupied()), 0xFFFF_0000_0000_FFFF);
+    assert_eq!(u64::from(board.occupied_by(Color::White)), 0xFFFF);
+    assert_eq!(board.pieces(Piece::Knight, Color::Black).bits(), vec![57, 62]);
+}
+
+#[test]
+fn test_attacked_mask_matches_square_queries() {
+    for fen in move_generation::bench::BENCH_FENS.iter().chain(&[
+        "8/8/8/3k4/8/8/8/R3K2R w KQ - 0 1",
+        "7q/1n6/8/2P5/8/5b2/6P1/K1r4N b - - 0 1",
+        "rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3",
+    ]) {
+        let board = Board::from(fen.parse::<Position>().unwrap());
+        for color in [Color::White, Color::Black] {
+            let mask = attacked_mask(&board, color);
+            for sq in 0..64 {
+                assert_eq!(mask.contains(sq), is_square_attacked(&board, sq, color.opposite()), "{} {:?} {}", fen, color, sq);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_sort_moves() {
+    let board = Board::from("4k3/1P6/8/8/8/8/8/R3K2R w KQ - 0 1".parse::<Position>().unwrap());
+    let mut moves = generate_moves(&board, Color::White);
+    let count = moves.len();
+    moves.extend(moves.clone());
+    moves.reverse();
+    sort_moves(&mut moves);
+    assert_eq!(moves.len(), count);
+    assert!(moves.windows(2).all(|pair| (pair[0].from, pair[0].to) <= (pair[1].from, pair[1].to)));
+
+    // Promotions on one square come in piece order
+    let promotions: Vec<Option<Piece>> = moves.iter().filter(|mv| mv.from == 49 && mv.to == 57).map(|mv| mv.promotion).collect();
+    assert_eq!(promotions, [Some(Piece::Knight), Some(Piece::Bishop), Some(Piece::Rook), Some(Piece::Queen)]);
+
+    let mut moves = vec![
+        Move { from: 12, to: 28, promotion: None, drop: None },
+        Move { from: 6, to: 21, promotion: None, drop: None },
+        Move { from: 12, to: 20, promotion: None, drop: None },
+        Move { from: 6, to: 21, promotion: None, drop: None },
+    ];
+    sort_moves(&mut moves);
+    assert_eq!(moves.iter().map(|mv| (mv.from, mv.to)).collect::<Vec<_>>(), [(6, 21), (12, 20), (12, 28)]);
+}
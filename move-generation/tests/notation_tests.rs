@@ -0,0 +1,163 @@
+use move_generation::move_gen::{generate_moves_with_promotions, Promotions};
+use move_generation::notation::{parse_san, parse_uci, parse_uci_promoting, replay_uci, sans_for_game, to_san, to_standard_uci, to_uci, ucis_for_game};
+use move_generation::perft::perft;
+use move_generation::pgn::{ChessError, Position};
+use move_generation::types::{name_to_square, square_to_name, Color, Move, Piece};
+
+mod common;
+
+use common::board_from_fen;
+
+#[test]
+fn test_promotion_generation_options() {
+    let board = board_from_fen("3k4/P7/8/8/8/8/8/4K3 w - - 0 1");
+    let promotions = |option| {
+        generate_moves_with_promotions(&board, Color::White, option)
+            .into_iter()
+            .filter(|m| m.from == 48)
+            .map(|m| to_uci(&m))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(promotions(Promotions::All), vec!["a7a8q", "a7a8r", "a7a8b", "a7a8n"]);
+    assert_eq!(promotions(Promotions::QueenAndKnight), vec!["a7a8q", "a7a8n"]);
+    assert_eq!(promotions(Promotions::QueenOnly), vec!["a7a8q"]);
+
+    // Promotions, including captures that promote, count toward perft
+    let board = board_from_fen("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8");
+    assert_eq!(perft(&board, 1), 44);
+    assert_eq!(perft(&board, 2), 1486);
+}
+
+#[test]
+fn test_parse_uci_promotions() {
+    let board = board_from_fen("3k4/P7/8/8/8/8/8/4K3 w - - 0 1");
+    let knight = parse_uci(&board, "a7a8n").unwrap();
+    assert_eq!(knight, Move { from: 48, to: 56, promotion: Some(Piece::Knight), drop: None });
+    assert_eq!(to_uci(&knight), "a7a8n");
+
+    assert!(matches!(parse_uci(&board, "a7a8"), Err(ChessError::IllegalMove(_))));
+    assert!(matches!(parse_uci(&board, "a7a8k"), Err(ChessError::ParseError(_))));
+    assert!(matches!(parse_uci(&board, "e1e2q"), Err(ChessError::IllegalMove(_))));
+    assert!(matches!(parse_uci(&board, "z9e2"), Err(ChessError::ParseError(_))));
+}
+
+#[test]
+fn test_parse_san() {
+    let board = board_from_fen("3k4/P7/8/8/8/8/8/4K3 w - - 0 1");
+    assert_eq!(parse_san(&board, "a8=N+").unwrap().promotion, Some(Piece::Knight));
+    assert_eq!(parse_san(&board, "a8R").unwrap().promotion, Some(Piece::Rook));
+    assert!(parse_san(&board, "a8").is_err());
+
+    let board = board_from_fen("r3k2r/8/8/8/8/8/8/RN2K2R w KQkq - 0 1");
+    assert_eq!(parse_san(&board, "O-O").unwrap(), Move { from: 4, to: 7, promotion: None, drop: None });
+    assert!(parse_san(&board, "O-O-O").is_err()); // b1 knight is in the way
+    assert_eq!(parse_san(&board, "Rxh8").unwrap().to, 63);
+
+    // Both knights reach d2
+    let board = board_from_fen("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1");
+    assert!(matches!(parse_san(&board, "Nd2"), Err(ChessError::ParseError(_))));
+    assert_eq!(parse_san(&board, "Nbd2").unwrap().from, 1);
+    assert_eq!(parse_san(&board, "Nfd2").unwrap().from, 5);
+}
+
+#[test]
+fn test_castling_notation() {
+    let board = board_from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+    let castle = Move { from: 4, to: 7, promotion: None, drop: None };
+    assert_eq!(parse_uci(&board, "e1g1").unwrap(), castle);
+    assert_eq!(parse_uci(&board, "e1h1").unwrap(), castle);
+    assert_eq!(to_standard_uci(&board, &castle), "e1g1");
+    assert_eq!(to_uci(&castle), "e1h1");
+
+    // Chess960: the king on g1 castles with both rooks
+    let board = board_from_fen("1r4kr/8/8/8/8/8/8/1R4KR w KQkq - 0 1");
+    let kingside = parse_san(&board, "O-O").unwrap();
+    assert_eq!(to_standard_uci(&board, &kingside), "g1h1");
+    let mut after = board.clone();
+    after.make_move(&parse_san(&board, "O-O-O").unwrap());
+    assert_eq!(Position::from(&after).to_fen(), "1r4kr/8/8/8/8/8/8/2KR3R b kq - 1 1");
+}
+
+#[test]
+fn test_to_san() {
+    let san = |fen: &str, uci: &str| {
+        let board = board_from_fen(fen);
+        to_san(&board, &parse_uci(&board, uci).unwrap())
+    };
+    assert_eq!(san("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1", "b1d2"), "Nbd2");
+    assert_eq!(san("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1", "f1g3"), "Ng3");
+    assert_eq!(san("4k3/8/8/1R6/8/8/8/1R2K3 w - - 0 1", "b1b3"), "R1b3");
+    assert_eq!(san("4k3/8/5Q2/8/8/5Q2/8/3QK3 w - - 0 1", "f3d5"), "Qfd5");
+    assert_eq!(san("8/5Q2/8/8/8/1Q3Q2/7k/4K3 w - - 0 1", "f3d5"), "Qf3d5");
+    assert_eq!(san("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6"), "exd6");
+    assert_eq!(san("3rk3/2P5/8/8/8/8/8/4K3 w - - 0 1", "c7d8n"), "cxd8=N");
+    assert_eq!(san("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1c1"), "O-O-O");
+    assert_eq!(san("rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq - 0 2", "d8h4"), "Qh4#");
+    assert_eq!(san("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", "a1a8"), "Ra8+");
+}
+
+#[test]
+fn test_replay_uci() {
+    let start = board_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    let positions = replay_uci(&start, &["e2e4", "e7e5", "g1f3"]).unwrap();
+    assert_eq!(positions.len(), 3);
+    assert_eq!(Position::from(&positions[1]).to_fen(), "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2");
+    assert_eq!(positions[2].side_to_move, Color::Black);
+    assert!(replay_uci::<&str>(&start, &[]).unwrap().is_empty());
+
+    let error = replay_uci(&start, &["e2e4", "e7e5", "e4e5"]).unwrap_err();
+    assert_eq!(error.to_string(), "Illegal move: e4e5 at ply 3");
+    let error = replay_uci(&start, &["e2e4", "e7"]).unwrap_err();
+    assert_eq!(error.to_string(), "Parse error: Invalid UCI move: e7 at ply 2");
+}
+
+#[test]
+fn test_parse_uci_promoting() {
+    let board = board_from_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1");
+    let queen = parse_uci_promoting(&board, "b7b8", Some(Piece::Queen)).unwrap();
+    assert_eq!(queen.promotion, Some(Piece::Queen));
+    // A named piece wins over the default
+    let knight = parse_uci_promoting(&board, "b7b8n", Some(Piece::Queen)).unwrap();
+    assert_eq!(knight.promotion, Some(Piece::Knight));
+    assert_eq!(parse_uci_promoting(&board, "b7b8", None).unwrap_err().to_string(), "Illegal move: b7b8 needs a promotion piece");
+    // Moves that are not promotions are left alone
+    assert_eq!(parse_uci_promoting(&board, "e1d2", Some(Piece::Queen)).unwrap().promotion, None);
+    assert!(parse_uci_promoting(&board, "e1e3", Some(Piece::Queen)).is_err());
+
+    let error = replay_uci(&board, &["e1d1", "e8d8", "b7b8"]).unwrap_err();
+    assert_eq!(error.to_string(), "Illegal move: b7b8 needs a promotion piece at ply 3");
+}
+
+#[test]
+fn test_square_names() {
+    assert_eq!(square_to_name(0), Some("a1"));
+    assert_eq!(square_to_name(28), Some("e4"));
+    assert_eq!(square_to_name(63), Some("h8"));
+    assert_eq!(square_to_name(64), None);
+    assert_eq!(name_to_square("e4"), Some(28));
+    assert_eq!(name_to_square("i1"), None);
+    assert_eq!(name_to_square("e9"), None);
+    assert_eq!(name_to_square("e44"), None);
+    for sq in 0..64 {
+        assert_eq!(square_to_name(sq).and_then(name_to_square), Some(sq));
+    }
+    assert_eq!(to_uci(&Move { from: 12, to: 64, promotion: None, drop: None }), "0000");
+}
+
+#[test]
+fn test_batch_game_conversion() {
+    let ucis = ["e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "g8f6", "e1g1"];
+    let sans = sans_for_game(None, &ucis).unwrap();
+    assert_eq!(sans, ["e4", "e5", "Nf3", "Nc6", "Bc4", "Nf6", "O-O"]);
+    assert_eq!(ucis_for_game(None, &sans).unwrap(), ucis);
+
+    let fen = "4k3/P7/8/8/8/8/8/4K3 w - - 0 1";
+    assert_eq!(sans_for_game(Some(fen), &["a7a8q", "e8d7"]).unwrap(), ["a8=Q+", "Kd7"]);
+    assert_eq!(ucis_for_game(Some(fen), &["a8=N"]).unwrap(), ["a7a8n"]);
+
+    let err = sans_for_game(None, &["e2e4", "e2e4"]).unwrap_err();
+    assert!(matches!(err, ChessError::IllegalMove(ref message) if message == "e2e4 at ply 2"), "{}", err);
+    let err = ucis_for_game(None, &["e4", "Nz9"]).unwrap_err();
+    assert!(matches!(err, ChessError::ParseError(ref message) if message.ends_with("at ply 2")), "{}", err);
+    assert!(sans_for_game(Some("not a fen"), &["e2e4"]).is_err());
+}
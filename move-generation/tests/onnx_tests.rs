@@ -0,0 +1,14 @@
+#![cfg(feature = "onnx")]
+
+use move_generation::onnx::ValueOutput;
+use move_generation::score::Score;
+
+#[test]
+fn test_value_output_to_score() {
+    assert_eq!(ValueOutput::ExpectedResult.to_score(0.0), Score::Cp(0));
+    let winning = ValueOutput::ExpectedResult.to_score(0.5);
+    assert!(winning > Score::Cp(250) && winning < Score::Cp(350), "{:?}", winning);
+    assert_eq!(ValueOutput::ExpectedResult.to_score(-0.5), -winning);
+    assert!(ValueOutput::ExpectedResult.to_score(1.0) < Score::Mate(100));
+    assert_eq!(ValueOutput::Centipawns.to_score(123.4), Score::Cp(123));
+}
@@ -0,0 +1,57 @@
+use move_generation::pawns::{pawn_structure, PawnTable};
+use move_generation::types::{name_to_square, Color, Move};
+use move_generation::zobrist::pawn_key;
+
+mod common;
+
+use common::board_from_fen;
+
+fn squares(names: &[&str]) -> Vec<u8> {
+    names.iter().map(|name| name_to_square(name).unwrap()).collect()
+}
+
+const FEN: &str = "4k3/6pp/8/5p2/3P4/2P1P3/P1P4P/4K3 w - - 0 1";
+
+#[test]
+fn test_pawn_structure() {
+    let structure = pawn_structure(&board_from_fen(FEN));
+    let white = structure.side(Color::White);
+    assert_eq!(white.doubled.bits(), squares(&["c2", "c3"]));
+    assert_eq!(white.isolated.bits(), squares(&["a2", "h2"]));
+    // e3 cannot be supported by d4 and e4 is covered by f5
+    assert_eq!(white.backward.bits(), squares(&["e3"]));
+    assert_eq!(white.passed.bits(), squares(&["a2", "c2", "c3", "d4"]));
+    assert_eq!(white.islands, 3);
+
+    let black = structure.side(Color::Black);
+    assert!(black.doubled.bits().is_empty());
+    assert!(black.isolated.bits().is_empty());
+    assert!(black.backward.bits().is_empty());
+    assert!(black.passed.bits().is_empty());
+    assert_eq!(black.islands, 1);
+
+    let empty = pawn_structure(&board_from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1"));
+    assert_eq!(empty.white.islands + empty.black.islands, 0);
+}
+
+#[test]
+fn test_pawn_table() {
+    let mut board = board_from_fen(FEN);
+    let mut table = PawnTable::new(16);
+    assert!(table.len() > 1);
+    let key = pawn_key(&board);
+    assert_eq!(table.probe(key), None);
+    assert_eq!(table.pawn_structure(&board), pawn_structure(&board));
+    assert_eq!(table.probe(key), Some(pawn_structure(&board)));
+
+    // Only pawns change the key
+    let sq = |name| name_to_square(name).unwrap();
+    board.make_move(&Move { from: sq("e1"), to: sq("d2"), promotion: None, drop: None });
+    assert_eq!(pawn_key(&board), key);
+    board.make_move(&Move { from: sq("f5"), to: sq("f4"), promotion: None, drop: None });
+    assert_ne!(pawn_key(&board), key);
+    assert_eq!(table.pawn_structure(&board), pawn_structure(&board));
+
+    table.clear();
+    assert_eq!(table.probe(key), None);
+}
@@ -0,0 +1,52 @@
+use move_generation::perft::{perft, perft_hashed, perft_many};
+
+mod common;
+
+use common::board_from_fen;
+
+#[test]
+fn test_perft_start_position() {
+    let board = board_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    assert_eq!(perft(&board, 1), 20);
+    assert_eq!(perft(&board, 2), 400);
+    assert_eq!(perft(&board, 3), 8902);
+}
+
+#[test]
+fn test_perft_position_3() {
+    // En passant captures here can uncover a rook check along the rank
+    let board = board_from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1");
+    assert_eq!(perft(&board, 1), 14);
+    assert_eq!(perft(&board, 2), 191);
+    assert_eq!(perft(&board, 3), 2812);
+    assert_eq!(perft(&board, 4), 43238);
+}
+
+#[test]
+fn test_perft_hashed_matches_perft() {
+    let kiwipete = board_from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+    assert_eq!(perft(&kiwipete, 1), 48);
+    assert_eq!(perft(&kiwipete, 2), 2039);
+    assert_eq!(perft_hashed(&kiwipete, 2, 1), 2039);
+
+    let start = board_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    assert_eq!(perft_hashed(&start, 4, 4), 197281);
+    // A single-entry table still gives the right count
+    assert_eq!(perft_hashed(&start, 3, 0), 8902);
+}
+
+#[test]
+fn test_perft_many() {
+    let fens = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "not a fen",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    ];
+    let nodes = perft_many(&fens, 2);
+    assert_eq!(nodes.len(), 4);
+    assert_eq!(nodes[0].as_ref().unwrap(), &400);
+    assert_eq!(nodes[1].as_ref().unwrap(), &2039);
+    assert!(nodes[2].is_err());
+    assert_eq!(nodes[3].as_ref().unwrap(), &264);
+}
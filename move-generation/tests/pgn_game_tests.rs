@@ -0,0 +1,243 @@
+use move_generation::pgn::{ChessError, Encoding, Eval, PgnGame, PgnGameReader, PgnReader, Position};
+use move_generation::types::{Color, Piece};
+
+const PGN: &str = r#"[Event "Casual"]
+[White "Alice"]
+[Black "Bob"]
+[Result "1-0"]
+
+1. e4 {best by test} e5 2. Bc4 (2. f4 exf4) Nc6 3. Qh5 $1 Nf6?? ; hangs mate
+4. Qxf7# 1-0
+
+[Event "Setup"]
+[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"]
+[Result "*"]
+
+1. e4 Kd7 2. Ke2 *
+"#;
+
+#[test]
+fn test_reads_games_and_skips_annotations() {
+    let games: Vec<_> = PgnGameReader::new(PGN.as_bytes()).collect::<Result<_, _>>().unwrap();
+    assert_eq!(games.len(), 2);
+
+    let first = &games[0];
+    assert_eq!(first.header("White"), Some("Alice"));
+    assert_eq!(first.result.as_deref(), Some("1-0"));
+    assert_eq!(first.moves.len(), 7);
+    assert_eq!(&first.moves[..4], ["e4", "e5", "Bc4", "Nc6"]);
+    assert_eq!(first.moves[5], "Nf6??");
+
+    assert_eq!(games[1].moves, ["e4", "Kd7", "Ke2"]);
+    assert_eq!(games[1].result.as_deref(), Some("*"));
+}
+
+#[test]
+fn test_replay() {
+    let mut reader = PgnGameReader::new(PGN.as_bytes());
+    let game = reader.next_game().unwrap().unwrap();
+    let positions = game.replay().unwrap();
+    assert_eq!(positions.len(), 7);
+    let (board, mv) = &positions[6];
+    assert_eq!(board.side_to_move, Color::White);
+    assert_eq!(board.squares()[mv.from as usize], Some((Piece::Queen, Color::White)));
+
+    // The second game starts from its FEN tag
+    let game = reader.next_game().unwrap().unwrap();
+    let (board, _) = &game.replay().unwrap()[0];
+    assert_eq!(Position::from(board).to_fen(), "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+    assert!(reader.next_game().unwrap().is_none());
+}
+
+#[test]
+fn test_replay_rejects_illegal_move() {
+    let pgn = "1. e4 e5 2. Ke3 *";
+    let game = PgnGameReader::new(pgn.as_bytes()).next().unwrap().unwrap();
+    assert!(game.replay().is_err());
+}
+
+#[test]
+fn test_eval_annotations() {
+    let pgn = "1. e4 { [%eval 0.17] [%clk 0:03:00] } 1... e5 { [%eval 0.2,18] }\n2. Qh5 Nc6 { [%eval\n#-4] } 0-1";
+    let game = PgnGameReader::new(pgn.as_bytes()).next().unwrap().unwrap();
+    assert_eq!(game.moves, ["e4", "e5", "Qh5", "Nc6"]);
+    assert_eq!(game.evals, [Some(Eval::Pawns(0.17)), Some(Eval::Pawns(0.2)), None, Some(Eval::Mate(-4))]);
+}
+
+#[test]
+fn test_validate_game() {
+    use move_generation::pgn::{validate_game, GameIssue};
+
+    let pgn = "[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n[White \"A\"]\n[Black \"B\"]\n\
+               [Result \"1-0\"]\n\n1. e4 e5 2. Bc4 Nc6 3. Qh5 Nf6 4. Qxf7# 1-0\n";
+    let game = PgnGameReader::new(pgn.as_bytes()).next().unwrap().unwrap();
+    assert!(validate_game(&game).is_empty());
+
+    let pgn = "[White \"A\"]\n[Result \"0-1\"]\n\n1. e4 e5 2. Ke3 Nc6 1-0\n";
+    let game = PgnGameReader::new(pgn.as_bytes()).next().unwrap().unwrap();
+    let issues = validate_game(&game);
+    assert_eq!(issues.iter().filter(|i| matches!(i, GameIssue::MissingTag(_))).count(), 5);
+    assert!(issues.contains(&GameIssue::IllegalMove {
+        ply: 3,
+        san: "Ke3".to_string(),
+        fen: "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2".to_string(),
+    }));
+    assert!(issues.contains(&GameIssue::ResultMismatch { header: "0-1".to_string(), movetext: "1-0".to_string() }));
+
+    let game = PgnGameReader::new("1. e4 e5".as_bytes()).next().unwrap().unwrap();
+    assert!(validate_game(&game).contains(&GameIssue::MissingTermination));
+}
+
+#[test]
+fn test_check_result_against_final_position() {
+    use move_generation::pgn::{check_result, GameIssue};
+
+    let read = |pgn: &str| PgnGameReader::new(pgn.as_bytes()).next().unwrap().unwrap();
+    let mate = "1. f3 e5 2. g4 Qh4#";
+    assert!(check_result(&read(&format!("[Result \"0-1\"]\n\n{} 0-1\n", mate))).is_empty());
+
+    let issues = check_result(&read(&format!("[Result \"1/2-1/2\"]\n\n{} 1-0\n", mate)));
+    assert_eq!(issues.len(), 3);
+    assert!(issues.contains(&GameIssue::ResultContradictsPosition { result: "1-0".to_string(), expected: "0-1" }));
+    assert!(issues.contains(&GameIssue::ResultContradictsPosition { result: "1/2-1/2".to_string(), expected: "0-1" }));
+
+    let stalemate = "[FEN \"7k/8/6K1/8/8/8/8/5Q2 w - - 0 1\"]\n\n1. Qf7 *\n";
+    assert_eq!(
+        check_result(&read(stalemate)),
+        [GameIssue::ResultContradictsPosition { result: "*".to_string(), expected: "1/2-1/2" }]
+    );
+}
+
+#[test]
+fn test_lenient_reader_skips_malformed_games() {
+    let mut pgn = b"[Event \"Bad tag\n\n1. e4 e5 1-0\n\n".to_vec();
+    pgn.extend_from_slice(b"[Event \"Illegal\"]\n\n1. e4 e4 0-1\n\n");
+    pgn.extend_from_slice(b"[Event \"Bytes \xff\"]\n\n1. d4 *\n\n");
+    pgn.extend_from_slice(PGN.as_bytes());
+
+    let mut reader = PgnGameReader::new(pgn.as_slice()).lenient();
+    let games: Vec<_> = reader.by_ref().collect::<Result<_, _>>().unwrap();
+    assert_eq!(games.len(), 2);
+    assert_eq!(games[0].header("White"), Some("Alice"));
+
+    let skipped = reader.skipped();
+    assert_eq!(skipped.iter().map(|s| (s.index, s.line)).collect::<Vec<_>>(), [(0, 1), (1, 7), (2, 9)]);
+    assert_eq!(reader.stats().games, 2);
+    assert_eq!(reader.stats().skipped, 3);
+
+    let mut strict = PgnGameReader::new(pgn.as_slice());
+    assert!(strict.next_game().is_err());
+}
+
+#[test]
+fn test_unmatched_closing_brace() {
+    for pgn in ["1. e4 } e5 *\n", "1. e4 ; a {note\n} e5 *\n"] {
+        assert!(PgnGameReader::new(pgn.as_bytes()).next_game().is_err(), "{:?}", pgn);
+        let game = PgnGameReader::new(pgn.as_bytes()).lenient().next_game().unwrap().unwrap();
+        assert_eq!(game.moves, ["e4", "e5"]);
+    }
+}
+
+#[test]
+fn test_lenient_reader_continues_past_a_stray_brace() {
+    let pgn = "[Event \"a\"]\n\n1. e4 } e4 *\n\n[Event \"b\"]\n\n1. d4 d5 *\n";
+    let mut reader = PgnGameReader::new(pgn.as_bytes()).lenient();
+    let games: Vec<_> = reader.by_ref().collect::<Result<_, _>>().unwrap();
+    assert_eq!(games.len(), 1);
+    assert_eq!(games[0].header("Event"), Some("b"));
+    assert_eq!(games[0].moves, ["d4", "d5"]);
+    assert_eq!(reader.stats().skipped, 1);
+}
+
+#[test]
+fn test_encodings() {
+    let pgn = b"[White \"Jos\xe9 Ra\xfal Capablanca\"]\n[Black \"\x93Nimzo\x94\"]\n\n1. e4 *\n";
+
+    assert!(PgnGameReader::new(&pgn[..]).next_game().is_err());
+
+    let game = PgnGameReader::new(&pgn[..]).with_encoding(Encoding::Auto).next_game().unwrap().unwrap();
+    assert_eq!(game.header("White"), Some("José Raúl Capablanca"));
+    assert_eq!(game.header("Black"), Some("\u{201C}Nimzo\u{201D}"));
+
+    let game = PgnGameReader::new(&pgn[..]).with_encoding(Encoding::Lossy).next_game().unwrap().unwrap();
+    assert_eq!(game.header("White"), Some("Jos\u{FFFD} Ra\u{FFFD}l Capablanca"));
+
+    // Valid UTF-8 is left alone in auto mode
+    let utf8 = "[White \"José\"]\n\n1. e4 *\n";
+    let game = PgnGameReader::new(utf8.as_bytes()).with_encoding(Encoding::Auto).next_game().unwrap().unwrap();
+    assert_eq!(game.header("White"), Some("José"));
+    assert_eq!("cp1252".parse::<Encoding>().unwrap(), Encoding::Windows1252);
+}
+
+#[test]
+fn test_tag_escaping() {
+    let pgn = "[White \"Robert \\\"Bobby\\\" Fischer\"]\n[Site \"C:\\\\games\"]\n\n1. e4 *\n";
+    let game = PgnGameReader::new(pgn.as_bytes()).next_game().unwrap().unwrap();
+    assert_eq!(game.header("White"), Some("Robert \"Bobby\" Fischer"));
+    assert_eq!(game.header("Site"), Some("C:\\games"));
+    assert!(game.to_pgn().starts_with(pgn.lines().next().unwrap()));
+
+    assert!(PgnGameReader::new(&b"[White \"unterminated\\\"]\n\n1. e4 *\n"[..]).next_game().is_err());
+}
+
+#[test]
+fn test_write_round_trip() {
+    let mut games: Vec<_> = PgnGameReader::new(PGN.as_bytes()).collect::<Result<_, _>>().unwrap();
+    games[0].headers.push(("Annotator".to_string(), "\\\"quoted\\\" \\ back".to_string()));
+    games[0].evals[1] = Some(Eval::Pawns(0.31));
+    games[0].evals[2] = Some(Eval::Mate(-4));
+
+    let text: String = games.iter().map(|game| game.to_pgn()).collect();
+    let reread: Vec<_> = PgnGameReader::new(text.as_bytes()).collect::<Result<_, _>>().unwrap();
+    assert_eq!(reread, games);
+    assert!(text.contains("1. e4 e5 {[%eval 0.31]} 2. Bc4 {[%eval #-4]} Nc6"));
+}
+
+#[test]
+fn test_setup_tags() {
+    let read = |pgn: &str| PgnGameReader::new(pgn.as_bytes()).next_game().unwrap().unwrap();
+
+    // A FEN without SetUp is honored, and the first move must be legal from it
+    let game = read("[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 20\"]\n\n20... Kd7 *\n");
+    assert_eq!(game.replay().unwrap().len(), 1);
+    let game = read("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 20\"]\n\n20... e4 *\n");
+    assert!(matches!(game.replay(), Err(ChessError::IllegalMove(msg)) if msg.contains("first move e4")));
+
+    assert!(read("[SetUp \"1\"]\n\n1. e4 *\n").replay().is_err());
+    assert!(read("[SetUp \"0\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 w - - 0 1\"]\n\n1. e2e4 *\n").starting_board().is_err());
+    assert!(read("[FEN \"8/8/8/8/8/8/4P3/4K3 w - - 0 1\"]\n\n1. e4 *\n").starting_board().is_err());
+}
+
+#[test]
+fn test_pgn_reader_positions() {
+    let pgn = "[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 20\"]\n\n20... Kd7\n21. e4 *\n\n[Event \"Next\"]\n\n1. d4 *\n";
+    let mut reader = PgnReader::new(pgn.as_bytes());
+    assert_eq!(reader.next_position().unwrap().unwrap().to_fen(), "4k3/8/8/8/8/8/4P3/4K3 b - - 0 20");
+    assert_eq!(
+        reader.next_position().unwrap().unwrap().to_fen(),
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+    );
+    assert!(reader.next_position().unwrap().is_none());
+}
+
+#[test]
+fn test_from_uci() {
+    let headers = vec![("White".to_string(), "engine-a".to_string()), ("Black".to_string(), "engine-b".to_string())];
+    let game = PgnGame::from_uci(None, &["f2f3", "e7e5", "g2g4", "d8h4"], headers).unwrap();
+    assert_eq!(game.moves, ["f3", "e5", "g4", "Qh4#"]);
+    assert_eq!(game.result.as_deref(), Some("0-1"));
+    assert_eq!(game.header("White"), Some("engine-a"));
+    assert_eq!(game.header("Result"), Some("0-1"));
+    assert!(game.to_pgn().ends_with("1. f3 e5 2. g4 Qh4# 0-1\n\n"));
+
+    let fen = "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 10";
+    let game = PgnGame::from_uci(Some(fen), &["e8g8", "e1c1"], Vec::new()).unwrap();
+    assert_eq!(game.moves, ["O-O", "O-O-O"]);
+    assert_eq!(game.header("FEN"), Some(fen));
+    assert_eq!(game.result.as_deref(), Some("*"));
+    assert!(game.to_pgn().contains("10... O-O 11. O-O-O *"));
+
+    let error = PgnGame::from_uci(None, &["e2e4", "e2e4"], Vec::new()).unwrap_err();
+    assert_eq!(error.to_string(), ChessError::IllegalMove("e2e4 at ply 2".to_string()).to_string());
+}
@@ -0,0 +1,69 @@
+use move_generation::pgn_tools::{merge_pgn, split_pgn, DedupKey, MergeStats, RawGameReader, ShardMode};
+use std::io::Read;
+use std::path::PathBuf;
+
+// The second game has a Latin-1 player name and a comment line starting with '['
+const PGN: &[u8] = b"[Event \"A\"]\n[Result \"1-0\"]\n\n1. e4 e5 1-0\n\n\
+[Event \"B\"]\n[White \"Jos\xe9\"]\n\n1. d4 {a comment\n[not a tag]} d5 *\n\n\
+[Event \"C\"]\n\n1. c4 *\n\n\
+[Event \"D\"]\n\n1. Nf3 *";
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("move_generation_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn test_raw_games_keep_their_bytes() {
+    let games: Vec<Vec<u8>> = RawGameReader::new(PGN).collect::<Result<_, _>>().unwrap();
+    assert_eq!(games.len(), 4);
+    assert_eq!(games[1], b"[Event \"B\"]\n[White \"Jos\xe9\"]\n\n1. d4 {a comment\n[not a tag]} d5 *\n\n");
+    assert_eq!(games.concat(), PGN);
+}
+
+#[test]
+fn test_split_round_robin_and_contiguous() {
+    let dir = temp_dir("split");
+    let paths = split_pgn(PGN, &dir, "games", ShardMode::Count(2), false).unwrap();
+    assert_eq!(paths, [dir.join("games-00000.pgn"), dir.join("games-00001.pgn")]);
+    let second = std::fs::read(&paths[1]).unwrap();
+    assert!(second.starts_with(b"[Event \"B\"]"));
+    assert_eq!(RawGameReader::new(second.as_slice()).count(), 2);
+
+    let paths = split_pgn(PGN, &dir, "run", ShardMode::GamesPerShard(3), false).unwrap();
+    assert_eq!(paths.len(), 2);
+    let last = std::fs::read(&paths[1]).unwrap();
+    assert_eq!(last, b"[Event \"D\"]\n\n1. Nf3 *\n\n");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_split_gzip() {
+    let dir = temp_dir("split_gz");
+    let paths = split_pgn(PGN, &dir, "games", ShardMode::Count(1), true).unwrap();
+    assert_eq!(paths, [dir.join("games-00000.pgn.gz")]);
+    let mut text = Vec::new();
+    flate2::read::GzDecoder::new(std::fs::File::open(&paths[0]).unwrap()).read_to_end(&mut text).unwrap();
+    assert!(text.starts_with(PGN));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_merge_removes_duplicates() {
+    let first: &[u8] = b"[White \"A\"]\n\n1. e4 e5 2. Nf3 *\n\n[White \"B\"]\n\n1. d4 *\n\n";
+    // Same moves with annotations, and a game repeating B's headers
+    let second: &[u8] = b"[White \"C\"]\n\n1. e4! {good} e5 2. Nf3+ *\n\n[White \"B\"]\n\n1. c4 *\n";
+
+    let mut output = Vec::new();
+    let stats = merge_pgn(vec![first, second], &mut output, DedupKey::Movetext).unwrap();
+    assert_eq!(stats, MergeStats { games: 3, duplicates: 1 });
+    assert_eq!(&output[..first.len()], first);
+    assert!(output.ends_with(b"[White \"B\"]\n\n1. c4 *\n\n"));
+
+    let mut output = Vec::new();
+    let stats = merge_pgn(vec![first, second], &mut output, DedupKey::Headers).unwrap();
+    assert_eq!(stats, MergeStats { games: 3, duplicates: 1 });
+    assert!(String::from_utf8(output).unwrap().contains("1. e4! {good}"));
+}
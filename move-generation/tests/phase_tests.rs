@@ -0,0 +1,24 @@
+use move_generation::phase::{game_phase, phase_value, Phase, PHASE_MAX};
+
+mod common;
+
+use common::board_from_fen;
+
+#[test]
+fn test_phase_value_range() {
+    assert_eq!(phase_value(&board_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")), 0);
+    assert_eq!(phase_value(&board_from_fen("4k3/pppp4/8/8/8/8/PPPP4/4K3 w - - 0 1")), PHASE_MAX);
+    // Queens traded: 16 of 24 phase units remain
+    assert_eq!(phase_value(&board_from_fen("r1b1kb1r/pppp1ppp/2n2n2/4p3/4P3/2N2N2/PPPP1PPP/R1B1KB1R w KQkq - 0 6")), 85);
+}
+
+#[test]
+fn test_game_phase_classification() {
+    assert_eq!(game_phase(&board_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")), Phase::Opening);
+    assert_eq!(
+        game_phase(&board_from_fen("r2q1rk1/pp2bppp/2n1bn2/3p4/3P4/2NBBN2/PP3PPP/R2Q1RK1 w - - 4 11")),
+        Phase::Middlegame
+    );
+    assert_eq!(game_phase(&board_from_fen("8/5pk1/6p1/8/8/6P1/r4PK1/3R4 w - - 0 40")), Phase::Endgame);
+    assert_eq!(Phase::Endgame.as_str(), "endgame");
+}
@@ -0,0 +1,57 @@
+use move_generation::notation::parse_san;
+use move_generation::pgn::{starting_board, PgnGameReader};
+use move_generation::polyglot::{book_moves, build_book, encode_book_move, polyglot_key, read_book, write_book, BookOptions};
+use move_generation::types::Variant;
+
+mod common;
+
+use common::board_from_fen;
+
+#[test]
+fn test_polyglot_keys() {
+    // Reference keys from the Polyglot format specification
+    let expected = [
+        0x823c9b50fd114196, 0x0756b94461c50fb0, 0x662fafb965db29d4, 0x22a48b5a8e47ff78,
+        0x652a607ca3f242c1, 0x00fdd303c946bdd9,
+    ];
+    let mut board = starting_board(Variant::Standard);
+    assert_eq!(polyglot_key(&board), 0x463b96181691fc9c);
+    for (san, key) in ["e4", "d5", "e5", "f5", "Ke2", "Kf7"].iter().zip(expected) {
+        let mv = parse_san(&board, san).unwrap();
+        board.make_move(&mv);
+        assert_eq!(polyglot_key(&board), key, "after {}", san);
+    }
+}
+
+#[test]
+fn test_castling_is_encoded_as_king_takes_rook() {
+    let board = board_from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+    let short = parse_san(&board, "O-O").unwrap();
+    let long = parse_san(&board, "O-O-O").unwrap();
+    assert_eq!(encode_book_move(&board, &short), (4 << 6) | 7);
+    assert_eq!(encode_book_move(&board, &long), 4 << 6);
+}
+
+#[test]
+fn test_build_book() {
+    let pgn = "[Result \"1-0\"]\n\n1. e4 e5 1-0\n\n[Result \"0-1\"]\n\n1. e4 c5 0-1\n\n\
+               [Result \"1/2-1/2\"]\n\n1. d4 d5 1/2-1/2\n\n[Result \"*\"]\n\n1. c4 *\n";
+    let options = BookOptions { max_ply: 1, min_games: 1, ..BookOptions::default() };
+    let entries = build_book(PgnGameReader::new(pgn.as_bytes()), options).unwrap();
+
+    // e4 scored a win and a loss, d4 a draw; the unfinished game is ignored
+    let mut bytes = Vec::new();
+    write_book(&entries, &mut bytes).unwrap();
+    assert_eq!(bytes.len(), 32);
+    let entries = read_book(bytes.as_slice()).unwrap();
+
+    let board = starting_board(Variant::Standard);
+    let moves: Vec<(String, u16)> = book_moves(&entries, &board).iter()
+        .map(|(mv, weight)| (mv.to_string(), *weight))
+        .collect();
+    assert_eq!(moves, [("e2e4".to_string(), 2), ("d2d4".to_string(), 1)]);
+
+    // Moves below the game threshold are left out
+    let options = BookOptions { max_ply: 1, min_games: 2, ..BookOptions::default() };
+    assert_eq!(build_book(PgnGameReader::new(pgn.as_bytes()), options).unwrap().len(), 1);
+}
@@ -0,0 +1,21 @@
+use move_generation::prelude::*;
+
+#[test]
+fn test_prelude_covers_common_use() {
+    let mut board = Board::from("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1".parse::<Position>().unwrap());
+    assert!(validate_board(&board).is_valid());
+
+    let moves = generate_legal_moves(&board);
+    assert_eq!(moves.len(), count_legal_moves(&board));
+    assert_eq!(perft(&board, 1), moves.len() as u64);
+
+    let castle = parse_san(&board, "O-O").unwrap();
+    assert!(is_legal_move(&board, &castle));
+    let e8: Square = name_to_square("e8").unwrap();
+    assert_eq!(square_to_name(e8), Some("e8"));
+    assert_eq!(board.squares()[e8 as usize], Some((Piece::King, Color::Black)));
+
+    board.make_move(&castle);
+    assert!(!is_in_check(&board, board.side_to_move));
+    assert_eq!(explain_move(&board, &Move { from: 60, to: 63, promotion: None, drop: None }), MoveLegality::CastlingThroughCheck);
+}
@@ -0,0 +1,32 @@
+#![cfg(feature = "proptest")]
+
+use move_generation::pgn::Position;
+use move_generation::rules::generate_legal_moves;
+use move_generation::types::Board;
+use proptest::prelude::*;
+
+proptest! {
+    #[test]
+    fn fen_round_trips(position in any::<Position>()) {
+        let fen = position.to_fen();
+        let parsed: Position = fen.parse().unwrap();
+        prop_assert_eq!(parsed.to_fen(), fen);
+    }
+
+    #[test]
+    fn fen_parsing_never_panics(fen in "[1-9pnbrqkPNBRQK/~\\[\\]+-]{0,80} [wb-] [KQkqA-H-]{1,4} [a-h1-8-]{1,2} [0-9x]{1,3} [0-9]{1,3}") {
+        for position in [fen.parse::<Position>(), Position::parse_lenient(&fen)].into_iter().flatten() {
+            let _ = Board::from(position);
+        }
+    }
+
+    #[test]
+    fn make_then_unmake_is_identity(board in any::<Board>()) {
+        for mv in generate_legal_moves(&board) {
+            let mut after = board.clone();
+            let undo = after.make_move(&mv);
+            after.unmake_move(&mv, undo);
+            prop_assert!(after.eq_strict(&board), "{:?} after {:?}", after, mv);
+        }
+    }
+}
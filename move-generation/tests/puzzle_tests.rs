@@ -0,0 +1,51 @@
+use move_generation::notation::to_uci;
+use move_generation::pgn::{PgnGameReader, Position};
+use move_generation::puzzle::{PuzzleMiner, PuzzleOptions, Theme};
+use move_generation::search::{SearchLimits, SearchOptions};
+use move_generation::tune::EvalParams;
+use move_generation::types::Board;
+
+fn miner(depth: u32, max_plies: usize) -> PuzzleMiner<EvalParams> {
+    let options = PuzzleOptions { limits: SearchLimits { depth: Some(depth), ..Default::default() }, max_plies, ..Default::default() };
+    PuzzleMiner::new(EvalParams::default(), options, SearchOptions { hash_mb: 1, ..SearchOptions::default() })
+}
+
+#[test]
+fn test_mine_mate_in_one() {
+    let pgn = "[Site \"https://example.org/1\"]\n\n1. e4 e5 2. Bc4 Nc6 3. Qh5 Nf6 4. Qxf7# 1-0\n\n";
+    let game = PgnGameReader::new(pgn.as_bytes()).next().unwrap().unwrap();
+    let puzzles = miner(4, 7).mine_game(&game).unwrap();
+    assert_eq!(puzzles.len(), 1);
+    let puzzle = &puzzles[0];
+    assert_eq!((puzzle.blunder.as_str(), puzzle.ply), ("Nf6", 6));
+    assert_eq!(puzzle.solution_san(), ["Qxf7#"]);
+    assert_eq!(puzzle.themes, [Theme::MateIn(1)]);
+    let json = puzzle.to_json();
+    assert_eq!(json["moves"][0], "h5f7");
+    assert_eq!(json["themes"][0], "mateIn1");
+    assert_eq!(json["site"], "https://example.org/1");
+}
+
+#[test]
+fn test_mine_fork() {
+    let pgn = "[SetUp \"1\"]\n[FEN \"r7/4k3/8/1N6/8/8/P7/4K3 b - - 0 1\"]\n\n1... Ke8 2. Nc7+ Kd7 3. Nxa8 *\n\n";
+    let game = PgnGameReader::new(pgn.as_bytes()).next().unwrap().unwrap();
+    let puzzles = miner(4, 3).mine_game(&game).unwrap();
+    assert_eq!(puzzles.len(), 1);
+    let puzzle = &puzzles[0];
+    assert_eq!(puzzle.blunder, "Ke8");
+    assert_eq!(puzzle.solution_san()[0], "Nc7+");
+    assert_eq!(puzzle.solution.len(), 3);
+    assert!(puzzle.themes.contains(&Theme::Fork));
+}
+
+#[test]
+fn test_solve_rejects_positions_without_a_unique_win() {
+    // Two ways to win the hanging queen
+    let board = Board::from("4k3/8/8/3q4/8/2N1N3/8/4K3 w - - 0 1".parse::<Position>().unwrap());
+    assert_eq!(miner(3, 3).solve(&board).unwrap(), None);
+
+    let board = Board::from("4k3/8/8/3q4/8/4N3/8/4K3 w - - 0 1".parse::<Position>().unwrap());
+    let solution = miner(3, 1).solve(&board).unwrap().unwrap();
+    assert_eq!(solution.iter().map(to_uci).collect::<Vec<_>>(), ["e3d5"]);
+}
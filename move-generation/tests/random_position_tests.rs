@@ -0,0 +1,35 @@
+use move_generation::random::{generate_random_position, PositionConstraints};
+use move_generation::rules::{is_in_check, validate_board};
+use move_generation::types::{Color, Piece};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+#[test]
+fn test_random_positions_are_legal() {
+    let mut rng = StdRng::seed_from_u64(1);
+    for _ in 0..200 {
+        let board = generate_random_position(&mut rng, &PositionConstraints::default());
+        assert!(validate_board(&board).is_valid());
+        assert!(!is_in_check(&board, board.side_to_move.opposite()));
+        for sq in (0..8).chain(56..64) {
+            assert!(!matches!(board.squares()[sq], Some((Piece::Pawn, _))));
+        }
+    }
+}
+
+#[test]
+fn test_random_position_constraints() {
+    let mut rng = StdRng::seed_from_u64(2);
+    let constraints = PositionConstraints {
+        white_pieces: 3..=3,
+        black_pieces: 0..=1,
+        side_to_move: Some(Color::Black),
+    };
+    for _ in 0..50 {
+        let board = generate_random_position(&mut rng, &constraints);
+        let count = |color| board.squares().iter().flatten().filter(|&&(_, c)| c == color).count();
+        assert_eq!(count(Color::White), 4);
+        assert!((1..=2).contains(&count(Color::Black)));
+        assert_eq!(board.side_to_move, Color::Black);
+    }
+}
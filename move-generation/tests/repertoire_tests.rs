@@ -0,0 +1,37 @@
+use move_generation::pgn::PgnGameReader;
+use move_generation::repertoire::{build_tree, TreeStats};
+
+const GAMES: &str = "1. e4 e5 2. Nf3 Nc6 1-0\n\n1. e4 e5 2. Nf3 Nf6 1/2-1/2\n\n1. e4 c5!? 2. Nf3 0-1\n\n\
+                     1. d4 d5 1-0\n\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 w - - 0 1\"]\n\n1. e4 *\n";
+
+#[test]
+fn test_tree_counts_games() {
+    let tree = build_tree(PgnGameReader::new(GAMES.as_bytes()), 3).unwrap();
+    assert_eq!(tree.stats(), TreeStats { games: 4, white_wins: 2, draws: 1, black_wins: 1 });
+
+    let e4 = &tree.moves()[0];
+    assert_eq!((e4.san.as_str(), e4.stats.games), ("e4", 3));
+    let replies: Vec<_> = e4.children.iter().map(|node| (node.san.as_str(), node.stats.games)).collect();
+    assert_eq!(replies, [("e5", 2), ("c5", 1)]);
+    // Only three plies are kept
+    assert!(e4.children[0].children[0].children.is_empty());
+}
+
+#[test]
+fn test_write_repertoire() {
+    let tree = build_tree(PgnGameReader::new(GAMES.as_bytes()), 4).unwrap();
+    let mut out = Vec::new();
+    assert_eq!(tree.write_repertoire(&mut out, 1).unwrap(), 2);
+    let text = String::from_utf8(out).unwrap();
+    let flat = text.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    let chapters: Vec<_> = PgnGameReader::new(text.as_bytes()).collect::<Result<_, _>>().unwrap();
+    assert_eq!(chapters[0].header("Event"), Some("Repertoire: 1. e4"));
+    assert_eq!(chapters[0].moves, ["e4", "e5", "Nf3", "Nc6"]);
+    assert!(flat.contains("1... e5 {2 games, +1 =1 -0} (1... c5 {1 game, +0 =0 -1} 2. Nf3 {1 game, +0 =0 -1})"));
+    assert!(flat.contains("(2... Nf6 {1 game, +0 =1 -0}) *"));
+
+    let mut out = Vec::new();
+    assert_eq!(tree.write_repertoire(&mut out, 2).unwrap(), 1);
+    assert!(!String::from_utf8(out).unwrap().contains("c5"));
+}
@@ -0,0 +1,171 @@
+use move_generation::move_gen::Bitboard;
+use move_generation::rules::{count_legal_moves, generate_legal_moves, generate_moves_masked, has_legal_move, validate_board, Severity, Violation};
+use move_generation::pgn::Position;
+use move_generation::types::{Board, Color};
+use move_generation::types::Variant;
+
+mod common;
+
+use common::board_from_fen;
+
+#[test]
+fn test_count_and_has_legal_move() {
+    for fen in [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "4k3/8/8/8/8/8/8/r3K3[N] w - - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    ] {
+        let board = board_from_fen(fen);
+        assert_eq!(count_legal_moves(&board), generate_legal_moves(&board).len());
+        assert!(has_legal_move(&board));
+    }
+    assert_eq!(count_legal_moves(&board_from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1")), 14);
+    assert_eq!(count_legal_moves(&board_from_fen("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1")), 6);
+
+    // Racing Kings forbids giving check
+    let mut board = board_from_fen("8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1");
+    board.variant = Variant::RacingKings;
+    assert_eq!(count_legal_moves(&board), generate_legal_moves(&board).len());
+    assert_eq!(count_legal_moves(&board), 21);
+
+    // Checkmate and stalemate
+    for fen in ["rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3", "k7/8/1Q6/8/8/8/8/7K b - - 0 1"] {
+        let board = board_from_fen(fen);
+        assert_eq!(count_legal_moves(&board), 0);
+        assert!(!has_legal_move(&board));
+    }
+}
+
+#[test]
+fn test_generate_moves_masked() {
+    let board = board_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    let all = Bitboard::full();
+
+    // Moves of the g1 knight only
+    let knight = generate_moves_masked(&board, Bitboard::from_square(6), all);
+    let mut targets: Vec<u8> = knight.iter().map(|m| m.to).collect();
+    targets.sort();
+    assert_eq!(targets, vec![21, 23]);
+
+    // Moves onto the fourth rank
+    let fourth_rank = Bitboard::from(0xFF00_0000u64);
+    assert_eq!(generate_moves_masked(&board, all, fourth_rank).len(), 8);
+
+    let everything = generate_moves_masked(&board, all, all);
+    assert_eq!(everything.len(), generate_legal_moves(&board).len());
+}
+
+#[test]
+fn test_validate_en_passant_and_castling() {
+    for fen in [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+        "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2",
+        "1r4kr/8/8/8/8/8/8/1R4KR w KQkq - 0 1",
+    ] {
+        assert!(validate_board(&board_from_fen(fen)).is_valid(), "{}", fen);
+    }
+
+    for fen in [
+        // En passant square on the wrong rank, without a pawn in front of
+        // it, or with the pawn's starting square occupied
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e3 0 1",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq d6 0 1",
+        "rnbqkbnr/pppppppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2",
+        // Castling rights without the king or rook in place
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPKPPP/RNBQ1BNR w KQkq - 0 1",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBN1 w KQkq - 0 1",
+        "1nbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    ] {
+        assert!(!validate_board(&board_from_fen(fen)).is_valid(), "{}", fen);
+    }
+}
+
+#[test]
+fn test_castling_violation_uses_the_fen_letter() {
+    // The g-file right is written K in FEN with no rook beyond it, and G
+    // with the h-file rook still there
+    for (fen, letter) in [("4k3/8/8/8/8/8/8/4K3 w G - 0 1", 'K'), ("4k3/8/8/8/8/8/8/4K2R w G - 0 1", 'G')] {
+        let position: Position = fen.parse().unwrap();
+        assert!(position.to_fen().contains(&format!(" w {} ", letter)), "{}", position.to_fen());
+        let report = validate_board(&Board::from(position));
+        assert!(report.violations.contains(&Violation::InvalidCastlingRight(letter)), "{}", fen);
+    }
+}
+
+#[test]
+fn test_validation_report_lists_every_violation() {
+    // Adjacent kings, White in check with Black to move, and a ninth pawn
+    let report = validate_board(&board_from_fen("8/8/8/8/8/8/PPPPPPPP/P2kK3 b - - 0 1"));
+    assert!(!report.is_valid());
+    assert!(report.violations.contains(&Violation::KingsAdjacent));
+    assert!(report.violations.contains(&Violation::OpponentInCheck));
+    assert!(report.violations.contains(&Violation::TooManyPawns { color: Color::White, count: 9 }));
+
+    // Three queens with eight pawns can be played but cannot arise
+    let report = validate_board(&board_from_fen("QQQ1k3/8/8/8/8/8/PPPPPPPP/4K3 b - - 0 1"));
+    assert!(report.is_valid());
+    assert_eq!(report.violations, vec![Violation::ImpossibleMaterial(Color::White)]);
+    assert_eq!(report.warnings().count(), 1);
+    assert_eq!(report.violations[0].severity(), Severity::Warning);
+
+    let report = validate_board(&board_from_fen("4k3/8/8/8/8/8/8/8 w - - 0 1"));
+    assert_eq!(report.errors().collect::<Vec<_>>(), vec![&Violation::KingCount { color: Color::White, count: 0 }]);
+}
+
+#[test]
+fn test_validate_pawns_on_back_rank() {
+    let report = validate_board(&board_from_fen("3Pk3/8/8/8/8/8/8/4K2p w - - 0 1"));
+    assert_eq!(report.errors().collect::<Vec<_>>(), vec![&Violation::PawnOnBackRank(7), &Violation::PawnOnBackRank(59)]);
+    assert_eq!(Violation::PawnOnBackRank(59).to_string(), "pawn on back rank square d8");
+    assert_eq!(Violation::InvalidEnPassant(43).to_string(), "no pawn can have double-pushed across en passant square d6");
+
+    // En passant squares far off the board are reported, not overflowed
+    for (ep, side) in [(250, Color::White), (255, Color::Black), (3, Color::Black)] {
+        let mut board = board_from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+        board.side_to_move = side;
+        board.en_passant = Some(ep);
+        assert!(validate_board(&board).errors().any(|v| *v == Violation::InvalidEnPassant(ep)), "{}", ep);
+    }
+    assert_eq!(Violation::InvalidEnPassant(250).to_string(), "en passant square 250 is off the board");
+}
+
+#[test]
+fn test_explain_move() {
+    use move_generation::rules::{explain_move, MoveLegality};
+    use move_generation::types::{Move, Piece};
+
+    let mv = |from: u8, to: u8| Move { from, to, promotion: None, drop: None };
+    let start = board_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    assert_eq!(explain_move(&start, &mv(12, 28)), MoveLegality::Legal);
+    assert_eq!(explain_move(&start, &mv(20, 28)), MoveLegality::NoPieceOnFrom);
+    assert_eq!(explain_move(&start, &mv(52, 36)), MoveLegality::WrongColor);
+    assert_eq!(explain_move(&start, &mv(2, 20)), MoveLegality::BlockedPath);
+    assert_eq!(explain_move(&start, &mv(3, 11)), MoveLegality::OwnPieceOnTarget);
+    assert_eq!(explain_move(&start, &mv(6, 14 + 8)), MoveLegality::CannotMoveThatWay);
+    assert_eq!(explain_move(&start, &mv(12, 21)), MoveLegality::NothingToCapture);
+    assert_eq!(explain_move(&start, &mv(12, 36)), MoveLegality::CannotMoveThatWay);
+    assert_eq!(explain_move(&start, &Move { promotion: Some(Piece::Queen), ..mv(12, 20) }), MoveLegality::BadPromotion);
+
+    let pinned = board_from_fen("4k3/4r3/8/8/8/8/4B3/4K3 w - - 0 1");
+    assert_eq!(explain_move(&pinned, &mv(12, 21)), MoveLegality::LeavesKingInCheck);
+
+    let promotion = board_from_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1");
+    assert_eq!(explain_move(&promotion, &mv(48, 56)), MoveLegality::MissingPromotion);
+    assert_eq!(explain_move(&promotion, &Move { promotion: Some(Piece::King), ..mv(48, 56) }), MoveLegality::BadPromotion);
+
+    // Castling, as the king taking its rook or moving two squares
+    let castling = board_from_fen("r3k2r/8/8/8/8/8/5r2/R3K2R w Qkq - 0 1");
+    assert_eq!(explain_move(&castling, &mv(4, 7)), MoveLegality::NoCastlingRight);
+    assert_eq!(explain_move(&castling, &mv(4, 6)), MoveLegality::NoCastlingRight);
+    assert_eq!(explain_move(&castling, &mv(4, 0)), MoveLegality::Legal);
+    assert_eq!(explain_move(&castling, &mv(4, 2)), MoveLegality::Legal);
+    let through_check = board_from_fen("r3k2r/8/8/8/8/8/3r4/R3K2R w KQkq - 0 1");
+    assert_eq!(explain_move(&through_check, &mv(4, 0)), MoveLegality::CastlingThroughCheck);
+    let in_check = board_from_fen("r3k2r/8/8/8/8/8/4r3/R3K2R w KQkq - 0 1");
+    assert_eq!(explain_move(&in_check, &mv(4, 7)), MoveLegality::CastlingOutOfCheck);
+    let blocked = board_from_fen("r3k2r/8/8/8/8/8/8/RN2K2R w KQkq - 0 1");
+    assert_eq!(explain_move(&blocked, &mv(4, 0)), MoveLegality::BlockedPath);
+}
@@ -0,0 +1,39 @@
+use move_generation::accuracy::game_accuracy;
+use move_generation::pgn::Eval;
+use move_generation::score::{Score, MATE_CP};
+use move_generation::types::Color;
+
+#[test]
+fn test_score_order_and_negation() {
+    let mut scores = vec![Score::Cp(35), Score::Mate(-2), Score::Mate(3), Score::Cp(-5000), Score::MATED, Score::Mate(1), Score::Mate(-7)];
+    scores.sort();
+    assert_eq!(scores, [Score::MATED, Score::Mate(-2), Score::Mate(-7), Score::Cp(-5000), Score::Cp(35), Score::Mate(3), Score::Mate(1)]);
+    assert!(scores.windows(2).all(|pair| pair[0].to_cp() < pair[1].to_cp()));
+    assert_eq!(Score::Mate(1).to_cp(), MATE_CP - 1);
+
+    assert_eq!(-Score::Cp(35), Score::Cp(-35));
+    assert_eq!(-Score::Mate(3), Score::Mate(-3));
+    assert_eq!(Score::MATED.from_child(), Score::Mate(1));
+    assert_eq!(Score::Mate(-2).from_child(), Score::Mate(3));
+    assert_eq!(Score::Mate(2).from_child(), Score::Mate(-2));
+    assert_eq!(Score::from(Eval::Pawns(-1.25)), Score::Cp(-125));
+}
+
+#[test]
+fn test_score_uci_format() {
+    assert_eq!(Score::Cp(35).to_string(), "cp 35");
+    assert_eq!(Score::Mate(-3).to_string(), "mate -3");
+    assert_eq!("mate 3".parse::<Score>().unwrap(), Score::Mate(3));
+    assert_eq!("cp -12".parse::<Score>().unwrap(), Score::Cp(-12));
+    for bad in ["", "cp", "pawns 3", "cp 1.5", "mate 2 extra"] {
+        assert!(bad.parse::<Score>().is_err(), "{:?}", bad);
+    }
+}
+
+#[test]
+fn test_accuracy_with_mate_scores() {
+    // Black allows a mate, then White lets it slip
+    let result = game_accuracy(&[Score::Cp(20), Score::Cp(30), Score::Mate(3), Score::Cp(10)], Color::White);
+    assert_eq!((result.white.blunders, result.black.blunders), (1, 1));
+    assert_eq!(result.black.acpl, 1000.0 - 30.0);
+}
@@ -0,0 +1,155 @@
+use move_generation::notation::parse_uci;
+use move_generation::score::Score;
+use move_generation::search::{search, search_with_handle, Engine, SearchHandle, SearchLimits, SearchOptions};
+use move_generation::tune::EvalParams;
+use std::time::Duration;
+
+mod common;
+
+use common::board_from_fen;
+
+fn depth(depth: u32) -> SearchLimits {
+    SearchLimits { depth: Some(depth), ..Default::default() }
+}
+
+#[test]
+fn test_search_finds_mates_and_material() {
+    let root = board_from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+    let mut infos = Vec::new();
+    let result = search(&root, &depth(4), &EvalParams::default(), |info| infos.push(info.clone())).unwrap();
+    assert_eq!(result.best_move, Some(parse_uci(&root, "a1a8").unwrap()));
+    assert_eq!(result.score, Score::Mate(1));
+    // The search stops once the mate is proven
+    assert_eq!(infos.last().unwrap().depth, result.depth);
+    assert!(infos.last().unwrap().to_string().starts_with(&format!("depth {} score mate 1 nodes ", result.depth)));
+    assert!(infos.last().unwrap().to_string().ends_with(" pv a1a8"));
+
+    // Mate in two: Kb6, then Rh8
+    let root = board_from_fen("k7/8/2K5/8/8/8/8/7R w - - 0 1");
+    let result = search(&root, &depth(4), &EvalParams::default(), |_| {}).unwrap();
+    assert_eq!(result.score, Score::Mate(2));
+
+    let root = board_from_fen("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1");
+    let mut depths = Vec::new();
+    let result = search(&root, &depth(3), &EvalParams::default(), |info| depths.push(info.depth)).unwrap();
+    assert_eq!(depths, [1, 2, 3]);
+    assert_eq!(result.best_move, Some(parse_uci(&root, "d2d5").unwrap()));
+    assert!(result.score > Score::Cp(300));
+    assert_eq!(result.pv.len(), 3);
+}
+
+#[test]
+fn test_search_game_over() {
+    let mated = board_from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 1 1");
+    let result = search(&mated, &depth(3), &EvalParams::default(), |_| {}).unwrap();
+    assert_eq!((result.best_move, result.score), (None, Score::MATED));
+
+    let stalemate = board_from_fen("k7/8/1Q6/8/8/8/8/6K1 b - - 0 1");
+    let result = search(&stalemate, &depth(3), &EvalParams::default(), |_| {}).unwrap();
+    assert_eq!((result.best_move, result.score), (None, Score::Cp(0)));
+}
+
+#[test]
+fn test_search_limits() {
+    let root = board_from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+    let limits = SearchLimits { nodes: Some(5000), ..Default::default() };
+    let result = search(&root, &limits, &EvalParams::default(), |_| {}).unwrap();
+    assert!(result.nodes < 5000 + 1024, "{}", result.nodes);
+    assert!(result.best_move.is_some());
+
+    let limits = SearchLimits { movetime: Some(Duration::from_millis(50)), ..Default::default() };
+    let start = std::time::Instant::now();
+    let result = search(&root, &limits, &EvalParams::default(), |_| {}).unwrap();
+    assert!(start.elapsed() < Duration::from_secs(2));
+    assert!(result.best_move.is_some());
+}
+
+#[test]
+fn test_search_stopped_by_handle() {
+    let root = board_from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+    let handle = SearchHandle::new();
+    let stopper = handle.clone();
+    let thread = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(50));
+        stopper.stop();
+    });
+    let start = std::time::Instant::now();
+    let result = search_with_handle(&root, &SearchLimits::default(), &EvalParams::default(), &handle, |_| {}).unwrap();
+    thread.join().unwrap();
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert!(handle.is_stopped());
+    assert!(result.best_move.is_some());
+
+    // Stopping from the progress callback ends the search after that depth
+    handle.reset();
+    let result = search_with_handle(&root, &SearchLimits::default(), &EvalParams::default(), &handle, |info| {
+        if info.depth == 2 {
+            handle.stop();
+        }
+    }).unwrap();
+    assert_eq!(result.depth, 2);
+}
+
+#[test]
+fn test_engine_threads_and_hash() {
+    let engine = Engine::new(SearchOptions { threads: 4, hash_mb: 4, ..SearchOptions::default() });
+    let root = board_from_fen("k7/8/2K5/8/8/8/8/7R w - - 0 1");
+    let result = engine.search(&root, &depth(4), &EvalParams::default(), &SearchHandle::new(), |_| {}).unwrap();
+    assert_eq!(result.score, Score::Mate(2));
+
+    let root = board_from_fen("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1");
+    let mut depths = Vec::new();
+    let result = engine.search(&root, &depth(5), &EvalParams::default(), &SearchHandle::new(), |info| depths.push(info.depth)).unwrap();
+    assert_eq!(depths, [1, 2, 3, 4, 5]);
+    assert_eq!(result.best_move, Some(parse_uci(&root, "d2d5").unwrap()));
+
+    // The table kept from the first search makes the second one cheaper
+    let mut engine = Engine::new(SearchOptions::default());
+    let root = board_from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+    let first = engine.search(&root, &depth(4), &EvalParams::default(), &SearchHandle::new(), |_| {}).unwrap();
+    let second = engine.search(&root, &depth(4), &EvalParams::default(), &SearchHandle::new(), |_| {}).unwrap();
+    assert!(second.nodes < first.nodes, "{} {}", second.nodes, first.nodes);
+    assert_eq!(second.score, first.score);
+    engine.set_options(SearchOptions { threads: 2, ..SearchOptions::default() });
+    assert_eq!(engine.options().threads, 2);
+}
+
+#[test]
+fn test_search_extensions() {
+    // Qd8+ Rxd8 Rxd8#: only the check extensions see the mate at depth 2
+    let root = board_from_fen("r5k1/5ppp/8/8/8/8/3Q4/3R2K1 w - - 0 1");
+    let search_with = |options: SearchOptions, depth: u32| {
+        let limits = SearchLimits { depth: Some(depth), ..Default::default() };
+        Engine::new(options).search(&root, &limits, &EvalParams::default(), &SearchHandle::new(), |_| {}).unwrap()
+    };
+    let plain = SearchOptions { check_extensions: false, singular_extensions: false, ..SearchOptions::default() };
+    assert!(!search_with(plain.clone(), 2).score.is_mate());
+    let result = search_with(SearchOptions { check_extensions: true, ..plain.clone() }, 2);
+    assert_eq!((result.best_move, result.score), (Some(parse_uci(&root, "d2d8").unwrap()), Score::Mate(2)));
+
+    // Singular extensions change the effort, not the answer
+    let root = board_from_fen("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1");
+    let singular = SearchOptions { singular_extensions: true, ..plain };
+    let result = Engine::new(singular).search(&root, &depth(7), &EvalParams::default(), &SearchHandle::new(), |_| {}).unwrap();
+    assert_eq!(result.best_move, Some(parse_uci(&root, "d2d5").unwrap()));
+}
+
+#[test]
+fn test_search_multipv() {
+    let root = board_from_fen("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1");
+    let engine = Engine::new(SearchOptions { multipv: 3, ..SearchOptions::default() });
+    let mut infos = Vec::new();
+    let result = engine.search(&root, &depth(3), &EvalParams::default(), &SearchHandle::new(), |info| infos.push(info.clone())).unwrap();
+    assert_eq!(result.lines.len(), 3);
+    assert_eq!(result.lines[0].pv, result.pv);
+    assert_eq!(result.best_move, Some(parse_uci(&root, "d2d5").unwrap()));
+    assert!(result.lines.windows(2).all(|pair| pair[0].score >= pair[1].score && pair[0].pv[0] != pair[1].pv[0]));
+    assert_eq!(infos.len(), 9);
+    assert!(infos[4].to_string().starts_with("depth 2 multipv 2 score "));
+
+    // Fewer legal moves than lines: only Ka7
+    let root = board_from_fen("k7/8/2K5/8/8/8/8/1R6 b - - 0 1");
+    let engine = Engine::new(SearchOptions { multipv: 4, ..SearchOptions::default() });
+    let result = engine.search(&root, &depth(2), &EvalParams::default(), &SearchHandle::new(), |_| {}).unwrap();
+    assert_eq!(result.lines.len(), 1);
+}
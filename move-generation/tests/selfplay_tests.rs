@@ -0,0 +1,70 @@
+use move_generation::mcts::MctsConfig;
+use move_generation::search::{SearchLimits, SearchOptions};
+use move_generation::selfplay::{generate, SelfPlayConfig, SelfPlayEngine};
+use move_generation::tune::EvalParams;
+use serde_json::Value;
+
+fn lines(output: &[u8]) -> Vec<Value> {
+    std::str::from_utf8(output).unwrap().lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+}
+
+fn policy_sum(example: &Value) -> f64 {
+    example["policy"].as_object().unwrap().values().map(|p| p.as_f64().unwrap()).sum()
+}
+
+#[test]
+fn test_mcts_self_play_writes_examples() {
+    let config = SelfPlayConfig {
+        games: 3,
+        engine: SelfPlayEngine::Mcts { playouts: 16, config: MctsConfig { dirichlet_epsilon: 0.25, ..MctsConfig::default() } },
+        max_plies: 10,
+        seed: Some(7),
+        ..SelfPlayConfig::default()
+    };
+    let mut output = Vec::new();
+    let stats = generate(&config, &EvalParams::default(), &mut output).unwrap();
+    let examples = lines(&output);
+
+    assert_eq!(stats.wdl.games(), 3);
+    assert_eq!(stats.wdl.draws, 3);
+    assert_eq!(stats.examples, 30);
+    assert_eq!(examples.len(), 30);
+    for example in &examples {
+        assert!(example["fen"].as_str().unwrap().contains(' '));
+        assert!((policy_sum(example) - 1.0).abs() < 1e-4);
+        assert_eq!(example["outcome"], 0.0);
+    }
+    assert_eq!(examples[0]["policy"].as_object().unwrap().len(), 20);
+
+    let mut again = Vec::new();
+    let one = SelfPlayConfig { games: 1, ..config };
+    generate(&one, &EvalParams::default(), &mut again).unwrap();
+    let mut once_more = Vec::new();
+    generate(&one, &EvalParams::default(), &mut once_more).unwrap();
+    assert_eq!(again, once_more);
+}
+
+#[test]
+fn test_search_self_play_scores_the_winner() {
+    let config = SelfPlayConfig {
+        games: 1,
+        engine: SelfPlayEngine::Search {
+            limits: SearchLimits { depth: Some(2), ..SearchLimits::default() },
+            options: SearchOptions { multipv: 3, ..SearchOptions::default() },
+        },
+        temperature: 0.0,
+        start_fen: Some("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1".to_string()),
+        seed: Some(1),
+        ..SelfPlayConfig::default()
+    };
+    let mut output = Vec::new();
+    let stats = generate(&config, &EvalParams::default(), &mut output).unwrap();
+    let examples = lines(&output);
+
+    assert_eq!((stats.wdl.wins, stats.examples), (1, 1));
+    let policy = examples[0]["policy"].as_object().unwrap();
+    assert_eq!(policy.len(), 3);
+    assert!(policy["a1a8"].as_f64().unwrap() > 0.9);
+    assert!((policy_sum(&examples[0]) - 1.0).abs() < 1e-4);
+    assert_eq!(examples[0]["outcome"], 1.0);
+}
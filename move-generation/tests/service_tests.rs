@@ -0,0 +1,64 @@
+use move_generation::service::{handle_request, serve_lines};
+use serde_json::json;
+
+const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+#[test]
+fn test_legal_moves_and_perft() {
+    let reply = handle_request(&json!({ "cmd": "legal_moves", "fen": START })).unwrap();
+    assert_eq!(reply["moves"].as_array().unwrap().len(), 20);
+
+    let reply = handle_request(&json!({ "cmd": "perft", "fen": START, "depth": 3 })).unwrap();
+    assert_eq!(reply["nodes"], 8902);
+    assert!(handle_request(&json!({ "cmd": "perft", "fen": START, "depth": 9 })).is_err());
+}
+
+#[test]
+fn test_validate_move() {
+    let reply = handle_request(&json!({ "cmd": "validate_move", "fen": START, "move": "e2e4" })).unwrap();
+    assert_eq!(reply["legal"], true);
+    assert_eq!(reply["fen"], "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+
+    let reply = handle_request(&json!({ "cmd": "validate_move", "fen": START, "move": "e2e5" })).unwrap();
+    assert_eq!(reply["legal"], false);
+    assert!(handle_request(&json!({ "cmd": "validate_move", "fen": START, "move": "e9" })).is_err());
+}
+
+#[test]
+fn test_evaluate() {
+    let reply = handle_request(&json!({ "cmd": "evaluate", "fen": "4k3/8/8/8/8/8/8/3QK3 b - - 0 1" })).unwrap();
+    assert_eq!(reply["material"], 900);
+    assert_eq!(reply["status"], "ongoing");
+    assert_eq!(reply["score"], "cp -900");
+
+    let reply = handle_request(&json!({ "cmd": "evaluate", "fen": "R3k3/8/4K3/8/8/8/8/8 b - - 0 1" })).unwrap();
+    assert_eq!(reply["status"], "checkmate");
+    assert_eq!(reply["in_check"], true);
+    assert_eq!(reply["score"], "mate 0");
+}
+
+#[test]
+fn test_bad_requests() {
+    assert!(handle_request(&json!({ "fen": START })).is_err());
+    assert!(handle_request(&json!({ "cmd": "legal_moves" })).is_err());
+    assert!(handle_request(&json!({ "cmd": "fly", "fen": START })).is_err());
+}
+
+#[test]
+fn test_serve_lines() {
+    let input = format!(
+        "{}\n\nnot json\n{}\n",
+        json!({ "id": 1, "cmd": "legal_moves", "fen": START }),
+        json!({ "id": "b", "cmd": "perft", "fen": START, "depth": 1 }),
+    );
+    let mut output = Vec::new();
+    serve_lines(input.as_bytes(), &mut output).unwrap();
+
+    let replies: Vec<serde_json::Value> = String::from_utf8(output).unwrap().lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(replies.len(), 3);
+    assert_eq!(replies[0]["id"], 1);
+    assert!(replies[1]["error"].is_string());
+    assert_eq!(replies[2], json!({ "id": "b", "nodes": 20 }));
+}
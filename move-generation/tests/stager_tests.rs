@@ -0,0 +1,60 @@
+use move_generation::rules::generate_legal_moves;
+use move_generation::see::see;
+use move_generation::stager::{MoveStager, Stage};
+use move_generation::types::Move;
+
+mod common;
+
+use common::board_from_fen;
+
+fn mv(from: u8, to: u8) -> Move {
+    Move { from, to, promotion: None, drop: None }
+}
+
+#[test]
+fn test_see() {
+    // exd5 cxd5 Qxd5 wins a knight
+    let board = board_from_fen("4k3/8/2p5/3n4/4P3/8/8/3QK3 w - - 0 1");
+    assert_eq!(see(&board, &mv(28, 35)), 320);
+    // Qxd5 cxd5 exd5 trades the queen for a knight and a pawn
+    assert_eq!(see(&board, &mv(3, 35)), -480);
+    // Qxd5 loses the queen to cxd5
+    let board = board_from_fen("4k3/8/2p5/3p4/8/8/8/3QK3 w - - 0 1");
+    assert_eq!(see(&board, &mv(3, 35)), -800);
+    // Undefended pawn
+    let board = board_from_fen("4k3/8/8/3p4/8/8/8/3QK3 w - - 0 1");
+    assert_eq!(see(&board, &mv(3, 35)), 100);
+}
+
+#[test]
+fn test_stager_yields_every_legal_move_once() {
+    let board = board_from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+    // Hash move Qf3-f5, a legal killer (a2-a3) and an illegal one
+    let stager = MoveStager::new(&board, Some(mv(21, 37)), [Some(mv(8, 16)), Some(mv(0, 63))]);
+    let mut staged: Vec<Move> = stager.collect();
+    let mut legal = generate_legal_moves(&board);
+    assert_eq!(staged.len(), legal.len());
+
+    let key = |m: &Move| (m.from, m.to);
+    staged.sort_by_key(key);
+    legal.sort_by_key(key);
+    assert_eq!(staged, legal);
+}
+
+#[test]
+fn test_stager_order() {
+    // Good capture exd5, bad capture Qxd5, and quiet moves
+    let board = board_from_fen("4k3/8/2p5/3n4/4P3/8/8/3QK3 w - - 0 1");
+    let mut stager = MoveStager::new(&board, Some(mv(4, 5)), [Some(mv(3, 2)), None]);
+
+    assert_eq!(stager.next(), Some(mv(4, 5)));
+    assert_eq!(stager.stage(), Stage::HashMove);
+    assert_eq!(stager.next(), Some(mv(28, 35)));
+    assert_eq!(stager.stage(), Stage::GoodCaptures);
+    assert_eq!(stager.next(), Some(mv(3, 2)));
+    assert_eq!(stager.stage(), Stage::Killers);
+
+    let rest: Vec<Move> = stager.by_ref().collect();
+    assert_eq!(rest.last(), Some(&mv(3, 35)));
+    assert_eq!(stager.stage(), Stage::Done);
+}
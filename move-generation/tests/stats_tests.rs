@@ -0,0 +1,41 @@
+use move_generation::stats::{Sprt, SprtStatus, Wdl};
+
+#[test]
+fn test_sprt_bounds_and_llr() {
+    let sprt = Sprt::new(0.0, 5.0, 0.05, 0.05);
+    let (lower, upper) = sprt.bounds();
+    assert!((lower + 2.944).abs() < 1e-3);
+    assert!((upper - 2.944).abs() < 1e-3);
+
+    let wdl = Wdl::new(100, 200, 50);
+    let llr = sprt.llr(&wdl);
+    assert!((llr - 1.67).abs() < 0.01, "unexpected LLR {}", llr);
+    assert_eq!(sprt.status(&wdl), SprtStatus::Continue);
+    assert_eq!(sprt.llr(&Wdl::default()), 0.0);
+}
+
+#[test]
+fn test_sprt_accepts_either_hypothesis() {
+    let sprt = Sprt::new(0.0, 5.0, 0.05, 0.05);
+    assert_eq!(sprt.status(&Wdl::new(400, 500, 200)), SprtStatus::AcceptH1);
+    assert_eq!(sprt.status(&Wdl::new(200, 500, 400)), SprtStatus::AcceptH0);
+
+    let report = sprt.report(&Wdl::new(400, 500, 200)).to_string();
+    assert!(report.starts_with("Games: 1100 W: 400 D: 500 L: 200 LLR:"));
+    assert!(report.ends_with("H1 accepted"));
+}
+
+#[test]
+fn test_elo_estimate() {
+    let estimate = Wdl::new(100, 200, 50).elo().unwrap();
+    assert!((estimate.elo - 49.98).abs() < 0.01, "unexpected Elo {}", estimate.elo);
+    assert!((estimate.error - 23.78).abs() < 0.01, "unexpected error {}", estimate.error);
+    assert!(estimate.los > 0.999);
+    assert!((estimate.draw_ratio - 200.0 / 350.0).abs() < 1e-12);
+    assert!(estimate.to_string().starts_with("Elo difference: 50.0 +/- 23.8, LOS: 100.0 %, DrawRatio: 57.1 %"));
+
+    assert!((Wdl::new(10, 0, 10).los().unwrap() - 0.5).abs() < 1e-6);
+    assert_eq!(Wdl::new(0, 5, 0).los(), None);
+    assert!(Wdl::new(3, 0, 0).elo().is_none());
+    assert!(Wdl::default().elo().is_none());
+}
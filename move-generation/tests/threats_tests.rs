@@ -0,0 +1,53 @@
+use move_generation::move_gen::attackers;
+use move_generation::threats::threats;
+use move_generation::types::{name_to_square, Color, Piece};
+
+mod common;
+
+use common::board_from_fen;
+
+fn squares(names: &[&str]) -> Vec<u8> {
+    names.iter().map(|name| name_to_square(name).unwrap()).collect()
+}
+
+#[test]
+fn test_attackers() {
+    let board = board_from_fen("1r2k3/8/3p4/2B1N3/1P6/P7/8/4K3 w - - 0 1");
+    let sq = |name| name_to_square(name).unwrap();
+    assert_eq!(attackers(&board, sq("d6"), Color::White).bits(), squares(&["c5"]));
+    assert_eq!(attackers(&board, sq("c5"), Color::White).bits(), squares(&["b4"]));
+    assert_eq!(attackers(&board, sq("c5"), Color::Black).bits(), squares(&["d6"]));
+    assert_eq!(attackers(&board, sq("b4"), Color::Black).bits(), squares(&["b8"]));
+    assert_eq!(attackers(&board, sq("d7"), Color::Black).bits(), squares(&["e8"]));
+    assert!(attackers(&board, sq("h1"), Color::Black).bits().is_empty());
+}
+
+#[test]
+fn test_threats() {
+    let board = board_from_fen("1r2k3/8/3p4/2B1N3/1P6/P7/8/4K3 w - - 0 1");
+    let report = threats(&board, Color::Black);
+    let summary: Vec<_> = report.iter()
+        .map(|threat| (threat.square, threat.piece, threat.gain, threat.hanging))
+        .collect();
+    assert_eq!(summary, vec![
+        (name_to_square("e5").unwrap(), Piece::Knight, 320, true),
+        (name_to_square("c5").unwrap(), Piece::Bishop, 230, true),
+        (name_to_square("b4").unwrap(), Piece::Pawn, -400, false),
+    ]);
+    assert_eq!(report[0].attackers.bits(), squares(&["d6"]));
+    assert!(report[0].defenders.bits().is_empty());
+    assert_eq!(report[1].defenders.bits(), squares(&["b4"]));
+    assert_eq!(report[2].defenders.bits(), squares(&["a3", "c5"]));
+
+    let report = threats(&board, Color::White);
+    assert_eq!(report.len(), 1);
+    assert_eq!((report[0].piece, report[0].gain, report[0].hanging), (Piece::Pawn, 100, true));
+}
+
+#[test]
+fn test_threats_leave_out_kings() {
+    let board = board_from_fen("4k3/8/8/8/8/8/8/R3K3 b - - 0 1");
+    assert!(threats(&board, Color::White).is_empty());
+    let board = board_from_fen("4k3/4q3/8/8/8/8/8/R3K3 b - - 0 1");
+    assert!(threats(&board, Color::White).is_empty());
+}
@@ -0,0 +1,69 @@
+use move_generation::tt::{Bound, TranspositionTable, TtEntry};
+use move_generation::types::{Move, Piece};
+
+fn entry(key: u64, depth: i32, bound: Bound) -> TtEntry {
+    let best_move = Some(Move { from: 12, to: 28, promotion: None, drop: None });
+    TtEntry { key, best_move, value: 35, depth, bound }
+}
+
+#[test]
+fn test_tt_store_and_probe() {
+    let tt = TranspositionTable::new(1);
+    assert!(tt.len() > 1000);
+    assert_eq!(tt.probe(42), None);
+    tt.store(entry(42, 5, Bound::Lower));
+    assert_eq!(tt.probe(42), Some(entry(42, 5, Bound::Lower)));
+    // Another key in the same slot does not match
+    assert_eq!(tt.probe(42 + tt.len() as u64), None);
+
+    // Shallower bounds do not replace deeper entries of the same position
+    tt.store(entry(42, 3, Bound::Upper));
+    assert_eq!(tt.probe(42).unwrap().depth, 5);
+    tt.store(entry(42, 3, Bound::Exact));
+    assert_eq!(tt.probe(42).unwrap().bound, Bound::Exact);
+
+    tt.clear();
+    assert_eq!(tt.probe(42), None);
+}
+
+#[test]
+fn test_tt_round_trips_entries() {
+    let tt = TranspositionTable::new(1);
+    let moves = [
+        None,
+        Some(Move { from: 52, to: 60, promotion: Some(Piece::Knight), drop: None }),
+        Some(Move { from: 27, to: 27, promotion: None, drop: Some(Piece::Queen) }),
+        Some(Move { from: 63, to: 0, promotion: None, drop: None }),
+    ];
+    for (i, best_move) in moves.into_iter().enumerate() {
+        for (value, depth, bound) in [(-31_990, -3, Bound::Upper), (31_999, 64, Bound::Lower), (0, 0, Bound::Exact)] {
+            let key = 0x9e37_79b9_7f4a_7c15u64.wrapping_mul(i as u64 + 1);
+            let entry = TtEntry { key, best_move: best_move.clone(), value, depth, bound };
+            tt.clear();
+            tt.store(entry.clone());
+            assert_eq!(tt.probe(key), Some(entry));
+        }
+    }
+}
+
+#[test]
+fn test_tt_concurrent_stores() {
+    let tt = TranspositionTable::new(0);
+    assert_eq!(tt.len(), 1);
+    // Every thread writes its own consistent entries into the one slot; a
+    // probe sees one of them whole or nothing
+    std::thread::scope(|scope| {
+        for thread in 1..=4u64 {
+            let tt = &tt;
+            scope.spawn(move || {
+                for i in 0..20_000u64 {
+                    let key = thread << 32 | i;
+                    tt.store(TtEntry { key, best_move: None, value: key as i32, depth: thread as i32, bound: Bound::Exact });
+                    if let Some(entry) = tt.probe(key) {
+                        assert_eq!((entry.value, entry.depth), (key as i32, thread as i32));
+                    }
+                }
+            });
+        }
+    });
+}
@@ -0,0 +1,80 @@
+use move_generation::pgn::PgnGameReader;
+use move_generation::tune::{samples_from_games, EvalParams, Sample, TuneConfig, Tuner, PARAMS};
+
+mod common;
+
+use common::board_from_fen;
+
+#[test]
+fn test_eval_params_are_symmetric() {
+    let mut params = EvalParams::default();
+    params.pst[0][1][18] = 25; // knight on c3
+    let start = board_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    assert_eq!(params.evaluate(&start), 0);
+
+    let white = board_from_fen("4k3/8/8/8/8/2N5/8/4K3 w - - 0 1");
+    let black = board_from_fen("4k3/8/2n5/8/8/8/8/4K3 b - - 0 1");
+    assert_eq!(params.evaluate(&white), -params.evaluate(&black));
+    // Only kings and a knight: close to the endgame values
+    assert!(params.evaluate(&white) > 300);
+}
+
+#[test]
+fn test_tuning_fits_results() {
+    let mut samples = Vec::new();
+    for _ in 0..10 {
+        samples.push(Sample::new(&board_from_fen("4k3/8/8/8/8/2N5/PPP5/4K3 w - - 0 1"), 1.0));
+        samples.push(Sample::new(&board_from_fen("4k3/ppp5/2n5/8/8/8/8/4K3 w - - 0 1"), 0.0));
+        samples.push(Sample::new(&board_from_fen("4k3/ppp5/8/8/8/8/PPP5/4K3 w - - 0 1"), 0.5));
+    }
+    let params = EvalParams { material: [[100, 0, 330, 500, 900, 0]; 2], ..Default::default() };
+    let mut tuner = Tuner::new(samples);
+    tuner.k = 1.0;
+    let before = tuner.error(&params);
+
+    let config = TuneConfig { max_passes: 5, step: 20, fix_material: false };
+    let mut passes = 0;
+    let tuned = tuner.tune(&params, &config, |_, _| passes += 1);
+    assert!(passes > 0 && passes <= 5);
+    assert!(tuner.error(&tuned) < before);
+    assert!(tuned.material[1][1] > 0, "{:?}", tuned.material);
+    assert_eq!(tuned.material[0][5], 0);
+    // Rooks appear in no sample and keep their value
+    assert_eq!(tuned.material[0][3], 500);
+}
+
+#[test]
+fn test_samples_from_games() {
+    let pgn = "[Result \"1-0\"]\n\n1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0\n\n[Result \"*\"]\n\n1. d4 *\n";
+    let samples = samples_from_games(PgnGameReader::new(pgn.as_bytes())).unwrap();
+    assert_eq!(samples.len(), 7);
+
+    // A knight up wins three games in four
+    let up = board_from_fen("4k3/8/8/8/8/2N5/8/4K3 w - - 0 1");
+    let results = [1.0, 1.0, 1.0, 0.5];
+    let mut tuner = Tuner::new(results.iter().map(|&result| Sample::new(&up, result)).collect());
+    let k = tuner.fit_k(&EvalParams::default());
+    let predicted = 1.0 / (1.0 + 10f64.powf(-k * EvalParams::default().evaluate(&up) as f64 / 400.0));
+    assert!((predicted - 0.875).abs() < 1e-3, "k = {}", k);
+    assert!(EvalParams::default().to_rust_source().contains("pub const PST: [[[i32; 64]; 6]; 2] = ["));
+}
+
+#[test]
+fn test_tuning_king_safety() {
+    // The side whose king lost its shield loses, material being level
+    let mut samples = Vec::new();
+    for _ in 0..10 {
+        samples.push(Sample::new(&board_from_fen("rq4k1/p4p1p/8/8/8/8/5PPP/RQ4K1 w - - 0 1"), 1.0));
+        samples.push(Sample::new(&board_from_fen("rq4k1/5ppp/8/8/8/8/P4P1P/RQ4K1 w - - 0 1"), 0.0));
+    }
+    let params = EvalParams { king_safety: [0; 7], ..Default::default() };
+    assert_eq!(params.param(PARAMS - 6), 0);
+    assert_eq!(EvalParams::default().param(PARAMS - 6), EvalParams::default().king_safety[1]);
+    let tuner = Tuner::new(samples);
+    assert!(tuner.error(&EvalParams::default()) < tuner.error(&params));
+
+    let config = TuneConfig { max_passes: 5, step: 10, fix_material: true };
+    let tuned = tuner.tune(&params, &config, |_, _| ());
+    assert_ne!(tuned.king_safety, params.king_safety);
+    assert!(tuner.error(&tuned) < tuner.error(&params));
+}
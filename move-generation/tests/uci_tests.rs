@@ -0,0 +1,74 @@
+use move_generation::search::SearchOptions;
+use move_generation::uci::{move_time, run, UciOptions, OPTIONS};
+use std::time::Duration;
+
+fn session(input: &str) -> Vec<String> {
+    let mut output = Vec::new();
+    run(input.as_bytes(), &mut output).unwrap();
+    String::from_utf8(output).unwrap().lines().map(str::to_string).collect()
+}
+
+#[test]
+fn test_uci_options() {
+    let mut options = UciOptions::default();
+    options.set("hash", Some("64")).unwrap();
+    options.set("Threads", Some("4")).unwrap();
+    options.set("MultiPV", Some("3")).unwrap();
+    options.set("Move Overhead", Some("100")).unwrap();
+    options.set("Ponder", Some("true")).unwrap();
+    options.set("SingularExtensions", Some("false")).unwrap();
+    options.set("SyzygyPath", Some("/tb")).unwrap();
+    options.set("Clear Hash", None).unwrap();
+    let search = SearchOptions { threads: 4, hash_mb: 64, multipv: 3, singular_extensions: false, ..SearchOptions::default() };
+    assert_eq!(options.search, search);
+    assert_eq!(options.move_overhead, Duration::from_millis(100));
+    assert!(options.ponder);
+    assert_eq!(options.syzygy_path.as_deref(), Some("/tb"));
+    options.set("SyzygyPath", Some("<empty>")).unwrap();
+    assert_eq!(options.syzygy_path, None);
+
+    assert!(options.set("Threads", Some("0")).is_err());
+    assert!(options.set("Hash", Some("lots")).is_err());
+    assert!(options.set("Ponder", None).is_err());
+    assert!(options.set("Contempt", Some("20")).is_err());
+    assert_eq!(OPTIONS[0].to_string(), "option name Hash type spin default 16 min 1 max 1048576");
+
+    let ms = Duration::from_millis;
+    assert_eq!(move_time(ms(60_000), ms(0), None, ms(10)), ms(1990));
+    assert_eq!(move_time(ms(10_000), ms(1000), Some(1), ms(0)), ms(5000));
+    assert_eq!(move_time(ms(5), ms(0), None, ms(100)), ms(1));
+}
+
+#[test]
+fn test_uci_session() {
+    let lines = session("uci\nisready\nsetoption name MultiPV value 2\nsetoption name Threads value 0\nposition fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1\ngo depth 3\n");
+    assert_eq!(lines[0], "id name move_generation 0.1.0");
+    assert!(lines.contains(&"option name Move Overhead type spin default 10 min 0 max 5000".to_string()));
+    let uciok = lines.iter().position(|line| line == "uciok").unwrap();
+    assert_eq!(lines[uciok + 1], "readyok");
+    assert_eq!(lines[uciok + 2], "info string Parse error: option Threads ranges from 1 to 1024");
+    assert!(lines.iter().any(|line| line.starts_with("info depth 1 multipv 2 score ")));
+    assert!(lines.iter().any(|line| line.starts_with("info depth 1 multipv 1 score mate 1 ") && line.ends_with(" pv a1a8")));
+    assert_eq!(lines.last().unwrap(), "bestmove a1a8");
+
+    // Castling is written the standard way, and a bad move is reported
+    let lines = session("position startpos moves e2e4 e7e5 g1f3 b8c6 f1c4 g8f6 e1g1\nposition startpos moves e2e5\ngo depth 1\n");
+    assert!(lines[0].starts_with("info string "));
+    assert!(lines.last().unwrap().starts_with("bestmove "));
+    let lines = session("position fen 4k3/8/8/8/8/8/8/4K2R w K - 0 1\nsetoption name MultiPV value 20\ngo depth 1\n");
+    assert!(lines.iter().any(|line| line.ends_with(" pv e1g1")));
+}
+
+#[test]
+fn test_uci_stop_and_ponder() {
+    // An infinite search only answers once stopped, here by the end of input
+    let lines = session("position startpos\ngo infinite\n");
+    assert!(lines.last().unwrap().starts_with("bestmove "));
+    let lines = session("setoption name Ponder value true\nposition startpos\ngo ponder wtime 100 btime 100\nponderhit\nquit\n");
+    assert!(lines.last().unwrap().starts_with("bestmove "));
+
+    // With Ponder off, go ponder is a normal search that ends on its own
+    let lines = session("position startpos\ngo ponder depth 1\n");
+    assert_eq!(lines[0], "info string Ponder is off; searching this as a normal go");
+    assert!(lines.last().unwrap().starts_with("bestmove "));
+}
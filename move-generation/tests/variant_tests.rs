@@ -0,0 +1,99 @@
+use move_generation::pgn::{starting_board, Position};
+use move_generation::rules::{generate_legal_moves, is_checkmate, is_insufficient_material, is_variant_draw, validate_board, variant_winner};
+use move_generation::types::{Color, Move, Variant};
+
+mod common;
+
+use common::board_from_fen;
+
+fn mv(from: u8, to: u8) -> Move {
+    Move { from, to, promotion: None, drop: None }
+}
+
+#[test]
+fn test_three_check_fen() {
+    let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 3+2 0 1";
+    let board = board_from_fen(fen);
+    assert_eq!(board.variant, Variant::ThreeCheck);
+    assert_eq!(board.checks, [0, 1]);
+    assert_eq!(Position::from(&board).to_fen(), fen);
+
+    // Checks-given suffix form
+    let board = board_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 +2+0");
+    assert_eq!(board.checks, [2, 0]);
+}
+
+#[test]
+fn test_third_check_wins() {
+    // White has given two checks; Bb5+ is the third
+    let mut board = board_from_fen("rnbqkbnr/ppp2ppp/8/3pp3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 1+3 0 3");
+    assert_eq!(variant_winner(&board), None);
+
+    let undo = board.make_move(&mv(5, 33));
+    assert_eq!(board.checks, [3, 0]);
+    assert_eq!(variant_winner(&board), Some(Color::White));
+    assert!(generate_legal_moves(&board).is_empty());
+    assert!(!is_checkmate(&board));
+
+    board.unmake_move(&mv(5, 33), undo);
+    assert_eq!(board.checks, [2, 0]);
+}
+
+#[test]
+fn test_king_of_the_hill() {
+    let mut board = board_from_fen("4k3/8/8/8/8/4K3/8/8 w - - 0 1");
+    board.variant = Variant::KingOfTheHill;
+    assert!(!is_insufficient_material(&board));
+    assert_eq!(variant_winner(&board), None);
+
+    board.make_move(&mv(20, 28));
+    assert_eq!(variant_winner(&board), Some(Color::White));
+    assert!(generate_legal_moves(&board).is_empty());
+}
+
+#[test]
+fn test_horde() {
+    let board = starting_board(Variant::Horde);
+    assert!(validate_board(&board).is_valid());
+    assert_eq!(generate_legal_moves(&board).len(), 8);
+
+    // Pawns on the first rank may advance two squares, without en passant
+    let mut board = board_from_fen("4k3/8/8/8/8/8/8/P7 w - - 0 1");
+    board.variant = Variant::Horde;
+    assert!(generate_legal_moves(&board).iter().any(|m| m.from == 0 && m.to == 16));
+    board.make_move(&mv(0, 16));
+    assert_eq!(board.en_passant, None);
+
+    // Black wins by capturing the last White piece
+    let mut board = board_from_fen("4k3/8/8/8/8/8/3r4/3P4 b - - 0 1");
+    board.variant = Variant::Horde;
+    board.make_move(&mv(11, 3));
+    assert_eq!(variant_winner(&board), Some(Color::Black));
+}
+
+#[test]
+fn test_racing_kings() {
+    let board = starting_board(Variant::RacingKings);
+    assert_eq!(generate_legal_moves(&board).len(), 21);
+
+    // Rook moves giving check are not allowed
+    let mut board = board_from_fen("k7/8/8/8/8/8/8/1R5K w - - 0 1");
+    board.variant = Variant::RacingKings;
+    assert!(!generate_legal_moves(&board).iter().any(|m| m.from == 1 && m.to == 0));
+    assert!(!generate_legal_moves(&board).iter().any(|m| m.from == 1 && m.to == 57));
+
+    // White reaches the eighth rank and Black cannot follow
+    let mut board = board_from_fen("8/6K1/1k6/8/8/8/8/8 w - - 0 1");
+    board.variant = Variant::RacingKings;
+    board.make_move(&mv(54, 62));
+    assert_eq!(variant_winner(&board), Some(Color::White));
+
+    // Black follows on the next move and the game is drawn
+    let mut board = board_from_fen("8/1k4K1/8/8/8/8/8/8 w - - 0 1");
+    board.variant = Variant::RacingKings;
+    board.make_move(&mv(54, 62));
+    assert_eq!(variant_winner(&board), None);
+    board.make_move(&mv(49, 57));
+    assert!(is_variant_draw(&board));
+    assert!(generate_legal_moves(&board).is_empty());
+}
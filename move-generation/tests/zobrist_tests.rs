@@ -0,0 +1,93 @@
+use move_generation::pgn::Position;
+use move_generation::types::{Color, Piece};
+use move_generation::zobrist;
+
+mod common;
+
+use common::board_from_fen;
+
+#[test]
+fn test_hash_ignores_clocks() {
+    let a = board_from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1");
+    let b = board_from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 7 30");
+    assert_eq!(zobrist::hash(&a), zobrist::hash(&b));
+}
+
+#[test]
+fn test_hash_distinguishes_state() {
+    let base = board_from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1");
+    let key = zobrist::hash(&base);
+
+    let mut other_side = base.clone();
+    other_side.side_to_move = Color::White;
+    assert_eq!(zobrist::hash(&other_side), key ^ zobrist::side_key());
+
+    let mut no_castling = base.clone();
+    no_castling.castling_rights.remove(Color::White, false);
+    assert_ne!(zobrist::hash(&no_castling), key);
+
+    let mut en_passant = base.clone();
+    en_passant.en_passant = Some(20);
+    assert_eq!(zobrist::hash(&en_passant), key ^ zobrist::en_passant_key(20));
+
+    let mut moved = base.clone();
+    moved.remove(28);
+    moved.put(20, Piece::Pawn, Color::White);
+    assert_ne!(zobrist::hash(&moved), key);
+}
+
+#[test]
+fn test_incremental_hash_matches_full_recompute() {
+    use move_generation::rules::generate_legal_moves;
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::SeedableRng;
+
+    // Castling, en passant and promotions, plus Crazyhouse and Three-check state
+    let fens = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "rnbqkb1r/pp1p1pPp/8/2p1pP2/1P1P4/3P3P/P1P1P3/RNBQKBNR w KQkq e6 0 1",
+        "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R[Pn] w KQkq - 0 3",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 3+3 0 1",
+    ];
+    let mut rng = StdRng::seed_from_u64(1151);
+    for fen in fens {
+        for _ in 0..20 {
+            let mut board = board_from_fen(fen);
+            let start = board.clone();
+            let mut history = Vec::new();
+            for _ in 0..60 {
+                let Some(mv) = generate_legal_moves(&board).choose(&mut rng).cloned() else { break };
+                let undo = board.make_move(&mv);
+                assert_eq!(board.hash, zobrist::hash(&board), "{} after {}", fen, mv);
+                history.push((mv, undo));
+            }
+            while let Some((mv, undo)) = history.pop() {
+                board.unmake_move(&mv, undo);
+            }
+            assert!(board.eq_strict(&start), "{}", fen);
+        }
+    }
+}
+
+#[test]
+fn test_board_equality_follows_repetition() {
+    use std::collections::HashSet;
+
+    // Clocks differ, and the e3 square is set but no black pawn can use it
+    let a = board_from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+    let b = board_from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 4 9");
+    assert_eq!(a, b);
+    assert!(!a.eq_strict(&b));
+    assert_eq!(HashSet::from([a.clone(), b]).len(), 1);
+
+    // A usable en passant square does count
+    let c = board_from_fen("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 3");
+    let d = board_from_fen("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 3");
+    assert_ne!(c, d);
+
+    let position: Position = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1".parse().unwrap();
+    assert_eq!(position, Position::from(&a));
+    assert!(position.eq_strict(&Position::from(&a)));
+}